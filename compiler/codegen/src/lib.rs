@@ -6,6 +6,7 @@
 #![feature(once_cell)]
 
 pub mod linker;
+pub mod lto;
 pub mod meta;
 pub mod passes;
 
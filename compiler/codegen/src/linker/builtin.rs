@@ -1,17 +1,24 @@
-use std::ffi::CString;
+use std::env;
+use std::ffi::{CString, OsString};
 use std::io;
+use std::mem::MaybeUninit;
 use std::os;
 use std::os::raw::{c_char, c_int};
 
+use firefly_llvm::OwnedStringRef;
 use firefly_util::fs;
 
+use super::command::Command;
+
 extern "C" {
+    fn LLVMFireflyHasLLD() -> bool;
+
     #[cfg(windows)]
     pub fn LLVMFireflyLink(
         argc: c_int,
         argv: *const *const c_char,
         stdout: os::windows::io::RawHandle,
-        stderr: os::windows::io::RawHandle,
+        output: *mut *mut c_char,
     ) -> bool;
 
     #[cfg(not(windows))]
@@ -19,33 +26,85 @@ extern "C" {
         argc: c_int,
         argv: *const *const c_char,
         stdout: os::unix::io::RawFd,
-        stderr: os::unix::io::RawFd,
+        output: *mut *mut c_char,
     ) -> bool;
 }
 
+/// Returns true if `lld` was found when building the compiler, and so can be invoked via `link`
+pub fn is_available() -> bool {
+    unsafe { LLVMFireflyHasLLD() }
+}
+
 /// Invoke the statically linked `lld` linker with the given arguments.
 ///
+/// As the linker runs in-process, the environment configured on `cmd` (e.g. `LIB` for
+/// `lld-link`) is applied to the current process for the duration of the link.
+///
+/// On success, returns any warnings printed by the linker; on failure, returns the errors
+/// it printed, so that they can be reported as diagnostics.
+///
 /// NOTE: Assumes that the first value of the argument vector contains the
 /// program name which informs lld which flavor of linker is being run.
-pub fn link(argv: &[CString]) -> Result<(), ()> {
-    // Acquire exclusive access to stdout/stderr for the linker
+pub fn link(argv: &[CString], cmd: &Command) -> Result<String, String> {
+    let _env = ScopedEnv::new(cmd);
+
+    // Acquire exclusive access to stdout for the linker
     let stdout = io::stdout();
-    let stderr = io::stderr();
     let stdout_lock = stdout.lock();
-    let stderr_lock = stderr.lock();
     let stdout_fd = fs::get_file_descriptor(&stdout_lock);
-    let stderr_fd = fs::get_file_descriptor(&stderr_lock);
 
     let argc = argv.len();
     let mut c_argv = Vec::with_capacity(argc);
     for arg in argv {
         c_argv.push(arg.as_ptr());
     }
-    let is_ok = unsafe { LLVMFireflyLink(argc as c_int, c_argv.as_ptr(), stdout_fd, stderr_fd) };
+    let mut output = MaybeUninit::uninit();
+    let is_ok = unsafe {
+        LLVMFireflyLink(
+            argc as c_int,
+            c_argv.as_ptr(),
+            stdout_fd,
+            output.as_mut_ptr(),
+        )
+    };
+    let output = unsafe { OwnedStringRef::from_ptr(output.assume_init()) };
+    let output = output.to_string();
 
     if is_ok {
-        Ok(())
+        Ok(output)
     } else {
-        Err(())
+        Err(output)
+    }
+}
+
+/// Applies the environment of a linker command to the current process, restoring the
+/// previous environment when dropped
+struct ScopedEnv {
+    saved: Vec<(OsString, Option<OsString>)>,
+}
+impl ScopedEnv {
+    fn new(cmd: &Command) -> Self {
+        let mut saved = Vec::new();
+        for (key, value) in cmd.get_env() {
+            saved.push((key.clone(), env::var_os(key)));
+            env::set_var(key, value);
+        }
+        for key in cmd.get_env_remove() {
+            saved.push((key.clone(), env::var_os(key)));
+            env::remove_var(key);
+        }
+        Self { saved }
+    }
+}
+impl Drop for ScopedEnv {
+    fn drop(&mut self) {
+        // Restore in reverse, so that a variable which was set more than once ends up
+        // with the value it had originally
+        for (key, value) in self.saved.drain(..).rev() {
+            match value {
+                Some(value) => env::set_var(key, value),
+                None => env::remove_var(key),
+            }
+        }
     }
 }
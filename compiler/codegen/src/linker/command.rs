@@ -175,6 +175,14 @@ impl Command {
         mem::take(&mut self.args)
     }
 
+    pub fn get_env(&self) -> &[(OsString, OsString)] {
+        &self.env
+    }
+
+    pub fn get_env_remove(&self) -> &[OsString] {
+        &self.env_remove
+    }
+
     /// Returns a `true` if we're pretty sure that this'll blow OS spawn limits,
     /// or `false` if we should attempt to spawn and see what the OS says.
     pub fn very_likely_to_exceed_some_spawn_limit(&self) -> bool {
@@ -2,7 +2,7 @@ use std::ascii;
 use std::cell::OnceCell;
 use std::char;
 use std::env;
-use std::ffi::OsString;
+use std::ffi::{CString, OsStr, OsString};
 use std::fmt;
 use std::fs;
 use std::io;
@@ -28,6 +28,7 @@ use firefly_target::{
 use firefly_util::diagnostics::DiagnosticsHandler;
 use firefly_util::fs::{fix_windows_verbatim_for_gcc, NativeLibraryKind};
 
+use crate::linker::builtin;
 use crate::linker::command::Command;
 use crate::linker::rpath::{self, RPathConfig};
 use crate::linker::Linker;
@@ -224,7 +225,7 @@ fn link_natively(
     info!("preparing {:?} to {:?}", project_type, output_file);
     let (linker_path, flavor) = linker_and_flavor(options);
 
-    let (is_ld, mut cmd) = linker_with_args(
+    let mut cmd = linker_with_args(
         &linker_path,
         flavor,
         options,
@@ -249,8 +250,8 @@ fn link_natively(
     // May have not found libraries in the right formats.
     diagnostics.abort_if_errors();
 
-    if is_ld {
-        todo!("invoke firefly-lld");
+    if let Some(lld_flavor) = builtin_lld_flavor(options, &linker_path, flavor) {
+        link_with_builtin_lld(diagnostics, lld_flavor, &cmd);
     } else {
         match exec_linker(options, &mut cmd, output_file, tmpdir) {
             Ok(prog) => {
@@ -378,6 +379,97 @@ fn link_natively(
     Ok(())
 }
 
+/// Returns the flavor of the lld bundled with the compiler to link with, if the selected
+/// linker is lld, or linking was requested to be self-contained.
+///
+/// The bundled lld runs in-process, so linking with it doesn't require a system toolchain.
+fn builtin_lld_flavor(options: &Options, linker: &Path, flavor: LinkerFlavor) -> Option<LldFlavor> {
+    let LinkerFlavor::Lld(lld_flavor) = flavor else { return None; };
+    if options.codegen_opts.link_self_contained == Some(true) {
+        return Some(lld_flavor);
+    }
+    let stem = linker.file_stem().and_then(|stem| stem.to_str());
+    match stem {
+        Some("lld" | "firefly-lld") => Some(lld_flavor),
+        _ => None,
+    }
+}
+
+/// Links using the lld bundled with the compiler, reporting its output as diagnostics
+fn link_with_builtin_lld(diagnostics: &DiagnosticsHandler, flavor: LldFlavor, cmd: &Command) {
+    // The driver lld runs is selected by the program name, as with its executable aliases
+    let program = match flavor {
+        LldFlavor::Ld => "ld.lld",
+        LldFlavor::Ld64 => "ld64.lld",
+        LldFlavor::Link => "lld-link",
+        LldFlavor::Wasm => "wasm-ld",
+    };
+    if !builtin::is_available() {
+        let mut err = diagnostics.diagnostic(Severity::Error);
+        err.with_message(format!("unable to link with `{}`", program));
+        err.with_note("this compiler was built without lld");
+        err.with_note(
+            "use `-C linker=<path>` to link with a system linker instead, \
+             rather than requesting lld or self-contained linking",
+        );
+        err.emit();
+        diagnostics.abort_if_errors();
+    }
+
+    let mut argv = Vec::with_capacity(cmd.get_args().len() + 1);
+    argv.push(CString::new(program).unwrap());
+    for arg in cmd.get_args() {
+        match linker_arg_to_c_string(arg) {
+            Ok(arg) => argv.push(arg),
+            Err(reason) => diagnostics
+                .fatal(format!("invalid linker argument {:?}: {}", arg, reason))
+                .raise(),
+        }
+    }
+
+    match builtin::link(argv.as_slice(), cmd) {
+        Ok(output) => {
+            if !output.is_empty() {
+                let mut warning = diagnostics.diagnostic(Severity::Warning);
+                warning.with_message(format!("linking with `{}` produced warnings", program));
+                warning.with_note(output.trim_end());
+                warning.emit();
+            }
+        }
+        Err(output) => {
+            let mut err = diagnostics.diagnostic(Severity::Error);
+            err.with_message(format!("linking with `{}` failed", program));
+            err.with_note(format!("{:?}", cmd));
+            if output.contains("undefined symbol") {
+                err.with_note(output.trim_end());
+                err.with_note(
+                    "some `extern` functions couldn't be found; some native libraries may \
+                     need to be installed or have their path specified",
+                );
+                err.with_note("use the `-l` flag to specify native libraries to link");
+            } else {
+                err.with_note(output.trim_end());
+            }
+            err.emit();
+        }
+    }
+    diagnostics.abort_if_errors();
+}
+
+/// Converts an argument for the bundled lld to a C string without loss, i.e. the raw bytes
+/// of the argument on unix, and its UTF-8 encoding elsewhere, as lld expects
+fn linker_arg_to_c_string(arg: &OsStr) -> Result<CString, &'static str> {
+    #[cfg(unix)]
+    let bytes = {
+        use std::os::unix::ffi::OsStrExt;
+        arg.as_bytes()
+    };
+    #[cfg(not(unix))]
+    let bytes = arg.to_str().ok_or("not valid unicode")?.as_bytes();
+
+    CString::new(bytes).map_err(|_| "contains a nul byte")
+}
+
 fn strip_symbols_in_osx(
     _options: &Options,
     diagnostics: &DiagnosticsHandler,
@@ -569,6 +661,13 @@ pub fn linker_and_flavor(options: &Options) -> (PathBuf, LinkerFlavor) {
         return ret;
     }
 
+    // Self-contained linking uses the lld bundled with the compiler, unless a linker was
+    // specified on the command line
+    if options.codegen_opts.link_self_contained == Some(true) {
+        let flavor = LinkerFlavor::Lld(options.target.options.lld_flavor);
+        return (PathBuf::from("firefly-lld"), flavor);
+    }
+
     if let Some(ret) = infer_from(
         options,
         options.target.options.linker.as_deref().map(PathBuf::from),
@@ -1084,7 +1183,7 @@ fn linker_with_args(
     tmpdir: &Path,
     out_filename: &Path,
     codegen_results: &CodegenResults,
-) -> (bool, Command) {
+) -> Command {
     let crt_objects_fallback = crt_objects_fallback(options, project_type);
     let cmd = &mut *super::get_linker(
         options,
@@ -1234,9 +1333,7 @@ fn linker_with_args(
     // to it and remove the option.
     add_post_link_args(cmd, options, flavor);
 
-    let is_ld = cmd.is_ld();
-    let command = cmd.take_cmd();
-    (is_ld, command)
+    cmd.take_cmd()
 }
 
 fn add_order_independent_options(
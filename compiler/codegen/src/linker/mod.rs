@@ -1,4 +1,5 @@
 pub(crate) mod archive;
+mod builtin;
 mod command;
 pub(crate) mod link;
 mod rpath;
@@ -0,0 +1,203 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use log::debug;
+
+use firefly_intern::{symbols, Symbol};
+use firefly_llvm::archives::Archive;
+use firefly_llvm::lto::LinkTimeOptimizer;
+use firefly_llvm::target::TargetMachine;
+use firefly_rt::function::ON_LOAD_SYMBOL;
+use firefly_session::{Options, OutputType};
+use firefly_syntax_base::{ApplicationMetadata, FunctionName};
+use firefly_util::diagnostics::DiagnosticsHandler;
+
+use crate::linker::archive;
+use crate::linker::link::archive_search_paths;
+use crate::meta::{CodegenResults, CompiledModule};
+
+/// The runtime library linked into every executable on non-wasm targets
+const RUNTIME_LIBRARY: &str = "firefly_rt_tiny";
+
+/// The magic number with which all LLVM bitcode files begin
+const BITCODE_MAGIC: &[u8] = b"BC\xC0\xDE";
+
+/// The BIFs which look up functions in the dispatch table by a name only known at runtime
+const DYNAMIC_DISPATCH_BIFS: &[(&str, u8)] = &[
+    ("apply", 3),
+    ("binary_to_term", 1),
+    ("binary_to_term", 2),
+    ("function_exported", 3),
+    ("hibernate", 3),
+    ("make_fun", 3),
+    ("module_loaded", 1),
+    ("spawn", 3),
+    ("spawn", 4),
+    ("spawn_link", 3),
+    ("spawn_link", 4),
+    ("spawn_monitor", 3),
+    ("spawn_monitor", 4),
+    ("spawn_opt", 4),
+    ("spawn_opt", 5),
+];
+
+/// Performs link-time optimization of the bitcode produced for each module of the application,
+/// replacing it with the object files generated from the optimized program.
+///
+/// When `-C lto_runtime` is set, the bitcode members of the runtime library are optimized along
+/// with the application, and its remaining members are linked as object files in place of the
+/// library itself.
+///
+/// Only the dispatch table entries which may be used by the application are kept, see
+/// `used_dispatch_entries`, so that functions which are never called are stripped.
+pub fn optimize(
+    options: &Options,
+    diagnostics: &DiagnosticsHandler,
+    app: &ApplicationMetadata,
+    codegen_results: &mut CodegenResults,
+    target_machine: TargetMachine,
+) -> anyhow::Result<()> {
+    let app_name = codegen_results.app_name;
+    let prefix = options.output_dir().join(format!("{}.lto", app_name));
+
+    let mut bitcode = Vec::with_capacity(codegen_results.modules.len());
+    for module in codegen_results.modules.iter() {
+        let path = module
+            .bytecode()
+            .with_context(|| format!("no bitcode was generated for '{}'", module.name))?;
+        let data = fs::read(path)
+            .with_context(|| format!("unable to read bitcode from {}", path.display()))?;
+        bitcode.push((module.name.to_string(), data, false));
+    }
+
+    let mut objects = Vec::new();
+    if options.codegen_opts.lto_runtime {
+        let library = codegen_results
+            .project_info
+            .used_libraries
+            .iter()
+            .position(|lib| lib.name.as_deref() == Some(RUNTIME_LIBRARY))
+            .with_context(|| {
+                format!(
+                    "the runtime library '{}' is not used by this target",
+                    RUNTIME_LIBRARY
+                )
+            })?;
+        codegen_results.project_info.used_libraries.remove(library);
+
+        let search_paths = archive_search_paths(options);
+        let path = archive::find_library(RUNTIME_LIBRARY, false, &search_paths, options)?;
+        let runtime = Archive::open(&path)?;
+        for (i, member) in runtime.iter().enumerate() {
+            let member = member?;
+            let name = member
+                .name()
+                .map(|name| String::from_utf8_lossy(name.as_bytes()).into_owned())
+                .unwrap_or_else(|| format!("{}.{}", RUNTIME_LIBRARY, i));
+            let data = member.data();
+            if data.starts_with(BITCODE_MAGIC) {
+                // The runtime may be referenced by its native members, which are not optimized
+                bitcode.push((name, data.to_vec(), true));
+            } else if name.ends_with(".o") || name.ends_with(".obj") {
+                let object = PathBuf::from(format!("{}.rt.{}.o", prefix.display(), i));
+                fs::write(&object, data)
+                    .with_context(|| format!("unable to write {}", object.display()))?;
+                objects.push((name, object));
+            } else {
+                debug!("skipping runtime archive member {}", &name);
+            }
+        }
+    }
+
+    let mut optimizer = LinkTimeOptimizer::new(options, target_machine);
+    for (name, data, preserve) in bitcode.iter() {
+        optimizer.add(name, data.as_slice(), *preserve);
+    }
+    // The runtime calls the entry point by name, and finds atoms via the `__atoms` section, which
+    // is always preserved. All other generated code is either called directly, or found via the
+    // dispatch table, of which only the entries that may be used are preserved.
+    optimizer.preserve("init:boot/1");
+    for symbol in codegen_results.project_info.exported_symbols.iter() {
+        optimizer.preserve(symbol);
+    }
+    match used_dispatch_entries(app) {
+        Some(used) => {
+            for callee in used.iter() {
+                optimizer.preserve_dispatch_entry(
+                    callee.module.unwrap().as_str().get(),
+                    callee.function.as_str().get(),
+                    callee.arity,
+                );
+            }
+        }
+        None => optimizer.preserve_dispatch_table(),
+    }
+    let outputs = optimizer.run(&prefix)?;
+
+    // The intermediate bitcode is only kept if it was requested
+    if !options.output_types.contains_key(&OutputType::LLVMBitcode) {
+        for module in codegen_results.modules.iter() {
+            if let Some(path) = module.bytecode() {
+                if let Err(e) = fs::remove_file(path) {
+                    diagnostics.warn(format!("failed to remove {}: {}", path.display(), e));
+                }
+            }
+        }
+    }
+
+    let num_modules = codegen_results.modules.len();
+    codegen_results.modules = outputs
+        .into_iter()
+        .enumerate()
+        .map(|(i, object)| CompiledModule {
+            name: Symbol::intern(&format!("{}.lto.{}", app_name, i)),
+            object: Some(object),
+            dwarf_object: None,
+            bytecode: None,
+        })
+        .chain(objects.into_iter().map(|(name, object)| CompiledModule {
+            name: Symbol::intern(&name),
+            object: Some(object),
+            dwarf_object: None,
+            bytecode: None,
+        }))
+        .collect();
+
+    diagnostics.success(
+        "Optimized",
+        format!("{} modules of {} at link time", num_modules, app_name),
+    );
+
+    Ok(())
+}
+
+/// Returns the functions whose dispatch table entries may be used, or `None` if any of them may be
+///
+/// Calls to functions whose name is known statically refer to them directly, so the entries which
+/// are used are those of the entry point, the `-on_load` functions, which the runtime runs during
+/// boot, and the functions referenced as funs, e.g. `fun m:f/1`, which are looked up when the fun
+/// is created. If the application calls a function by a name which is only known at runtime, e.g.
+/// via `erlang:apply/3`, any entry may be used.
+fn used_dispatch_entries(app: &ApplicationMetadata) -> Option<BTreeSet<FunctionName>> {
+    let on_load = Symbol::intern(ON_LOAD_SYMBOL);
+    let mut used = BTreeSet::new();
+    used.insert(FunctionName::new(symbols::Init, symbols::Boot, 1));
+    for module in app.modules.values() {
+        let dynamic = module.calls.iter().any(|callee| {
+            callee.module == Some(symbols::Erlang)
+                && DYNAMIC_DISPATCH_BIFS
+                    .iter()
+                    .any(|(f, a)| callee.function.as_str().get() == *f && callee.arity == *a)
+        });
+        if dynamic {
+            return None;
+        }
+        used.extend(module.funs.iter().copied());
+        if module.on_load.is_some() {
+            used.insert(FunctionName::new(module.name.name, on_load, 0));
+        }
+    }
+    Some(used)
+}
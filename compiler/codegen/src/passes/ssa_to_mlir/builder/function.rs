@@ -740,6 +740,16 @@ impl<'m> ModuleBuilder<'m> {
                 self.cir().build_get_element(loc, lhs, rhs).base()
             }
             Opcode::IsTaggedTuple => {
+                use firefly_syntax_base::{TermType, Type as CoreType};
+
+                // Inputs which are known not to be tuples can never be tagged tuples
+                if let CoreType::Term(input_ty) = dfg.value_type(op.arg) {
+                    if input_ty.test_outcome(&TermType::Tuple(None)) == Some(false) {
+                        let constant = self.immediate_to_constant(loc, Immediate::I1(false));
+                        self.values.insert(results[0], constant);
+                        return Ok(());
+                    }
+                }
                 match op.imm {
                     Immediate::Term(ImmediateTerm::Atom(a)) => self.cir().build_is_tagged_tuple(loc, lhs, a).base(),
                     _ => panic!("invalid is_tagged_tuple immediate argument, only atom immediates are supported"),
@@ -1154,8 +1164,19 @@ impl<'m> ModuleBuilder<'m> {
     ) -> anyhow::Result<()> {
         use firefly_syntax_base::{TermType, Type as CoreType};
 
-        let builder = CirBuilder::new(&self.builder);
         let loc = self.location_from_span(span);
+        let result = dfg.first_result(inst);
+
+        // Type tests whose outcome is known from the inferred type of the input are folded to constants
+        if let (CoreType::Term(input_ty), CoreType::Term(test)) = (dfg.value_type(op.arg), &op.ty) {
+            if let Some(outcome) = input_ty.test_outcome(test) {
+                let constant = self.immediate_to_constant(loc, Immediate::I1(outcome));
+                self.values.insert(result, constant);
+                return Ok(());
+            }
+        }
+
+        let builder = CirBuilder::new(&self.builder);
         let input = self.values[&op.arg];
         let op = match op.ty {
             CoreType::Term(TermType::List(_)) => builder.build_is_list(loc, input).base(),
@@ -1189,7 +1210,6 @@ impl<'m> ModuleBuilder<'m> {
         };
 
         // Map syntax_ssa results to MLIR results
-        let result = dfg.first_result(inst);
         let mlir_result = op.get_result(0);
         self.values.insert(result, mlir_result.base());
 
@@ -1213,8 +1233,9 @@ impl<'m> ModuleBuilder<'m> {
             }
             Opcode::RecvStart => builder.build_recv_start(loc, self.values[&args[0]]).base(),
             Opcode::RecvNext => builder.build_recv_next(loc, self.values[&args[0]]).base(),
-            Opcode::RecvPeek => builder.build_recv_next(loc, self.values[&args[0]]).base(),
+            Opcode::RecvPeek => builder.build_recv_peek(loc, self.values[&args[0]]).base(),
             Opcode::RecvPop => builder.build_recv_pop(loc, self.values[&args[0]]).base(),
+            Opcode::RecvDone => builder.build_recv_done(loc, self.values[&args[0]]).base(),
             Opcode::RecvWait => builder.build_yield(loc).base(),
             Opcode::Raise => {
                 let class = self.values[&args[0]];
@@ -4,16 +4,16 @@ use std::collections::{HashMap, HashSet};
 
 use log::debug;
 
-use firefly_diagnostics::{CodeMap, SourceSpan};
+use firefly_diagnostics::{CodeMap, SourceSpan, Spanned};
 use firefly_intern::{symbols, Symbol};
 use firefly_llvm::Linkage;
 use firefly_mlir as mlir;
 use firefly_mlir::cir::{CirBuilder, DispatchTableOp};
 use firefly_mlir::llvm::LlvmBuilder;
 use firefly_mlir::{Builder, OpBuilder, Operation, OwnedOpBuilder, Variadic};
-use firefly_rt::function::FunctionSymbol;
+use firefly_rt::function::{FunctionSymbol, ON_LOAD_SYMBOL};
 use firefly_session::Options;
-use firefly_syntax_base::{self as syntax_base, Signature};
+use firefly_syntax_base::{self as syntax_base, ApplicationMetadata, Signature};
 use firefly_syntax_ssa as syntax_ssa;
 
 /// This builder holds the state necessary to build an MLIR module
@@ -25,6 +25,7 @@ use firefly_syntax_ssa as syntax_ssa;
 pub struct ModuleBuilder<'m> {
     options: &'m Options,
     codemap: &'m CodeMap,
+    app: &'m ApplicationMetadata,
     module: &'m syntax_ssa::Module,
     mlir_module: mlir::OwnedModule,
     builder: OwnedOpBuilder,
@@ -51,6 +52,7 @@ impl<'m> ModuleBuilder<'m> {
         codemap: &'m CodeMap,
         context: mlir::Context,
         options: &'m Options,
+        app: &'m ApplicationMetadata,
     ) -> Self {
         let builder = OwnedOpBuilder::new(context);
         let module_span = module.span();
@@ -82,6 +84,7 @@ impl<'m> ModuleBuilder<'m> {
         Self {
             options,
             codemap,
+            app,
             module,
             mlir_module,
             builder,
@@ -226,6 +229,19 @@ impl<'m> ModuleBuilder<'m> {
             }
         }
 
+        // Register the on_load function, if present, under a reserved name, so that the runtime can run it during boot
+        if let Some(on_load) = self.module.on_load.as_ref() {
+            let priority = self.app.on_load_priority(module_name).min(u8::MAX as usize) as u8;
+            let name = on_load.to_string();
+            self.dispatch_table.append_on_load(
+                self.location_from_span(on_load.span()),
+                self.builder.get_string_attr(ON_LOAD_SYMBOL),
+                self.builder.get_i8_attr(0),
+                self.builder.get_flat_symbol_ref_attr_by_name(name.as_str()),
+                self.builder.get_i8_attr(priority as i8),
+            );
+        }
+
         // Inject declaration for personality function
         {
             let loc = self.location_from_span(self.module.span());
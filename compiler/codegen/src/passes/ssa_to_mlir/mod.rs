@@ -6,6 +6,7 @@ use firefly_diagnostics::CodeMap;
 use firefly_mlir::{self as mlir, Context, OwnedContext};
 use firefly_pass::Pass;
 use firefly_session::Options;
+use firefly_syntax_base::ApplicationMetadata;
 use firefly_syntax_ssa as syntax_ssa;
 use log::debug;
 
@@ -13,13 +14,20 @@ pub struct SsaToMlir<'a> {
     context: Context,
     codemap: &'a CodeMap,
     options: &'a Options,
+    app: &'a ApplicationMetadata,
 }
 impl<'a> SsaToMlir<'a> {
-    pub fn new(context: &OwnedContext, codemap: &'a CodeMap, options: &'a Options) -> Self {
+    pub fn new(
+        context: &OwnedContext,
+        codemap: &'a CodeMap,
+        options: &'a Options,
+        app: &'a ApplicationMetadata,
+    ) -> Self {
         Self {
             context: **context,
             codemap,
             options,
+            app,
         }
     }
 }
@@ -31,7 +39,8 @@ impl<'m> Pass for SsaToMlir<'m> {
     fn run<'a>(&mut self, module: Self::Input<'a>) -> anyhow::Result<Self::Output<'a>> {
         debug!("building mlir module for {}", module.name());
 
-        let builder = ModuleBuilder::new(&module, self.codemap, self.context, self.options);
+        let builder =
+            ModuleBuilder::new(&module, self.codemap, self.context, self.options, self.app);
         builder.build()
     }
 }
@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::thread;
@@ -11,8 +11,9 @@ use salsa::{ParallelDatabase, Snapshot};
 use firefly_codegen as codegen;
 use firefly_codegen::linker;
 use firefly_codegen::meta::{CodegenResults, CompiledModule, ProjectInfo};
-use firefly_diagnostics::{CodeMap, Diagnostic, Label};
-use firefly_session::{CodegenOptions, DebuggingOptions, Options};
+use firefly_diagnostics::{CodeMap, Diagnostic, Label, Span};
+use firefly_intern::Symbol;
+use firefly_session::{CodegenOptions, DebuggingOptions, Input, Lto, Options};
 use firefly_syntax_base::{ApplicationMetadata, Deprecation, FunctionName, ModuleMetadata};
 use firefly_util::diagnostics::Emitter;
 use firefly_util::time::HumanDuration;
@@ -82,6 +83,9 @@ pub fn handle_command<'a>(
         }
     }
 
+    // Load the behaviours implemented by modules in this application which are defined elsewhere
+    let dependencies = resolve_behaviours(&db, &options, &modules);
+
     // Do not proceed with compilation if there were frontend errors
     diagnostics.abort_if_errors();
 
@@ -95,6 +99,7 @@ pub fn handle_command<'a>(
     let app = Arc::new(ApplicationMetadata {
         name: options.app.name,
         modules,
+        dependencies,
     });
 
     // Spawn tasks for each input to be compiled
@@ -157,6 +162,16 @@ pub fn handle_command<'a>(
             }
         } else {
             if options.app_type.requires_link() {
+                if options.lto() != Lto::No {
+                    let target_machine = db.target_machine(thread::current().id());
+                    codegen::lto::optimize(
+                        &options,
+                        &diagnostics,
+                        &app,
+                        &mut codegen_results,
+                        target_machine.handle(),
+                    )?;
+                }
                 linker::link_binary(&options, &diagnostics, &codegen_results)?;
             } else {
                 debug!("skipping link because project type does not require it");
@@ -190,7 +205,20 @@ where
         Ok(module) => {
             let diagnostics = db.diagnostics();
             let name = module.name;
-            let exports = module.exports.iter().cloned().collect();
+            let export_all = module
+                .compile
+                .as_ref()
+                .map(|opts| opts.export_all)
+                .unwrap_or(false);
+            let exports = if export_all {
+                module
+                    .functions
+                    .iter()
+                    .map(|(name, function)| Span::new(function.name.span, *name))
+                    .collect()
+            } else {
+                module.exports.iter().cloned().collect()
+            };
             let mut deprecation = module.deprecation.clone();
             let mut deprecations: BTreeMap<FunctionName, Deprecation> = BTreeMap::new();
             for dep in module.deprecations.iter().copied() {
@@ -238,16 +266,78 @@ where
                     }
                 }
             }
+            let on_load = module.on_load.as_ref().map(|f| f.resolve(name.name));
+            let on_load_dependencies = module.on_load_dependencies();
+            let behaviours = module.behaviours.iter().map(|b| b.name).collect();
+            let callbacks = module
+                .callbacks
+                .iter()
+                .filter_map(|(name, cb)| if cb.optional { None } else { Some(*name) })
+                .collect();
+            let optional_callbacks = module
+                .callbacks
+                .iter()
+                .filter_map(|(name, cb)| if cb.optional { Some(*name) } else { None })
+                .collect();
+            let calls = module.remote_calls();
+            let funs = module.remote_funs();
             Ok(ModuleMetadata {
                 name,
                 exports,
                 deprecation,
                 deprecations,
+                on_load,
+                on_load_dependencies,
+                behaviours,
+                callbacks,
+                optional_callbacks,
+                calls,
+                funs,
             })
         }
     }
 }
 
+/// Behaviours may be defined outside of the application being compiled, e.g. `gen_server`, in which
+/// case we look for the source of the behaviour module in the include path so that its callbacks can be
+/// checked against the modules which implement it.
+///
+/// Behaviours which cannot be found are left for semantic analysis, which falls back to the callbacks
+/// of the behaviours defined by OTP, and reports any others as undefined.
+fn resolve_behaviours<C>(
+    db: &C,
+    options: &Options,
+    modules: &BTreeMap<Symbol, ModuleMetadata>,
+) -> BTreeMap<Symbol, ModuleMetadata>
+where
+    C: ParserQueryGroup + ParallelDatabase,
+{
+    let mut dependencies = BTreeMap::new();
+    let behaviours = modules
+        .values()
+        .flat_map(|m| m.behaviours.iter().copied())
+        .collect::<BTreeSet<_>>();
+    for behaviour in behaviours {
+        if modules.contains_key(&behaviour) {
+            continue;
+        }
+        let filename = format!("{}.erl", behaviour);
+        let path = options
+            .include_path
+            .iter()
+            .map(|dir| dir.join(&filename))
+            .find(|path| path.is_file());
+        if let Some(path) = path {
+            debug!("loading behaviour {} from {}", behaviour, path.display());
+            let input = db.intern_input(Input::File(path));
+            if let Ok(metadata) = parse(db.snapshot(), input) {
+                dependencies.insert(behaviour, metadata);
+            }
+        }
+    }
+    dependencies
+}
+
 fn compile<C>(
     db: Snapshot<C>,
     input: InternedInput,
@@ -5,7 +5,7 @@ use log::debug;
 
 use firefly_codegen::meta::CompiledModule;
 use firefly_intern::Symbol;
-use firefly_session::OutputType;
+use firefly_session::{Lto, OutputType};
 use firefly_syntax_base::ApplicationMetadata;
 
 use super::prelude::*;
@@ -105,7 +105,6 @@ where
     let mut pm = PassManager::new(**mlir_context, &pm_opts);
     //let mpm = pm.nest("builtin.module");
     //mpm.add(firefly_mlir::conversions::ConvertCIRToLLVMPass::new());
-    firefly_mlir::conversions::ConvertCIRToLLVMPass::register();
     pm.parse_pipeline("convert-cir-to-llvm,reconcile-unrealized-casts")
         .unwrap();
 
@@ -151,6 +150,36 @@ where
         return Ok(None);
     }
 
+    // When performing link-time optimization, code generation is deferred until the bitcode
+    // of all modules is available, see `firefly_codegen::lto`
+    let lto = options.lto();
+    if lto != Lto::No {
+        let bc_path = db.output_dir().join(
+            options
+                .output_types
+                .always_emit(&input_info, OutputType::LLVMBitcode),
+        );
+        let bc_path = db.emit_file_with_callback(bc_path, |outfile| {
+            debug!(
+                "emitting llvm bitcode for link-time optimization of {:?}",
+                input
+            );
+            match lto {
+                Lto::Thin | Lto::ThinLocal => module.emit_thin_lto_bc(outfile),
+                _ => module.emit_bc(outfile),
+            }
+        })?;
+
+        debug!("compilation finished for {:?}", input);
+        diagnostics.success("Compiled", format!("{}", &module_name));
+        return Ok(Some(CompiledModule {
+            name: module_sym,
+            object: None,
+            dwarf_object: None,
+            bytecode: Some(bc_path),
+        }));
+    }
+
     // Emit textual assembly file
     db.maybe_emit_file_with_callback_and_opts(&options, input, OutputType::Assembly, |outfile| {
         debug!("emitting asm for {:?}", input);
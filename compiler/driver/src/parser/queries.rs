@@ -8,16 +8,21 @@ use firefly_diagnostics::{Reporter, ToDiagnostic};
 use firefly_intern::symbols;
 use firefly_llvm as llvm;
 use firefly_mlir as mlir;
-use firefly_session::{Input, InputType};
+use firefly_session::{Input, InputType, Options};
 use firefly_syntax_base::ApplicationMetadata;
 use firefly_syntax_core as syntax_core;
 use firefly_syntax_erl::{self as syntax_erl, ParseConfig};
 use firefly_syntax_kernel as syntax_kernel;
+use firefly_syntax_pp as syntax_pp;
 use firefly_syntax_ssa as syntax_ssa;
 use firefly_util::diagnostics::FileName;
 
 use super::prelude::*;
 
+/// The number of reductions a process may execute before being preempted, unless overridden
+/// with `-C reductions`, this is the same as the default in BEAM
+const DEFAULT_REDUCTION_BUDGET: u64 = 4000;
+
 macro_rules! unwrap_or_bail {
     ($db:ident, $e:expr) => {
         match $e {
@@ -69,6 +74,36 @@ where
     parse_config
 }
 
+/// Returns the command-line options reported by `module_info(compile)`, in the same form as
+/// the equivalent options given to `erlc`
+fn compile_options(options: &Options) -> Vec<syntax_erl::ast::Literal> {
+    use firefly_diagnostics::SourceSpan;
+    use firefly_intern::{Ident, Symbol};
+    use syntax_erl::ast::Literal;
+
+    let atom = |name: &str| Literal::Atom(Ident::with_empty_span(Symbol::intern(name)));
+    let string = |value: &str| Literal::String(Ident::with_empty_span(Symbol::intern(value)));
+    let tuple = |elements| Literal::Tuple(SourceSpan::default(), elements);
+
+    let mut compile_options = vec![];
+    if let Some(dir) = options.output_dir.as_ref() {
+        compile_options.push(tuple(vec![atom("outdir"), string(&dir.to_string_lossy())]));
+    }
+    for dir in options.include_path.iter() {
+        compile_options.push(tuple(vec![atom("i"), string(&dir.to_string_lossy())]));
+    }
+    for (name, value) in options.cli_defines.iter() {
+        match value {
+            None => compile_options.push(tuple(vec![atom("d"), atom(name)])),
+            Some(value) => compile_options.push(tuple(vec![atom("d"), atom(name), string(value)])),
+        }
+    }
+    if options.warnings_as_errors {
+        compile_options.push(atom("warnings_as_errors"));
+    }
+    compile_options
+}
+
 pub(crate) fn output_dir<P>(db: &P) -> PathBuf
 where
     P: Parser,
@@ -128,7 +163,8 @@ where
         //
         // 1. `stdin` for standard input
         // 2. `path/to/file.erl` for a single file
-        // 3. `path/to/dir` for a directory containing a standard Erlang application
+        // 3. `path/to/dir` for a directory containing a standard Erlang application, or
+        //    a directory of `.beam` files, e.g. `ebin`
         match input {
             // Read from standard input
             &FileName::Virtual(ref name) if name == "stdin" => {
@@ -211,6 +247,31 @@ where
                     parser.parse_string::<syntax_erl::Module, _, _>(reporter.clone(), input)
                 }
             }
+            .map_err(|e| e.to_diagnostic())
+        }
+        InputType::AbstractErlang => {
+            let parser = parse::Parser::new((), codemap.clone());
+            match db.lookup_intern_input(input) {
+                Input::File(ref path) => {
+                    parser.parse_file::<syntax_pp::Root, &Path, _>(reporter.clone(), path)
+                }
+                Input::Str { ref input, .. } => {
+                    parser.parse_string::<syntax_pp::Root, _, _>(reporter.clone(), input)
+                }
+            }
+            .and_then(|root| syntax_pp::translate(&reporter, codemap.clone(), &root))
+            .map_err(|e| e.to_diagnostic())
+        }
+        InputType::Beam => {
+            let parser = parse::Parser::new((), codemap.clone());
+            match db.lookup_intern_input(input) {
+                Input::File(ref path) => syntax_pp::parse_beam(&parser, reporter.clone(), path),
+                Input::Str { .. } => {
+                    bail!(db, "beam files can only be loaded from disk, not from a string")
+                }
+            }
+            .and_then(|root| syntax_pp::translate(&reporter, codemap.clone(), &root))
+            .map_err(|e| e.to_diagnostic())
         }
         ty => bail!(db, "invalid input type: {}", ty),
     };
@@ -221,8 +282,8 @@ where
             db.maybe_emit_file_with_opts(&options, input, &module)?;
             Ok(module)
         }
-        Err(e) => {
-            reporter.diagnostic(e.to_diagnostic());
+        Err(diagnostic) => {
+            reporter.diagnostic(diagnostic);
             reporter.print(&codemap);
             bail!(db, "parsing failed, see diagnostics for details");
         }
@@ -238,7 +299,9 @@ where
     P: Parser,
 {
     use firefly_pass::Pass;
+    use firefly_session::OptLevel;
     use firefly_syntax_erl::passes::{AstToCore, CanonicalizeSyntax, SemanticAnalysis};
+    use syntax_core::passes::InlineFunctions;
 
     // Get Erlang AST
     let ast = db.input_ast(input)?;
@@ -252,9 +315,20 @@ where
         Reporter::new()
     };
 
-    let mut passes = SemanticAnalysis::new(reporter.clone(), &app)
-        .chain(CanonicalizeSyntax::new(reporter.clone(), codemap.clone()))
-        .chain(AstToCore::new(reporter.clone()));
+    let compile_options = compile_options(&options);
+    let mut passes = SemanticAnalysis::new(
+        reporter.clone(),
+        codemap.clone(),
+        &app,
+        crate::FIREFLY_RELEASE,
+        &compile_options,
+    )
+    .chain(CanonicalizeSyntax::new(reporter.clone(), codemap.clone()))
+    .chain(AstToCore::new(
+        reporter.clone(),
+        options.opt_level != OptLevel::No,
+    ))
+    .chain(InlineFunctions::new());
 
     let module = unwrap_or_bail!(db, reporter, &codemap, passes.run(ast));
 
@@ -301,13 +375,12 @@ pub(crate) fn input_ssa<P>(
 where
     P: Parser,
 {
+    use firefly_parser as parse;
     use firefly_pass::Pass;
+    use firefly_session::OptLevel;
     use firefly_syntax_kernel::passes::KernelToSsa;
+    use firefly_syntax_ssa::passes::{InferTypes, PassManager};
 
-    // Get Kernel Erlang module
-    let cst = db.input_kernel(input, app)?;
-
-    // Run lowering passes
     let options = db.options();
     let codemap = db.codemap().clone();
     let reporter = if options.warnings_as_errors {
@@ -316,7 +389,43 @@ where
         Reporter::new()
     };
 
-    let mut passes = KernelToSsa::new(reporter.clone());
+    // Textual SSA IR is parsed directly, bypassing the frontend entirely
+    if db.input_type(input) == InputType::SSA {
+        let parser = parse::Parser::new((), codemap.clone());
+        let result = match db.lookup_intern_input(input) {
+            Input::File(ref path) => {
+                parser.parse_file::<syntax_ssa::Module, &Path, _>(reporter.clone(), path)
+            }
+            Input::Str { ref input, .. } => {
+                parser.parse_string::<syntax_ssa::Module, _, _>(reporter.clone(), input)
+            }
+        };
+        return match result {
+            Ok(module) => {
+                reporter.print(&codemap);
+                db.maybe_emit_file(input, &module)?;
+                Ok(module)
+            }
+            Err(err) => {
+                reporter.diagnostic(err.to_diagnostic());
+                reporter.print(&codemap);
+                bail!(db, "parsing failed, see diagnostics for details");
+            }
+        };
+    }
+
+    // Get Kernel Erlang module
+    let cst = db.input_kernel(input, app)?;
+
+    // Run lowering passes, followed by optimizations, if enabled
+    let optimize = if options.opt_level == OptLevel::No {
+        PassManager::new()
+    } else {
+        PassManager::default_pipeline()
+    };
+    let mut passes = KernelToSsa::new(reporter.clone())
+        .chain(InferTypes::new())
+        .chain(optimize);
     let module = unwrap_or_bail!(db, reporter, &codemap, passes.run(cst));
 
     db.maybe_emit_file(input, &module)?;
@@ -354,20 +463,44 @@ where
                 }
             }
         }
-        InputType::Erlang | InputType::AbstractErlang | InputType::SSA => {
+        InputType::Erlang | InputType::AbstractErlang | InputType::Beam | InputType::SSA => {
             debug!("generating mlir for {:?} on {:?}", input, thread_id);
-            let module = db.input_ssa(input, app)?;
+            let module = db.input_ssa(input, app.clone())?;
             let codemap = db.codemap();
             let context = db.mlir_context(thread_id);
 
-            let mut passes = SsaToMlir::new(&context, &codemap, &options);
-            match unwrap_or_bail!(db, passes.run(module)) {
+            let mut passes = SsaToMlir::new(&context, &codemap, &options, &app);
+            let mlir_module = match unwrap_or_bail!(db, passes.run(module)) {
                 Ok(mlir_module) => mlir_module,
                 Err(mlir_module) => {
                     db.maybe_emit_file_with_opts(&options, input, &mlir_module)?;
                     bail!(db, "mlir module verification failed");
                 }
+            };
+
+            // Unless preemption is disabled, yield points are injected so that processes
+            // are preempted once they have exhausted their reduction budget. MLIR given as
+            // input is expected to have had this done already.
+            let budget = options
+                .codegen_opts
+                .reductions
+                .unwrap_or(DEFAULT_REDUCTION_BUDGET);
+            if budget > 0 {
+                let pm_opts = mlir::PassManagerOptions::new(&options);
+                let mut pm = mlir::PassManager::new(**context, &pm_opts);
+                let pipeline = format!(
+                    "func.func(inject-yields{{reduction-budget={}}})",
+                    budget.min(i32::MAX as u64)
+                );
+                if pm.parse_pipeline(pipeline.as_str()).is_err() {
+                    bail!(db, "invalid pass pipeline: {}", pipeline);
+                }
+                if !pm.run(&mlir_module) {
+                    bail!(db, "failed to inject yield points into mlir module");
+                }
             }
+
+            mlir_module
         }
         ty => bail!(db, "invalid input type: {}", ty),
     };
@@ -382,6 +515,8 @@ where
     D: Parser,
     P: AsRef<Path>,
 {
+    use std::collections::HashSet;
+
     use walkdir::{DirEntry, WalkDir};
 
     fn is_hidden(entry: &DirEntry) -> bool {
@@ -397,12 +532,16 @@ where
         if is_hidden(entry) {
             return false;
         }
-        // Recurse into the root directory, and nested src directory, no others
+        // Recurse into the root directory, and nested src/ebin directories, no others
         let path = entry.path();
         if entry.file_type().is_dir() {
-            return path == root || path.file_name().unwrap().to_str().unwrap() == "src";
+            if path == root {
+                return true;
+            }
+            let name = path.file_name().unwrap().to_str().unwrap();
+            return name == "src" || name == "ebin";
         }
-        InputType::Erlang.validate(path)
+        InputType::Erlang.validate(path) || InputType::Beam.validate(path)
     }
 
     let root = dir.as_ref();
@@ -411,16 +550,37 @@ where
         .follow_links(false)
         .into_iter();
 
-    let mut inputs = Vec::new();
+    let mut sources = Vec::new();
+    let mut beams = Vec::new();
 
     for maybe_entry in walker.filter_entry(|e| is_valid_entry(root, e)) {
         let entry = maybe_entry?;
-        if entry.path().is_file() {
-            let input = Input::from(entry.path());
-            let interned = db.intern_input(input);
-            inputs.push(interned);
+        let path = entry.path();
+        if path.is_file() {
+            if InputType::Beam.validate(path) {
+                beams.push(path.to_path_buf());
+            } else {
+                sources.push(path.to_path_buf());
+            }
         }
     }
 
+    // When both the source and the compiled form of a module are present, prefer the source
+    let modules = sources
+        .iter()
+        .filter_map(|path| path.file_stem().map(|stem| stem.to_os_string()))
+        .collect::<HashSet<_>>();
+    beams.retain(|path| {
+        path.file_stem()
+            .map(|stem| !modules.contains(stem))
+            .unwrap_or(true)
+    });
+
+    let inputs = sources
+        .into_iter()
+        .chain(beams)
+        .map(|path| db.intern_input(Input::from(path)))
+        .collect();
+
     Ok(inputs)
 }
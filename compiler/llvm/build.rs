@@ -11,6 +11,10 @@ const ENV_LLVM_LINK_LLVM_DYLIB: &'static str = "LLVM_LINK_LLVM_DYLIB";
 const ENV_FIREFLY_LLVM_LTO: &'static str = "FIREFLY_LLVM_LTO";
 const ENV_LLVM_USE_SANITIZER: &'static str = "LLVM_USE_SANITIZER";
 
+/// The lld libraries, in the order they must be linked, i.e. before the LLVM libraries they
+/// depend on
+const LLD_LIBRARIES: &[&'static str] = &["lldCOFF", "lldELF", "lldMachO", "lldWasm", "lldCommon"];
+
 fn main() {
     let cwd = env::current_dir().unwrap();
     let llvm_prefix = detect_llvm_prefix();
@@ -57,6 +61,15 @@ fn main() {
         "asmparser",
         "lto",
         "instrumentation",
+        // Required by lld
+        "debuginfodwarf",
+        "libdriver",
+        "objcarcopts",
+        "option",
+        "passes",
+        "textapi",
+        "windowsdriver",
+        "windowsmanifest",
         //"orcjit",
     ];
 
@@ -125,6 +138,20 @@ fn main() {
         cfg.debug(false);
     }
 
+    // lld is bundled with our LLVM build, so that we can link without depending on a system
+    // linker, but other LLVM installations may not include it, in which case linking with
+    // the bundled lld reports an error rather than failing the build
+    let has_lld = detect_lld(&llvm_lib_dir);
+    if has_lld {
+        cfg.define("FIREFLY_HAS_LLD", None);
+    } else {
+        println!(
+            "cargo:warning=lld was not found in {}, linking with the bundled lld will be unavailable",
+            llvm_lib_dir.display()
+        );
+    }
+    println!("cargo:lld={}", has_lld);
+
     let include_dir = cwd.join("c_src/include");
     println!("cargo:include={}", include_dir.display());
 
@@ -136,6 +163,8 @@ fn main() {
        .file("c_src/Diagnostics.cpp")
        .file("c_src/ErrorHandling.cpp")
        .file("c_src/IR.cpp")
+       .file("c_src/Linker.cpp")
+       .file("c_src/LTO.cpp")
        //.file("c_src/Orc.cpp")
        .file("c_src/Passes.cpp")
        .file("c_src/Target.cpp")
@@ -157,6 +186,13 @@ fn main() {
         llvm_lib_dir.as_path().display()
     );
 
+    // Link in lld, if available. These are always static libraries, as lld doesn't build a dylib.
+    if has_lld {
+        for lib in LLD_LIBRARIES {
+            println!("cargo:rustc-link-lib=static={}", lib);
+        }
+    }
+
     if !link_static && link_llvm_dylib {
         println!("cargo:rustc-link-lib=dylib=LLVM");
     } else {
@@ -340,6 +376,14 @@ fn detect_llvm_prefix() -> PathBuf {
     fail("LLVM_PREFIX is not defined and unable to locate LLVM to build with");
 }
 
+/// Returns true if all of the lld libraries are present in the given LLVM library directory
+fn detect_lld(llvm_lib_dir: &Path) -> bool {
+    LLD_LIBRARIES.iter().all(|lib| {
+        llvm_lib_dir.join(format!("lib{}.a", lib)).exists()
+            || llvm_lib_dir.join(format!("{}.lib", lib)).exists()
+    })
+}
+
 fn output(cmd: &mut Command) -> String {
     let output = match cmd.stderr(Stdio::inherit()).output() {
         Ok(status) => status,
@@ -0,0 +1,241 @@
+// On Windows we have a custom output stream type that
+// can wrap the raw file handle we get from Rust
+#if defined(_WIN32)
+#include "firefly/llvm/raw_win32_handle_ostream.h"
+#endif
+
+#include "llvm-c/Core.h"
+#include "llvm-c/TargetMachine.h"
+#include "llvm/ADT/StringExtras.h"
+#include "llvm/ADT/StringSet.h"
+#include "llvm/Analysis/ModuleSummaryAnalysis.h"
+#include "llvm/Analysis/ProfileSummaryInfo.h"
+#include "llvm/Bitcode/BitcodeWriter.h"
+#include "llvm/IR/Module.h"
+#include "llvm/IR/ModuleSummaryIndex.h"
+#include "llvm/LTO/Config.h"
+#include "llvm/LTO/LTO.h"
+#include "llvm/Support/Caching.h"
+#include "llvm/Support/CBindingWrapping.h"
+#include "llvm/Support/FileSystem.h"
+#include "llvm/Support/MemoryBuffer.h"
+#include "llvm/Support/SHA1.h"
+#include "llvm/Support/Threading.h"
+#include "llvm/Support/raw_ostream.h"
+#include "llvm/Target/TargetMachine.h"
+
+#include <cstdlib>
+#include <cstring>
+#include <string>
+
+using namespace llvm;
+
+DEFINE_STDCXX_CONVERSION_FUNCTIONS(TargetMachine, LLVMTargetMachineRef);
+
+/// Writes the given module as bitcode, along with the module summary used by
+/// ThinLTO to decide what to import across modules during the link.
+#if defined(_WIN32)
+extern "C" bool
+LLVMFireflyEmitThinLTOBitcodeToFileDescriptor(LLVMModuleRef m, HANDLE handle,
+                                              char **errorMessage) {
+  raw_win32_handle_ostream stream(handle, /*shouldClose=*/false,
+                                  /*unbuffered=*/false);
+#else
+extern "C" bool
+LLVMFireflyEmitThinLTOBitcodeToFileDescriptor(LLVMModuleRef m, int fd,
+                                              char **errorMessage) {
+  raw_fd_ostream stream(fd, /*shouldClose=*/false, /*unbuffered=*/false,
+                        raw_ostream::OStreamKind::OK_FDStream);
+#endif
+  Module *mod = unwrap(m);
+
+  ProfileSummaryInfo psi(*mod);
+  ModuleSummaryIndex index = buildModuleSummaryIndex(*mod, nullptr, &psi);
+  WriteBitcodeToFile(*mod, stream, /*shouldPreserveUseListOrder=*/false,
+                     &index);
+
+  if (stream.has_error()) {
+    std::string err = "Error printing to file: " + stream.error().message();
+    *errorMessage = strdup(err.c_str());
+    return true;
+  }
+
+  stream.flush();
+
+  return false;
+}
+
+/// A bitcode module given as input to link-time optimization
+struct FireflyLTOInput {
+  const char *name;
+  const char *data;
+  size_t len;
+  // When true, the symbols defined by this module are always visible outside
+  // of the optimized modules, i.e. they are never internalized
+  bool preserve;
+};
+
+/// The configuration of a link-time optimization run
+///
+/// Whether modules are optimized with ThinLTO, or merged and optimized as one,
+/// depends on whether their bitcode was written with a module summary.
+struct FireflyLTOConfig {
+  unsigned optLevel;
+  // When true, the optimized modules are linked into an executable, so symbols
+  // which are not visible to native code are internalized, and those which
+  // are unused can be stripped
+  bool executable;
+  // When true, the optimized IR of each task is written to
+  // `<outputPrefix>.<task>.ll` before code generation
+  bool emitIR;
+  // When true, every entry of the dispatch table is kept, otherwise only those
+  // given as preserved dispatch entries are
+  bool preserveDispatchTable;
+};
+
+/// A function whose dispatch table entry must be kept
+struct FireflyDispatchEntry {
+  const char *module;
+  const char *function;
+  unsigned arity;
+};
+
+/// Returns the name of the global holding the dispatch table entry of the
+/// given function, which must match the name given to it in
+/// `DispatchTableOpLowering`
+static std::string dispatchEntryName(const FireflyDispatchEntry &entry) {
+  SHA1 hasher;
+  hasher.update(StringRef(entry.module));
+  hasher.update(StringRef(entry.function));
+  hasher.update(StringRef(std::to_string(entry.arity)));
+  return std::string("firefly_dispatch_") + toHex(hasher.result(), true);
+}
+
+/// Returns true if the given section is the one holding the dispatch table
+static bool isDispatchSection(StringRef section) {
+  return section == "__dispatch" || section == "__DATA,__dispatch";
+}
+
+/// Runs link-time optimization on the given bitcode modules, writing the
+/// resulting object files to `<outputPrefix>.<task>.o`, and if requested, the
+/// optimized IR to `<outputPrefix>.<task>.ll`.
+///
+/// Code generation is configured from the given target machine. Symbols named
+/// in `preserved`, and symbols placed in an explicit section, which are found
+/// by the runtime using the section bounds rather than by name, are always
+/// kept visible to native code. The exception is the dispatch table, of which
+/// only the entries of the functions in `dispatch` are kept, unless the config
+/// says otherwise, so that functions only reachable from the dispatch table,
+/// e.g. unused exports, are stripped.
+///
+/// On success, `numOutputs` is set to the number of tasks run, not every task
+/// necessarily produces an object file.
+extern "C" bool LLVMFireflyRunLTO(LLVMTargetMachineRef tm,
+                                  const FireflyLTOConfig *config,
+                                  const FireflyLTOInput *inputs,
+                                  unsigned numInputs, const char **preserved,
+                                  unsigned numPreserved,
+                                  const FireflyDispatchEntry *dispatch,
+                                  unsigned numDispatch,
+                                  const char *outputPrefix,
+                                  unsigned *numOutputs, char **errorMessage) {
+  TargetMachine *targetMachine = unwrap(tm);
+
+  lto::Config conf;
+  conf.CPU = targetMachine->getTargetCPU().str();
+  SmallVector<StringRef, 8> features;
+  targetMachine->getTargetFeatureString().split(features, ',', -1, false);
+  for (auto feature : features)
+    conf.MAttrs.push_back(feature.str());
+  conf.Options = targetMachine->Options;
+  conf.RelocModel = targetMachine->getRelocationModel();
+  conf.CodeModel = targetMachine->getCodeModel();
+  conf.CGOptLevel = targetMachine->getOptLevel();
+  conf.OptLevel = config->optLevel;
+  conf.DefaultTriple = targetMachine->getTargetTriple().str();
+
+  std::string prefix(outputPrefix);
+  if (config->emitIR) {
+    conf.PreCodeGenModuleHook = [prefix](unsigned task, const Module &mod) {
+      std::string path = prefix + "." + std::to_string(task) + ".ll";
+      std::error_code ec;
+      raw_fd_ostream os(path, ec, sys::fs::OF_Text);
+      if (ec) {
+        errs() << "failed to write " << path << ": " << ec.message() << "\n";
+        return false;
+      }
+      mod.print(os, /*AAW=*/nullptr);
+      return true;
+    };
+  }
+
+  lto::ThinBackend backend =
+      lto::createInProcessThinBackend(llvm::heavyweight_hardware_concurrency());
+  lto::LTO lto(std::move(conf), std::move(backend));
+
+  StringSet<> preservedSymbols;
+  for (unsigned i = 0; i < numPreserved; ++i)
+    preservedSymbols.insert(preserved[i]);
+  for (unsigned i = 0; i < numDispatch; ++i)
+    preservedSymbols.insert(dispatchEntryName(dispatch[i]));
+
+  // The first definition of a symbol is the one which is kept
+  StringSet<> defined;
+  for (unsigned i = 0; i < numInputs; ++i) {
+    const FireflyLTOInput &input = inputs[i];
+    MemoryBufferRef buffer(StringRef(input.data, input.len), input.name);
+    auto fileOrErr = lto::InputFile::create(buffer);
+    if (!fileOrErr) {
+      std::string err = "failed to read bitcode from '" +
+                        std::string(input.name) +
+                        "': " + toString(fileOrErr.takeError());
+      *errorMessage = strdup(err.c_str());
+      return true;
+    }
+    std::unique_ptr<lto::InputFile> file = std::move(*fileOrErr);
+
+    std::vector<lto::SymbolResolution> resolutions;
+    for (const lto::InputFile::Symbol &sym : file->symbols()) {
+      lto::SymbolResolution res;
+      if (!sym.isUndefined())
+        res.Prevailing = defined.insert(sym.getName()).second;
+      StringRef section = sym.getSectionName();
+      bool inSection = isDispatchSection(section)
+                           ? config->preserveDispatchTable
+                           : !section.empty();
+      res.VisibleToRegularObj = !config->executable || input.preserve ||
+                                inSection ||
+                                preservedSymbols.contains(sym.getName());
+      res.FinalDefinitionInLinkageUnit = config->executable && res.Prevailing;
+      resolutions.push_back(res);
+    }
+
+    if (auto err = lto.add(std::move(file), resolutions)) {
+      std::string msg = "failed to add '" + std::string(input.name) +
+                        "' to link-time optimization: " +
+                        toString(std::move(err));
+      *errorMessage = strdup(msg.c_str());
+      return true;
+    }
+  }
+
+  auto addStream =
+      [&](unsigned task) -> Expected<std::unique_ptr<CachedFileStream>> {
+    std::string path = prefix + "." + std::to_string(task) + ".o";
+    std::error_code ec;
+    auto os = std::make_unique<raw_fd_ostream>(path, ec, sys::fs::OF_None);
+    if (ec)
+      return errorCodeToError(ec);
+    return std::make_unique<CachedFileStream>(std::move(os), path);
+  };
+
+  *numOutputs = lto.getMaxTasks();
+  if (auto err = lto.run(addStream)) {
+    std::string msg =
+        "link-time optimization failed: " + toString(std::move(err));
+    *errorMessage = strdup(msg.c_str());
+    return true;
+  }
+
+  return false;
+}
@@ -0,0 +1,122 @@
+// On Windows we have a custom output stream type that
+// can wrap the raw file handle we get from Rust
+#if defined(_WIN32)
+#include "firefly/llvm/raw_win32_handle_ostream.h"
+#endif
+
+// lld is only available if it was found alongside LLVM when building, see
+// build.rs
+#if defined(FIREFLY_HAS_LLD)
+#include "lld/Common/CommonLinkerContext.h"
+#include "lld/Common/Driver.h"
+#endif
+#include "llvm/ADT/ArrayRef.h"
+#include "llvm/ADT/StringRef.h"
+#include "llvm/Support/CrashRecoveryContext.h"
+#include "llvm/Support/Path.h"
+#include "llvm/Support/raw_ostream.h"
+
+#include <cstdlib>
+#include <cstring>
+#include <string>
+
+using namespace llvm;
+
+namespace {
+enum class Flavor {
+  Invalid,
+  Gnu,     // -flavor gnu
+  WinLink, // -flavor link
+  Darwin,  // -flavor darwin
+  Wasm,    // -flavor wasm
+};
+}
+
+/// Returns true if lld was linked in, and can be invoked via LLVMFireflyLink
+extern "C" bool LLVMFireflyHasLLD() {
+#if defined(FIREFLY_HAS_LLD)
+  return true;
+#else
+  return false;
+#endif
+}
+
+/// Selects the lld driver to run from the program name in `argv[0]`, in the
+/// same way as the `lld` executable does when invoked via one of its aliases.
+static Flavor getFlavor(StringRef programName) {
+  StringRef stem = sys::path::filename(programName);
+  stem.consume_back_insensitive(".exe");
+  if (stem.endswith("ld.lld") || stem == "ld")
+    return Flavor::Gnu;
+  if (stem.endswith("ld64.lld") || stem == "ld64")
+    return Flavor::Darwin;
+  if (stem.endswith("lld-link") || stem == "link")
+    return Flavor::WinLink;
+  if (stem.endswith("wasm-ld"))
+    return Flavor::Wasm;
+  return Flavor::Invalid;
+}
+
+/// Runs the lld driver selected by the program name in `argv[0]`.
+///
+/// Anything the linker prints to stdout (e.g. in response to `--version`) is
+/// written to the given file descriptor, while errors and warnings are
+/// collected and returned via `output`, so that they can be reported as
+/// diagnostics. `output` is set to null if nothing was printed.
+///
+/// Returns true if linking succeeded.
+#if defined(_WIN32)
+extern "C" bool LLVMFireflyLink(int argc, const char **argv, HANDLE handle,
+                                char **output) {
+  raw_win32_handle_ostream stdoutOS(handle, /*shouldClose=*/false,
+                                    /*unbuffered=*/false);
+#else
+extern "C" bool LLVMFireflyLink(int argc, const char **argv, int fd,
+                                char **output) {
+  raw_fd_ostream stdoutOS(fd, /*shouldClose=*/false, /*unbuffered=*/false);
+#endif
+  std::string diagnostics;
+  raw_string_ostream stderrOS(diagnostics);
+
+  ArrayRef<const char *> args(argv, argv + argc);
+  bool ok;
+#if defined(FIREFLY_HAS_LLD)
+  switch (getFlavor(args[0])) {
+  case Flavor::Gnu:
+    ok = lld::elf::link(args, stdoutOS, stderrOS, /*exitEarly=*/false,
+                        /*disableOutput=*/false);
+    break;
+  case Flavor::Darwin:
+    ok = lld::macho::link(args, stdoutOS, stderrOS, /*exitEarly=*/false,
+                          /*disableOutput=*/false);
+    break;
+  case Flavor::WinLink:
+    ok = lld::coff::link(args, stdoutOS, stderrOS, /*exitEarly=*/false,
+                         /*disableOutput=*/false);
+    break;
+  case Flavor::Wasm:
+    ok = lld::wasm::link(args, stdoutOS, stderrOS, /*exitEarly=*/false,
+                         /*disableOutput=*/false);
+    break;
+  default:
+    stderrOS << "unable to determine the lld flavor for '" << args[0] << "'";
+    ok = false;
+    break;
+  }
+
+  // lld keeps global state for the duration of a link, which must be reset
+  // before the linker can be invoked again from the same process
+  CrashRecoveryContext crc;
+  if (!crc.RunSafely([&]() { lld::CommonLinkerContext::destroy(); }))
+    ok = false;
+#else
+  stderrOS << "unable to run '" << args[0]
+           << "': this compiler was built without lld";
+  ok = false;
+#endif
+
+  stdoutOS.flush();
+  stderrOS.flush();
+  *output = diagnostics.empty() ? nullptr : strdup(diagnostics.c_str());
+  return ok;
+}
@@ -410,6 +410,21 @@ impl Module {
         }
     }
 
+    /// Write this module as LLVM bitcode with a module summary, for use in ThinLTO
+    pub fn emit_thin_lto_bc(self, f: &mut std::fs::File) -> anyhow::Result<()> {
+        let fd = util::fs::get_file_descriptor(f);
+        let mut error = MaybeUninit::uninit();
+        let failed =
+            unsafe { LLVMFireflyEmitThinLTOBitcodeToFileDescriptor(self, fd, error.as_mut_ptr()) };
+
+        if failed {
+            let error = unsafe { OwnedStringRef::from_ptr(error.assume_init()) };
+            Err(anyhow!("{}", &error))
+        } else {
+            Ok(())
+        }
+    }
+
     /// Generate textual target-specific assembly from this module using the given TargetMachine, writing it to the given file
     ///
     /// The assembly generated by this function is generally written to files with a `.s` extension.
@@ -714,4 +729,18 @@ extern "C" {
         fd: std::os::windows::io::RawHandle,
         error_message: *mut *mut std::os::raw::c_char,
     ) -> bool;
+
+    #[cfg(not(windows))]
+    pub fn LLVMFireflyEmitThinLTOBitcodeToFileDescriptor(
+        m: Module,
+        fd: std::os::unix::io::RawFd,
+        error_message: *mut *mut std::os::raw::c_char,
+    ) -> bool;
+
+    #[cfg(windows)]
+    pub fn LLVMFireflyEmitThinLTOBitcodeToFileDescriptor(
+        m: Module,
+        fd: std::os::windows::io::RawHandle,
+        error_message: *mut *mut std::os::raw::c_char,
+    ) -> bool;
 }
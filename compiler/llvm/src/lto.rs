@@ -0,0 +1,192 @@
+use std::ffi::CString;
+use std::mem::MaybeUninit;
+use std::os::raw::{c_char, c_uint};
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+
+use firefly_session::{Options, OutputType, ProjectType};
+use firefly_util::fs::path_to_c_string;
+
+use crate::codegen::{self, CodeGenOptLevel};
+use crate::support::OwnedStringRef;
+use crate::target::TargetMachine;
+
+/// A module of bitcode given as input to link-time optimization
+#[repr(C)]
+struct LtoInput {
+    name: *const c_char,
+    data: *const u8,
+    len: usize,
+    preserve: bool,
+}
+
+/// The configuration of a link-time optimization run
+#[repr(C)]
+struct LtoConfig {
+    opt_level: c_uint,
+    executable: bool,
+    emit_ir: bool,
+    preserve_dispatch_table: bool,
+}
+
+/// A function whose dispatch table entry is kept by link-time optimization
+#[repr(C)]
+struct LtoDispatchEntry {
+    module: *const c_char,
+    function: *const c_char,
+    arity: c_uint,
+}
+
+/// Optimizes a set of LLVM bitcode modules as a whole, and generates object code for the result
+///
+/// Modules whose bitcode was written with a module summary (see `Module::emit_thin_lto_bc`) are
+/// optimized using ThinLTO, in which each module is optimized in parallel, after importing the
+/// functions of other modules it would benefit from inlining. All other modules are merged and
+/// optimized as one.
+///
+/// When linking an executable, symbols which are not visible to native code are internalized, so
+/// that those which are unused after optimization are stripped. This includes the entries of the
+/// dispatch table, other than those which are preserved, see `preserve_dispatch_entry`.
+///
+/// If LLVM IR output was requested, the optimized IR of each task is also written alongside its
+/// object file, see `run`.
+pub struct LinkTimeOptimizer<'a> {
+    target_machine: TargetMachine,
+    config: LtoConfig,
+    inputs: Vec<(CString, &'a [u8], bool)>,
+    preserved: Vec<CString>,
+    dispatch: Vec<(CString, CString, u8)>,
+}
+impl<'a> LinkTimeOptimizer<'a> {
+    pub fn new(options: &Options, target_machine: TargetMachine) -> Self {
+        let (speed, _) = codegen::to_llvm_opt_settings(options.opt_level);
+        let opt_level = match speed {
+            CodeGenOptLevel::Less => 1,
+            CodeGenOptLevel::Default => 2,
+            CodeGenOptLevel::Aggressive => 3,
+            _ => 0,
+        };
+        Self {
+            target_machine,
+            config: LtoConfig {
+                opt_level,
+                executable: options.app_type == ProjectType::Executable,
+                emit_ir: options.output_types.contains_key(&OutputType::LLVMAssembly),
+                preserve_dispatch_table: false,
+            },
+            inputs: Vec::new(),
+            preserved: Vec::new(),
+            dispatch: Vec::new(),
+        }
+    }
+
+    /// Adds a module of bitcode to be optimized, named `name` in diagnostics
+    ///
+    /// If `preserve` is true, the symbols defined by the module are always kept visible to
+    /// native code, e.g. because they may be referenced by objects which are not optimized.
+    pub fn add(&mut self, name: &str, bitcode: &'a [u8], preserve: bool) {
+        self.inputs
+            .push((CString::new(name).unwrap(), bitcode, preserve));
+    }
+
+    /// Keeps `symbol` visible to native code, so that it is never stripped
+    pub fn preserve(&mut self, symbol: &str) {
+        self.preserved.push(CString::new(symbol).unwrap());
+    }
+
+    /// Keeps the dispatch table entry of `module:function/arity`, so that the runtime can find
+    /// the function by name
+    pub fn preserve_dispatch_entry(&mut self, module: &str, function: &str, arity: u8) {
+        self.dispatch.push((
+            CString::new(module).unwrap(),
+            CString::new(function).unwrap(),
+            arity,
+        ));
+    }
+
+    /// Keeps every entry of the dispatch table, for when any function may be looked up by name
+    pub fn preserve_dispatch_table(&mut self) {
+        self.config.preserve_dispatch_table = true;
+    }
+
+    /// Runs link-time optimization, writing the resulting object files to `<prefix>.<n>.o`
+    ///
+    /// When LLVM IR output was requested, the optimized IR is written to `<prefix>.<n>.ll`.
+    ///
+    /// Returns the paths of the object files which were written.
+    pub fn run(&self, prefix: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        extern "C" {
+            fn LLVMFireflyRunLTO(
+                target_machine: TargetMachine,
+                config: *const LtoConfig,
+                inputs: *const LtoInput,
+                num_inputs: c_uint,
+                preserved: *const *const c_char,
+                num_preserved: c_uint,
+                dispatch: *const LtoDispatchEntry,
+                num_dispatch: c_uint,
+                output_prefix: *const c_char,
+                num_outputs: *mut c_uint,
+                error: *mut *mut c_char,
+            ) -> bool;
+        }
+
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|(name, bitcode, preserve)| LtoInput {
+                name: name.as_ptr(),
+                data: bitcode.as_ptr(),
+                len: bitcode.len(),
+                preserve: *preserve,
+            })
+            .collect::<Vec<_>>();
+        let preserved = self
+            .preserved
+            .iter()
+            .map(|symbol| symbol.as_ptr())
+            .collect::<Vec<_>>();
+        let dispatch = self
+            .dispatch
+            .iter()
+            .map(|(module, function, arity)| LtoDispatchEntry {
+                module: module.as_ptr(),
+                function: function.as_ptr(),
+                arity: *arity as c_uint,
+            })
+            .collect::<Vec<_>>();
+        let output_prefix = path_to_c_string(prefix);
+
+        let mut num_outputs = 0;
+        let mut error = MaybeUninit::uninit();
+        let failed = unsafe {
+            LLVMFireflyRunLTO(
+                self.target_machine,
+                &self.config,
+                inputs.as_ptr(),
+                inputs.len() as c_uint,
+                preserved.as_ptr(),
+                preserved.len() as c_uint,
+                dispatch.as_ptr(),
+                dispatch.len() as c_uint,
+                output_prefix.as_ptr(),
+                &mut num_outputs,
+                error.as_mut_ptr(),
+            )
+        };
+        if failed {
+            let error = unsafe { OwnedStringRef::from_ptr(error.assume_init()) };
+            return Err(anyhow!("{}", &error));
+        }
+
+        // Not every task produces an object file, e.g. the task which optimizes the merged
+        // modules has nothing to do when all modules are optimized using ThinLTO
+        let prefix = prefix.to_string_lossy();
+        let outputs = (0..num_outputs)
+            .map(|task| PathBuf::from(format!("{}.{}.o", prefix, task)))
+            .filter(|path| path.exists())
+            .collect();
+        Ok(outputs)
+    }
+}
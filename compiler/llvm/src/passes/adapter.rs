@@ -1,5 +1,5 @@
 use firefly_pass::Pass;
-use firefly_session::{Options, Sanitizer};
+use firefly_session::{Lto, Options, Sanitizer};
 
 use crate::codegen;
 use crate::target::TargetMachine;
@@ -21,6 +21,14 @@ impl PassManagerPass {
         manager.verify(options.debugging_opts.verify_llvm_ir);
         manager.debug(options.debug_assertions);
         manager.optimize(opt_level);
+        match options.lto() {
+            Lto::Fat => manager.stage(OptStage::PreLinkFatLTO),
+            Lto::Thin | Lto::ThinLocal => {
+                manager.stage(OptStage::PreLinkThinLTO);
+                manager.thin_lto_buffers(true);
+            }
+            Lto::No => manager.stage(OptStage::PreLinkNoLTO),
+        }
 
         for sanitizer in &options.debugging_opts.sanitizers {
             match sanitizer {
@@ -44,6 +44,11 @@ impl PassManager {
         self.config.opt_stage = stage;
     }
 
+    /// Prepare modules for being written as ThinLTO bitcode
+    pub fn thin_lto_buffers(&mut self, enable: bool) {
+        self.config.use_thinlto_buffers = enable;
+    }
+
     /// Enable the MSan sanitizer
     pub fn sanitize_memory(&mut self, track_origins: bool) {
         self.config.sanitizer_opts.memory = true;
@@ -9,7 +9,8 @@ class Pass;
 
 namespace cir {
 
-std::unique_ptr<OperationPass<ModuleOp>> createInjectYieldPointsPass();
+std::unique_ptr<Pass> createInjectYieldPointsPass();
+std::unique_ptr<Pass> createInjectYieldPointsPass(unsigned reductionBudget);
 std::unique_ptr<OperationPass<ModuleOp>> createConvertCIRToLLVMPass();
 std::unique_ptr<OperationPass<ModuleOp>>
 createConvertCIRToLLVMPass(bool enableNanboxing);
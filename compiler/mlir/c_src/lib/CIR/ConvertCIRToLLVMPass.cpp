@@ -447,7 +447,7 @@ public:
       return dispatchEntryTy;
 
     // Corresponds to FunctionSymbol in firefly_rt
-    // { module: *AtomData, function: *AtomData, arity: u8, fun: *const () }
+    // { module: *AtomData, function: *AtomData, arity: u8, priority: u8, fun: *const () }
     auto atomDataTy = getAtomDataType();
     auto atomDataPtrTy = LLVM::LLVMPointerType::get(atomDataTy);
     auto i8Ty = getI8Type();
@@ -455,9 +455,9 @@ public:
         LLVM::LLVMFunctionType::get(getVoidType(), ArrayRef<Type>{});
     auto opaqueFnPtrTy = LLVM::LLVMPointerType::get(opaqueFnTy);
     auto bitwidth = getPointerBitwidth();
-    auto paddingTy = LLVM::LLVMArrayType::get(i8Ty, (bitwidth / 8) - 1);
+    auto paddingTy = LLVM::LLVMArrayType::get(i8Ty, (bitwidth / 8) - 2);
     assert(succeeded(dispatchEntryTy.setBody(
-        {atomDataPtrTy, atomDataPtrTy, i8Ty, paddingTy, opaqueFnPtrTy},
+        {atomDataPtrTy, atomDataPtrTy, i8Ty, i8Ty, paddingTy, opaqueFnPtrTy},
         /*packed=*/false)));
     return dispatchEntryTy;
   }
@@ -2758,6 +2758,48 @@ struct YieldOpLowering : public ConvertCIROpToLLVMPattern<cir::YieldOp> {
   }
 };
 
+//===------------===//
+// ReduceOp
+//===------------===//
+struct ReduceOpLowering : public ConvertCIROpToLLVMPattern<cir::ReduceOp> {
+  using ConvertCIROpToLLVMPattern<cir::ReduceOp>::ConvertCIROpToLLVMPattern;
+
+  LogicalResult
+  matchAndRewrite(cir::ReduceOp op, OpAdaptor adaptor,
+                  ConversionPatternRewriter &rewriter) const override {
+    auto loc = op.getLoc();
+    auto module = op->getParentOfType<ModuleOp>();
+    auto i32Ty = rewriter.getI32Type();
+
+    auto reductions =
+        module.lookupSymbol<LLVM::GlobalOp>("__firefly_process_reductions");
+    if (!reductions)
+      reductions = insertReductionCountThreadLocal(rewriter, loc, module);
+
+    // Consume a single reduction
+    Value reductionsPtr = rewriter.create<LLVM::AddressOfOp>(loc, reductions);
+    Value count = rewriter.create<LLVM::LoadOp>(loc, reductionsPtr);
+    Value one = rewriter.create<LLVM::ConstantOp>(
+        loc, i32Ty, rewriter.getI32IntegerAttr(1));
+    Value newCount = rewriter.create<LLVM::AddOp>(loc, count, one);
+    rewriter.create<LLVM::StoreOp>(loc, newCount, reductionsPtr);
+
+    // If the budget has been exhausted, yield to the scheduler, which will
+    // reset the count when the process is scheduled again
+    Value budget = rewriter.create<LLVM::ConstantOp>(
+        loc, i32Ty, rewriter.getI32IntegerAttr(op.getBudget()));
+    Value exhausted = rewriter.create<LLVM::ICmpOp>(
+        loc, LLVM::ICmpPredicate::uge, newCount, budget);
+    rewriter.create<scf::IfOp>(loc, TypeRange(), exhausted,
+                               [&](OpBuilder &builder, Location l) {
+                                 builder.create<cir::YieldOp>(l);
+                                 builder.create<scf::YieldOp>(l);
+                               });
+    rewriter.eraseOp(op);
+    return success();
+  }
+};
+
 //===------------===//
 // RecvStartOp
 //===------------===//
@@ -2791,7 +2833,7 @@ struct RecvNextOpLowering : public ConvertCIROpToLLVMPattern<cir::RecvNextOp> {
     auto context = adaptor.context();
 
     rewriter.replaceOpWithNewOp<LLVM::CallOp>(op, TypeRange({i8Ty}),
-                                              "__firefly_builtin_receive_start",
+                                              "__firefly_builtin_receive_next",
                                               ValueRange({context}));
     return success();
   }
@@ -2898,6 +2940,7 @@ struct DispatchTableOpLowering
       auto loc = entryOp->getLoc();
       auto function = entryOp.getFunction();
       auto arity = entryOp.getArity();
+      auto priority = entryOp.getPriorityOrDefault();
       auto symbol = entryOp.getSymbol();
 
       llvm::SHA1 hasher;
@@ -2944,6 +2987,12 @@ struct DispatchTableOpLowering
       entry = rewriter.create<LLVM::InsertValueOp>(loc, entry, arityVal,
                                                    rewriter.getI64ArrayAttr(2));
 
+      // Store the on-load priority
+      auto priorityVal = rewriter.create<LLVM::ConstantOp>(
+          loc, i8ty, rewriter.getI8IntegerAttr(priority));
+      entry = rewriter.create<LLVM::InsertValueOp>(loc, entry, priorityVal,
+                                                   rewriter.getI64ArrayAttr(3));
+
       // Get the LLVM type of the function referenced by the symbol
       Operation *fun = mod.lookupSymbol(symbol.getValue());
       Type funTy;
@@ -2961,7 +3010,7 @@ struct DispatchTableOpLowering
           rewriter.create<LLVM::BitcastOp>(loc, opaqueFunPtrTy, funPtr);
       // Store the function pointer
       entry = rewriter.create<LLVM::InsertValueOp>(loc, entry, opaqueFunPtr,
-                                                   rewriter.getI64ArrayAttr(4));
+                                                   rewriter.getI64ArrayAttr(5));
 
       rewriter.create<LLVM::ReturnOp>(loc, entry);
     }
@@ -3359,6 +3408,7 @@ void ConvertCIRToLLVMPass::runOnOperation() {
   patterns.add<ExceptionReasonOpLowering>(typeConverter);
   patterns.add<ExceptionTraceOpLowering>(typeConverter);
   patterns.add<YieldOpLowering>(typeConverter);
+  patterns.add<ReduceOpLowering>(typeConverter);
   patterns.add<RecvStartOpLowering>(typeConverter);
   patterns.add<RecvNextOpLowering>(typeConverter);
   patterns.add<RecvPeekOpLowering>(typeConverter);
@@ -0,0 +1,72 @@
+#include "CIR/Dialect.h"
+#include "CIR/Ops.h"
+#include "mlir/IR/Dominance.h"
+#include "mlir/IR/FunctionInterfaces.h"
+#include "mlir/Pass/Pass.h"
+#include "llvm/ADT/SmallVector.h"
+
+#include "PassDetail.h"
+
+using namespace mlir;
+using namespace mlir::cir;
+
+//===----------------------------------------------------------------------===//
+// InjectYieldsPass
+//
+// Reductions are consumed on entry to a function and on each loop back-edge, as
+// those are the only points at which a process can execute indefinitely without
+// passing through another reduction. Since Erlang has no looping constructs of
+// its own, back-edges only arise from lowerings such as receive and binary
+// matching, so the cost of a reduction is effectively that of a function call,
+// as it is in BEAM.
+//===----------------------------------------------------------------------===//
+namespace {
+struct InjectYieldsPass : public InjectYieldsBase<InjectYieldsPass> {
+  InjectYieldsPass() = default;
+  InjectYieldsPass(unsigned budget) { this->reductionBudget = budget; }
+
+  void runOnOperation() override;
+};
+} // namespace
+
+void InjectYieldsPass::runOnOperation() {
+  FunctionOpInterface func = getOperation();
+  if (func.isExternal())
+    return;
+
+  Region &body = func.getBody();
+  auto &dominance = getAnalysis<DominanceInfo>();
+  auto loc = func.getLoc();
+  OpBuilder builder(func.getContext());
+
+  // Consume a reduction on entry
+  builder.setInsertionPointToStart(&body.front());
+  builder.create<cir::ReduceOp>(loc, reductionBudget.getValue());
+
+  // Consume a reduction before every branch to a block which dominates the
+  // branching block, i.e. each loop back-edge
+  SmallVector<Operation *, 4> backEdges;
+  for (Block &block : body) {
+    Operation *terminator = block.getTerminator();
+    for (Block *successor : terminator->getSuccessors()) {
+      if (dominance.dominates(successor, &block)) {
+        backEdges.push_back(terminator);
+        break;
+      }
+    }
+  }
+  for (Operation *terminator : backEdges) {
+    builder.setInsertionPoint(terminator);
+    builder.create<cir::ReduceOp>(terminator->getLoc(),
+                                  reductionBudget.getValue());
+  }
+}
+
+std::unique_ptr<Pass> mlir::cir::createInjectYieldPointsPass() {
+  return std::make_unique<InjectYieldsPass>();
+}
+
+std::unique_ptr<Pass>
+mlir::cir::createInjectYieldPointsPass(unsigned reductionBudget) {
+  return std::make_unique<InjectYieldsPass>(reductionBudget);
+}
@@ -2236,6 +2236,29 @@ impl DispatchTableOp {
         function: StringAttr,
         arity: IntegerAttr,
         symbol: FlatSymbolRefAttr,
+    ) {
+        self.append_entry(loc, function, arity, symbol, None)
+    }
+
+    /// Appends the entry for the `-on_load` function of the module, which is run in order of `priority`
+    pub fn append_on_load(
+        &self,
+        loc: Location,
+        function: StringAttr,
+        arity: IntegerAttr,
+        symbol: FlatSymbolRefAttr,
+        priority: IntegerAttr,
+    ) {
+        self.append_entry(loc, function, arity, symbol, Some(priority))
+    }
+
+    fn append_entry(
+        &self,
+        loc: Location,
+        function: StringAttr,
+        arity: IntegerAttr,
+        symbol: FlatSymbolRefAttr,
+        priority: Option<IntegerAttr>,
     ) {
         extern "C" {
             fn mlirCirDispatchTableAppendEntry(op: OperationBase, entry: OperationBase);
@@ -2247,6 +2270,10 @@ impl DispatchTableOp {
         let arity = NamedAttribute::get(StringAttr::get(context, "arity"), arity);
         let symbol = NamedAttribute::get(StringAttr::get(context, "symbol"), symbol);
         state.add_attributes(&[function, arity, symbol]);
+        if let Some(priority) = priority {
+            let priority = NamedAttribute::get(StringAttr::get(context, "priority"), priority);
+            state.add_attributes(&[priority]);
+        }
         let entry = state.create().release();
 
         unsafe {
@@ -22,6 +22,7 @@ pub use self::support::{LogicalResult, OwnedStringRef, StringRef};
 ///
 /// * Registers all MLIR-specific command-line options provided to the compiler
 /// * Registers all MLIR built-in passes
+/// * Registers the passes of the CIR dialect
 ///
 /// NOTE: It is important this is called before invoking any MLIR APIs, as it
 /// guarantees that MLIR is properly configured. Without this, MLIR may behave
@@ -36,6 +37,8 @@ pub fn init(_options: &firefly_session::Options) -> anyhow::Result<()> {
         register_mlir_cli_options();
     }
     pass::register_all_passes();
+    conversions::ConvertCIRToLLVMPass::register();
+    conversions::InjectYieldsPass::register();
 
     Ok(())
 }
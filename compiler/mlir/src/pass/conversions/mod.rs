@@ -86,6 +86,8 @@ macro_rules! module_conversion_pass_impl {
 }
 
 namespaced_module_pass_impl!(CIR, ConvertCIRToLLVM, convert_cir_to_llvm);
+namespaced_pass_impl!(CIR, InjectYields, inject_yields);
+impl crate::pass::OpPass<crate::FuncOp> for InjectYieldsPass {}
 
 conversion_pass_impl!(ConvertAffineToStandard, convert_affine_to_standard);
 conversion_pass_impl!(ConvertArithmeticToLLVM, convert_arithmetic_to_llvm);
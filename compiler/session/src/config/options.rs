@@ -78,6 +78,8 @@ pub struct Options {
     pub include_path: VecDeque<PathBuf>,
     pub link_libraries: Vec<(String, Option<String>, NativeLibraryKind)>,
     pub defines: HashMap<String, Option<String>>,
+    /// The defines given with `-D`, in the order they were given
+    pub cli_defines: Vec<(String, Option<String>)>,
 
     pub cli_forced_thinlto_off: bool,
 }
@@ -227,13 +229,14 @@ impl Options {
 
         let output_file = args.value_of_os("output").map(PathBuf::from);
         let output_dir = args.value_of_os("output-dir").map(PathBuf::from);
+        let mut cli_defines = vec![];
         if let Some(values) = args.values_of("define") {
             for value in values {
                 let define = self::parse_key_value(value)?;
-                defines.insert(
-                    define.name().to_string(),
-                    define.value().map(|s| s.to_string()),
-                );
+                let name = define.name().to_string();
+                let value = define.value().map(|s| s.to_string());
+                defines.insert(name.clone(), value.clone());
+                cli_defines.push((name, value));
             }
         }
         let (warnings_as_errors, no_warn) = match args.value_of("warn") {
@@ -281,6 +284,7 @@ impl Options {
             include_path,
             link_libraries,
             defines,
+            cli_defines,
             cli_forced_thinlto_off: false,
         })
     }
@@ -359,6 +363,7 @@ impl Options {
             include_path: Default::default(),
             link_libraries: Default::default(),
             defines,
+            cli_defines: vec![],
             cli_forced_thinlto_off: false,
         })
     }
@@ -384,11 +389,17 @@ impl Options {
     }
 
     pub fn lto(&self) -> Lto {
+        // Link-time optimization is only performed when linking an executable or dynamic library
+        if !self.should_link() || !self.app_type.requires_link() {
+            return Lto::No;
+        }
+
         match self.codegen_opts.lto {
             LtoCli::No => Lto::No,
             LtoCli::Yes => Lto::Fat,
             LtoCli::Thin => Lto::Thin,
             LtoCli::Fat => Lto::Fat,
+            LtoCli::Unspecified if self.codegen_opts.thinlto == Some(true) => Lto::Thin,
             LtoCli::Unspecified => Lto::No,
         }
     }
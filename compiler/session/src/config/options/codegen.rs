@@ -67,9 +67,9 @@ pub struct CodegenOptions {
     #[option(default_value("true"))]
     /// Link native libraries in the linker invocation
     pub link_native_libraries: bool,
-    #[option(hidden(true))]
-    /// Control whether to link Rust provided C objects/libraries or rely on
-    /// C toolchain installed on the system
+    #[option]
+    /// Link with the lld and C objects/libraries bundled with the compiler,
+    /// rather than relying on a C toolchain installed on the system
     pub link_self_contained: Option<bool>,
     #[option(value_name("PATH"), takes_value(true))]
     /// The system linker to link with
@@ -94,13 +94,14 @@ pub struct CodegenOptions {
     #[option(value_name("ARGS"), takes_value(true), requires_delimiter(true))]
     /// Extra arguments to pass through to LLVM (comma separated list)
     pub llvm_args: Vec<String>,
-    #[option(
-        takes_value(true),
-        hidden(true),
-        possible_values("no", "yes", "thin", "fat")
-    )]
-    /// Perform link-time optimization
+    #[option(takes_value(true), possible_values("no", "yes", "thin", "fat"))]
+    /// Perform link-time optimization across all modules of an executable or
+    /// dynamic library, either as a single unit ('fat'), or in parallel ('thin')
     pub lto: LtoCli,
+    #[option]
+    /// Include the runtime in link-time optimization, this requires that the
+    /// runtime library was built with LLVM bitcode, e.g. `-C linker-plugin-lto`
+    pub lto_runtime: bool,
     #[option(
         takes_value(true),
         possible_values("disabled", "trampolines", "aliases"),
@@ -156,6 +157,10 @@ pub struct CodegenOptions {
     #[option]
     /// Prefer dynamic linking to static linking
     pub prefer_dynamic: bool,
+    #[option(value_name("N"), takes_value(true))]
+    /// Set the number of reductions a process may execute before it is preempted
+    /// (default 4000), or 0 to disable preemption
+    pub reductions: Option<u64>,
     #[option(value_name("MODEL"), takes_value(true), hidden(true))]
     /// Choose the relocation model to use
     pub relocation_model: Option<RelocModel>,
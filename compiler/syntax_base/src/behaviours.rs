@@ -0,0 +1,119 @@
+///! This module contains the callbacks of the behaviours defined by OTP
+///!
+///! The modules defining these behaviours are not part of the applications we compile, so unless
+///! their source is found in the include path, these are used to check the modules implementing them.
+use std::collections::{BTreeMap, BTreeSet};
+
+use firefly_intern::Symbol;
+use lazy_static::lazy_static;
+
+use crate::FunctionName;
+
+/// The callbacks required of the modules implementing a behaviour
+#[derive(Debug, Copy, Clone)]
+pub struct Behaviour<'a> {
+    pub callbacks: &'a BTreeSet<FunctionName>,
+    pub optional_callbacks: &'a BTreeSet<FunctionName>,
+}
+
+struct Callbacks {
+    callbacks: BTreeSet<FunctionName>,
+    optional_callbacks: BTreeSet<FunctionName>,
+}
+impl Callbacks {
+    fn new(callbacks: &[(&str, u8)], optional_callbacks: &[(&str, u8)]) -> Self {
+        let to_set = |names: &[(&str, u8)]| {
+            names
+                .iter()
+                .map(|(name, arity)| FunctionName::new_local(Symbol::intern(name), *arity))
+                .collect()
+        };
+        Self {
+            callbacks: to_set(callbacks),
+            optional_callbacks: to_set(optional_callbacks),
+        }
+    }
+}
+
+lazy_static! {
+    static ref OTP_BEHAVIOURS: BTreeMap<Symbol, Callbacks> = {
+        let mut behaviours = BTreeMap::new();
+        behaviours.insert(
+            Symbol::intern("application"),
+            Callbacks::new(
+                &[("start", 2), ("stop", 1)],
+                &[("prep_stop", 1), ("start_phase", 3), ("config_change", 3)],
+            ),
+        );
+        behaviours.insert(
+            Symbol::intern("gen_event"),
+            Callbacks::new(
+                &[("init", 1), ("handle_event", 2), ("handle_call", 2)],
+                &[
+                    ("handle_info", 2),
+                    ("terminate", 2),
+                    ("code_change", 3),
+                    ("format_status", 1),
+                    ("format_status", 2),
+                ],
+            ),
+        );
+        behaviours.insert(
+            Symbol::intern("gen_fsm"),
+            Callbacks::new(
+                &[("init", 1), ("handle_event", 3), ("handle_sync_event", 4)],
+                &[
+                    ("handle_info", 3),
+                    ("terminate", 3),
+                    ("code_change", 4),
+                    ("format_status", 2),
+                ],
+            ),
+        );
+        behaviours.insert(
+            Symbol::intern("gen_server"),
+            Callbacks::new(
+                &[("init", 1), ("handle_call", 3), ("handle_cast", 2)],
+                &[
+                    ("handle_info", 2),
+                    ("handle_continue", 2),
+                    ("terminate", 2),
+                    ("code_change", 3),
+                    ("format_status", 1),
+                    ("format_status", 2),
+                ],
+            ),
+        );
+        behaviours.insert(
+            Symbol::intern("gen_statem"),
+            Callbacks::new(
+                &[("init", 1), ("callback_mode", 0)],
+                &[
+                    ("state_name", 3),
+                    ("handle_event", 4),
+                    ("terminate", 3),
+                    ("code_change", 4),
+                    ("format_status", 1),
+                    ("format_status", 2),
+                ],
+            ),
+        );
+        behaviours.insert(
+            Symbol::intern("supervisor"),
+            Callbacks::new(&[("init", 1)], &[]),
+        );
+        behaviours.insert(
+            Symbol::intern("supervisor_bridge"),
+            Callbacks::new(&[("init", 1), ("terminate", 2)], &[]),
+        );
+        behaviours
+    };
+}
+
+/// Get the callbacks of the OTP behaviour with the given name, if it is one
+pub fn get(name: Symbol) -> Option<Behaviour<'static>> {
+    OTP_BEHAVIOURS.get(&name).map(|cbs| Behaviour {
+        callbacks: &cbs.callbacks,
+        optional_callbacks: &cbs.optional_callbacks,
+    })
+}
@@ -75,7 +75,10 @@ lazy_static! {
             guard_bif!(pub erlang:bit_size/1(bitstring) -> non_neg_integer),
             bif!(pub erlang:bitstring_to_list/1(bitstring) -> list),
             guard_bif!(pub erlang:byte_size/1(bitstring) -> non_neg_integer),
+            bif!(pub erlang:cancel_timer/1(reference) -> term),
+            bif!(pub erlang:cancel_timer/2(reference, list) -> term),
             guard_bif!(pub erlang:ceil/1(number) -> integer),
+            bif!(pub erlang:convert_time_unit/3(integer, term, term) -> integer),
             bif!(pub erlang:date/0() -> tuple),
             bif!(pub erlang:demonitor/1(reference) -> boolean),
             bif!(pub erlang:demonitor/2(reference, list) -> boolean),
@@ -157,6 +160,8 @@ lazy_static! {
             bif!(pub erlang:monitor/3(atom, term, list) -> reference),
             bif!(pub erlang:monitor_node/2(node, boolean) -> boolean),
             bif!(pub erlang:monitor_node/3(node, boolean, list) -> boolean),
+            bif!(pub erlang:monotonic_time/0() -> integer),
+            bif!(pub erlang:monotonic_time/1(term) -> integer),
             guard_bif!(pub erlang:node/0() -> node),
             guard_bif!(pub erlang:node/1(term) -> node),
             bif!(pub erlang:nodes/0() -> list),
@@ -178,12 +183,17 @@ lazy_static! {
             bif!(pub erlang:put/2(term, term) -> term),
             bif!(pub erlang:raise/2(any, trace) -> term),
             bif!(pub erlang:raise/3(atom, any, list) -> term),
+            bif!(pub erlang:read_timer/1(reference) -> term),
+            bif!(pub erlang:read_timer/2(reference, list) -> term),
             bif!(pub erlang:ref_to_list/1(reference) -> string),
             bif!(pub erlang:register/2(atom, term) -> boolean),
             bif!(pub erlang:registered/0() -> list),
             guard_bif!(pub erlang:round/1(number) -> integer),
             bif!(pub erlang:setelement/3(pos_integer, tuple, term) -> tuple),
             guard_bif!(pub erlang:self/0() -> pid),
+            bif!(pub erlang:send/2(term, term) -> term),
+            bif!(pub erlang:send_after/3(non_neg_integer, term, term) -> reference),
+            bif!(pub erlang:send_after/4(integer, term, term, list) -> reference),
             guard_bif!(pub erlang:size/1(term) -> non_neg_integer),
             bif!(pub erlang:spawn/1(function) -> pid),
             bif!(pub erlang:spawn/2(node, function) -> pid),
@@ -209,13 +219,20 @@ lazy_static! {
             bif!(pub erlang:spawn_request/5(node, module, atom, list, list) -> reference),
             bif!(pub erlang:spawn_request_abandon/1(reference) -> boolean),
             bif!(pub erlang:split_binary/2(binary, non_neg_integer) -> binary_split),
+            bif!(pub erlang:start_timer/3(non_neg_integer, term, term) -> reference),
+            bif!(pub erlang:start_timer/4(integer, term, term, list) -> reference),
             bif!(pub erlang:statistics/1(atom) -> term),
+            bif!(pub erlang:system_flag/2(atom, term) -> term),
+            bif!(pub erlang:system_time/0() -> integer),
+            bif!(pub erlang:system_time/1(term) -> integer),
             bif!(pub erlang:term_to_binary/1(term) -> binary),
             bif!(pub erlang:term_to_binary/2(term, list) -> binary),
             bif!(pub erlang:term_to_iovec/1(term) -> list),
             bif!(pub erlang:term_to_iovec/2(term, list) -> list),
             bif!(pub erlang:throw/1(any) -> term),
             bif!(pub erlang:time/0() -> time),
+            bif!(pub erlang:time_offset/0() -> integer),
+            bif!(pub erlang:time_offset/1(term) -> integer),
             guard_bif!(pub erlang:tl/1(nonempty_maybe_improper_list) -> term),
             guard_bif!(pub erlang:trunc/1(number) -> integer),
             guard_bif!(pub erlang:tuple_size/1(tuple) -> non_neg_integer),
@@ -4,6 +4,7 @@ mod macros;
 pub use self::macros::*;
 
 mod annotations;
+pub mod behaviours;
 pub mod bifs;
 mod deprecations;
 mod functions;
@@ -26,15 +27,37 @@ pub use self::var::Var;
 use std::collections::{BTreeMap, BTreeSet, HashSet};
 
 use firefly_diagnostics::{SourceSpan, Span};
-use firefly_intern::{Ident, Symbol};
+use firefly_intern::{symbols, Ident, Symbol};
 
 /// This structure contains metadata representing an OTP application gathered during parsing and semantic analysis.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ApplicationMetadata {
     pub name: Symbol,
     pub modules: BTreeMap<Symbol, ModuleMetadata>,
+    /// Metadata for modules outside of this application which were loaded from the include path,
+    /// e.g. the modules defining behaviours implemented by modules of this application
+    pub dependencies: BTreeMap<Symbol, ModuleMetadata>,
 }
 impl ApplicationMetadata {
+    /// Returns the metadata for the given module, if it belongs to this application or is a known dependency
+    pub fn get_module(&self, name: Symbol) -> Option<&ModuleMetadata> {
+        self.modules
+            .get(&name)
+            .or_else(|| self.dependencies.get(&name))
+    }
+
+    /// Returns the callbacks of the given behaviour, if it is defined by a module of this application or a known
+    /// dependency, or is one of the behaviours defined by OTP
+    pub fn get_behaviour(&self, name: Symbol) -> Option<behaviours::Behaviour<'_>> {
+        match self.get_module(name) {
+            Some(module) => Some(behaviours::Behaviour {
+                callbacks: &module.callbacks,
+                optional_callbacks: &module.optional_callbacks,
+            }),
+            None => behaviours::get(name),
+        }
+    }
+
     /// Returns the deprecation associated with the given module name, if one was declared
     pub fn get_module_deprecation(&self, name: &Symbol) -> Option<Deprecation> {
         self.modules.get(name).and_then(|m| m.deprecation)
@@ -54,6 +77,38 @@ impl ApplicationMetadata {
             self.get_module_deprecation(&module_name)
         }
     }
+
+    /// Returns the priority of the `-on_load` function of the given module, relative to other modules in this application
+    ///
+    /// On-load functions must run after those of the modules they call, so modules with a lower priority are loaded
+    /// first. A module which calls no other module with an on-load function has priority 0, otherwise its priority is
+    /// one more than the highest priority of those modules. Cyclic dependencies are ignored.
+    pub fn on_load_priority(&self, name: Symbol) -> usize {
+        self.on_load_priority_of(name, &mut BTreeSet::new())
+    }
+
+    fn on_load_priority_of(&self, name: Symbol, visiting: &mut BTreeSet<Symbol>) -> usize {
+        let Some(module) = self.modules.get(&name) else { return 0; };
+        if !visiting.insert(name) {
+            return 0;
+        }
+        let mut priority = 0;
+        for dep in module.on_load_dependencies.iter().copied() {
+            if visiting.contains(&dep) {
+                continue;
+            }
+            let has_on_load = self
+                .modules
+                .get(&dep)
+                .map(|m| m.on_load.is_some())
+                .unwrap_or(false);
+            if has_on_load {
+                priority = priority.max(self.on_load_priority_of(dep, visiting) + 1);
+            }
+        }
+        visiting.remove(&name);
+        priority
+    }
 }
 
 /// This structure contains metadata about a module gathered during initial parsing and semantic analysis,
@@ -65,6 +120,33 @@ pub struct ModuleMetadata {
     pub exports: BTreeSet<Span<FunctionName>>,
     pub deprecation: Option<Deprecation>,
     pub deprecations: BTreeMap<FunctionName, Deprecation>,
+    /// The function declared with `-on_load`, if present
+    pub on_load: Option<FunctionName>,
+    /// The modules referenced by the `-on_load` function, whose own on-load functions must run first
+    pub on_load_dependencies: BTreeSet<Symbol>,
+    /// The behaviours implemented by this module
+    pub behaviours: BTreeSet<Symbol>,
+    /// The callbacks required of modules implementing this module as a behaviour
+    pub callbacks: BTreeSet<FunctionName>,
+    /// The callbacks which modules implementing this module as a behaviour may omit
+    pub optional_callbacks: BTreeSet<FunctionName>,
+    /// The functions of other modules which are called or referenced by this module
+    pub calls: BTreeSet<FunctionName>,
+    /// The functions of other modules which are referenced by this module as funs, e.g. `fun m:f/1`
+    pub funs: BTreeSet<FunctionName>,
+}
+impl ModuleMetadata {
+    /// Returns true if this module exports the given function
+    ///
+    /// The `module_info/0,1` functions are always considered exported, as they are defined implicitly
+    pub fn is_exported(&self, name: &FunctionName) -> bool {
+        let local_name = name.to_local();
+        if local_name.function == symbols::ModuleInfo && local_name.arity < 2 {
+            return true;
+        }
+        self.exports
+            .contains(&Span::new(SourceSpan::default(), local_name))
+    }
 }
 
 /// This structure holds module-specific compiler options and configuration; it is passed through all phases of
@@ -115,9 +197,19 @@ pub struct CompileOptions {
     pub no_warn_deprecated_functions: HashSet<Span<FunctionName>>,
     pub warn_deprecated_type: bool,
     pub warn_obsolete_guard: bool,
+    // Warns about calls to functions not exported by a known module
+    pub warn_undefined_function: bool,
+    // Warns about calls to modules not known to the compiler
+    pub warn_unknown_module: bool,
+    // Warns about exported functions which are not called by any other module in the application
+    pub warn_unused_export: bool,
     pub inline: bool,
     // Inlines the given functions
     pub inline_functions: HashSet<Span<FunctionName>>,
+    // The maximum size of a function which will be inlined when `inline` is set
+    pub inline_size: usize,
+    // The maximum amount of work the inliner may perform at a single call site
+    pub inline_effort: usize,
 }
 impl Default for CompileOptions {
     fn default() -> Self {
@@ -133,6 +225,8 @@ impl Default for CompileOptions {
             no_auto_imports: HashSet::new(),
             inline: false,
             inline_functions: HashSet::new(),
+            inline_size: 24,
+            inline_effort: 150,
 
             // Warning toggles
             warn_export_all: true,
@@ -154,6 +248,9 @@ impl Default for CompileOptions {
             no_warn_deprecated_functions: HashSet::new(),
             warn_deprecated_type: true,
             warn_obsolete_guard: true,
+            warn_undefined_function: true,
+            warn_unknown_module: false,
+            warn_unused_export: false,
         }
     }
 }
@@ -214,6 +214,112 @@ impl TermType {
         }
     }
 
+    /// Returns true if every value of this type is also a value of `other`
+    pub fn is_subtype_of(&self, other: &Self) -> bool {
+        match (self, other) {
+            (_, Self::Any) => true,
+            (Self::Bool, Self::Atom) => true,
+            (Self::Integer | Self::Float, Self::Number) => true,
+            (Self::Binary, Self::Bitstring) => true,
+            (Self::Nil, Self::List(_)) => true,
+            (Self::Nil | Self::Cons | Self::List(_), Self::MaybeImproperList) => true,
+            (Self::List(_), Self::List(None)) => true,
+            (Self::List(Some(a)), Self::List(Some(b))) => a.is_subtype_of(b),
+            (Self::Tuple(_), Self::Tuple(None)) => true,
+            (Self::Tuple(Some(a)), Self::Tuple(Some(b))) => {
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| a.is_subtype_of(b))
+            }
+            (Self::Fun(_), Self::Fun(None)) => true,
+            (a, b) => a == b,
+        }
+    }
+
+    /// Returns true if no value can be of both this type and `other`
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        if self.is_subtype_of(other) || other.is_subtype_of(self) {
+            return false;
+        }
+        match (self, other) {
+            (Self::Tuple(Some(a)), Self::Tuple(Some(b))) => {
+                a.len() != b.len() || a.iter().zip(b.iter()).any(|(a, b)| a.is_disjoint(b))
+            }
+            // Both types contain the empty list
+            (Self::List(_), Self::List(_)) => false,
+            (Self::Cons, Self::List(_)) | (Self::List(_), Self::Cons) => false,
+            (Self::Integer, Self::Float) | (Self::Float, Self::Integer) => true,
+            (Self::Nil, Self::Cons) | (Self::Cons, Self::Nil) => true,
+            (a, b) => a.kind() != b.kind(),
+        }
+    }
+
+    /// Returns the most precise type which contains every value of both this type and `other`
+    pub fn union(&self, other: &Self) -> Self {
+        if self.is_subtype_of(other) {
+            return other.clone();
+        }
+        if other.is_subtype_of(self) {
+            return self.clone();
+        }
+        match (self, other) {
+            (Self::List(Some(a)), Self::List(Some(b))) => Self::List(Some(Box::new(a.union(b)))),
+            (Self::Tuple(Some(a)), Self::Tuple(Some(b))) if a.len() == b.len() => Self::Tuple(
+                Some(a.iter().zip(b.iter()).map(|(a, b)| a.union(b)).collect()),
+            ),
+            (a, b) => match a.kind() {
+                kind if kind != b.kind() => Self::Any,
+                TypeKind::Number => Self::Number,
+                TypeKind::Atom => Self::Atom,
+                TypeKind::Bitstring => Self::Bitstring,
+                TypeKind::List => Self::MaybeImproperList,
+                TypeKind::Tuple => Self::Tuple(None),
+                TypeKind::Fun => Self::Fun(None),
+                _ => Self::Any,
+            },
+        }
+    }
+
+    /// Returns the type of the values which pass a runtime type test against this type
+    pub fn tested_type(&self) -> Self {
+        match self {
+            // List type tests accept improper lists
+            Self::List(_) => Self::MaybeImproperList,
+            // Tuple type tests only check the arity
+            Self::Tuple(Some(elements)) => Self::Tuple(Some(vec![Self::Any; elements.len()])),
+            other => other.clone(),
+        }
+    }
+
+    /// Returns the outcome of a runtime type test against `test` for a value of this type,
+    /// or `None` if the outcome can only be known at runtime
+    pub fn test_outcome(&self, test: &Self) -> Option<bool> {
+        let test = test.tested_type();
+        if self.is_subtype_of(&test) {
+            Some(true)
+        } else if self.is_disjoint(&test) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// The broad category of values to which this type belongs, values of types
+    /// in different categories are always distinct.
+    fn kind(&self) -> TypeKind {
+        match self {
+            Self::Any => TypeKind::Any,
+            Self::Integer | Self::Float | Self::Number => TypeKind::Number,
+            Self::Bool | Self::Atom => TypeKind::Atom,
+            Self::Bitstring | Self::Binary => TypeKind::Bitstring,
+            Self::Nil | Self::Cons | Self::List(_) | Self::MaybeImproperList => TypeKind::List,
+            Self::Tuple(_) => TypeKind::Tuple,
+            Self::Map => TypeKind::Map,
+            Self::Reference => TypeKind::Reference,
+            Self::Port => TypeKind::Port,
+            Self::Pid => TypeKind::Pid,
+            Self::Fun(_) => TypeKind::Fun,
+        }
+    }
+
     /// If we have to coerce this to the most precise numeric type we can, what type would that be?
     pub fn coerce_to_numeric(&self) -> Self {
         match self {
@@ -239,6 +345,22 @@ impl TermType {
         }
     }
 }
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum TypeKind {
+    Any,
+    Number,
+    Atom,
+    Bitstring,
+    List,
+    Tuple,
+    Map,
+    Reference,
+    Port,
+    Pid,
+    Fun,
+}
+
 impl fmt::Display for TermType {
     /// Print this type for display using the provided module context
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -819,6 +819,10 @@ pub struct IGen {
     // tail_pat is the tail pattern, respectively [] and <<_/bitstring>> for list
     // and bit string generators.
     pub tail_pattern: Box<IExpr>,
+    // refill is the pattern, guards and action used to fetch the next element of
+    // the generator input when it is matched by none of the other patterns, this
+    // is only used by map generators, to step the map iterator.
+    pub refill: Option<(Var, Vec<IExpr>, Box<IExpr>)>,
     // pre is the list of expressions to be inserted before the comprehension function
     pub pre: Vec<IExpr>,
     // arg is the expression that the comprehension function should be passed
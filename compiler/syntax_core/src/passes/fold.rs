@@ -0,0 +1,900 @@
+///! This pass performs constant folding and simplification of Core IR, in the spirit of
+///! `sys_core_fold` from the reference implementation.
+///!
+///! The following transformations are performed, bottom-up:
+///!
+///! * Calls to pure BIFs (i.e. guard BIFs) whose arguments are all literals are evaluated
+///! * Literals bound by `let` are propagated to their uses
+///! * `let` and `do` expressions whose result is unused, and whose argument has no side
+///! effects, are removed
+///! * `case` clauses which can never match, either because the guard is always false or the
+///! patterns cannot match the argument, or which follow a clause that always matches, are removed
+///! * `case` expressions on a known constructor are replaced with the body of the matching clause
+///! * `if` expressions on a literal boolean are replaced with the selected branch
+///!
+///! Calls which would raise at runtime, e.g. `1 + foo`, are left as-is, so that the error is
+///! raised where the user expects it.
+use std::cmp::Ordering;
+
+use firefly_binary::Bitstring as _;
+use firefly_intern::{symbols, Symbol};
+use firefly_number::{Integer, Number};
+use firefly_pass::Pass;
+use firefly_syntax_base::*;
+
+use crate::*;
+
+#[derive(Default)]
+pub struct FoldConstants;
+impl FoldConstants {
+    pub fn new() -> Self {
+        Self
+    }
+}
+impl Pass for FoldConstants {
+    type Input<'a> = Fun;
+    type Output<'a> = Fun;
+
+    fn run<'a>(&mut self, mut fun: Self::Input<'a>) -> anyhow::Result<Self::Output<'a>> {
+        fun.body = self.fold_boxed(fun.body);
+        Ok(fun)
+    }
+}
+
+impl FoldConstants {
+    fn fold_boxed(&mut self, expr: Box<Expr>) -> Box<Expr> {
+        Box::new(self.fold(*expr))
+    }
+
+    fn fold_all(&mut self, exprs: Vec<Expr>) -> Vec<Expr> {
+        exprs.into_iter().map(|expr| self.fold(expr)).collect()
+    }
+
+    fn fold_clauses(&mut self, clauses: Vec<Clause>) -> Vec<Clause> {
+        clauses
+            .into_iter()
+            .map(|mut clause| {
+                clause.guard = clause.guard.map(|guard| self.fold_boxed(guard));
+                clause.body = self.fold_boxed(clause.body);
+                clause
+            })
+            .collect()
+    }
+
+    fn fold(&mut self, expr: Expr) -> Expr {
+        match expr {
+            expr @ (Expr::Alias(_) | Expr::Literal(_) | Expr::Var(_)) => expr,
+            Expr::Apply(mut apply) => {
+                apply.callee = self.fold_boxed(apply.callee);
+                apply.args = self.fold_all(apply.args);
+                Expr::Apply(apply)
+            }
+            Expr::Binary(mut bin) => {
+                bin.segments = std::mem::take(&mut bin.segments)
+                    .into_iter()
+                    .map(|mut segment| {
+                        segment.value = self.fold_boxed(segment.value);
+                        segment.size = segment.size.map(|size| self.fold_boxed(size));
+                        segment
+                    })
+                    .collect();
+                Expr::Binary(bin)
+            }
+            Expr::Call(mut call) => {
+                call.module = self.fold_boxed(call.module);
+                call.function = self.fold_boxed(call.function);
+                call.args = self.fold_all(call.args);
+                self.fold_call(call)
+            }
+            Expr::Case(case) => self.fold_case(case),
+            Expr::Catch(mut catch) => {
+                catch.body = self.fold_boxed(catch.body);
+                Expr::Catch(catch)
+            }
+            Expr::Cons(mut cons) => {
+                cons.head = self.fold_boxed(cons.head);
+                cons.tail = self.fold_boxed(cons.tail);
+                Expr::Cons(cons)
+            }
+            Expr::Fun(mut fun) => {
+                fun.body = self.fold_boxed(fun.body);
+                Expr::Fun(fun)
+            }
+            Expr::If(mut expr) => {
+                expr.guard = self.fold_boxed(expr.guard);
+                expr.then_body = self.fold_boxed(expr.then_body);
+                expr.else_body = self.fold_boxed(expr.else_body);
+                match expr.guard.as_boolean() {
+                    Some(true) => *expr.then_body,
+                    Some(false) => *expr.else_body,
+                    None => Expr::If(expr),
+                }
+            }
+            Expr::Let(expr) => self.fold_let(expr),
+            Expr::LetRec(mut expr) => {
+                expr.defs = std::mem::take(&mut expr.defs)
+                    .into_iter()
+                    .map(|(var, def)| (var, self.fold(def)))
+                    .collect();
+                expr.body = self.fold_boxed(expr.body);
+                Expr::LetRec(expr)
+            }
+            Expr::Map(mut map) => {
+                map.arg = self.fold_boxed(map.arg);
+                map.pairs = std::mem::take(&mut map.pairs)
+                    .into_iter()
+                    .map(|mut pair| {
+                        pair.key = self.fold_boxed(pair.key);
+                        pair.value = self.fold_boxed(pair.value);
+                        pair
+                    })
+                    .collect();
+                Expr::Map(map)
+            }
+            Expr::PrimOp(mut op) => {
+                op.args = self.fold_all(op.args);
+                Expr::PrimOp(op)
+            }
+            Expr::Receive(mut recv) => {
+                recv.clauses = self.fold_clauses(recv.clauses);
+                recv.timeout = self.fold_boxed(recv.timeout);
+                recv.action = self.fold_boxed(recv.action);
+                Expr::Receive(recv)
+            }
+            Expr::Seq(mut seq) => {
+                seq.arg = self.fold_boxed(seq.arg);
+                seq.body = self.fold_boxed(seq.body);
+                // The result of the first expression is discarded, so if it has no effects, drop it
+                if is_pure(seq.arg.as_ref()) {
+                    *seq.body
+                } else {
+                    Expr::Seq(seq)
+                }
+            }
+            Expr::Try(mut expr) => {
+                expr.arg = self.fold_boxed(expr.arg);
+                expr.body = self.fold_boxed(expr.body);
+                expr.handler = self.fold_boxed(expr.handler);
+                Expr::Try(expr)
+            }
+            Expr::Tuple(mut tuple) => {
+                tuple.elements = self.fold_all(tuple.elements);
+                Expr::Tuple(tuple)
+            }
+            Expr::Values(mut values) => {
+                values.values = self.fold_all(values.values);
+                Expr::Values(values)
+            }
+        }
+    }
+
+    /// Evaluates calls to pure BIFs when all of the arguments are known
+    fn fold_call(&mut self, call: Call) -> Expr {
+        let Some(name) = call_name(&call) else { return Expr::Call(call); };
+        if name.module != Some(symbols::Erlang) || !name.is_guard_bif() {
+            return Expr::Call(call);
+        }
+        let args = call.args.iter().map(as_literal).collect::<Option<Vec<_>>>();
+        let Some(args) = args else { return Expr::Call(call); };
+        match eval_bif(name.function, args.as_slice()) {
+            Some(value) => Expr::Literal(Literal {
+                span: call.span,
+                annotations: call.annotations,
+                value,
+            }),
+            None => Expr::Call(call),
+        }
+    }
+
+    /// Propagates literal bindings into the body of the `let`, and removes the `let` if
+    /// none of its variables are used, and evaluating the argument has no side effects
+    fn fold_let(&mut self, mut expr: Let) -> Expr {
+        let arg = self.fold(*expr.arg);
+        let mut body = *expr.body;
+        if let ([var], Expr::Literal(value)) = (expr.vars.as_slice(), &arg) {
+            substitute(&mut body, var.name(), value);
+        }
+        let body = self.fold(body);
+
+        if is_pure(&arg) && expr.vars.iter().all(|var| !body.is_var_used(var)) {
+            return body;
+        }
+
+        expr.arg = Box::new(arg);
+        expr.body = Box::new(body);
+        Expr::Let(expr)
+    }
+
+    /// Removes clauses which are unreachable, and if the argument is known to match the first
+    /// reachable clause, replaces the `case` with the body of that clause.
+    fn fold_case(&mut self, mut case: Case) -> Expr {
+        case.arg = self.fold_boxed(case.arg);
+        case.clauses = self.fold_clauses(case.clauses);
+
+        let args = match case.arg.as_ref() {
+            Expr::Values(Values { values, .. }) => values.as_slice(),
+            arg => core::slice::from_ref(arg),
+        };
+        // We only try to match against simple arguments, as parts of the argument may be
+        // duplicated when binding pattern variables
+        let is_known = args.iter().all(|arg| arg.is_simple());
+
+        let mut reachable = Vec::with_capacity(case.clauses.len());
+        let mut selected = None;
+        for clause in case.clauses.iter() {
+            let guard = clause.guard.as_deref().map(Expr::as_boolean);
+            if guard == Some(Some(false)) {
+                reachable.push(false);
+                continue;
+            }
+            let mut bindings = vec![];
+            let matches = if is_known && clause.patterns.len() == args.len() {
+                match_all(clause.patterns.as_slice(), args, &mut bindings)
+            } else {
+                None
+            };
+            // If the patterns match, the guard may be known once the values bound by the
+            // patterns are substituted into it
+            let guard = match (guard, matches, clause.guard.as_deref()) {
+                (Some(None), Some(true), Some(expr)) => {
+                    let mut expr = expr.clone();
+                    for (var, value) in bindings.iter() {
+                        if let Expr::Literal(value) = value {
+                            substitute(&mut expr, var.name(), value);
+                        }
+                    }
+                    match self.fold(expr).as_boolean() {
+                        Some(false) => {
+                            reachable.push(false);
+                            continue;
+                        }
+                        known => Some(known),
+                    }
+                }
+                (guard, _, _) => guard,
+            };
+            match matches {
+                Some(false) => reachable.push(false),
+                // This clause always matches, so all following clauses are unreachable
+                Some(true) if guard.is_none() || guard == Some(Some(true)) => {
+                    if !reachable.contains(&true) {
+                        selected = Some((reachable.len(), bindings));
+                    }
+                    reachable.push(true);
+                    break;
+                }
+                _ => reachable.push(true),
+            }
+        }
+
+        if let Some((index, bindings)) = selected {
+            let span = case.span;
+            let clause = case.clauses.swap_remove(index);
+            let body = bindings
+                .into_iter()
+                .rev()
+                .fold(*clause.body, |body, (var, value)| {
+                    Expr::Let(Let::new(span, vec![var], value, body))
+                });
+            // Fold again to propagate any literals bound by the clause patterns
+            return self.fold(body);
+        }
+
+        // If no clauses can match, we leave the case as-is, so that it fails at runtime
+        if reachable.contains(&true) {
+            reachable.resize(case.clauses.len(), false);
+            let mut reachable = reachable.drain(..);
+            case.clauses.retain(|_| reachable.next().unwrap());
+        }
+
+        Expr::Case(case)
+    }
+}
+
+/// Returns the name of the function being called, if statically known
+fn call_name(call: &Call) -> Option<FunctionName> {
+    let module = call.module.as_atom()?;
+    let function = call.function.as_atom()?;
+    let arity = call.args.len().try_into().ok()?;
+    Some(FunctionName::new(module, function, arity))
+}
+
+/// Returns true if evaluating `expr` has no side effects, and cannot fail
+fn is_pure(expr: &Expr) -> bool {
+    match expr {
+        Expr::Literal(_) | Expr::Var(_) | Expr::Fun(_) => true,
+        Expr::Cons(Cons { head, tail, .. }) => is_pure(head) && is_pure(tail),
+        Expr::Tuple(Tuple { elements, .. }) => elements.iter().all(is_pure),
+        Expr::Values(Values { values, .. }) => values.iter().all(is_pure),
+        Expr::Call(call) => {
+            call_name(call)
+                .map(|name| name.is_safe())
+                .unwrap_or_default()
+                && call.args.iter().all(is_pure)
+        }
+        _ => false,
+    }
+}
+
+/// Converts `expr` to a literal, if it is a literal or a data constructor with literal elements
+fn as_literal(expr: &Expr) -> Option<Literal> {
+    match expr {
+        Expr::Literal(lit) => Some(lit.clone()),
+        Expr::Cons(Cons {
+            span, head, tail, ..
+        }) => Some(Literal::cons(*span, as_literal(head)?, as_literal(tail)?)),
+        Expr::Tuple(Tuple { span, elements, .. }) => Some(Literal::tuple(
+            *span,
+            elements
+                .iter()
+                .map(as_literal)
+                .collect::<Option<Vec<_>>>()?,
+        )),
+        _ => None,
+    }
+}
+
+/// Replaces all uses of `var` in `expr` with `value`, taking care not to replace shadowed uses
+fn substitute(expr: &mut Expr, var: Symbol, value: &Literal) {
+    match expr {
+        Expr::Var(v) if v.arity.is_none() && v.name() == var => {
+            *expr = Expr::Literal(value.clone());
+        }
+        Expr::Alias(_) | Expr::Literal(_) | Expr::Var(_) => (),
+        Expr::Apply(Apply { callee, args, .. }) => {
+            substitute(callee, var, value);
+            substitute_all(args, var, value);
+        }
+        Expr::Binary(Binary { segments, .. }) => {
+            for segment in segments.iter_mut() {
+                substitute(&mut segment.value, var, value);
+                if let Some(size) = segment.size.as_deref_mut() {
+                    substitute(size, var, value);
+                }
+            }
+        }
+        Expr::Call(Call {
+            module,
+            function,
+            args,
+            ..
+        }) => {
+            substitute(module, var, value);
+            substitute(function, var, value);
+            substitute_all(args, var, value);
+        }
+        Expr::Case(Case { arg, clauses, .. }) => {
+            substitute(arg, var, value);
+            substitute_clauses(clauses, var, value);
+        }
+        Expr::Catch(Catch { body, .. }) => substitute(body, var, value),
+        Expr::Cons(Cons { head, tail, .. }) => {
+            substitute(head, var, value);
+            substitute(tail, var, value);
+        }
+        Expr::Fun(Fun { vars, body, .. }) => {
+            if !binds(vars, var) {
+                substitute(body, var, value);
+            }
+        }
+        Expr::If(If {
+            guard,
+            then_body,
+            else_body,
+            ..
+        }) => {
+            substitute(guard, var, value);
+            substitute(then_body, var, value);
+            substitute(else_body, var, value);
+        }
+        Expr::Let(Let {
+            vars, arg, body, ..
+        }) => {
+            substitute(arg, var, value);
+            if !binds(vars, var) {
+                substitute(body, var, value);
+            }
+        }
+        Expr::LetRec(LetRec { defs, body, .. }) => {
+            if defs.iter().all(|(v, _)| v.name() != var) {
+                for (_, def) in defs.iter_mut() {
+                    substitute(def, var, value);
+                }
+                substitute(body, var, value);
+            }
+        }
+        Expr::Map(Map { arg, pairs, .. }) => {
+            substitute(arg, var, value);
+            for pair in pairs.iter_mut() {
+                substitute(&mut pair.key, var, value);
+                substitute(&mut pair.value, var, value);
+            }
+        }
+        Expr::PrimOp(PrimOp { args, .. }) => substitute_all(args, var, value),
+        Expr::Receive(Receive {
+            clauses,
+            timeout,
+            action,
+            ..
+        }) => {
+            substitute_clauses(clauses, var, value);
+            substitute(timeout, var, value);
+            substitute(action, var, value);
+        }
+        Expr::Seq(Seq { arg, body, .. }) => {
+            substitute(arg, var, value);
+            substitute(body, var, value);
+        }
+        Expr::Try(Try {
+            arg,
+            vars,
+            body,
+            evars,
+            handler,
+            ..
+        }) => {
+            substitute(arg, var, value);
+            if !binds(vars, var) {
+                substitute(body, var, value);
+            }
+            if !binds(evars, var) {
+                substitute(handler, var, value);
+            }
+        }
+        Expr::Tuple(Tuple { elements, .. }) => substitute_all(elements, var, value),
+        Expr::Values(Values { values, .. }) => substitute_all(values, var, value),
+    }
+}
+
+fn substitute_all(exprs: &mut [Expr], var: Symbol, value: &Literal) {
+    for expr in exprs.iter_mut() {
+        substitute(expr, var, value);
+    }
+}
+
+fn substitute_clauses(clauses: &mut [Clause], var: Symbol, value: &Literal) {
+    for clause in clauses.iter_mut() {
+        // Patterns which refer to the variable either shadow it, or use it in a binary size
+        // or map key, in either case we leave the clause untouched
+        if clause.patterns.iter().any(|p| mentions(p, var)) {
+            continue;
+        }
+        if let Some(guard) = clause.guard.as_deref_mut() {
+            substitute(guard, var, value);
+        }
+        substitute(&mut clause.body, var, value);
+    }
+}
+
+fn binds(vars: &[Var], var: Symbol) -> bool {
+    vars.iter().any(|v| v.name() == var)
+}
+
+/// Returns true if `pattern` refers to `var` anywhere
+fn mentions(pattern: &Expr, var: Symbol) -> bool {
+    match pattern {
+        Expr::Var(v) => v.name() == var,
+        Expr::Alias(Alias {
+            var: v, pattern, ..
+        }) => v.name() == var || mentions(pattern, var),
+        Expr::Literal(_) => false,
+        Expr::Cons(Cons { head, tail, .. }) => mentions(head, var) || mentions(tail, var),
+        Expr::Tuple(Tuple { elements, .. }) => elements.iter().any(|e| mentions(e, var)),
+        Expr::Values(Values { values, .. }) => values.iter().any(|v| mentions(v, var)),
+        Expr::Map(Map { arg, pairs, .. }) => {
+            mentions(arg, var)
+                || pairs
+                    .iter()
+                    .any(|p| mentions(&p.key, var) || mentions(&p.value, var))
+        }
+        Expr::Binary(Binary { segments, .. }) => segments.iter().any(|s| {
+            mentions(&s.value, var)
+                || s.size
+                    .as_deref()
+                    .map(|sz| mentions(sz, var))
+                    .unwrap_or_default()
+        }),
+        _ => true,
+    }
+}
+
+/// Matches each pattern against the corresponding argument, see `match_pattern`
+fn match_all(patterns: &[Expr], args: &[Expr], bindings: &mut Vec<(Var, Expr)>) -> Option<bool> {
+    let mut result = Some(true);
+    for (pattern, arg) in patterns.iter().zip(args.iter()) {
+        match match_pattern(pattern, arg, bindings) {
+            Some(false) => return Some(false),
+            Some(true) => (),
+            None => result = None,
+        }
+    }
+    result
+}
+
+/// Statically matches `pattern` against `arg`
+///
+/// Returns `Some(true)` if the pattern always matches, in which case `bindings` contains the
+/// values bound to each pattern variable; `Some(false)` if the pattern can never match; or
+/// `None` if it cannot be determined at compile-time.
+fn match_pattern(pattern: &Expr, arg: &Expr, bindings: &mut Vec<(Var, Expr)>) -> Option<bool> {
+    match (pattern, arg) {
+        (Expr::Var(var), _) => {
+            bindings.push((var.clone(), arg.clone()));
+            Some(true)
+        }
+        (Expr::Alias(alias), _) => {
+            bindings.push((alias.var.clone(), arg.clone()));
+            match_pattern(&alias.pattern, arg, bindings)
+        }
+        (
+            Expr::Literal(Literal {
+                value: Lit::Map(_), ..
+            }),
+            _,
+        ) => None,
+        (Expr::Literal(p), Expr::Literal(a)) => Some(p.value == a.value),
+        (Expr::Literal(p), Expr::Cons(a)) => match &p.value {
+            Lit::Cons(head, tail) => match_all(
+                &[
+                    Expr::Literal(head.as_ref().clone()),
+                    Expr::Literal(tail.as_ref().clone()),
+                ],
+                &[a.head.as_ref().clone(), a.tail.as_ref().clone()],
+                bindings,
+            ),
+            _ => Some(false),
+        },
+        (Expr::Literal(p), Expr::Tuple(a)) => match &p.value {
+            Lit::Tuple(elements) if elements.len() == a.elements.len() => {
+                let elements = elements
+                    .iter()
+                    .cloned()
+                    .map(Expr::Literal)
+                    .collect::<Vec<_>>();
+                match_all(elements.as_slice(), a.elements.as_slice(), bindings)
+            }
+            _ => Some(false),
+        },
+        (Expr::Tuple(p), Expr::Tuple(a)) => {
+            if p.elements.len() != a.elements.len() {
+                return Some(false);
+            }
+            match_all(p.elements.as_slice(), a.elements.as_slice(), bindings)
+        }
+        (Expr::Tuple(p), Expr::Literal(a)) => match &a.value {
+            Lit::Tuple(elements) if elements.len() == p.elements.len() => {
+                let elements = elements
+                    .iter()
+                    .cloned()
+                    .map(Expr::Literal)
+                    .collect::<Vec<_>>();
+                match_all(p.elements.as_slice(), elements.as_slice(), bindings)
+            }
+            _ => Some(false),
+        },
+        (Expr::Tuple(_), Expr::Cons(_)) => Some(false),
+        (Expr::Cons(p), Expr::Cons(a)) => match_all(
+            &[p.head.as_ref().clone(), p.tail.as_ref().clone()],
+            &[a.head.as_ref().clone(), a.tail.as_ref().clone()],
+            bindings,
+        ),
+        (Expr::Cons(p), Expr::Literal(a)) => match &a.value {
+            Lit::Cons(head, tail) => match_all(
+                &[p.head.as_ref().clone(), p.tail.as_ref().clone()],
+                &[
+                    Expr::Literal(head.as_ref().clone()),
+                    Expr::Literal(tail.as_ref().clone()),
+                ],
+                bindings,
+            ),
+            _ => Some(false),
+        },
+        (Expr::Cons(_), Expr::Tuple(_)) => Some(false),
+        _ => None,
+    }
+}
+
+/// Evaluates the pure BIF `erlang:<function>/N` with the given arguments
+///
+/// Returns `None` if the call would fail, or if we don't know how to evaluate it
+fn eval_bif(function: Symbol, args: &[Literal]) -> Option<Lit> {
+    let value = match (function, args) {
+        (symbols::EqualStrict, [x, y]) => boolean(x == y),
+        (symbols::NotEqualStrict, [x, y]) => boolean(x != y),
+        (symbols::Equal, [x, y]) => boolean(x.cmp(y) == Ordering::Equal),
+        (symbols::NotEqual, [x, y]) => boolean(x.cmp(y) != Ordering::Equal),
+        // We only evaluate the term order of numbers, as atoms are ordered by name rather
+        // than by symbol, and tuples are ordered by size before their elements
+        (symbols::Lt, [x, y]) if x.value.is_number() && y.value.is_number() => boolean(x < y),
+        (symbols::Lte, [x, y]) if x.value.is_number() && y.value.is_number() => boolean(x <= y),
+        (symbols::Gt, [x, y]) if x.value.is_number() && y.value.is_number() => boolean(x > y),
+        (symbols::Gte, [x, y]) if x.value.is_number() && y.value.is_number() => boolean(x >= y),
+        (symbols::Not, [x]) => boolean(!to_boolean(x)?),
+        (symbols::And, [x, y]) => boolean(to_boolean(x)? & to_boolean(y)?),
+        (symbols::Or, [x, y]) => boolean(to_boolean(x)? | to_boolean(y)?),
+        (symbols::Xor, [x, y]) => boolean(to_boolean(x)? ^ to_boolean(y)?),
+        (symbols::Plus, [x, y]) => from_number((to_number(x)? + to_number(y)?).ok()?),
+        (symbols::Minus, [x, y]) => from_number((to_number(x)? - to_number(y)?).ok()?),
+        (symbols::Minus, [x]) => from_number(-to_number(x)?),
+        (symbols::Star, [x, y]) => from_number((to_number(x)? * to_number(y)?).ok()?),
+        (symbols::Div, [x, y]) => Lit::Integer((to_integer(x)? / to_integer(y)?).ok()?),
+        (symbols::Rem, [x, y]) => Lit::Integer((to_integer(x)? % to_integer(y)?).ok()?),
+        (symbols::Band, [x, y]) => Lit::Integer(to_integer(x)? & to_integer(y)?),
+        (symbols::Bor, [x, y]) => Lit::Integer(to_integer(x)? | to_integer(y)?),
+        (symbols::Bxor, [x, y]) => Lit::Integer(to_integer(x)? ^ to_integer(y)?),
+        (symbols::Bnot, [x]) => Lit::Integer(!to_integer(x)?),
+        (symbols::Abs, [x]) => from_number(to_number(x)?.abs()),
+        (symbols::IsAtom, [x]) => boolean(matches!(x.value, Lit::Atom(_))),
+        (symbols::IsBoolean, [x]) => boolean(matches!(x.value, Lit::Atom(a) if a.is_boolean())),
+        (symbols::IsInteger, [x]) => boolean(x.is_integer()),
+        (symbols::IsFloat, [x]) => boolean(matches!(x.value, Lit::Float(_))),
+        (symbols::IsNumber, [x]) => boolean(x.value.is_number()),
+        (symbols::IsList, [x]) => boolean(matches!(x.value, Lit::Nil | Lit::Cons(_, _))),
+        (symbols::IsTuple, [x]) => boolean(matches!(x.value, Lit::Tuple(_))),
+        (symbols::IsMap, [x]) => boolean(matches!(x.value, Lit::Map(_))),
+        (symbols::IsBinary, [x]) => {
+            boolean(matches!(&x.value, Lit::Binary(bits) if bits.is_binary()))
+        }
+        (symbols::IsBitstring, [x]) => boolean(matches!(x.value, Lit::Binary(_))),
+        // None of these types can be represented as literals
+        (symbols::IsFunction | symbols::IsPid | symbols::IsPort | symbols::IsReference, [_]) => {
+            boolean(false)
+        }
+        (symbols::Element, [index, tuple]) => {
+            let Lit::Tuple(elements) = &tuple.value else { return None; };
+            let index = index.as_integer()?.to_usize()?;
+            elements.get(index.checked_sub(1)?)?.value.clone()
+        }
+        (symbols::TupleSize, [tuple]) => {
+            let Lit::Tuple(elements) = &tuple.value else { return None; };
+            Lit::Integer(elements.len().into())
+        }
+        (symbols::Hd, [list]) => {
+            let Lit::Cons(head, _) = &list.value else { return None; };
+            head.value.clone()
+        }
+        (symbols::Tl, [list]) => {
+            let Lit::Cons(_, tail) = &list.value else { return None; };
+            tail.value.clone()
+        }
+        (symbols::Length, [list]) => {
+            let mut len = 0usize;
+            let mut current = &list.value;
+            while let Lit::Cons(_, tail) = current {
+                len += 1;
+                current = &tail.value;
+            }
+            if *current != Lit::Nil {
+                return None;
+            }
+            Lit::Integer(len.into())
+        }
+        (symbols::MapSize, [map]) => {
+            let Lit::Map(map) = &map.value else { return None; };
+            Lit::Integer(map.len().into())
+        }
+        (symbols::ByteSize, [bits]) => {
+            let Lit::Binary(bits) = &bits.value else { return None; };
+            Lit::Integer(bits.byte_size().into())
+        }
+        (symbols::BitSize, [bits]) => {
+            let Lit::Binary(bits) = &bits.value else { return None; };
+            Lit::Integer(bits.bit_size().into())
+        }
+        _ => return None,
+    };
+    Some(value)
+}
+
+#[inline]
+fn boolean(value: bool) -> Lit {
+    Lit::Atom(if value { symbols::True } else { symbols::False })
+}
+
+fn to_boolean(lit: &Literal) -> Option<bool> {
+    match lit.value {
+        Lit::Atom(symbols::True) => Some(true),
+        Lit::Atom(symbols::False) => Some(false),
+        _ => None,
+    }
+}
+
+fn to_integer(lit: &Literal) -> Option<Integer> {
+    lit.as_integer().cloned()
+}
+
+fn to_number(lit: &Literal) -> Option<Number> {
+    match &lit.value {
+        Lit::Integer(i) => Some(Number::Integer(i.clone())),
+        Lit::Float(f) => Some(Number::Float(*f)),
+        _ => None,
+    }
+}
+
+fn from_number(number: Number) -> Lit {
+    match number {
+        Number::Integer(i) => Lit::Integer(i),
+        Number::Float(f) => Lit::Float(f),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use firefly_diagnostics::SourceSpan;
+    use firefly_intern::{symbols, Ident, Symbol};
+    use firefly_pass::Pass;
+    use firefly_syntax_base::*;
+
+    use crate::*;
+
+    use super::FoldConstants;
+
+    const SPAN: SourceSpan = SourceSpan::UNKNOWN;
+
+    fn fold(body: Expr) -> Expr {
+        let fun = Fun {
+            span: SPAN,
+            annotations: Annotations::default(),
+            name: Symbol::intern("test"),
+            vars: vec![var("Arg")],
+            body: Box::new(body),
+        };
+        *FoldConstants::new().run(fun).unwrap().body
+    }
+
+    fn var(name: &str) -> Var {
+        Var::new(Ident::with_empty_span(Symbol::intern(name)))
+    }
+
+    fn int(i: i64) -> Expr {
+        Expr::Literal(Literal::integer(SPAN, i))
+    }
+
+    fn float(f: f64) -> Expr {
+        Expr::Literal(Literal::float(SPAN, f))
+    }
+
+    fn atom(sym: Symbol) -> Expr {
+        Expr::Literal(Literal::atom(SPAN, sym))
+    }
+
+    fn call(function: Symbol, args: Vec<Expr>) -> Expr {
+        Expr::Call(Call::new(SPAN, symbols::Erlang, function, args))
+    }
+
+    fn bind(name: &str, arg: Expr, body: Expr) -> Expr {
+        Expr::Let(Let::new(SPAN, vec![var(name)], arg, body))
+    }
+
+    fn clause(pattern: Expr, guard: Option<Expr>, body: Expr) -> Clause {
+        let mut clause = Clause::new(SPAN, vec![pattern], body);
+        clause.guard = guard.map(Box::new);
+        clause
+    }
+
+    fn case(arg: Expr, clauses: Vec<Clause>) -> Expr {
+        Expr::Case(Case {
+            span: SPAN,
+            annotations: Annotations::default(),
+            arg: Box::new(arg),
+            clauses,
+        })
+    }
+
+    #[test]
+    fn literals_are_propagated_and_evaluated() {
+        // let <X> = 1 in call 'erlang':'+'(X, 2)
+        let input = bind(
+            "X",
+            int(1),
+            call(symbols::Plus, vec![Expr::Var(var("X")), int(2)]),
+        );
+        assert_eq!(fold(input), int(3));
+    }
+
+    #[test]
+    fn shadowed_variables_are_not_substituted() {
+        // let <X> = 1 in {fun (X) -> X, case Arg of <X> -> X end}
+        let fun = Expr::Fun(Fun {
+            span: SPAN,
+            annotations: Annotations::default(),
+            name: Symbol::intern("-test/1-fun-0-"),
+            vars: vec![var("X")],
+            body: Box::new(Expr::Var(var("X"))),
+        });
+        let shadowing_case = case(
+            Expr::Var(var("Arg")),
+            vec![clause(Expr::Var(var("X")), None, Expr::Var(var("X")))],
+        );
+        let input = bind(
+            "X",
+            int(1),
+            Expr::Tuple(Tuple::new(SPAN, vec![fun.clone(), shadowing_case])),
+        );
+        // The case is reduced to a `let` rebinding X, which must not be replaced by 1
+        let rebind = bind("X", Expr::Var(var("Arg")), Expr::Var(var("X")));
+        let expected = Expr::Tuple(Tuple::new(SPAN, vec![fun, rebind]));
+        assert_eq!(fold(input), expected);
+    }
+
+    #[test]
+    fn exact_and_arithmetic_equality_differ() {
+        let exact = call(symbols::EqualStrict, vec![int(1), float(1.0)]);
+        assert_eq!(fold(exact), atom(symbols::False));
+        let arith = call(symbols::Equal, vec![int(1), float(1.0)]);
+        assert_eq!(fold(arith), atom(symbols::True));
+        let exact_ne = call(symbols::NotEqualStrict, vec![int(1), float(1.0)]);
+        assert_eq!(fold(exact_ne), atom(symbols::True));
+        let arith_ne = call(symbols::NotEqual, vec![int(1), float(1.0)]);
+        assert_eq!(fold(arith_ne), atom(symbols::False));
+    }
+
+    #[test]
+    fn clauses_with_false_guards_are_removed() {
+        // case Arg of
+        //   <X> when call 'erlang':'>'(1, 2) -> a
+        //   <X> when call 'erlang':is_atom(X) -> b
+        //   <_> -> c
+        //   <_> -> d
+        // end
+        let input = case(
+            Expr::Var(var("Arg")),
+            vec![
+                clause(
+                    Expr::Var(var("X")),
+                    Some(call(symbols::Gt, vec![int(1), int(2)])),
+                    atom(Symbol::intern("a")),
+                ),
+                clause(
+                    Expr::Var(var("X")),
+                    Some(call(symbols::IsAtom, vec![Expr::Var(var("X"))])),
+                    atom(Symbol::intern("b")),
+                ),
+                clause(Expr::Var(var("_")), None, atom(Symbol::intern("c"))),
+                clause(Expr::Var(var("_")), None, atom(Symbol::intern("d"))),
+            ],
+        );
+        let Expr::Case(folded) = fold(input) else { panic!("expected case to be preserved"); };
+        let bodies = folded
+            .clauses
+            .iter()
+            .map(|clause| clause.body.as_atom().unwrap().as_str().get())
+            .collect::<Vec<_>>();
+        assert_eq!(bodies, vec!["b", "c"]);
+    }
+
+    #[test]
+    fn guards_select_a_clause_on_known_arguments() {
+        // case {1, Arg} of
+        //   <{X, _}> when call 'erlang':'=:='(X, 1.0) -> a
+        //   <{X, _}> when call 'erlang':'=='(X, 1.0) -> b
+        //   <_> -> c
+        // end
+        let pattern = || {
+            Expr::Tuple(Tuple::new(
+                SPAN,
+                vec![Expr::Var(var("X")), Expr::Var(var("_"))],
+            ))
+        };
+        let input = case(
+            Expr::Tuple(Tuple::new(SPAN, vec![int(1), Expr::Var(var("Arg"))])),
+            vec![
+                clause(
+                    pattern(),
+                    Some(call(
+                        symbols::EqualStrict,
+                        vec![Expr::Var(var("X")), float(1.0)],
+                    )),
+                    atom(Symbol::intern("a")),
+                ),
+                clause(
+                    pattern(),
+                    Some(call(symbols::Equal, vec![Expr::Var(var("X")), float(1.0)])),
+                    atom(Symbol::intern("b")),
+                ),
+                clause(Expr::Var(var("_")), None, atom(Symbol::intern("c"))),
+            ],
+        );
+        assert_eq!(fold(input), atom(Symbol::intern("b")));
+    }
+}
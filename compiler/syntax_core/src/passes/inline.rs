@@ -0,0 +1,753 @@
+///! This pass inlines local functions, and funs bound to variables, at their call sites.
+///!
+///! Inlining is controlled by the following compile options:
+///!
+///! * `inline`, enables inlining of any local function, or fun bound by `let`, whose size
+///! does not exceed `inline_size`
+///! * `{inline, [F/A]}`, enables inlining of the given functions regardless of their size
+///! * `{inline_size, N}`, sets the maximum size of functions inlined due to `inline`
+///! * `{inline_effort, N}`, sets the maximum total size of the functions inlined due to `inline`
+///! into any single function
+///!
+///! Only local calls are inlined, calls of the form `?MODULE:f()` always call the latest
+///! version of the module, so they are left as-is.
+///!
+///! Only the original definition of a function is ever inlined, so recursive functions are
+///! unrolled at most once, and inlining always terminates. Functions containing funs or
+///! `letrec` are never inlined, as the functions they define are lifted to the module level
+///! under a unique name, which inlining would duplicate.
+///!
+///! Every variable bound by an inlined body is given a fresh name, and its parameters are bound
+///! to the arguments of the call with `let`. Functions into which calls were inlined are then
+///! run through `FoldConstants`, so that inlined bodies are specialized to their arguments.
+///!
+///! ## Example
+///!
+///! Given the following source:
+///!
+///! ```erlang,ignore
+///! -compile({inline, [double/1]}).
+///!
+///! foo() -> double(2).
+///!
+///! double(X) -> X * 2.
+///! ```
+///!
+///! The body of `foo/0` will be `let <$1> = 2 in call 'erlang':'*'($1, 2)` after inlining,
+///! which is then folded to the literal `4`.
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::rc::Rc;
+
+use rpds::RedBlackTreeMap;
+
+use firefly_diagnostics::*;
+use firefly_intern::{symbols, Ident, Symbol};
+use firefly_pass::Pass;
+use firefly_syntax_base::*;
+
+use crate::*;
+
+use super::FoldConstants;
+
+#[derive(Default)]
+pub struct InlineFunctions;
+impl InlineFunctions {
+    pub fn new() -> Self {
+        Self
+    }
+}
+impl Pass for InlineFunctions {
+    type Input<'a> = Module;
+    type Output<'a> = Module;
+
+    fn run<'a>(&mut self, mut module: Self::Input<'a>) -> anyhow::Result<Self::Output<'a>> {
+        let options = &module.compile;
+        if !options.inline && options.inline_functions.is_empty() {
+            return Ok(module);
+        }
+
+        // Select the functions which may be inlined, using their original definitions
+        let mut candidates = BTreeMap::new();
+        for (name, function) in module.functions.iter() {
+            let is_nif = module
+                .nifs
+                .iter()
+                .any(|nif| nif.function == name.function && nif.arity == name.arity);
+            if is_nif {
+                continue;
+            }
+            let is_listed = options
+                .inline_functions
+                .iter()
+                .any(|f| f.function == name.function && f.arity == name.arity);
+            if !is_listed && !options.inline {
+                continue;
+            }
+            let Some(candidate) = Candidate::new(function.fun.clone(), is_listed) else { continue; };
+            if is_listed || candidate.size <= options.inline_size {
+                candidates.insert((name.function, name.arity as usize), Rc::new(candidate));
+            }
+        }
+
+        let mut functions = BTreeMap::new();
+        for (name, mut function) in std::mem::take(&mut module.functions) {
+            let mut inliner = Inliner {
+                current: name,
+                candidates: &candidates,
+                inline_funs: options.inline,
+                inline_size: options.inline_size,
+                effort: options.inline_effort,
+                var_counter: function.var_counter,
+                changed: false,
+            };
+            function.fun.body = Box::new(inliner.expr(*function.fun.body, &Env::new()));
+            function.var_counter = inliner.var_counter;
+            if inliner.changed {
+                function.fun = FoldConstants::new().run(function.fun)?;
+            }
+            functions.insert(name, function);
+        }
+        module.functions = functions;
+
+        Ok(module)
+    }
+}
+
+/// A function which is eligible for inlining
+struct Candidate {
+    fun: Fun,
+    /// The names of all variables referenced in `fun`, used to determine when inlining a fun
+    /// bound to a variable would capture a shadowed variable
+    names: BTreeSet<Symbol>,
+    /// The size of the body of `fun`
+    size: usize,
+    /// True if this function was given in `{inline, [F/A]}`, in which case it is inlined
+    /// regardless of its size
+    listed: bool,
+}
+impl Candidate {
+    /// Returns `None` if `fun` can never be inlined
+    fn new(fun: Fun, listed: bool) -> Option<Self> {
+        let size = size(fun.body.as_ref())?;
+        let mut names = BTreeSet::new();
+        names.extend(fun.vars.iter().map(|v| v.name()));
+        var_names(fun.body.as_ref(), &mut names);
+        Some(Self {
+            fun,
+            names,
+            size,
+            listed,
+        })
+    }
+}
+
+/// The funs bound to variables in the current scope which may be inlined
+type Env = RedBlackTreeMap<Symbol, Rc<Candidate>>;
+
+struct Inliner<'a> {
+    current: FunctionName,
+    candidates: &'a BTreeMap<(Symbol, usize), Rc<Candidate>>,
+    inline_funs: bool,
+    inline_size: usize,
+    /// The total size of the functions which may still be inlined into the current function,
+    /// this does not apply to functions given in `{inline, [F/A]}`
+    effort: usize,
+    var_counter: usize,
+    changed: bool,
+}
+impl<'a> Inliner<'a> {
+    fn exprs(&mut self, exprs: Vec<Expr>, env: &Env) -> Vec<Expr> {
+        exprs.into_iter().map(|expr| self.expr(expr, env)).collect()
+    }
+
+    fn boxed(&mut self, expr: Box<Expr>, env: &Env) -> Box<Expr> {
+        Box::new(self.expr(*expr, env))
+    }
+
+    fn clauses(&mut self, clauses: Vec<Clause>, env: &Env) -> Vec<Clause> {
+        clauses
+            .into_iter()
+            .map(|mut clause| {
+                let mut bound = BTreeSet::new();
+                for pattern in clause.patterns.iter() {
+                    pattern_vars(pattern, &mut bound);
+                }
+                let env = shadow(env, &bound);
+                clause.guard = clause.guard.map(|guard| self.boxed(guard, &env));
+                clause.body = self.boxed(clause.body, &env);
+                clause
+            })
+            .collect()
+    }
+
+    fn expr(&mut self, expr: Expr, env: &Env) -> Expr {
+        match expr {
+            expr @ (Expr::Alias(_) | Expr::Literal(_) | Expr::Var(_)) => expr,
+            Expr::Apply(mut apply) => {
+                apply.args = self.exprs(apply.args, env);
+                match self.callee(apply.callee.as_ref(), apply.args.len(), env) {
+                    Some(candidate) => self.inline(apply.span, candidate.as_ref(), apply.args),
+                    None => {
+                        apply.callee = self.boxed(apply.callee, env);
+                        Expr::Apply(apply)
+                    }
+                }
+            }
+            Expr::Binary(mut bin) => {
+                bin.segments = std::mem::take(&mut bin.segments)
+                    .into_iter()
+                    .map(|mut segment| {
+                        segment.value = self.boxed(segment.value, env);
+                        segment.size = segment.size.map(|size| self.boxed(size, env));
+                        segment
+                    })
+                    .collect();
+                Expr::Binary(bin)
+            }
+            Expr::Call(mut call) => {
+                call.args = self.exprs(call.args, env);
+                match self.local_callee(&call) {
+                    Some(candidate) => self.inline(call.span, candidate.as_ref(), call.args),
+                    None => {
+                        call.module = self.boxed(call.module, env);
+                        call.function = self.boxed(call.function, env);
+                        Expr::Call(call)
+                    }
+                }
+            }
+            Expr::Case(mut case) => {
+                case.arg = self.boxed(case.arg, env);
+                case.clauses = self.clauses(case.clauses, env);
+                Expr::Case(case)
+            }
+            Expr::Catch(mut catch) => {
+                catch.body = self.boxed(catch.body, env);
+                Expr::Catch(catch)
+            }
+            Expr::Cons(mut cons) => {
+                cons.head = self.boxed(cons.head, env);
+                cons.tail = self.boxed(cons.tail, env);
+                Expr::Cons(cons)
+            }
+            Expr::Fun(mut fun) => {
+                let bound = fun.vars.iter().map(|v| v.name()).collect();
+                fun.body = self.boxed(fun.body, &shadow(env, &bound));
+                Expr::Fun(fun)
+            }
+            Expr::If(mut expr) => {
+                expr.guard = self.boxed(expr.guard, env);
+                expr.then_body = self.boxed(expr.then_body, env);
+                expr.else_body = self.boxed(expr.else_body, env);
+                Expr::If(expr)
+            }
+            Expr::Let(mut expr) => {
+                expr.arg = self.boxed(expr.arg, env);
+                let bound = expr.vars.iter().map(|v| v.name()).collect();
+                let mut env = shadow(env, &bound);
+                if let ([var], Expr::Fun(fun)) = (expr.vars.as_slice(), expr.arg.as_ref()) {
+                    if self.inline_funs {
+                        match Candidate::new(fun.clone(), false) {
+                            Some(candidate) if candidate.size <= self.inline_size => {
+                                env.insert_mut(var.name(), Rc::new(candidate));
+                            }
+                            _ => (),
+                        }
+                    }
+                }
+                expr.body = self.boxed(expr.body, &env);
+                Expr::Let(expr)
+            }
+            Expr::LetRec(mut expr) => {
+                let bound = expr.defs.iter().map(|(v, _)| v.name()).collect();
+                let env = shadow(env, &bound);
+                expr.defs = std::mem::take(&mut expr.defs)
+                    .into_iter()
+                    .map(|(var, def)| (var, self.expr(def, &env)))
+                    .collect();
+                expr.body = self.boxed(expr.body, &env);
+                Expr::LetRec(expr)
+            }
+            Expr::Map(mut map) => {
+                map.arg = self.boxed(map.arg, env);
+                map.pairs = std::mem::take(&mut map.pairs)
+                    .into_iter()
+                    .map(|mut pair| {
+                        pair.key = self.boxed(pair.key, env);
+                        pair.value = self.boxed(pair.value, env);
+                        pair
+                    })
+                    .collect();
+                Expr::Map(map)
+            }
+            Expr::PrimOp(mut op) => {
+                op.args = self.exprs(op.args, env);
+                Expr::PrimOp(op)
+            }
+            Expr::Receive(mut recv) => {
+                recv.clauses = self.clauses(recv.clauses, env);
+                recv.timeout = self.boxed(recv.timeout, env);
+                recv.action = self.boxed(recv.action, env);
+                Expr::Receive(recv)
+            }
+            Expr::Seq(mut seq) => {
+                seq.arg = self.boxed(seq.arg, env);
+                seq.body = self.boxed(seq.body, env);
+                Expr::Seq(seq)
+            }
+            Expr::Try(mut expr) => {
+                expr.arg = self.boxed(expr.arg, env);
+                let bound = expr.vars.iter().map(|v| v.name()).collect();
+                expr.body = self.boxed(expr.body, &shadow(env, &bound));
+                let bound = expr.evars.iter().map(|v| v.name()).collect();
+                expr.handler = self.boxed(expr.handler, &shadow(env, &bound));
+                Expr::Try(expr)
+            }
+            Expr::Tuple(mut tuple) => {
+                tuple.elements = self.exprs(tuple.elements, env);
+                Expr::Tuple(tuple)
+            }
+            Expr::Values(mut values) => {
+                values.values = self.exprs(values.values, env);
+                Expr::Values(values)
+            }
+        }
+    }
+
+    /// Returns the function to inline in place of applying `callee` to `arity` arguments, if any
+    fn callee(&mut self, callee: &Expr, arity: usize, env: &Env) -> Option<Rc<Candidate>> {
+        let Expr::Var(var) = callee else { return None; };
+        let candidate = match var.arity {
+            Some(_) => self.local(var.name(), arity)?,
+            None => env.get(&var.name()).cloned()?,
+        };
+        if candidate.fun.vars.len() != arity {
+            return None;
+        }
+        self.spend(candidate)
+    }
+
+    /// Returns the function to inline in place of `call`, if it is a call to a local function
+    ///
+    /// Calls of the form `?MODULE:f()` are not local calls, as they must call the latest version
+    /// of the module, such calls don't have the `local` annotation.
+    fn local_callee(&mut self, call: &Call) -> Option<Rc<Candidate>> {
+        if !call.annotations.contains(symbols::Local) {
+            return None;
+        }
+        let candidate = self.local(call.function.as_atom()?, call.args.len())?;
+        self.spend(candidate)
+    }
+
+    /// Deducts the size of `candidate` from the remaining effort, unless it was given in
+    /// `{inline, [F/A]}`, returning `None` if there isn't enough effort left to inline it
+    fn spend(&mut self, candidate: Rc<Candidate>) -> Option<Rc<Candidate>> {
+        if !candidate.listed {
+            self.effort = self.effort.checked_sub(candidate.size)?;
+        }
+        Some(candidate)
+    }
+
+    fn local(&self, function: Symbol, arity: usize) -> Option<Rc<Candidate>> {
+        // Calls to the current function are never inlined
+        if function == self.current.function && arity == self.current.arity as usize {
+            return None;
+        }
+        self.candidates.get(&(function, arity)).cloned()
+    }
+
+    /// Replaces a call to `candidate` with a copy of its body, in which the parameters are bound
+    /// to `args`, and all other bound variables have been given fresh names
+    fn inline(&mut self, span: SourceSpan, candidate: &Candidate, args: Vec<Expr>) -> Expr {
+        self.changed = true;
+
+        let fun = &candidate.fun;
+        let mut scope = HashMap::new();
+        let mut params = fun.vars.clone();
+        for param in params.iter_mut() {
+            self.bind(param, &mut scope);
+        }
+        // Used to report `function_clause` errors raised by the inlined body
+        let origin = Literal::tuple(
+            span,
+            vec![
+                Literal::atom(span, fun.name),
+                Literal::integer(span, fun.vars.len()),
+            ],
+        );
+        let mut body = fun.body.as_ref().clone();
+        self.rename(&mut body, &scope, &origin);
+
+        params
+            .into_iter()
+            .zip(args)
+            .rev()
+            .fold(body, |body, (param, arg)| {
+                Expr::Let(Let::new(span, vec![param], arg, body))
+            })
+    }
+
+    /// Gives `var` a fresh name, recording the substitution in `scope`
+    fn bind(&mut self, var: &mut Var, scope: &mut HashMap<Symbol, Symbol>) {
+        let id = self.var_counter;
+        self.var_counter += 1;
+        let name = Symbol::intern(&format!("${}", id));
+        scope.insert(var.name(), name);
+        var.name = Ident::new(name, var.span());
+    }
+
+    fn bind_all(
+        &mut self,
+        vars: &mut [Var],
+        scope: &HashMap<Symbol, Symbol>,
+    ) -> HashMap<Symbol, Symbol> {
+        let mut scope = scope.clone();
+        for var in vars.iter_mut() {
+            self.bind(var, &mut scope);
+        }
+        scope
+    }
+
+    /// Renames all variables in `expr` according to `scope`, giving fresh names to any
+    /// variables bound within `expr`
+    fn rename(&mut self, expr: &mut Expr, scope: &HashMap<Symbol, Symbol>, origin: &Literal) {
+        match expr {
+            Expr::Var(var) => rename_var(var, scope),
+            Expr::Alias(Alias { var, pattern, .. }) => {
+                rename_var(var, scope);
+                self.rename(pattern, scope, origin);
+            }
+            Expr::Literal(_) => (),
+            Expr::Apply(Apply { callee, args, .. }) => {
+                self.rename(callee, scope, origin);
+                self.rename_all(args, scope, origin);
+            }
+            Expr::Binary(Binary { segments, .. }) => {
+                for segment in segments.iter_mut() {
+                    self.rename(&mut segment.value, scope, origin);
+                    if let Some(size) = segment.size.as_deref_mut() {
+                        self.rename(size, scope, origin);
+                    }
+                }
+            }
+            Expr::Call(Call {
+                module,
+                function,
+                args,
+                ..
+            }) => {
+                self.rename(module, scope, origin);
+                self.rename(function, scope, origin);
+                self.rename_all(args, scope, origin);
+            }
+            Expr::Case(Case { arg, clauses, .. }) => {
+                self.rename(arg, scope, origin);
+                self.rename_clauses(clauses, scope, origin);
+            }
+            Expr::Catch(Catch { body, .. }) => self.rename(body, scope, origin),
+            Expr::Cons(Cons { head, tail, .. }) => {
+                self.rename(head, scope, origin);
+                self.rename(tail, scope, origin);
+            }
+            Expr::Fun(Fun { vars, body, .. }) => {
+                let scope = self.bind_all(vars, scope);
+                self.rename(body, &scope, origin);
+            }
+            Expr::If(If {
+                guard,
+                then_body,
+                else_body,
+                ..
+            }) => {
+                self.rename(guard, scope, origin);
+                self.rename(then_body, scope, origin);
+                self.rename(else_body, scope, origin);
+            }
+            Expr::Let(Let {
+                vars, arg, body, ..
+            }) => {
+                self.rename(arg, scope, origin);
+                let scope = self.bind_all(vars, scope);
+                self.rename(body, &scope, origin);
+            }
+            Expr::LetRec(LetRec { defs, body, .. }) => {
+                for (_, def) in defs.iter_mut() {
+                    self.rename(def, scope, origin);
+                }
+                self.rename(body, scope, origin);
+            }
+            Expr::Map(Map { arg, pairs, .. }) => {
+                self.rename(arg, scope, origin);
+                for pair in pairs.iter_mut() {
+                    self.rename(&mut pair.key, scope, origin);
+                    self.rename(&mut pair.value, scope, origin);
+                }
+            }
+            Expr::PrimOp(PrimOp { name, args, .. }) => {
+                self.rename_all(args, scope, origin);
+                // Attribute `function_clause` errors to the function they originated from
+                if *name == symbols::MatchFail {
+                    if let Some(Expr::Tuple(reason)) = args.first_mut() {
+                        let is_function_clause = reason
+                            .elements
+                            .first()
+                            .map(|e| e.is_atom_value(symbols::FunctionClause))
+                            .unwrap_or_default();
+                        if is_function_clause && !reason.annotations.contains(symbols::Function) {
+                            reason
+                                .annotations
+                                .insert_mut(symbols::Function, origin.clone());
+                        }
+                    }
+                }
+            }
+            Expr::Receive(Receive {
+                clauses,
+                timeout,
+                action,
+                ..
+            }) => {
+                self.rename_clauses(clauses, scope, origin);
+                self.rename(timeout, scope, origin);
+                self.rename(action, scope, origin);
+            }
+            Expr::Seq(Seq { arg, body, .. }) => {
+                self.rename(arg, scope, origin);
+                self.rename(body, scope, origin);
+            }
+            Expr::Try(Try {
+                arg,
+                vars,
+                body,
+                evars,
+                handler,
+                ..
+            }) => {
+                self.rename(arg, scope, origin);
+                let body_scope = self.bind_all(vars, scope);
+                self.rename(body, &body_scope, origin);
+                let handler_scope = self.bind_all(evars, scope);
+                self.rename(handler, &handler_scope, origin);
+            }
+            Expr::Tuple(Tuple { elements, .. }) => self.rename_all(elements, scope, origin),
+            Expr::Values(Values { values, .. }) => self.rename_all(values, scope, origin),
+        }
+    }
+
+    fn rename_all(
+        &mut self,
+        exprs: &mut [Expr],
+        scope: &HashMap<Symbol, Symbol>,
+        origin: &Literal,
+    ) {
+        for expr in exprs.iter_mut() {
+            self.rename(expr, scope, origin);
+        }
+    }
+
+    fn rename_clauses(
+        &mut self,
+        clauses: &mut [Clause],
+        scope: &HashMap<Symbol, Symbol>,
+        origin: &Literal,
+    ) {
+        for clause in clauses.iter_mut() {
+            let mut bound = BTreeSet::new();
+            for pattern in clause.patterns.iter() {
+                pattern_vars(pattern, &mut bound);
+            }
+            let mut scope = scope.clone();
+            for name in bound.iter() {
+                let mut var = Var::new(Ident::with_empty_span(*name));
+                self.bind(&mut var, &mut scope);
+            }
+            // The bound variables have been added to the scope, so renaming the patterns will
+            // rename both the bound variables, and any variables used in binary sizes or map keys
+            self.rename_all(clause.patterns.as_mut_slice(), &scope, origin);
+            if let Some(guard) = clause.guard.as_deref_mut() {
+                self.rename(guard, &scope, origin);
+            }
+            self.rename(&mut clause.body, &scope, origin);
+        }
+    }
+}
+
+fn rename_var(var: &mut Var, scope: &HashMap<Symbol, Symbol>) {
+    if var.arity.is_some() {
+        return;
+    }
+    if let Some(name) = scope.get(&var.name()) {
+        var.name = Ident::new(*name, var.span());
+    }
+}
+
+/// Returns a new environment in which the given variables are bound, removing any funs which
+/// are themselves shadowed, or which refer to a variable that would now be shadowed
+fn shadow(env: &Env, bound: &BTreeSet<Symbol>) -> Env {
+    let mut env = env.clone();
+    let shadowed = env
+        .iter()
+        .filter(|(name, candidate)| bound.contains(name) || !candidate.names.is_disjoint(bound))
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>();
+    for name in shadowed.iter() {
+        env.remove_mut(name);
+    }
+    env
+}
+
+/// Collects the variables bound by `pattern`
+fn pattern_vars(pattern: &Expr, bound: &mut BTreeSet<Symbol>) {
+    match pattern {
+        Expr::Var(var) => {
+            bound.insert(var.name());
+        }
+        Expr::Alias(Alias { var, pattern, .. }) => {
+            bound.insert(var.name());
+            pattern_vars(pattern, bound);
+        }
+        Expr::Cons(Cons { head, tail, .. }) => {
+            pattern_vars(head, bound);
+            pattern_vars(tail, bound);
+        }
+        Expr::Tuple(Tuple { elements, .. }) => {
+            elements.iter().for_each(|e| pattern_vars(e, bound));
+        }
+        Expr::Values(Values { values, .. }) => values.iter().for_each(|v| pattern_vars(v, bound)),
+        // Binary sizes and map keys refer to variables, they do not bind them
+        Expr::Binary(Binary { segments, .. }) => {
+            segments.iter().for_each(|s| pattern_vars(&s.value, bound));
+        }
+        Expr::Map(Map { pairs, .. }) => pairs.iter().for_each(|p| pattern_vars(&p.value, bound)),
+        _ => (),
+    }
+}
+
+/// Collects the names of all variables referenced in `expr`
+fn var_names(expr: &Expr, names: &mut BTreeSet<Symbol>) {
+    match expr {
+        Expr::Var(var) => {
+            names.insert(var.name());
+        }
+        Expr::Alias(Alias { var, .. }) => {
+            names.insert(var.name());
+        }
+        Expr::Fun(Fun { vars, .. }) | Expr::Let(Let { vars, .. }) => {
+            names.extend(vars.iter().map(|v| v.name()));
+        }
+        Expr::Try(Try { vars, evars, .. }) => {
+            names.extend(vars.iter().chain(evars.iter()).map(|v| v.name()));
+        }
+        _ => (),
+    }
+    for child in subexprs(expr) {
+        var_names(child, names);
+    }
+}
+
+/// Returns the size of `expr`, measured in IR nodes, or `None` if `expr` cannot be inlined
+fn size(expr: &Expr) -> Option<usize> {
+    match expr {
+        Expr::Fun(_) | Expr::LetRec(_) | Expr::Receive(_) => None,
+        expr => subexprs(expr)
+            .drain(..)
+            .map(size)
+            .sum::<Option<usize>>()
+            .map(|size| size + 1),
+    }
+}
+
+/// Returns the immediate subexpressions of `expr`, including the patterns and guards of clauses
+fn subexprs(expr: &Expr) -> Vec<&Expr> {
+    fn clauses<'a>(clauses: &'a [Clause], children: &mut Vec<&'a Expr>) {
+        for clause in clauses.iter() {
+            children.extend(clause.patterns.iter());
+            children.extend(clause.guard.as_deref());
+            children.push(clause.body.as_ref());
+        }
+    }
+
+    let mut children = vec![];
+    match expr {
+        Expr::Literal(_) | Expr::Var(_) => (),
+        Expr::Alias(Alias { pattern, .. }) => children.push(pattern.as_ref()),
+        Expr::Apply(Apply { callee, args, .. }) => {
+            children.push(callee.as_ref());
+            children.extend(args.iter());
+        }
+        Expr::Binary(Binary { segments, .. }) => {
+            for segment in segments.iter() {
+                children.push(segment.value.as_ref());
+                children.extend(segment.size.as_deref());
+            }
+        }
+        Expr::Call(Call {
+            module,
+            function,
+            args,
+            ..
+        }) => {
+            children.push(module.as_ref());
+            children.push(function.as_ref());
+            children.extend(args.iter());
+        }
+        Expr::Case(Case {
+            arg, clauses: cs, ..
+        }) => {
+            children.push(arg.as_ref());
+            clauses(cs.as_slice(), &mut children);
+        }
+        Expr::Catch(Catch { body, .. }) | Expr::Fun(Fun { body, .. }) => {
+            children.push(body.as_ref())
+        }
+        Expr::Cons(Cons { head, tail, .. }) => {
+            children.push(head.as_ref());
+            children.push(tail.as_ref());
+        }
+        Expr::If(If {
+            guard,
+            then_body,
+            else_body,
+            ..
+        }) => {
+            children.push(guard.as_ref());
+            children.push(then_body.as_ref());
+            children.push(else_body.as_ref());
+        }
+        Expr::Let(Let { arg, body, .. }) | Expr::Seq(Seq { arg, body, .. }) => {
+            children.push(arg.as_ref());
+            children.push(body.as_ref());
+        }
+        Expr::LetRec(LetRec { defs, body, .. }) => {
+            children.extend(defs.iter().map(|(_, def)| def));
+            children.push(body.as_ref());
+        }
+        Expr::Map(Map { arg, pairs, .. }) => {
+            children.push(arg.as_ref());
+            for pair in pairs.iter() {
+                children.push(pair.key.as_ref());
+                children.push(pair.value.as_ref());
+            }
+        }
+        Expr::PrimOp(PrimOp { args, .. }) => children.extend(args.iter()),
+        Expr::Receive(Receive {
+            clauses: cs,
+            timeout,
+            action,
+            ..
+        }) => {
+            clauses(cs.as_slice(), &mut children);
+            children.push(timeout.as_ref());
+            children.push(action.as_ref());
+        }
+        Expr::Try(Try {
+            arg, body, handler, ..
+        }) => {
+            children.push(arg.as_ref());
+            children.push(body.as_ref());
+            children.push(handler.as_ref());
+        }
+        Expr::Tuple(Tuple { elements, .. }) => children.extend(elements.iter()),
+        Expr::Values(Values { values, .. }) => children.extend(values.iter()),
+    }
+    children
+}
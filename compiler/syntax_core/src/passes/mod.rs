@@ -3,10 +3,14 @@ use firefly_intern::{Ident, Symbol};
 use firefly_syntax_base::*;
 
 mod annotate;
+mod fold;
+mod inline;
 mod known;
 mod rewrites;
 
 pub use self::annotate::AnnotateVariableUsage;
+pub use self::fold::FoldConstants;
+pub use self::inline::InlineFunctions;
 pub(self) use self::known::Known;
 pub use self::rewrites::*;
 
@@ -38,6 +38,7 @@ pub enum Expr {
     // Comprehensions
     ListComprehension(ListComprehension),
     BinaryComprehension(BinaryComprehension),
+    MapComprehension(MapComprehension),
     Generator(Generator),
     // Complex expressions
     Begin(Begin),
@@ -51,6 +52,8 @@ pub enum Expr {
     Case(Case),
     Receive(Receive),
     Try(Try),
+    Maybe(Maybe),
+    MaybeMatch(MaybeMatch),
     Fun(Fun),
     Protect(Protect),
 }
@@ -978,6 +981,21 @@ impl PartialEq for BinaryComprehension {
     }
 }
 
+/// A map comprehension, e.g. `#{K => V || K := V <- Map}`
+#[derive(Debug, Clone, Spanned)]
+pub struct MapComprehension {
+    #[span]
+    pub span: SourceSpan,
+    pub key: Box<Expr>,
+    pub value: Box<Expr>,
+    pub qualifiers: Vec<Expr>,
+}
+impl PartialEq for MapComprehension {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key && self.value == other.value && self.qualifiers == other.qualifiers
+    }
+}
+
 /// A generator is one of two types of expressions that act as qualifiers in a commprehension, the other is a filter
 #[derive(Debug, Clone, Spanned)]
 pub struct Generator {
@@ -997,6 +1015,10 @@ impl PartialEq for Generator {
 pub enum GeneratorType {
     Default,
     Bitstring,
+    /// A map generator, e.g. `K := V <- Map`
+    ///
+    /// The pattern of a map generator is always a 2-tuple of the key and value patterns
+    Map,
 }
 impl Default for GeneratorType {
     fn default() -> Self {
@@ -1234,6 +1256,38 @@ impl PartialEq for Try {
     }
 }
 
+// A value-based error handling block (EEP-49), e.g. maybe expr1, .., exprN else clauses end
+//
+// Only available when the `maybe_expr` feature is enabled
+#[derive(Debug, Clone, Spanned)]
+pub struct Maybe {
+    #[span]
+    pub span: SourceSpan,
+    pub body: Vec<Expr>,
+    pub else_clauses: Option<Vec<Clause>>,
+}
+impl PartialEq for Maybe {
+    fn eq(&self, other: &Self) -> bool {
+        self.body == other.body && self.else_clauses == other.else_clauses
+    }
+}
+
+// A conditional match in the body of a `maybe` block, e.g. pattern ?= expr
+//
+// If the pattern does not match, the `maybe` block short-circuits with the value of `expr`
+#[derive(Debug, Clone, Spanned)]
+pub struct MaybeMatch {
+    #[span]
+    pub span: SourceSpan,
+    pub pattern: Box<Expr>,
+    pub expr: Box<Expr>,
+}
+impl PartialEq for MaybeMatch {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern && self.expr == other.expr
+    }
+}
+
 /// Represents the `after` clause of a `receive` expression
 #[derive(Debug, Clone, Spanned)]
 pub struct After {
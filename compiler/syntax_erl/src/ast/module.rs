@@ -1,14 +1,17 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::collections::{HashMap, HashSet};
+use std::ops::ControlFlow;
 use std::sync::Arc;
 
 use anyhow::anyhow;
 
 use firefly_diagnostics::*;
+use firefly_intern::symbols;
 use firefly_syntax_base::*;
 use firefly_util::emit::Emit;
 
 use crate::ast::{self, *};
+use crate::visit::{self, VisitMut};
 
 /// Represents expressions valid at the top level of a module body
 #[derive(Debug, Clone, PartialEq, Spanned)]
@@ -67,6 +70,8 @@ pub struct Module {
     pub vsn: Option<ast::Literal>,
     pub author: Option<ast::Literal>,
     pub compile: Option<CompileOptions>,
+    // The options given via `-compile`, in the order they were declared, as reported by module_info
+    pub compile_options: Vec<ast::Literal>,
     pub on_load: Option<Span<FunctionName>>,
     pub nifs: HashSet<Span<FunctionName>>,
     pub imports: HashMap<FunctionName, Span<Signature>>,
@@ -77,6 +82,8 @@ pub struct Module {
     pub specs: HashMap<FunctionName, TypeSpec>,
     pub behaviours: HashSet<Ident>,
     pub callbacks: HashMap<FunctionName, Callback>,
+    // The callbacks named by `-optional_callbacks`
+    pub optional_callbacks: HashSet<FunctionName>,
     pub records: HashMap<Symbol, Record>,
     pub attributes: HashMap<Ident, ast::Literal>,
     pub functions: BTreeMap<FunctionName, Function>,
@@ -115,6 +122,59 @@ impl Module {
         !self.is_local(&local_name) && self.imports.contains_key(&local_name)
     }
 
+    /// Returns the set of other modules referenced by the `-on_load` function of this module, if present
+    ///
+    /// This includes the modules referenced by any local function reachable from the `-on_load` function.
+    pub fn on_load_dependencies(&self) -> BTreeSet<Symbol> {
+        let Some(on_load) = self.on_load.as_ref() else { return BTreeSet::new(); };
+        let mut visitor = RemoteModules {
+            module: self,
+            modules: BTreeSet::new(),
+            locals: vec![on_load.item],
+        };
+        let mut visited = BTreeSet::new();
+        while let Some(name) = visitor.locals.pop() {
+            if !visited.insert(name) {
+                continue;
+            }
+            if let Some(function) = self.functions.get(&name) {
+                let _ = visitor.visit_mut_function(&mut function.clone());
+            }
+        }
+        visitor.modules
+    }
+
+    /// Returns the set of functions in other modules which are called or referenced by this module
+    ///
+    /// Calls in which both the module and function are statically known are included, along with
+    /// any functions imported via `-import`, and calls to auto-imported BIFs. Calls whose module or
+    /// function is only known at runtime are included as calls to `erlang:apply/3`, and funs of
+    /// such functions as calls to `erlang:make_fun/3`, as that is what they are compiled to.
+    pub fn remote_calls(&self) -> BTreeSet<FunctionName> {
+        let mut calls = self.remote_references().calls;
+        calls.extend(self.imports.values().map(|sig| sig.mfa()));
+        calls
+    }
+
+    /// Returns the set of functions in other modules which are referenced by this module as funs,
+    /// i.e. `fun m:f/1`, which are looked up by name when the fun is created
+    pub fn remote_funs(&self) -> BTreeSet<FunctionName> {
+        self.remote_references().funs
+    }
+
+    fn remote_references(&self) -> RemoteCalls {
+        let mut visitor = RemoteCalls {
+            module: self.name(),
+            locals: self.functions.keys().copied().collect(),
+            calls: BTreeSet::new(),
+            funs: BTreeSet::new(),
+        };
+        for function in self.functions.values() {
+            let _ = visitor.visit_mut_function(&mut function.clone());
+        }
+        visitor
+    }
+
     /// Creates a new, empty module with the given name and span
     pub fn new(name: Ident, span: SourceSpan) -> Self {
         Self {
@@ -125,6 +185,7 @@ impl Module {
             on_load: None,
             nifs: HashSet::new(),
             compile: None,
+            compile_options: vec![],
             imports: HashMap::new(),
             exports: HashSet::new(),
             removed: HashMap::new(),
@@ -133,6 +194,7 @@ impl Module {
             specs: HashMap::new(),
             behaviours: HashSet::new(),
             callbacks: HashMap::new(),
+            optional_callbacks: HashSet::new(),
             records: HashMap::new(),
             attributes: HashMap::new(),
             functions: BTreeMap::new(),
@@ -151,6 +213,11 @@ impl Module {
         let name = body.iter().find_map(|t| t.module_name()).ok_or_else(|| {
             anyhow!("invalid module, no module declaration present in given forms")
         })?;
+        // The module declaration has been consumed, all remaining forms are part of the body
+        let body = body
+            .into_iter()
+            .filter(|t| t.module_name().is_none())
+            .collect();
         Ok(Self::new_with_forms(reporter, codemap, span, name, body))
     }
 
@@ -176,6 +243,7 @@ impl Module {
             on_load: None,
             nifs: HashSet::new(),
             compile: None,
+            compile_options: vec![],
             imports: HashMap::new(),
             exports: HashSet::new(),
             removed: HashMap::new(),
@@ -185,6 +253,7 @@ impl Module {
 
             behaviours: HashSet::new(),
             callbacks: HashMap::new(),
+            optional_callbacks: HashSet::new(),
             records: HashMap::new(),
             attributes: HashMap::new(),
             functions: BTreeMap::new(),
@@ -251,3 +320,117 @@ impl PartialEq for Module {
         true
     }
 }
+
+/// Collects the modules referenced by remote calls and captures, other than the current module,
+/// along with the local functions referenced, so that they can be visited in turn
+struct RemoteModules<'m> {
+    module: &'m Module,
+    modules: BTreeSet<Symbol>,
+    locals: Vec<FunctionName>,
+}
+impl<'m> RemoteModules<'m> {
+    fn reference(&mut self, name: FunctionName) {
+        match name.module {
+            None if self.module.is_local(&name) => self.locals.push(name),
+            None => {
+                if let Some(import) = self.module.imports.get(&name) {
+                    self.modules.insert(import.module);
+                }
+            }
+            Some(module) if module == self.module.name() => self.locals.push(name.to_local()),
+            Some(module) => {
+                self.modules.insert(module);
+            }
+        }
+    }
+}
+impl<'m> VisitMut<()> for RemoteModules<'m> {
+    fn visit_mut_apply(&mut self, apply: &mut Apply) -> ControlFlow<()> {
+        let arity = apply.args.len() as u8;
+        match apply.callee.as_ref() {
+            Expr::Literal(ast::Literal::Atom(f)) => {
+                self.reference(FunctionName::new_local(f.name, arity))
+            }
+            Expr::Remote(remote) => {
+                if let Ok(name) = remote.try_eval(arity) {
+                    self.reference(name);
+                }
+            }
+            _ => (),
+        }
+        visit::visit_mut_apply(self, apply)
+    }
+
+    fn visit_mut_remote(&mut self, remote: &mut Remote) -> ControlFlow<()> {
+        if let Some(module) = remote.module.as_atom_symbol() {
+            if module != self.module.name() {
+                self.modules.insert(module);
+            }
+        }
+        visit::visit_mut_remote(self, remote)
+    }
+
+    fn visit_mut_function_var(&mut self, name: &mut FunctionVar) -> ControlFlow<()> {
+        match name {
+            FunctionVar::Resolved(name) | FunctionVar::PartiallyResolved(name) => {
+                self.reference(name.item)
+            }
+            FunctionVar::Unresolved(_) => (),
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+struct RemoteCalls {
+    module: Symbol,
+    locals: BTreeSet<FunctionName>,
+    calls: BTreeSet<FunctionName>,
+    funs: BTreeSet<FunctionName>,
+}
+impl VisitMut<()> for RemoteCalls {
+    fn visit_mut_apply(&mut self, apply: &mut Apply) -> ControlFlow<()> {
+        let arity = apply.args.len() as u8;
+        match apply.callee.as_ref() {
+            Expr::Remote(remote) => {
+                let module = remote.module.as_atom_symbol();
+                let function = remote.function.as_atom_symbol();
+                match (module, function) {
+                    (Some(m), _) if m == self.module => (),
+                    (Some(m), Some(f)) => {
+                        self.calls.insert(FunctionName::new(m, f, arity));
+                    }
+                    _ => {
+                        self.calls
+                            .insert(FunctionName::new(symbols::Erlang, symbols::Apply, 3));
+                    }
+                }
+            }
+            Expr::Literal(ast::Literal::Atom(f)) => {
+                let local = FunctionName::new_local(f.name, arity);
+                let bif = FunctionName::new(symbols::Erlang, f.name, arity);
+                if !self.locals.contains(&local) && bifs::get(&bif).is_some() {
+                    self.calls.insert(bif);
+                }
+            }
+            _ => (),
+        }
+        visit::visit_mut_apply(self, apply)
+    }
+
+    fn visit_mut_function_var(&mut self, name: &mut FunctionVar) -> ControlFlow<()> {
+        match name {
+            FunctionVar::Resolved(name) => {
+                if name.module.is_some() && name.module != Some(self.module) {
+                    self.calls.insert(name.item);
+                    self.funs.insert(name.item);
+                }
+            }
+            FunctionVar::PartiallyResolved(_) => (),
+            FunctionVar::Unresolved(_) => {
+                self.calls
+                    .insert(FunctionName::new(symbols::Erlang, symbols::MakeFun, 3));
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
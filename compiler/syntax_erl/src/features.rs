@@ -72,3 +72,8 @@ impl Feature {
 pub fn get(op: &Symbol) -> Option<&'static Feature> {
     FEATURE_MAP.get(op).copied()
 }
+
+/// Get an iterator over all of the known features
+pub fn iter() -> impl Iterator<Item = &'static Feature> {
+    FEATURES.iter()
+}
@@ -335,6 +335,9 @@ pub enum Token {
     Of,
     Receive,
     When,
+    // Feature-gated keywords, see `maybe_expr`
+    Maybe,
+    Else,
     // Attributes
     Record,
     Spec,
@@ -410,6 +413,8 @@ pub enum Token {
     DotDotDot,
     Question,
     DoubleQuestion,
+    // ?=
+    QuestionEquals,
 }
 impl PartialEq for Token {
     fn eq(&self, other: &Token) -> bool {
@@ -561,6 +566,8 @@ impl fmt::Display for Token {
             Token::Of => write!(f, "of"),
             Token::Receive => write!(f, "receive"),
             Token::When => write!(f, "when"),
+            Token::Maybe => write!(f, "maybe"),
+            Token::Else => write!(f, "else"),
             Token::Record => write!(f, "record"),
             Token::Spec => write!(f, "spec"),
             Token::Callback => write!(f, "callback"),
@@ -620,6 +627,7 @@ impl fmt::Display for Token {
             Token::DotDotDot => write!(f, "..."),
             Token::Question => write!(f, "?"),
             Token::DoubleQuestion => write!(f, "??"),
+            Token::QuestionEquals => write!(f, "?="),
         }
     }
 }
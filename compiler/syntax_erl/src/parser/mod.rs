@@ -887,6 +887,60 @@ bar() -> yay.
         );
     }
 
+    #[test]
+    fn parse_maybe() {
+        let _result: Module = parse(
+            ParseConfig::default(),
+            Arc::new(CodeMap::new()),
+            r#"-module(foo).
+-feature(maybe_expr, enable).
+
+bar(X) ->
+    maybe
+        {ok, Y} ?= baz(X),
+        Z = Y + 1,
+        {ok, _} ?= baz(Z)
+    end.
+
+qux(X) ->
+    maybe
+        {ok, Y} ?= baz(X),
+        Y
+    else
+        {error, _} = Err -> Err;
+        _ -> 'maybe'
+    end.
+
+baz(X) -> {ok, X}.
+"#,
+        );
+    }
+
+    #[test]
+    fn parse_maybe_disabled() {
+        let _result: Module = parse(
+            ParseConfig::default(),
+            Arc::new(CodeMap::new()),
+            r#"-module(foo).
+
+bar() -> [maybe, else].
+"#,
+        );
+
+        let _errs = parse_fail::<Module, &str>(
+            ParseConfig::default(),
+            Arc::new(CodeMap::new()),
+            r#"-module(foo).
+
+bar(X) ->
+    maybe
+        {ok, Y} ?= X,
+        Y
+    end.
+"#,
+        );
+    }
+
     #[test]
     fn parse_elixir_enum_erl() {
         use std::io::Read;
@@ -207,7 +207,7 @@ pub fn analyze_attribute(reporter: &Reporter, module: &mut Module, attr: Attribu
                 );
             }
         },
-        Attribute::Callback(callback) => {
+        Attribute::Callback(mut callback) => {
             let first_sig = callback.sigs.first().unwrap();
             let arity = first_sig.params.len();
 
@@ -241,6 +241,9 @@ pub fn analyze_attribute(reporter: &Reporter, module: &mut Module, attr: Attribu
             let local_cb_name = cb_name.to_local();
             match module.callbacks.get(&local_cb_name) {
                 None => {
+                    if module.optional_callbacks.contains(&local_cb_name) {
+                        callback.optional = true;
+                    }
                     module.callbacks.insert(local_cb_name, callback);
                     return;
                 }
@@ -302,15 +305,28 @@ pub fn analyze_attribute(reporter: &Reporter, module: &mut Module, attr: Attribu
                 }
             }
         }
-        Attribute::Compile(_, compile) => match module.compile {
-            None => match compile_opts_from_expr(module.name, &compile, reporter) {
-                Ok(opts) => module.compile = Some(opts),
-                Err(opts) => module.compile = Some(opts),
-            },
-            Some(ref mut opts) => {
-                let _ = merge_compile_opts_from_expr(opts, module.name, &compile, reporter);
+        Attribute::Compile(_, compile) => {
+            // Retain the options as written so that they can be reported by module_info(compile)
+            let compile_lit: Result<Literal, _> = compile.clone().try_into();
+            match compile_lit {
+                Ok(list @ (Literal::Nil(_) | Literal::Cons(..))) => {
+                    if let Ok(opts) = list.as_proper_list() {
+                        module.compile_options.extend(opts);
+                    }
+                }
+                Ok(opt) => module.compile_options.push(opt),
+                Err(_) => (),
+            }
+            match module.compile {
+                None => match compile_opts_from_expr(module.name, &compile, reporter) {
+                    Ok(opts) => module.compile = Some(opts),
+                    Err(opts) => module.compile = Some(opts),
+                },
+                Some(ref mut opts) => {
+                    let _ = merge_compile_opts_from_expr(opts, module.name, &compile, reporter);
+                }
             }
-        },
+        }
         Attribute::Deprecation(mut deprecations) => {
             for deprecation in deprecations.drain(..) {
                 match deprecation {
@@ -353,6 +369,23 @@ pub fn analyze_attribute(reporter: &Reporter, module: &mut Module, attr: Attribu
                     return;
                 }
                 "optional_callbacks" => {
+                    for callback in to_list_simple(&attr.value) {
+                        match callback {
+                            Expr::FunctionVar(FunctionVar::PartiallyResolved(name)) => {
+                                let name = name.to_local();
+                                if let Some(cb) = module.callbacks.get_mut(&name) {
+                                    cb.optional = true;
+                                }
+                                module.optional_callbacks.insert(name);
+                            }
+                            other => {
+                                reporter.show_warning(
+                                    "invalid -optional_callbacks attribute",
+                                    &[(other.span(), "expected function name/arity term")],
+                                );
+                            }
+                        }
+                    }
                     return;
                 }
                 // Drop dialyzer attributes as they are unused
@@ -496,6 +529,15 @@ fn set_compile_option(
                 "warn_untyped_record" => options.warn_untyped_record = true,
                 "nowarn_untyped_record" => options.warn_untyped_record = false,
 
+                "warn_undefined_function" => options.warn_undefined_function = true,
+                "nowarn_undefined_function" => options.warn_undefined_function = false,
+
+                "warn_unknown_module" => options.warn_unknown_module = true,
+                "nowarn_unknown_module" => options.warn_unknown_module = false,
+
+                "warn_unused_export" => options.warn_unused_export = true,
+                "nowarn_unused_export" => options.warn_unused_export = false,
+
                 "warn_missing_spec" => options.warn_missing_spec = true,
                 "nowarn_missing_spec" => options.warn_missing_spec = false,
 
@@ -539,6 +581,12 @@ fn set_compile_option(
                         no_warn_deprecated_functions(options, module, &list, reporter)
                     }
                     "inline" => inline_functions(options, module, &list, reporter),
+                    "inline_size" => {
+                        options.inline_size = inline_limit(&elements[1], reporter)?;
+                    }
+                    "inline_effort" => {
+                        options.inline_effort = inline_limit(&elements[1], reporter)?;
+                    }
                     // Ignored
                     "hipe" => {}
                     _name => {
@@ -632,16 +680,29 @@ fn no_warn_unused_functions(
     reporter: &Reporter,
 ) {
     for fun in funs {
-        match fun {
-            Expr::FunctionVar(FunctionVar::PartiallyResolved(name)) => {
-                options.no_warn_unused_functions.insert(*name);
+        let name = match fun {
+            // e.g. some_fun/0
+            Expr::FunctionVar(FunctionVar::PartiallyResolved(name)) => Some(*name),
+            // e.g. {some_fun, 0}
+            Expr::Tuple(tup) => match tup.elements.as_slice() {
+                [Expr::Literal(Literal::Atom(name)), Expr::Literal(Literal::Integer(_, arity))] => {
+                    let name = FunctionName::new_local(name.name, arity.to_arity());
+                    Some(Span::new(tup.span, name))
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        match name {
+            Some(name) => {
+                options.no_warn_unused_functions.insert(name);
             }
-            other => {
-                let other_span = other.span();
+            None => {
+                let fun_span = fun.span();
                 reporter.diagnostic(
                     Diagnostic::warning()
                         .with_message("invalid compile option")
-                        .with_labels(vec![Label::primary(other_span.source_id(), other_span)
+                        .with_labels(vec![Label::primary(fun_span.source_id(), fun_span)
                             .with_message(
                                 "expected function name/arity term for no_warn_unused_functions",
                             )]),
@@ -758,6 +819,23 @@ fn inline_functions(
     }
 }
 
+fn inline_limit(expr: &Expr, reporter: &Reporter) -> Result<usize, ()> {
+    if let Expr::Literal(Literal::Integer(_, i)) = expr {
+        if let Some(limit) = i.to_usize() {
+            return Ok(limit);
+        }
+    }
+
+    let span = expr.span();
+    reporter.diagnostic(
+        Diagnostic::warning()
+            .with_message("invalid compile option")
+            .with_labels(vec![Label::primary(span.source_id(), span)
+                .with_message("expected a non-negative integer")]),
+    );
+    Err(())
+}
+
 fn to_list_simple(mut expr: &Expr) -> Vec<Expr> {
     let mut list = Vec::new();
     loop {
@@ -1,7 +1,10 @@
+use firefly_binary::BitVec;
 use firefly_diagnostics::*;
-use firefly_intern::symbols;
+use firefly_intern::{symbols, Ident, Symbol};
+use firefly_number::{BigInt, Integer, Sign};
 use firefly_pass::Pass;
 use firefly_syntax_base::*;
+use firefly_util::md5;
 
 use crate::ast::{self, *};
 
@@ -45,40 +48,113 @@ impl Pass for AddAutoImports {
 /// * `record_info/2`
 /// * `behaviour_info/1` (optional)
 ///
-/// NOTE: We do not provide the `md5` module info key, as its definition in Erlang doesn't
-/// mean anything for us, and producing our own has no known benefit at this time.
-pub struct DefinePseudoLocals;
-impl Pass for DefinePseudoLocals {
+/// The data returned by `module_info` mirrors BEAM, with the exception of `md5`, which is
+/// computed from the module source rather than from compiled code, as we have no equivalent
+/// of the BEAM code chunks from which it is derived there.
+///
+/// The `compile` info reports the given compiler version, and the given command-line options
+/// followed by those of any `-compile` attributes in the module.
+pub struct DefinePseudoLocals<'cm> {
+    codemap: &'cm CodeMap,
+    compiler_version: &'cm str,
+    compile_options: &'cm [ast::Literal],
+}
+impl<'cm> DefinePseudoLocals<'cm> {
+    pub fn new(
+        codemap: &'cm CodeMap,
+        compiler_version: &'cm str,
+        compile_options: &'cm [ast::Literal],
+    ) -> Self {
+        Self {
+            codemap,
+            compiler_version,
+            compile_options,
+        }
+    }
+}
+impl<'cm> Pass for DefinePseudoLocals<'cm> {
     type Input<'a> = &'a mut Module;
     type Output<'a> = &'a mut Module;
 
     fn run<'a>(&mut self, module: Self::Input<'a>) -> anyhow::Result<Self::Output<'a>> {
-        // Build up list of attributes for module_info
-        let mut attributes = ast_lit_nil!();
-        if let Some(vsn) = module.vsn.take() {
-            let span = vsn.span();
-            let key = ast_lit_atom!(span, symbols::Vsn);
-            let value = ast_lit_tuple_with_span!(span, key, vsn);
+        let span = module.span;
+        let source = self.codemap.get_with_span(span).ok();
+        let md5 = source
+            .as_ref()
+            .map(|file| md5::digest(file.source().as_bytes()))
+            .unwrap_or_default();
 
-            attributes = ast_lit_cons!(value, attributes);
+        // Build up list of attributes for module_info, in source order
+        let mut entries = vec![];
+        match module.vsn.take() {
+            Some(vsn) => entries.push(attribute_entry(Ident::new(symbols::Vsn, vsn.span()), vsn)),
+            // Like BEAM, a module without an explicit version is versioned by its checksum
+            None => {
+                let checksum = Integer::from(BigInt::from_bytes_be(Sign::Plus, &md5));
+                entries.push(attribute_entry(
+                    Ident::new(symbols::Vsn, span),
+                    ast_lit_int!(span, checksum),
+                ));
+            }
         }
         if let Some(author) = module.author.take() {
-            let span = author.span();
-            let key = ast_lit_atom!(span, symbols::Author);
-            let value = ast_lit_tuple_with_span!(span, key, author);
-
-            attributes = ast_lit_cons!(value, attributes);
+            let key = Ident::new(symbols::Author, author.span());
+            entries.push(attribute_entry(key, author));
         }
-        for (name, value) in module.attributes.drain() {
-            let span = name.span;
-            let key = ast::Literal::Atom(name);
-            let value = ast_lit_tuple_with_span!(span, key, value);
-
-            attributes =
-                ast::Literal::Cons(SourceSpan::UNKNOWN, Box::new(value), Box::new(attributes));
+        let mut behaviours = module.behaviours.iter().copied().collect::<Vec<_>>();
+        behaviours.sort_by_key(|b| b.span.start());
+        for behaviour in behaviours {
+            let key = Ident::new(symbols::Behaviour, behaviour.span);
+            entries.push(attribute_entry(key, ast::Literal::Atom(behaviour)));
         }
+        let mut custom = module.attributes.drain().collect::<Vec<_>>();
+        custom.sort_by_key(|(name, _)| name.span.start());
+        for (name, value) in custom {
+            entries.push(attribute_entry(name, value));
+        }
+        let attributes = entries
+            .drain(..)
+            .rev()
+            .fold(ast_lit_nil!(), |tail, entry| ast_lit_cons!(entry, tail));
 
-        // Build up list of exports in {name, arity} form for module_info
+        // Build up the compiler info for module_info, i.e. the compiler version, options and source path
+        let options = self
+            .compile_options
+            .iter()
+            .chain(module.compile_options.iter())
+            .rev()
+            .fold(ast_lit_nil!(), |tail, opt| ast_lit_cons!(opt.clone(), tail));
+        let source_path = match source.as_ref().map(|file| file.name()) {
+            Some(FileName::Real(path)) => std::fs::canonicalize(path)
+                .unwrap_or_else(|_| path.clone())
+                .to_string_lossy()
+                .into_owned(),
+            Some(FileName::Virtual(name)) => name.to_string(),
+            None => String::new(),
+        };
+        let compile = ast_lit_list!(
+            ast_lit_tuple!(
+                ast_lit_atom!(symbols::Version),
+                ast::Literal::String(Ident::with_empty_span(Symbol::intern(
+                    self.compiler_version
+                )))
+            ),
+            ast_lit_tuple!(ast_lit_atom!(symbols::Options), options),
+            ast_lit_tuple!(
+                ast_lit_atom!(symbols::Source),
+                ast::Literal::String(Ident::with_empty_span(Symbol::intern(&source_path)))
+            )
+        );
+
+        let mut checksum = BitVec::new();
+        checksum.push_bytes(&md5);
+        let md5 = ast::Literal::Binary(span, checksum);
+
+        // Build up list of exports in {name, arity} form for module_info, which includes module_info itself
+        for arity in [0, 1] {
+            let name = FunctionName::new_local(symbols::ModuleInfo, arity);
+            module.exports.insert(Span::new(SourceSpan::UNKNOWN, name));
+        }
         let exports = module.exports.iter().fold(ast_lit_nil!(), |tail, export| {
             let name = ast_lit_atom!(export.span(), export.function);
             let arity = ast_lit_int!(export.span(), export.arity.into());
@@ -111,9 +187,10 @@ impl Pass for DefinePseudoLocals {
                 ast_lit_atom!(symbols::Module),
                 ast_lit_atom!(module.name.name)
             ),
-            ast_lit_tuple!(ast_lit_atom!(symbols::Attributes), attributes.clone()),
-            ast_lit_tuple!(ast_lit_atom!(symbols::Compile), ast_lit_nil!()),
             ast_lit_tuple!(ast_lit_atom!(symbols::Exports), exports.clone()),
+            ast_lit_tuple!(ast_lit_atom!(symbols::Attributes), attributes.clone()),
+            ast_lit_tuple!(ast_lit_atom!(symbols::Compile), compile.clone()),
+            ast_lit_tuple!(ast_lit_atom!(symbols::Md5), md5.clone()),
             ast_lit_tuple!(ast_lit_atom!(symbols::Native), ast_lit_atom!(symbols::True))
         );
         let mod_info_0 = Function {
@@ -169,7 +246,7 @@ impl Pass for DefinePseudoLocals {
                         span: SourceSpan::UNKNOWN,
                         patterns: vec![Expr::Literal(ast_lit_atom!(symbols::Compile))],
                         guards: vec![],
-                        body: vec![Expr::Literal(ast_lit_nil!())],
+                        body: vec![Expr::Literal(compile)],
                         compiler_generated: true,
                     },
                 ),
@@ -203,15 +280,13 @@ impl Pass for DefinePseudoLocals {
                         compiler_generated: true,
                     },
                 ),
-                // This clause is to avoid causing crashes in code which asks for this value,
-                // but we don't make guarantees about it being meaningful
                 (
                     Some(Name::Atom(ident!(module_info))),
                     Clause {
                         span: SourceSpan::UNKNOWN,
                         patterns: vec![Expr::Literal(ast_lit_atom!(symbols::Md5))],
                         guards: vec![],
-                        body: vec![Expr::Literal(ast_lit_nil!())],
+                        body: vec![Expr::Literal(md5)],
                         compiler_generated: true,
                     },
                 ),
@@ -322,3 +397,14 @@ fn define_function(module: &mut Module, f: Function) {
     module.exports.insert(Span::new(f.name.span, name));
     module.functions.insert(name, f);
 }
+
+/// Produces an entry of the `attributes` list in module_info, which always has the form
+/// `{Key, [Value]}`, i.e. non-list values are wrapped in a list
+fn attribute_entry(key: Ident, value: ast::Literal) -> ast::Literal {
+    let span = key.span;
+    let value = match value {
+        list @ (ast::Literal::Nil(_) | ast::Literal::Cons(..) | ast::Literal::String(_)) => list,
+        value => ast_lit_cons!(span, value, ast_lit_nil!(span)),
+    };
+    ast_lit_tuple_with_span!(span, ast::Literal::Atom(key), value)
+}
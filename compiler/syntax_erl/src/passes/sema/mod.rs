@@ -2,8 +2,12 @@ mod attributes;
 mod functions;
 mod inject;
 mod records;
+mod unused;
+mod variables;
 mod verify;
 
+use std::sync::Arc;
+
 use firefly_diagnostics::*;
 use firefly_intern::Ident;
 use firefly_pass::Pass;
@@ -14,6 +18,7 @@ use crate::ast;
 pub use self::attributes::analyze_attribute;
 pub use self::functions::analyze_function;
 pub use self::records::analyze_record;
+pub(crate) use self::unused::WarnUnusedFunctions;
 
 /// This pass is responsible for taking a set of top-level forms and
 /// analyzing them in the context of a new module to produce a fully
@@ -23,21 +28,44 @@ pub use self::records::analyze_record;
 ///
 /// * If configured to do so, warns if functions are missing type specs
 /// * Warns about type specs for undefined functions
+/// * Warns about unused records and types
+/// * If configured to do so, warns about exports not called by any other module in the application
 /// * Warns about redefined attributes
 /// * Errors on invalid nif declarations
+/// * Warns about missing or malformed callbacks of implemented behaviours
+/// * Warns about calls to functions not exported by other modules of the application
 /// * Errors on invalid syntax in built-in attributes (e.g. -import(..))
 /// * Errors on mismatched function clauses (name/arity)
 /// * Errors on unterminated function clauses
 /// * Errors on redefined functions
+/// * Errors on unbound and unsafe variables, warns on unused, shadowed and exported variables
 ///
 /// And a few other similar lints
+///
+/// The compiler version and command-line options given are those reported by
+/// `module_info(compile)`.
 pub struct SemanticAnalysis<'app> {
     reporter: Reporter,
+    codemap: Arc<CodeMap>,
     app: &'app ApplicationMetadata,
+    compiler_version: &'app str,
+    compile_options: &'app [ast::Literal],
 }
 impl<'app> SemanticAnalysis<'app> {
-    pub fn new(reporter: Reporter, app: &'app ApplicationMetadata) -> Self {
-        Self { reporter, app }
+    pub fn new(
+        reporter: Reporter,
+        codemap: Arc<CodeMap>,
+        app: &'app ApplicationMetadata,
+        compiler_version: &'app str,
+        compile_options: &'app [ast::Literal],
+    ) -> Self {
+        Self {
+            reporter,
+            codemap,
+            app,
+            compiler_version,
+            compile_options,
+        }
     }
 }
 impl<'app> Pass for SemanticAnalysis<'app> {
@@ -45,15 +73,25 @@ impl<'app> Pass for SemanticAnalysis<'app> {
     type Output<'a> = ast::Module;
 
     fn run<'a>(&mut self, mut module: Self::Input<'a>) -> anyhow::Result<Self::Output<'a>> {
+        let pseudo_locals = inject::DefinePseudoLocals::new(
+            &self.codemap,
+            self.compiler_version,
+            self.compile_options,
+        );
         let mut passes = inject::AddAutoImports
             .chain(verify::VerifyExports::new(self.reporter.clone()))
             .chain(verify::VerifyOnLoadFunctions::new(self.reporter.clone()))
+            .chain(verify::VerifyBehaviours::new(self.reporter.clone(), self.app))
             .chain(verify::VerifyTypeSpecs::new(self.reporter.clone()))
             .chain(verify::VerifyNifs::new(self.reporter.clone()))
+            .chain(unused::WarnUnusedRecords::new(self.reporter.clone()))
+            .chain(unused::WarnUnusedTypes::new(self.reporter.clone()))
+            .chain(unused::WarnUnusedExports::new(self.reporter.clone(), self.app))
+            .chain(variables::VerifyVariables::new(self.reporter.clone()))
             // We place this after VerifyNifs so that we have all the nifs available for module_info,
             // but before VerifyCalls so that any calls to module_info are not erroneously treated as
             // errors prior to them being defined by this pass
-            .chain(inject::DefinePseudoLocals)
+            .chain(pseudo_locals)
             .chain(verify::VerifyCalls::new(self.reporter.clone(), self.app));
 
         passes.run(&mut module)?;
@@ -0,0 +1,486 @@
+use core::ops::ControlFlow;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use firefly_diagnostics::*;
+use firefly_intern::{symbols, Symbol};
+use firefly_pass::Pass;
+use firefly_syntax_base::{ApplicationMetadata, FunctionName};
+
+use crate::ast::*;
+use crate::visit::{self, VisitMut};
+
+/// Warns about local functions which are not reachable from any of the module entry points,
+/// i.e. its exports, its `-on_load` function, and its NIFs.
+///
+/// This relies on calls to local functions having been resolved, so it must run after `ExpandUnqualifiedCalls`.
+///
+/// Warnings can be disabled entirely with `nowarn_unused_function`, or for specific functions with
+/// `-compile({nowarn_unused_function, [Name/Arity]})`.
+pub struct WarnUnusedFunctions {
+    reporter: Reporter,
+}
+impl WarnUnusedFunctions {
+    pub fn new(reporter: Reporter) -> Self {
+        Self { reporter }
+    }
+}
+impl Pass for WarnUnusedFunctions {
+    type Input<'a> = &'a mut Module;
+    type Output<'a> = &'a mut Module;
+
+    fn run<'a>(&mut self, module: Self::Input<'a>) -> anyhow::Result<Self::Output<'a>> {
+        let (enabled, export_all) = module
+            .compile
+            .as_ref()
+            .map(|opts| (opts.warn_unused_function, opts.export_all))
+            .unwrap_or((true, false));
+        if !enabled || export_all {
+            return Ok(module);
+        }
+
+        // Construct the call graph of the module
+        let module_name = module.name.name;
+        let mut callgraph = BTreeMap::<FunctionName, BTreeSet<FunctionName>>::new();
+        for (name, function) in module.functions.iter_mut() {
+            let mut visitor = LocalCalls {
+                module: module_name,
+                callees: BTreeSet::new(),
+            };
+            let _ = visitor.visit_mut_function(function);
+            callgraph.insert(*name, visitor.callees);
+        }
+
+        // Find all functions reachable from an entry point
+        let mut worklist = module
+            .exports
+            .iter()
+            .chain(module.nifs.iter())
+            .chain(module.on_load.iter())
+            .map(|name| name.to_local())
+            .collect::<Vec<_>>();
+        let mut reachable = HashSet::new();
+        while let Some(name) = worklist.pop() {
+            if !reachable.insert(name) {
+                continue;
+            }
+            if let Some(callees) = callgraph.get(&name) {
+                worklist.extend(callees.iter().copied());
+            }
+        }
+
+        let suppressed = module
+            .compile
+            .as_ref()
+            .map(|opts| &opts.no_warn_unused_functions);
+        let mut unused = module
+            .functions
+            .iter()
+            .filter(|(name, _)| !reachable.contains(*name))
+            .filter(|(name, _)| {
+                suppressed
+                    .map(|s| !s.contains(&Span::new(SourceSpan::UNKNOWN, **name)))
+                    .unwrap_or(true)
+            })
+            .map(|(name, function)| (*name, function.name.span))
+            .collect::<Vec<_>>();
+        unused.sort_by_key(|(_, span)| span.start());
+
+        for (name, span) in unused {
+            let message = format!("{} is never called", name);
+            self.reporter
+                .show_warning("unused function", &[(span, message.as_str())]);
+        }
+
+        Ok(module)
+    }
+}
+
+/// Warns about exported functions which are not called by any other module in the application, if enabled
+/// with `warn_unused_export`.
+///
+/// Functions which are called implicitly are never reported, i.e. the callbacks of implemented behaviours, the
+/// `-on_load` function, and `init:boot/1`, the entry point of executables.
+pub struct WarnUnusedExports<'app> {
+    reporter: Reporter,
+    app: &'app ApplicationMetadata,
+}
+impl<'app> WarnUnusedExports<'app> {
+    pub fn new(reporter: Reporter, app: &'app ApplicationMetadata) -> Self {
+        Self { reporter, app }
+    }
+}
+impl<'app> Pass for WarnUnusedExports<'app> {
+    type Input<'a> = &'a mut Module;
+    type Output<'a> = &'a mut Module;
+
+    fn run<'a>(&mut self, module: Self::Input<'a>) -> anyhow::Result<Self::Output<'a>> {
+        let (enabled, export_all) = module
+            .compile
+            .as_ref()
+            .map(|opts| (opts.warn_unused_export, opts.export_all))
+            .unwrap_or((false, false));
+        if !enabled || export_all {
+            return Ok(module);
+        }
+
+        let module_name = module.name.name;
+        let mut used = self
+            .app
+            .modules
+            .values()
+            .filter(|m| m.name.name != module_name)
+            .flat_map(|m| m.calls.iter())
+            .filter(|callee| callee.module == Some(module_name))
+            .map(|callee| callee.to_local())
+            .collect::<HashSet<_>>();
+        for behaviour in module.behaviours.iter() {
+            if let Some(behaviour) = self.app.get_behaviour(behaviour.name) {
+                used.extend(
+                    behaviour
+                        .callbacks
+                        .iter()
+                        .chain(behaviour.optional_callbacks.iter())
+                        .map(|callback| callback.to_local()),
+                );
+            }
+        }
+        used.extend(module.on_load.iter().map(|name| name.to_local()));
+        if module_name == symbols::Init {
+            used.insert(FunctionName::new_local(symbols::Boot, 1));
+        }
+
+        let mut unused = module
+            .exports
+            .iter()
+            .filter(|export| !used.contains(&export.to_local()))
+            .map(|export| (export.item, export.span()))
+            .collect::<Vec<_>>();
+        unused.sort_by_key(|(_, span)| span.start());
+
+        for (name, span) in unused {
+            let message = format!(
+                "{} is exported, but is never called by another module in this application",
+                name
+            );
+            self.reporter
+                .show_warning("unused export", &[(span, message.as_str())]);
+        }
+
+        Ok(module)
+    }
+}
+
+/// Collects the local functions referenced by a function, either by calls or as `fun name/arity`
+struct LocalCalls {
+    module: Symbol,
+    callees: BTreeSet<FunctionName>,
+}
+impl VisitMut<()> for LocalCalls {
+    fn visit_mut_function_var(&mut self, name: &mut FunctionVar) -> ControlFlow<()> {
+        if let FunctionVar::Resolved(name) = name {
+            if name.module == Some(self.module) {
+                self.callees.insert(name.to_local());
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Warns about records which are defined, but never used, unless disabled with `nowarn_unused_record`
+///
+/// This must run before records are expanded by `ExpandRecords`.
+pub struct WarnUnusedRecords {
+    reporter: Reporter,
+}
+impl WarnUnusedRecords {
+    pub fn new(reporter: Reporter) -> Self {
+        Self { reporter }
+    }
+}
+impl Pass for WarnUnusedRecords {
+    type Input<'a> = &'a mut Module;
+    type Output<'a> = &'a mut Module;
+
+    fn run<'a>(&mut self, module: Self::Input<'a>) -> anyhow::Result<Self::Output<'a>> {
+        let enabled = module
+            .compile
+            .as_ref()
+            .map(|opts| opts.warn_unused_record)
+            .unwrap_or(true);
+        if !enabled || module.records.is_empty() {
+            return Ok(module);
+        }
+
+        let mut visitor = RecordReferences {
+            records: BTreeSet::new(),
+        };
+        for function in module.functions.values_mut() {
+            let _ = visitor.visit_mut_function(function);
+        }
+        let mut used = visitor.records;
+
+        // Records may also be referenced by types, or by other record definitions
+        let types = module
+            .types
+            .values()
+            .map(|def| &def.ty)
+            .chain(
+                module
+                    .specs
+                    .values()
+                    .flat_map(|spec| signature_types(&spec.sigs)),
+            )
+            .chain(
+                module
+                    .callbacks
+                    .values()
+                    .flat_map(|cb| signature_types(&cb.sigs)),
+            );
+        for ty in types {
+            walk_type(ty, &mut |ty| {
+                if let Type::Record(_, name, _) = ty {
+                    used.insert(name.name);
+                }
+            });
+        }
+        for (name, record) in module.records.iter_mut() {
+            let mut visitor = RecordReferences {
+                records: BTreeSet::new(),
+            };
+            for field in record.fields.iter_mut() {
+                if let Some(value) = field.value.as_mut() {
+                    let _ = visitor.visit_mut_expr(value);
+                }
+                if let Some(ty) = field.ty.as_ref() {
+                    walk_type(ty, &mut |ty| {
+                        if let Type::Record(_, name, _) = ty {
+                            visitor.records.insert(name.name);
+                        }
+                    });
+                }
+            }
+            visitor.records.remove(name);
+            used.append(&mut visitor.records);
+        }
+
+        let mut unused = module
+            .records
+            .iter()
+            .filter(|(name, _)| !used.contains(*name))
+            .map(|(_, record)| record.name.span)
+            .collect::<Vec<_>>();
+        unused.sort_by_key(|span| span.start());
+
+        for span in unused {
+            self.reporter
+                .show_warning("unused record", &[(span, "this record is never used")]);
+        }
+
+        Ok(module)
+    }
+}
+
+/// Collects the records referenced by record expressions, or by `record_info/2` and `is_record/2,3`
+struct RecordReferences {
+    records: BTreeSet<Symbol>,
+}
+impl VisitMut<()> for RecordReferences {
+    fn visit_mut_record(&mut self, record: &mut Record) -> ControlFlow<()> {
+        self.records.insert(record.name.name);
+        visit::visit_mut_record(self, record)
+    }
+
+    fn visit_mut_record_access(&mut self, expr: &mut RecordAccess) -> ControlFlow<()> {
+        self.records.insert(expr.name.name);
+        visit::visit_mut_record_access(self, expr)
+    }
+
+    fn visit_mut_record_index(&mut self, expr: &mut RecordIndex) -> ControlFlow<()> {
+        self.records.insert(expr.name.name);
+        visit::visit_mut_record_index(self, expr)
+    }
+
+    fn visit_mut_record_update(&mut self, expr: &mut RecordUpdate) -> ControlFlow<()> {
+        self.records.insert(expr.name.name);
+        visit::visit_mut_record_update(self, expr)
+    }
+
+    fn visit_mut_apply(&mut self, apply: &mut Apply) -> ControlFlow<()> {
+        if let Expr::Literal(Literal::Atom(callee)) = apply.callee.as_ref() {
+            let is_record_fun = matches!(
+                (callee.name, apply.args.len()),
+                (symbols::RecordInfo, 2) | (symbols::IsRecord, 2 | 3)
+            );
+            if is_record_fun {
+                if let Some(Expr::Literal(Literal::Atom(name))) = apply.args.get(1) {
+                    self.records.insert(name.name);
+                }
+            }
+        }
+        visit::visit_mut_apply(self, apply)
+    }
+}
+
+/// Warns about local types which are neither exported, nor referenced by an exported type, a spec,
+/// a callback or a record definition, unless disabled with `nowarn_unused_type`
+pub struct WarnUnusedTypes {
+    reporter: Reporter,
+}
+impl WarnUnusedTypes {
+    pub fn new(reporter: Reporter) -> Self {
+        Self { reporter }
+    }
+}
+impl Pass for WarnUnusedTypes {
+    type Input<'a> = &'a mut Module;
+    type Output<'a> = &'a mut Module;
+
+    fn run<'a>(&mut self, module: Self::Input<'a>) -> anyhow::Result<Self::Output<'a>> {
+        let enabled = module
+            .compile
+            .as_ref()
+            .map(|opts| opts.warn_unused_type)
+            .unwrap_or(true);
+        if !enabled || module.types.is_empty() {
+            return Ok(module);
+        }
+
+        // Types referenced outside of type definitions are the roots from which we find used types
+        let mut worklist = module
+            .exported_types
+            .iter()
+            .map(|name| name.to_local())
+            .collect::<Vec<_>>();
+        let roots = module
+            .specs
+            .values()
+            .flat_map(|spec| signature_types(&spec.sigs))
+            .chain(
+                module
+                    .callbacks
+                    .values()
+                    .flat_map(|cb| signature_types(&cb.sigs)),
+            )
+            .chain(
+                module
+                    .records
+                    .values()
+                    .flat_map(|record| record.fields.iter().filter_map(|f| f.ty.as_ref())),
+            );
+        for ty in roots {
+            walk_type(ty, &mut |ty| {
+                if let Some(name) = local_type_name(ty) {
+                    worklist.push(name);
+                }
+            });
+        }
+
+        let mut used = HashSet::new();
+        while let Some(name) = worklist.pop() {
+            if !used.insert(name) {
+                continue;
+            }
+            if let Some(def) = module.types.get(&name) {
+                walk_type(&def.ty, &mut |ty| {
+                    if let Some(name) = local_type_name(ty) {
+                        worklist.push(name);
+                    }
+                });
+            }
+        }
+
+        let mut unused = module
+            .types
+            .iter()
+            .filter(|(name, _)| !used.contains(*name))
+            .map(|(name, def)| (*name, def.name.span))
+            .collect::<Vec<_>>();
+        unused.sort_by_key(|(_, span)| span.start());
+
+        for (name, span) in unused {
+            let message = format!("type {} is never used", name);
+            self.reporter
+                .show_warning("unused type", &[(span, message.as_str())]);
+        }
+
+        Ok(module)
+    }
+}
+
+fn local_type_name(ty: &Type) -> Option<FunctionName> {
+    match ty {
+        Type::Generic { fun, params, .. } => Some(FunctionName::new_local(
+            fun.name,
+            params.len().try_into().unwrap(),
+        )),
+        _ => None,
+    }
+}
+
+fn signature_types(sigs: &[TypeSig]) -> impl Iterator<Item = &Type> {
+    sigs.iter().flat_map(|sig| {
+        sig.params
+            .iter()
+            .chain(Some(sig.ret.as_ref()))
+            .chain(sig.guards.iter().flatten().map(|guard| &guard.ty))
+    })
+}
+
+/// Calls `f` on `ty` and every type nested within it
+fn walk_type<F>(ty: &Type, f: &mut F)
+where
+    F: FnMut(&Type),
+{
+    f(ty);
+    match ty {
+        Type::Annotated { ty, .. } | Type::List(_, ty) | Type::NonEmptyList(_, ty) => {
+            walk_type(ty, f)
+        }
+        Type::Field(_, _, ty) => walk_type(ty, f),
+        Type::Union { types, .. } | Type::Map(_, types) | Type::Tuple(_, types) => {
+            for ty in types.iter() {
+                walk_type(ty, f);
+            }
+        }
+        Type::Record(_, _, fields) => {
+            for ty in fields.iter() {
+                walk_type(ty, f);
+            }
+        }
+        Type::Generic { params, .. } => {
+            for ty in params.iter() {
+                walk_type(ty, f);
+            }
+        }
+        Type::Remote { args, .. } => {
+            for ty in args.iter() {
+                walk_type(ty, f);
+            }
+        }
+        Type::Range { start, end, .. } => {
+            walk_type(start, f);
+            walk_type(end, f);
+        }
+        Type::BinaryOp { lhs, rhs, .. } => {
+            walk_type(lhs, f);
+            walk_type(rhs, f);
+        }
+        Type::UnaryOp { rhs, .. } => walk_type(rhs, f),
+        Type::Binary(_, head, tail) | Type::KeyValuePair(_, head, tail) => {
+            walk_type(head, f);
+            walk_type(tail, f);
+        }
+        Type::AnyFun { ret, .. } => {
+            if let Some(ret) = ret {
+                walk_type(ret, f);
+            }
+        }
+        Type::Fun { params, ret, .. } => {
+            for ty in params.iter() {
+                walk_type(ty, f);
+            }
+            walk_type(ret, f);
+        }
+        Type::Name(_) | Type::Nil(_) | Type::Integer(_, _) | Type::Char(_, _) => (),
+    }
+}
@@ -0,0 +1,683 @@
+use std::collections::BTreeMap;
+
+use firefly_diagnostics::*;
+use firefly_intern::{Ident, Symbol};
+use firefly_pass::Pass;
+use firefly_syntax_base::BinaryOp;
+
+use crate::ast::*;
+
+/// Verifies the binding and use of variables in every function of a module, in the
+/// same manner as `erl_lint`.
+///
+/// The following are reported as errors:
+///
+/// * Use of an unbound variable
+/// * Use of an unsafe variable, i.e. one bound in only some clauses of a `case`, `if`,
+/// `receive` or `try`, or bound within a `catch`, `maybe`, or the right-hand side of
+/// `andalso`/`orelse`
+///
+/// The following are reported as warnings, subject to the compiler options in effect:
+///
+/// * Variables which are bound but never used (`warn_unused_vars`)
+/// * Variables in a fun head or generator pattern which shadow a variable of the
+/// enclosing scope (`warn_shadow_vars`)
+/// * Use of a variable exported from a `case`, `if`, `receive` or `try` (`warn_export_vars`)
+pub struct VerifyVariables {
+    reporter: Reporter,
+}
+impl VerifyVariables {
+    pub fn new(reporter: Reporter) -> Self {
+        Self { reporter }
+    }
+}
+impl Pass for VerifyVariables {
+    type Input<'a> = &'a mut Module;
+    type Output<'a> = &'a mut Module;
+
+    fn run<'a>(&mut self, module: Self::Input<'a>) -> anyhow::Result<Self::Output<'a>> {
+        let options = module.compile.as_ref();
+        let mut analysis = VariableAnalysis {
+            reporter: &self.reporter,
+            warn_unused_vars: options.map(|c| c.warn_unused_var).unwrap_or(true),
+            warn_shadow_vars: options.map(|c| c.warn_shadow_vars).unwrap_or(true),
+            warn_export_vars: options.map(|c| c.warn_export_vars).unwrap_or(true),
+        };
+
+        for function in module.functions.values() {
+            analysis.function(function);
+        }
+
+        Ok(module)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Status {
+    Bound,
+    /// The variable is bound in only some clauses of, or somewhere within, the given expression
+    Unsafe(&'static str, SourceSpan),
+    /// The variable is bound in every clause of the given expression
+    Exported(&'static str, SourceSpan),
+}
+
+#[derive(Debug, Clone)]
+struct Binding {
+    span: SourceSpan,
+    status: Status,
+    used: bool,
+    /// When the variable was bound in several clauses and used in only some of them, the
+    /// bindings in the clauses which did not use it, each of which is unused unless the
+    /// variable is used after the clauses
+    unused_in_clauses: Vec<SourceSpan>,
+}
+impl Binding {
+    fn new(span: SourceSpan) -> Self {
+        Self {
+            span,
+            status: Status::Bound,
+            used: false,
+            unused_in_clauses: vec![],
+        }
+    }
+
+    /// The bindings of this variable which are unused so far
+    fn unused_spans(&self) -> Vec<SourceSpan> {
+        if self.used {
+            vec![]
+        } else if self.unused_in_clauses.is_empty() {
+            vec![self.span]
+        } else {
+            self.unused_in_clauses.clone()
+        }
+    }
+
+    /// Returns true if any binding of this variable has been used
+    fn used_anywhere(&self) -> bool {
+        self.used || !self.unused_in_clauses.is_empty()
+    }
+}
+
+/// The set of variables in scope at some point in a function body
+type Bindings = BTreeMap<Symbol, Binding>;
+
+struct VariableAnalysis<'r> {
+    reporter: &'r Reporter,
+    warn_unused_vars: bool,
+    warn_shadow_vars: bool,
+    warn_export_vars: bool,
+}
+impl<'r> VariableAnalysis<'r> {
+    fn function(&mut self, function: &Function) {
+        for (_, clause) in function.clauses.iter() {
+            let mut vars = Bindings::new();
+            self.clause(clause, &mut vars);
+            self.check_unused(&vars, |_| true);
+        }
+    }
+
+    /// Analyzes a clause whose patterns match against the variables already in scope
+    fn clause(&mut self, clause: &Clause, vars: &mut Bindings) {
+        let mut new = Bindings::new();
+        for pattern in clause.patterns.iter() {
+            self.pattern(pattern, vars, &mut new, None);
+        }
+        vars.extend(new);
+        self.guards(&clause.guards, vars);
+        self.exprs(&clause.body, vars);
+    }
+
+    /// Analyzes the clause of a fun, whose patterns shadow the variables of the enclosing scope
+    fn fun_clause(&mut self, clause: &Clause, self_name: Option<Ident>, vars: &mut Bindings) {
+        let mut new = Bindings::new();
+        if let Some(name) = self_name {
+            self.check_shadowed(name, vars, "named fun");
+            new.insert(
+                name.name,
+                Binding {
+                    used: true,
+                    ..Binding::new(name.span)
+                },
+            );
+        }
+        for pattern in clause.patterns.iter() {
+            self.pattern(pattern, vars, &mut new, Some("fun"));
+        }
+
+        let mut scope = vars.clone();
+        scope.extend(new.iter().map(|(name, binding)| (*name, binding.clone())));
+        self.guards(&clause.guards, &mut scope);
+        self.exprs(&clause.body, &mut scope);
+
+        self.check_unused(&scope, |name| {
+            new.contains_key(name) || !vars.contains_key(name)
+        });
+        // Uses of variables from the enclosing scope count as uses there, unless shadowed
+        for (name, binding) in vars.iter_mut() {
+            if new.contains_key(name) {
+                continue;
+            }
+            if let Some(inner) = scope.get(name) {
+                binding.used |= inner.used;
+            }
+        }
+    }
+
+    fn guards(&mut self, guards: &[Guard], vars: &mut Bindings) {
+        self.expr_list(guards.iter().flat_map(|g| g.conditions.iter()), vars);
+    }
+
+    /// Analyzes a sequence of expressions, where each may use the variables bound by those before it
+    fn exprs(&mut self, exprs: &[Expr], vars: &mut Bindings) {
+        for expr in exprs.iter() {
+            self.expr(expr, vars);
+        }
+    }
+
+    /// Analyzes a list of expressions which are evaluated independently of each other, e.g. the
+    /// elements of a tuple, so the variables bound by one are not visible in the others
+    fn expr_list<'e, I>(&mut self, exprs: I, vars: &mut Bindings)
+    where
+        I: IntoIterator<Item = &'e Expr>,
+    {
+        let base = vars.clone();
+        for expr in exprs {
+            let mut scope = base.clone();
+            self.expr(expr, &mut scope);
+            for (name, binding) in scope {
+                match vars.get_mut(&name) {
+                    Some(existing) => {
+                        existing.used |= binding.used;
+                        if binding.status == Status::Bound {
+                            existing.status = Status::Bound;
+                        }
+                    }
+                    None => {
+                        vars.insert(name, binding);
+                    }
+                }
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr, vars: &mut Bindings) {
+        match expr {
+            Expr::Var(Var(id)) => self.use_var(*id, vars),
+            Expr::Literal(_) | Expr::DelayedSubstitution(..) | Expr::RecordIndex(_) => (),
+            Expr::FunctionVar(FunctionVar::Unresolved(name)) => {
+                if let Some(Name::Var(id)) = name.module {
+                    self.use_var(id, vars);
+                }
+                if let Name::Var(id) = name.function {
+                    self.use_var(id, vars);
+                }
+                if let Arity::Var(id) = name.arity {
+                    self.use_var(id, vars);
+                }
+            }
+            Expr::FunctionVar(_) => (),
+            Expr::Cons(cons) => self.expr_list([cons.head.as_ref(), cons.tail.as_ref()], vars),
+            Expr::Tuple(tuple) => self.expr_list(tuple.elements.iter(), vars),
+            Expr::Map(map) => self.expr_list(
+                map.fields.iter().flat_map(|f| [f.key_ref(), f.value_ref()]),
+                vars,
+            ),
+            Expr::MapUpdate(update) => self.expr_list(
+                core::iter::once(update.map.as_ref()).chain(
+                    update
+                        .updates
+                        .iter()
+                        .flat_map(|f| [f.key_ref(), f.value_ref()]),
+                ),
+                vars,
+            ),
+            Expr::Binary(bin) => self.expr_list(
+                bin.elements
+                    .iter()
+                    .flat_map(|e| core::iter::once(&e.bit_expr).chain(e.bit_size.iter())),
+                vars,
+            ),
+            Expr::Record(record) => self.expr_list(
+                record
+                    .fields
+                    .iter()
+                    .filter_map(|f| f.value.as_ref())
+                    .chain(record.default.as_deref()),
+                vars,
+            ),
+            Expr::RecordAccess(access) => self.expr(&access.record, vars),
+            Expr::RecordUpdate(update) => self.expr_list(
+                core::iter::once(update.record.as_ref())
+                    .chain(update.updates.iter().filter_map(|f| f.value.as_ref())),
+                vars,
+            ),
+            Expr::ListComprehension(lc) => {
+                self.comprehension(&[lc.body.as_ref()], &lc.qualifiers, vars)
+            }
+            Expr::BinaryComprehension(bc) => {
+                self.comprehension(&[bc.body.as_ref()], &bc.qualifiers, vars)
+            }
+            Expr::MapComprehension(mc) => {
+                self.comprehension(&[mc.key.as_ref(), mc.value.as_ref()], &mc.qualifiers, vars)
+            }
+            // Generators are handled as part of their comprehension
+            Expr::Generator(gen) => self.expr(&gen.expr, vars),
+            Expr::Begin(block) => self.exprs(&block.body, vars),
+            Expr::Apply(apply) => self.expr_list(
+                core::iter::once(apply.callee.as_ref()).chain(apply.args.iter()),
+                vars,
+            ),
+            Expr::Remote(remote) => {
+                self.expr_list([remote.module.as_ref(), remote.function.as_ref()], vars)
+            }
+            Expr::BinaryExpr(BinaryExpr {
+                span,
+                op: op @ (BinaryOp::AndAlso | BinaryOp::OrElse),
+                lhs,
+                rhs,
+            }) => {
+                // The right-hand side is conditionally evaluated, so anything it binds is unsafe
+                self.expr(lhs, vars);
+                let base = vars.clone();
+                self.expr(rhs, vars);
+                let kind = if *op == BinaryOp::AndAlso {
+                    "andalso"
+                } else {
+                    "orelse"
+                };
+                mark_unsafe(vars, &base, kind, *span);
+            }
+            Expr::BinaryExpr(expr) => self.expr_list([expr.lhs.as_ref(), expr.rhs.as_ref()], vars),
+            Expr::UnaryExpr(expr) => self.expr(&expr.operand, vars),
+            Expr::Match(Match { pattern, expr, .. })
+            | Expr::MaybeMatch(MaybeMatch { pattern, expr, .. }) => {
+                self.expr(expr, vars);
+                let mut new = Bindings::new();
+                self.pattern(pattern, vars, &mut new, None);
+                vars.extend(new);
+            }
+            Expr::If(expr) => self.clauses(&expr.clauses, "if", expr.span, vars),
+            Expr::Case(expr) => {
+                self.expr(&expr.expr, vars);
+                self.clauses(&expr.clauses, "case", expr.span, vars);
+            }
+            Expr::Catch(expr) => {
+                let base = vars.clone();
+                self.expr(&expr.expr, vars);
+                mark_unsafe(vars, &base, "catch", expr.span);
+            }
+            Expr::Receive(expr) => {
+                if let Some(after) = expr.after.as_ref() {
+                    self.expr(&after.timeout, vars);
+                }
+                let mut scopes = vec![];
+                for clause in expr.clauses.iter().flatten() {
+                    let mut scope = vars.clone();
+                    self.clause(clause, &mut scope);
+                    scopes.push(scope);
+                }
+                if let Some(after) = expr.after.as_ref() {
+                    let mut scope = vars.clone();
+                    self.exprs(&after.body, &mut scope);
+                    scopes.push(scope);
+                }
+                merge_clauses(vars, scopes, "receive", expr.span);
+            }
+            Expr::Try(expr) => self.try_expr(expr, vars),
+            Expr::Maybe(expr) => {
+                let base = vars.clone();
+                self.exprs(&expr.body, vars);
+                mark_unsafe(vars, &base, "maybe", expr.span);
+                if let Some(clauses) = expr.else_clauses.as_ref() {
+                    self.clauses(clauses, "maybe", expr.span, vars);
+                    mark_unsafe(vars, &base, "maybe", expr.span);
+                }
+            }
+            Expr::Fun(Fun::Anonymous(fun)) => {
+                for clause in fun.clauses.iter() {
+                    self.fun_clause(clause, None, vars);
+                }
+            }
+            Expr::Fun(Fun::Recursive(fun)) => {
+                for (_, clause) in fun.clauses.iter() {
+                    self.fun_clause(clause, Some(fun.self_name), vars);
+                }
+            }
+            Expr::Protect(protect) => self.expr(&protect.body, vars),
+        }
+    }
+
+    /// Analyzes the clauses of a `case`, `if` or `maybe .. else`
+    fn clauses(
+        &mut self,
+        clauses: &[Clause],
+        kind: &'static str,
+        span: SourceSpan,
+        vars: &mut Bindings,
+    ) {
+        let mut scopes = Vec::with_capacity(clauses.len());
+        for clause in clauses.iter() {
+            let mut scope = vars.clone();
+            self.clause(clause, &mut scope);
+            scopes.push(scope);
+        }
+        merge_clauses(vars, scopes, kind, span);
+    }
+
+    fn try_expr(&mut self, expr: &Try, vars: &mut Bindings) {
+        let base = vars.clone();
+        self.exprs(&expr.exprs, vars);
+        // Variables bound in the protected expressions are only safe to use in the success clauses
+        let body = vars.clone();
+        mark_unsafe(vars, &base, "try", expr.span);
+
+        let mut scopes = vec![];
+        for clause in expr.clauses.iter().flatten() {
+            let mut scope = body.clone();
+            self.clause(clause, &mut scope);
+            scopes.push(scope);
+        }
+        for clause in expr.catch_clauses.iter().flatten() {
+            let mut scope = vars.clone();
+            self.clause(clause, &mut scope);
+            scopes.push(scope);
+        }
+        merge_clauses(vars, scopes, "try", expr.span);
+        mark_unsafe(vars, &base, "try", expr.span);
+
+        if let Some(after) = expr.after.as_ref() {
+            self.exprs(after, vars);
+            mark_unsafe(vars, &base, "try", expr.span);
+        }
+    }
+
+    fn comprehension(&mut self, body: &[&Expr], qualifiers: &[Expr], vars: &mut Bindings) {
+        let mut scope = vars.clone();
+        let mut shadowed = Bindings::new();
+        for qualifier in qualifiers.iter() {
+            match qualifier {
+                Expr::Generator(gen) => {
+                    self.expr(&gen.expr, &mut scope);
+                    let mut new = Bindings::new();
+                    self.pattern(&gen.pattern, &mut scope, &mut new, Some("generate"));
+                    for (name, binding) in new {
+                        if let Some(outer) = vars.get_mut(&name) {
+                            // Propagate any uses which occurred before the variable was shadowed
+                            if !shadowed.contains_key(&name) {
+                                outer.used |= scope[&name].used;
+                            }
+                            shadowed.insert(name, binding.clone());
+                        }
+                        scope.insert(name, binding);
+                    }
+                }
+                filter => self.expr(filter, &mut scope),
+            }
+        }
+        for expr in body {
+            self.expr(expr, &mut scope);
+        }
+
+        self.check_unused(&scope, |name| {
+            shadowed.contains_key(name) || !vars.contains_key(name)
+        });
+        for (name, binding) in vars.iter_mut() {
+            if shadowed.contains_key(name) {
+                continue;
+            }
+            if let Some(inner) = scope.get(name) {
+                binding.used |= inner.used;
+            }
+        }
+    }
+
+    /// Analyzes `pattern`, adding the variables it binds to `new`
+    ///
+    /// Variables already in scope in `vars` are matched against, i.e. used, unless `shadow`
+    /// is given, in which case they are rebound, as in fun heads and generators.
+    fn pattern(
+        &mut self,
+        pattern: &Expr,
+        vars: &mut Bindings,
+        new: &mut Bindings,
+        shadow: Option<&'static str>,
+    ) {
+        match pattern {
+            Expr::Var(var) if var.is_wildcard() => (),
+            Expr::Var(Var(id)) => {
+                if let Some(binding) = new.get_mut(&id.name) {
+                    // A repeated variable in a pattern is a use of the first occurrence
+                    binding.used = true;
+                } else if vars.contains_key(&id.name) {
+                    match shadow {
+                        None => self.use_var(*id, vars),
+                        Some(kind) => {
+                            self.check_shadowed(*id, vars, kind);
+                            new.insert(id.name, Binding::new(id.span));
+                        }
+                    }
+                } else {
+                    new.insert(id.name, Binding::new(id.span));
+                }
+            }
+            Expr::Literal(_) | Expr::RecordIndex(_) | Expr::DelayedSubstitution(..) => (),
+            Expr::Cons(cons) => {
+                self.pattern(&cons.head, vars, new, shadow);
+                self.pattern(&cons.tail, vars, new, shadow);
+            }
+            Expr::Tuple(tuple) => {
+                for element in tuple.elements.iter() {
+                    self.pattern(element, vars, new, shadow);
+                }
+            }
+            Expr::Map(map) => {
+                for field in map.fields.iter() {
+                    // Keys in map patterns are guard expressions over variables already in scope
+                    self.expr(field.key_ref(), vars);
+                    self.pattern(field.value_ref(), vars, new, shadow);
+                }
+            }
+            Expr::Binary(bin) => {
+                for element in bin.elements.iter() {
+                    self.pattern(&element.bit_expr, vars, new, shadow);
+                    if let Some(size) = element.bit_size.as_ref() {
+                        self.size_expr(size, vars, new);
+                    }
+                }
+            }
+            Expr::Record(record) => {
+                for field in record.fields.iter() {
+                    if let Some(value) = field.value.as_ref() {
+                        self.pattern(value, vars, new, shadow);
+                    }
+                }
+                if let Some(default) = record.default.as_deref() {
+                    self.pattern(default, vars, new, shadow);
+                }
+            }
+            Expr::Match(expr) => {
+                self.pattern(&expr.pattern, vars, new, shadow);
+                self.pattern(&expr.expr, vars, new, shadow);
+            }
+            // String prefixes and constant arithmetic
+            Expr::BinaryExpr(expr) => {
+                self.pattern(&expr.lhs, vars, new, shadow);
+                self.pattern(&expr.rhs, vars, new, shadow);
+            }
+            Expr::UnaryExpr(expr) => self.pattern(&expr.operand, vars, new, shadow),
+            // Anything else is not a valid pattern, which is reported elsewhere
+            other => self.expr(other, vars),
+        }
+    }
+
+    /// Analyzes the size of a binary segment in a pattern, which may refer to variables bound
+    /// by earlier segments of the same binary
+    fn size_expr(&mut self, expr: &Expr, vars: &mut Bindings, new: &mut Bindings) {
+        match expr {
+            Expr::Var(Var(id)) if new.contains_key(&id.name) => {
+                new.get_mut(&id.name).unwrap().used = true;
+            }
+            Expr::BinaryExpr(expr) => {
+                self.size_expr(&expr.lhs, vars, new);
+                self.size_expr(&expr.rhs, vars, new);
+            }
+            Expr::UnaryExpr(expr) => self.size_expr(&expr.operand, vars, new),
+            other => self.expr(other, vars),
+        }
+    }
+
+    fn use_var(&mut self, id: Ident, vars: &mut Bindings) {
+        let Some(binding) = vars.get_mut(&id.name) else {
+            self.reporter.show_error(
+                "unbound variable",
+                &[(id.span, &format!("the variable '{}' is unbound", id))],
+            );
+            // Treat the variable as bound from here on, to avoid cascading errors
+            vars.insert(
+                id.name,
+                Binding {
+                    used: true,
+                    ..Binding::new(id.span)
+                },
+            );
+            return;
+        };
+
+        binding.used = true;
+        match binding.status {
+            Status::Bound => (),
+            Status::Unsafe(kind, span) => {
+                self.reporter.show_error(
+                    "unsafe variable",
+                    &[
+                        (
+                            id.span,
+                            &format!("the variable '{}' is unsafe in '{}'", id, kind),
+                        ),
+                        (
+                            span,
+                            "it is not bound on every path through this expression",
+                        ),
+                    ],
+                );
+                binding.status = Status::Bound;
+            }
+            Status::Exported(kind, span) => {
+                if self.warn_export_vars {
+                    self.reporter.show_warning(
+                        "exported variable",
+                        &[
+                            (
+                                id.span,
+                                &format!("the variable '{}' is exported from '{}'", id, kind),
+                            ),
+                            (span, "it is bound in every clause of this expression"),
+                        ],
+                    );
+                }
+                binding.status = Status::Bound;
+            }
+        }
+    }
+
+    fn check_shadowed(&mut self, id: Ident, vars: &Bindings, kind: &'static str) {
+        if !self.warn_shadow_vars {
+            return;
+        }
+        if let Some(outer) = vars.get(&id.name) {
+            self.reporter.show_warning(
+                "shadowed variable",
+                &[
+                    (
+                        id.span,
+                        &format!("the variable '{}' is shadowed in '{}'", id, kind),
+                    ),
+                    (outer.span, "it was previously bound here"),
+                ],
+            );
+        }
+    }
+
+    /// Warns about every unused variable in `vars` which satisfies `is_local`
+    fn check_unused<F>(&mut self, vars: &Bindings, is_local: F)
+    where
+        F: Fn(&Symbol) -> bool,
+    {
+        if !self.warn_unused_vars {
+            return;
+        }
+        for (name, binding) in vars.iter() {
+            if name.as_str().get().starts_with('_') || !is_local(name) {
+                continue;
+            }
+            for span in binding.unused_spans() {
+                self.reporter.show_warning(
+                    "unused variable",
+                    &[(
+                        span,
+                        &format!("the variable '{}' is bound but never used", name),
+                    )],
+                );
+            }
+        }
+    }
+}
+
+/// Marks every variable in `vars` which is not in `base` as unsafe, unless it already is
+fn mark_unsafe(vars: &mut Bindings, base: &Bindings, kind: &'static str, span: SourceSpan) {
+    for (name, binding) in vars.iter_mut() {
+        if base.contains_key(name) {
+            continue;
+        }
+        if !matches!(binding.status, Status::Unsafe(..)) {
+            binding.status = Status::Unsafe(kind, span);
+        }
+    }
+}
+
+/// Merges the scopes resulting from each clause of an expression into `vars`
+///
+/// Variables bound in every clause are exported from the expression, while those bound in
+/// only some of them are unsafe. Variables which were unsafe within a clause remain so.
+///
+/// A variable bound in several clauses is only used if it was used in each of them, so that
+/// a binding which is unused in its own clause is still reported when a sibling clause uses
+/// the variable, unless it is used after the expression.
+fn merge_clauses(vars: &mut Bindings, scopes: Vec<Bindings>, kind: &'static str, span: SourceSpan) {
+    let total = scopes.len();
+    let mut new = BTreeMap::<Symbol, (Binding, usize)>::new();
+    for scope in scopes {
+        for (name, binding) in scope {
+            if let Some(existing) = vars.get_mut(&name) {
+                existing.used |= binding.used;
+                continue;
+            }
+            match new.get_mut(&name) {
+                Some((merged, count)) => {
+                    if merged.used_anywhere() || binding.used_anywhere() {
+                        let mut unused = merged.unused_spans();
+                        unused.extend(binding.unused_spans());
+                        merged.used = unused.is_empty();
+                        merged.unused_in_clauses = unused;
+                    }
+                    if let Status::Unsafe(..) = binding.status {
+                        merged.status = binding.status;
+                    }
+                    *count += 1;
+                }
+                None => {
+                    new.insert(name, (binding, 1));
+                }
+            }
+        }
+    }
+
+    for (name, (mut binding, count)) in new {
+        binding.status = match binding.status {
+            status @ Status::Unsafe(..) => status,
+            _ if count == total => Status::Exported(kind, span),
+            _ => Status::Unsafe(kind, span),
+        };
+        vars.insert(name, binding);
+    }
+}
@@ -2,7 +2,7 @@ use core::ops::ControlFlow;
 use std::collections::{BTreeMap, BTreeSet};
 
 use firefly_diagnostics::*;
-use firefly_intern::Symbol;
+use firefly_intern::{symbols, Symbol};
 use firefly_pass::Pass;
 use firefly_syntax_base::{ApplicationMetadata, Deprecation, FunctionName};
 
@@ -107,6 +107,92 @@ impl Pass for VerifyOnLoadFunctions {
     }
 }
 
+/// Verifies that a module implementing a behaviour defines and exports all of the callbacks it requires
+///
+/// The callbacks of a behaviour are taken from the application metadata, so behaviours which are not
+/// part of the current application must either be resolvable from the include path, or be one of the
+/// behaviours defined by OTP.
+pub struct VerifyBehaviours<'app> {
+    reporter: Reporter,
+    app: &'app ApplicationMetadata,
+}
+impl<'app> VerifyBehaviours<'app> {
+    pub fn new(reporter: Reporter, app: &'app ApplicationMetadata) -> Self {
+        Self { reporter, app }
+    }
+}
+impl<'app> Pass for VerifyBehaviours<'app> {
+    type Input<'a> = &'a mut Module;
+    type Output<'a> = &'a mut Module;
+
+    fn run<'a>(&mut self, module: Self::Input<'a>) -> anyhow::Result<Self::Output<'a>> {
+        let export_all = module
+            .compile
+            .as_ref()
+            .map(|opts| opts.export_all)
+            .unwrap_or(false);
+
+        let mut behaviours = module.behaviours.iter().copied().collect::<Vec<_>>();
+        behaviours.sort_by_key(|b| b.span.start());
+
+        for behaviour in behaviours {
+            let span = behaviour.span;
+            let Some(metadata) = self.app.get_behaviour(behaviour.name) else { self.reporter.show_warning("undefined behaviour", &[(span, "the module defining this behaviour could not be found")]); continue; };
+            if metadata.callbacks.is_empty() && metadata.optional_callbacks.is_empty() {
+                self.reporter.show_warning(
+                    "invalid behaviour",
+                    &[(span, "this module does not define any callbacks")],
+                );
+                continue;
+            }
+
+            // Report problems in a stable order, regardless of symbol interning order
+            let mut callbacks = metadata.callbacks.iter().collect::<Vec<_>>();
+            callbacks.sort_by_cached_key(|callback| callback.to_string());
+
+            for callback in callbacks {
+                if module.functions.contains_key(callback) {
+                    if !export_all && !module.exports.contains(&Span::new(span, *callback)) {
+                        let message = format!(
+                            "{} is required by behaviour {}, but is not exported",
+                            callback, behaviour
+                        );
+                        self.reporter.show_warning(
+                            "callback function is not exported",
+                            &[(span, message.as_str())],
+                        );
+                    }
+                    continue;
+                }
+
+                // Check for a function with the same name, but the wrong arity
+                let similar = module
+                    .functions
+                    .iter()
+                    .find(|(name, _)| name.function == callback.function);
+                let message = format!("{} is required by behaviour {}", callback, behaviour);
+                match similar {
+                    Some((name, function)) => {
+                        let defined = format!("{} is defined here, but has the wrong arity", name);
+                        self.reporter.show_warning(
+                            "callback function has the wrong arity",
+                            &[(span, message.as_str()), (function.span, defined.as_str())],
+                        );
+                    }
+                    None => {
+                        self.reporter.show_warning(
+                            "undefined callback function",
+                            &[(span, message.as_str())],
+                        );
+                    }
+                }
+            }
+        }
+
+        Ok(module)
+    }
+}
+
 /// Like `VerifyExports`, but for `-nifs`; ensures all NIF declarations have a corresponding definition.
 pub struct VerifyNifs {
     reporter: Reporter,
@@ -186,10 +272,9 @@ impl Pass for VerifyTypeSpecs {
 ///
 /// Additionally, checks if the callee is known to be deprecated and raises appropriate diagnostics.
 ///
-/// NOTE: We could extend this analysis to cover calls to other modules, since at the point this analysis is run, we have
-/// access to the entire set of modules that was provided to the compiler, however this does not account for cases in which
-/// we're only compiling a library and thus only a subset of the modules is known - we could make such analysis optional and
-/// only perform it when the full set of modules is known.
+/// Calls to other modules are checked against the export tables of the modules provided to the compiler, warning about
+/// functions those modules don't export. Since we may only be compiling a library, and thus only know a subset of the
+/// modules which will be present at runtime, calls to unknown modules are only reported when `warn_unknown_module` is set.
 pub struct VerifyCalls<'app> {
     reporter: Reporter,
     app: &'app ApplicationMetadata,
@@ -212,6 +297,12 @@ impl<'app> Pass for VerifyCalls<'app> {
             .map(|(name, sig)| (*name, sig.mfa()))
             .collect::<BTreeMap<FunctionName, FunctionName>>();
 
+        let (warn_undefined_function, warn_unknown_module) = module
+            .compile
+            .as_ref()
+            .map(|opts| (opts.warn_undefined_function, opts.warn_unknown_module))
+            .unwrap_or((true, false));
+
         for (_, function) in module.functions.iter_mut() {
             let mut visitor = VerifyCallsVisitor {
                 reporter: self.reporter.clone(),
@@ -219,6 +310,8 @@ impl<'app> Pass for VerifyCalls<'app> {
                 module: module_name,
                 locals: &locals,
                 imports: &imports,
+                warn_undefined_function,
+                warn_unknown_module,
             };
             visitor.visit_mut_function(function);
         }
@@ -232,6 +325,38 @@ struct VerifyCallsVisitor<'a> {
     module: Symbol,
     locals: &'a BTreeSet<FunctionName>,
     imports: &'a BTreeMap<FunctionName, FunctionName>,
+    warn_undefined_function: bool,
+    warn_unknown_module: bool,
+}
+impl<'a> VerifyCallsVisitor<'a> {
+    /// Verifies that the callee of a call to another module is exported by that module, if the module is
+    /// known to the compiler, or warns about the module itself if configured to do so
+    fn verify_remote_call(&self, name: &FunctionName, span: SourceSpan) {
+        let module_name = name.module.unwrap();
+        match self.app.get_module(module_name) {
+            Some(module) => {
+                if self.warn_undefined_function && !module.is_exported(name) {
+                    let message = format!(
+                        "the function {} is not exported by module {}",
+                        name.to_local(),
+                        module_name
+                    );
+                    self.reporter
+                        .show_warning("call to undefined function", &[(span, message.as_str())]);
+                }
+            }
+            // The erlang module is provided by the runtime, so it is always known
+            None if module_name == symbols::Erlang => (),
+            None => {
+                if self.warn_unknown_module {
+                    let message =
+                        format!("the module {} is not part of this application", module_name);
+                    self.reporter
+                        .show_warning("call to unknown module", &[(span, message.as_str())]);
+                }
+            }
+        }
+    }
 }
 impl<'a> VisitMut<()> for VerifyCallsVisitor<'a> {
     fn visit_mut_apply(&mut self, apply: &mut Apply) -> ControlFlow<()> {
@@ -261,6 +386,7 @@ impl<'a> VisitMut<()> for VerifyCallsVisitor<'a> {
                 }
                 (Some(m), Some(f)) => {
                     let name = FunctionName::new(m.name, f.name, arity);
+                    self.verify_remote_call(&name, *rspan);
                     match self.app.get_function_deprecation(&name) {
                         None => ControlFlow::Continue(()),
                         Some(Deprecation::Module { span: dspan, flag }) => {
@@ -303,33 +429,36 @@ impl<'a> VisitMut<()> for VerifyCallsVisitor<'a> {
                                     &[(f.span, message.as_str())],
                                 );
                             }
-                            Some(imported) => match self.app.get_function_deprecation(&imported) {
-                                None => (),
-                                Some(Deprecation::Module { span: dspan, flag }) => {
-                                    let note =
-                                        format!("this function will be deprecated {}", &flag);
-                                    self.reporter.show_warning(
-                                        "use of deprecated module",
-                                        &[
-                                            (f.span, note.as_str()),
-                                            (dspan, "deprecation declared here"),
-                                        ],
-                                    );
-                                }
-                                Some(Deprecation::Function {
-                                    span: dspan, flag, ..
-                                }) => {
-                                    let note =
-                                        format!("this function will be deprecated {}", &flag);
-                                    self.reporter.show_warning(
-                                        "use of deprecated function",
-                                        &[
-                                            (f.span, note.as_str()),
-                                            (dspan, "deprecation declared here"),
-                                        ],
-                                    );
+                            Some(imported) => {
+                                self.verify_remote_call(imported, f.span);
+                                match self.app.get_function_deprecation(&imported) {
+                                    None => (),
+                                    Some(Deprecation::Module { span: dspan, flag }) => {
+                                        let note =
+                                            format!("this function will be deprecated {}", &flag);
+                                        self.reporter.show_warning(
+                                            "use of deprecated module",
+                                            &[
+                                                (f.span, note.as_str()),
+                                                (dspan, "deprecation declared here"),
+                                            ],
+                                        );
+                                    }
+                                    Some(Deprecation::Function {
+                                        span: dspan, flag, ..
+                                    }) => {
+                                        let note =
+                                            format!("this function will be deprecated {}", &flag);
+                                        self.reporter.show_warning(
+                                            "use of deprecated function",
+                                            &[
+                                                (f.span, note.as_str()),
+                                                (dspan, "deprecation declared here"),
+                                            ],
+                                        );
+                                    }
                                 }
-                            },
+                            }
                         }
                     }
                     ControlFlow::Continue(())
@@ -348,6 +477,7 @@ impl<'a> VisitMut<()> for VerifyCallsVisitor<'a> {
                         );
                     }
                 } else {
+                    self.verify_remote_call(&name, name.span());
                     match self.app.get_function_deprecation(&name) {
                         None => (),
                         Some(Deprecation::Module { span: dspan, flag }) => {
@@ -405,25 +535,35 @@ impl<'a> VisitMut<()> for VerifyCallsVisitor<'a> {
                                 &[(span, message.as_str())],
                             );
                         }
-                        Some(imported) => match self.app.get_function_deprecation(&imported) {
-                            None => (),
-                            Some(Deprecation::Module { span: dspan, flag }) => {
-                                let note = format!("this module will be deprecated {}", &flag);
-                                self.reporter.show_warning(
-                                    "use of deprecated module",
-                                    &[(span, note.as_str()), (dspan, "deprecation declared here")],
-                                );
-                            }
-                            Some(Deprecation::Function {
-                                span: dspan, flag, ..
-                            }) => {
-                                let note = format!("this function will be deprecated {}", &flag);
-                                self.reporter.show_warning(
-                                    "use of deprecated function",
-                                    &[(span, note.as_str()), (dspan, "deprecation declared here")],
-                                );
+                        Some(imported) => {
+                            self.verify_remote_call(imported, span);
+                            match self.app.get_function_deprecation(&imported) {
+                                None => (),
+                                Some(Deprecation::Module { span: dspan, flag }) => {
+                                    let note = format!("this module will be deprecated {}", &flag);
+                                    self.reporter.show_warning(
+                                        "use of deprecated module",
+                                        &[
+                                            (span, note.as_str()),
+                                            (dspan, "deprecation declared here"),
+                                        ],
+                                    );
+                                }
+                                Some(Deprecation::Function {
+                                    span: dspan, flag, ..
+                                }) => {
+                                    let note =
+                                        format!("this function will be deprecated {}", &flag);
+                                    self.reporter.show_warning(
+                                        "use of deprecated function",
+                                        &[
+                                            (span, note.as_str()),
+                                            (dspan, "deprecation declared here"),
+                                        ],
+                                    );
+                                }
                             }
-                        },
+                        }
                     }
                 }
 
@@ -473,6 +613,7 @@ impl<'a> VisitMut<()> for VerifyCallsVisitor<'a> {
                                     );
                                 }
                                 Some(imported) => {
+                                    self.verify_remote_call(imported, span);
                                     match self.app.get_function_deprecation(&imported) {
                                         None => (),
                                         Some(Deprecation::Module { span: dspan, flag }) => {
@@ -540,25 +681,35 @@ impl<'a> VisitMut<()> for VerifyCallsVisitor<'a> {
                                 &[(span, message.as_str())],
                             );
                         }
-                        Some(imported) => match self.app.get_function_deprecation(&imported) {
-                            None => (),
-                            Some(Deprecation::Module { span: dspan, flag }) => {
-                                let note = format!("this module will be deprecated {}", &flag);
-                                self.reporter.show_warning(
-                                    "use of deprecated module",
-                                    &[(span, note.as_str()), (dspan, "deprecation declared here")],
-                                );
-                            }
-                            Some(Deprecation::Function {
-                                span: dspan, flag, ..
-                            }) => {
-                                let note = format!("this function will be deprecated {}", &flag);
-                                self.reporter.show_warning(
-                                    "use of deprecated function",
-                                    &[(span, note.as_str()), (dspan, "deprecation declared here")],
-                                );
+                        Some(imported) => {
+                            self.verify_remote_call(imported, span);
+                            match self.app.get_function_deprecation(&imported) {
+                                None => (),
+                                Some(Deprecation::Module { span: dspan, flag }) => {
+                                    let note = format!("this module will be deprecated {}", &flag);
+                                    self.reporter.show_warning(
+                                        "use of deprecated module",
+                                        &[
+                                            (span, note.as_str()),
+                                            (dspan, "deprecation declared here"),
+                                        ],
+                                    );
+                                }
+                                Some(Deprecation::Function {
+                                    span: dspan, flag, ..
+                                }) => {
+                                    let note =
+                                        format!("this function will be deprecated {}", &flag);
+                                    self.reporter.show_warning(
+                                        "use of deprecated function",
+                                        &[
+                                            (span, note.as_str()),
+                                            (dspan, "deprecation declared here"),
+                                        ],
+                                    );
+                                }
                             }
-                        },
+                        }
                     }
                 }
                 ControlFlow::Continue(())
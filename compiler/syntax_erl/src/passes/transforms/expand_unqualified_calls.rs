@@ -3,6 +3,7 @@ use core::ops::ControlFlow;
 use crate::ast::*;
 
 use firefly_diagnostics::*;
+use firefly_intern::Ident;
 use firefly_pass::Pass;
 use firefly_syntax_base::FunctionName;
 
@@ -16,6 +17,9 @@ use crate::visit::{self as visit, VisitMut};
 ///
 /// Once this pass has run, we don't need to concern ourselves with imports anymore, as
 /// the distinction is erased.
+///
+/// Remote calls to the current module, e.g. `?MODULE:f()`, are left as-is, as they must call
+/// the latest version of the module, rather than the local function.
 pub struct ExpandUnqualifiedCalls<'m> {
     module: &'m Module,
 }
@@ -54,12 +58,21 @@ impl<'m> VisitMut<anyhow::Error> for ExpandUnqualifiedCalls<'m> {
                     return ControlFlow::Continue(());
                 }
             }
-            Expr::Remote(remote) => {
-                if let Ok(name) = remote.try_eval(arity) {
+            Expr::Remote(remote) => match remote.try_eval(arity) {
+                Ok(name) if name.module != Some(self.module.name()) => {
                     FunctionVar::Resolved(Span::new(span, name))
-                } else {
-                    return ControlFlow::Continue(());
                 }
+                _ => return ControlFlow::Continue(()),
+            },
+            // The parser resolves remote calls with a literal module and function eagerly,
+            // so turn those back into remote calls if they refer to the current module
+            Expr::FunctionVar(FunctionVar::Resolved(name))
+                if name.module == Some(self.module.name()) =>
+            {
+                let module = Expr::Literal(Literal::Atom(Ident::new(self.module.name(), span)));
+                let function = Expr::Literal(Literal::Atom(Ident::new(name.function, span)));
+                apply.callee = Box::new(Expr::Remote(Remote::new(span, module, function)));
+                return ControlFlow::Continue(());
             }
             _ => return ControlFlow::Continue(()),
         };
@@ -9,13 +9,13 @@ use firefly_diagnostics::*;
 use firefly_pass::Pass;
 
 use crate::ast;
+use crate::passes::sema::WarnUnusedFunctions;
 
 use self::expand_records::ExpandRecords;
 use self::expand_substitutions::ExpandSubstitutions;
 use self::expand_unqualified_calls::ExpandUnqualifiedCalls;
 
 pub struct CanonicalizeSyntax {
-    #[allow(dead_code)]
     reporter: Reporter,
     codemap: Arc<CodeMap>,
 }
@@ -42,6 +42,9 @@ impl Pass for CanonicalizeSyntax {
 
         module.functions = functions;
 
+        // Now that local calls are resolved, we can determine which functions are unused
+        WarnUnusedFunctions::new(self.reporter.clone()).run(&mut module)?;
+
         Ok(module)
     }
 }
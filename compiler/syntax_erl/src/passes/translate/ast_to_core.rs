@@ -68,7 +68,8 @@ use firefly_number::Integer;
 use firefly_pass::Pass;
 use firefly_syntax_base::*;
 use firefly_syntax_core::passes::{
-    AnnotateVariableUsage, FunctionContext, RewriteExports, RewriteReceivePrimitives,
+    AnnotateVariableUsage, FoldConstants, FunctionContext, RewriteExports,
+    RewriteReceivePrimitives,
 };
 use firefly_syntax_core::*;
 
@@ -82,12 +83,15 @@ const COLLAPSE_MAX_SIZE_SEGMENT: usize = 1024;
 /// This pass transforms an AST function into its Core IR form for further analysis and eventual lowering to Kernel IR
 ///
 /// This pass performs numerous small transformations to normalize the structure of the AST
+///
+/// When `optimize` is set, the resulting Core is also constant folded and simplified
 pub struct AstToCore {
     reporter: Reporter,
+    optimize: bool,
 }
 impl AstToCore {
-    pub fn new(reporter: Reporter) -> Self {
-        Self { reporter }
+    pub fn new(reporter: Reporter, optimize: bool) -> Self {
+        Self { reporter, optimize }
     }
 }
 impl Pass for AstToCore {
@@ -118,11 +122,15 @@ impl Pass for AstToCore {
                 is_nif,
             )));
 
-            let mut pipeline = TranslateAst::new(self.reporter.clone(), Rc::clone(&context))
+            let mut pipeline =
+                TranslateAst::new(self.reporter.clone(), module.name.name, Rc::clone(&context))
                 .chain(AnnotateVariableUsage::new(Rc::clone(&context)))
                 .chain(RewriteExports::new(Rc::clone(&context)))
                 .chain(RewriteReceivePrimitives::new(Rc::clone(&context)));
-            let fun = pipeline.run(function)?;
+            let mut fun = pipeline.run(function)?;
+            if self.optimize {
+                fun = FoldConstants::new().run(fun)?;
+            }
             let function = Function {
                 var_counter: unsafe { &*context.get() }.var_counter,
                 fun,
@@ -141,11 +149,16 @@ impl Pass for AstToCore {
 /// transformations and lowering.
 struct TranslateAst {
     reporter: Reporter,
+    module: Symbol,
     context: Rc<UnsafeCell<FunctionContext>>,
 }
 impl TranslateAst {
-    fn new(reporter: Reporter, context: Rc<UnsafeCell<FunctionContext>>) -> Self {
-        Self { reporter, context }
+    fn new(reporter: Reporter, module: Symbol, context: Rc<UnsafeCell<FunctionContext>>) -> Self {
+        Self {
+            reporter,
+            module,
+            context,
+        }
     }
 
     #[inline(always)]
@@ -1093,6 +1106,29 @@ impl TranslateAst {
                 let qualifiers = self.preprocess_quals(qualifiers)?;
                 self.bc_tq(span, *body, qualifiers)
             }
+            ast::Expr::MapComprehension(ast::MapComprehension {
+                span,
+                key,
+                value,
+                qualifiers,
+            }) => {
+                // Map comprehensions are lowered to `maps:from_list([{K, V} || Qualifiers])`
+                let qualifiers = self.preprocess_quals(qualifiers)?;
+                let body = ast::Expr::Tuple(ast::Tuple {
+                    span,
+                    elements: vec![*key, *value],
+                });
+                let (lc, mut pre) = self.lc_tq(span, body, qualifiers, inil!(span))?;
+                let lcvar = self.context_mut().next_var(Some(span));
+                pre.push(IExpr::Set(ISet::new(span, lcvar.clone(), lc)));
+                let call = IExpr::Call(ICall::new(
+                    span,
+                    symbols::Maps,
+                    symbols::FromList,
+                    vec![IExpr::Var(lcvar)],
+                ));
+                Ok((call, pre))
+            }
             ast::Expr::Tuple(ast::Tuple { span, elements }) => {
                 let (elements, pre) = self.safe_list(elements)?;
                 Ok((IExpr::Tuple(ITuple::new(span, elements)), pre))
@@ -1281,6 +1317,58 @@ impl TranslateAst {
                 });
                 self.expr(outer)
             }
+            // maybe .. end
+            ast::Expr::Maybe(ast::Maybe {
+                span,
+                body,
+                else_clauses: None,
+            }) => {
+                // This is equivalent to maybe .. else V -> V end
+                let var = ast::Expr::Var(ast::Var(self.context_mut().next_var_name(Some(span))));
+                let clause = ast::Clause::new(span, vec![var.clone()], vec![], vec![var], true);
+                self.expr(ast::Expr::Maybe(ast::Maybe {
+                    span,
+                    body,
+                    else_clauses: Some(vec![clause]),
+                }))
+            }
+            // maybe .. else .. end
+            ast::Expr::Maybe(ast::Maybe {
+                span,
+                body,
+                else_clauses: Some(else_clauses),
+            }) => {
+                let clauses = self.clauses(else_clauses)?;
+                let fpat = self.context_mut().next_var(Some(span));
+                let reason = ituple!(
+                    span,
+                    iatom!(span, symbols::ElseClause),
+                    IExpr::Var(fpat.clone())
+                );
+                let fail = fail_clause(span, vec![IExpr::Var(fpat.clone())], reason);
+                // The else clauses become a local function which is jumped to when a
+                // conditional match fails, the letrec_goto annotation ensures it is
+                // lowered to a label rather than a closure
+                let name = self.context_mut().new_fun_name(Some("maybe_else"));
+                let label = Var::new_with_arity(Ident::new(name, span), 1);
+                let fun = IExpr::Fun(IFun {
+                    span,
+                    annotations: Annotations::default_compiler_generated(),
+                    id: Some(Ident::new(name, span)),
+                    name: Some(Ident::new(name, span)),
+                    vars: vec![fpat],
+                    clauses,
+                    fail,
+                });
+                let body = self.maybe_match_exprs(body, &label)?;
+                let expr = IExpr::LetRec(ILetRec {
+                    span,
+                    annotations: Annotations::from([symbols::LetrecGoto, symbols::NoInline]),
+                    defs: vec![(label, fun)],
+                    body,
+                });
+                Ok((expr, vec![]))
+            }
             ast::Expr::Catch(ast::Catch { span, expr }) => {
                 let (expr, mut pre) = self.expr(*expr)?;
                 pre.push(expr);
@@ -1446,6 +1534,7 @@ impl TranslateAst {
                 }
                 ast::Expr::FunctionVar(name) => {
                     let nspan = name.span();
+                    let is_local = name.module() == Some(self.module);
                     let (m, f, _) = name.mfa();
                     let remote = ast::Expr::Remote(ast::Remote {
                         span: nspan,
@@ -1457,7 +1546,15 @@ impl TranslateAst {
                         callee: Box::new(remote),
                         args,
                     });
-                    self.expr(apply)
+                    // Calls to local functions are distinguished from calls of the form
+                    // `?MODULE:f()`, which are never resolved to the local definition
+                    match self.expr(apply)? {
+                        (IExpr::Call(mut call), pre) if is_local => {
+                            call.annotations.set(symbols::Local);
+                            Ok((IExpr::Call(call), pre))
+                        }
+                        result => Ok(result),
+                    }
                 }
                 ast::Expr::Literal(ast::Literal::Atom(f)) => {
                     let (args, pre) = self.safe_list(args)?;
@@ -1647,6 +1744,18 @@ impl TranslateAst {
                 ));
                 Ok((call, pre))
             }
+            ast::Expr::MaybeMatch(ast::MaybeMatch { span, .. }) => {
+                // The parser only accepts conditional matches in the body of a maybe expression,
+                // but abstract code is not so constrained
+                self.reporter.show_error(
+                    "illegal conditional match",
+                    &[(
+                        span,
+                        "?= is only permitted at the top level of a maybe expression",
+                    )],
+                );
+                bail!("illegal conditional match");
+            }
             _ => unreachable!(),
         }
     }
@@ -1730,7 +1839,14 @@ impl TranslateAst {
                     guards: vec![],
                     body: vec![last],
                 };
-                let clauses = match (gen.acc_pattern, gen.skip_pattern) {
+                let refill_clause = gen.refill.map(|(pattern, guards, action)| IClause {
+                    span,
+                    annotations: Annotations::default(),
+                    patterns: vec![IExpr::Var(pattern)],
+                    guards,
+                    body: vec![*action, nc.clone()],
+                });
+                let mut clauses = match (gen.acc_pattern, gen.skip_pattern) {
                     (None, None) => vec![tail_clause],
                     (None, Some(skip_pat)) => {
                         let skip_clause = IClause {
@@ -1769,6 +1885,7 @@ impl TranslateAst {
                     }
                     _ => unreachable!(),
                 };
+                clauses.extend(refill_clause);
                 let fun = IExpr::Fun(IFun {
                     span,
                     annotations: Annotations::default(),
@@ -1848,7 +1965,24 @@ impl TranslateAst {
                     guards: vec![],
                     body: vec![IExpr::Var(acc_var.clone())],
                 };
-                let clauses = match (gen.acc_pattern, gen.skip_pattern) {
+                let refill_clause = gen.refill.map(|(pattern, guards, action)| IClause {
+                    span,
+                    annotations: Annotations::default(),
+                    patterns: vec![IExpr::Var(pattern), IExpr::Var(ignore.clone())],
+                    guards,
+                    body: vec![
+                        *action,
+                        IExpr::Apply(IApply::new(
+                            span,
+                            IExpr::Var(f.clone()),
+                            vec![
+                                IExpr::Var(gen.tail.clone().unwrap()),
+                                IExpr::Var(acc_var.clone()),
+                            ],
+                        )),
+                    ],
+                });
+                let mut clauses = match (gen.acc_pattern, gen.skip_pattern) {
                     (None, None) => vec![tail_clause],
                     (None, Some(skip_pat)) => {
                         let skip_clause = IClause {
@@ -1898,6 +2032,7 @@ impl TranslateAst {
                     }
                     _ => unreachable!(),
                 };
+                clauses.extend(refill_clause);
                 let fun = IExpr::Fun(IFun {
                     span,
                     annotations: Annotations::default(),
@@ -2147,7 +2282,9 @@ impl TranslateAst {
         //  - tail is the variable used in AccPat and SkipPat bound to the rest of the
         //    generator input.
         //  - tail_pat is the tail pattern, respectively [] and <<_/bitstring>> for list
-        //    and bit string generators.
+        //    and bit string generators, and `none` for map generators.
+        //  - refill is a tuple {Pat,Guards,Action} used by map generators to fetch the next
+        //    element from the map iterator when none of the other patterns match.
         //  - arg is a pair {Pre,Arg} where Pre is the list of expressions to be
         //    inserted before the comprehension function and Arg is the expression
         //    that it should be passed.
@@ -2159,6 +2296,9 @@ impl TranslateAst {
             ast::GeneratorType::Bitstring => {
                 self.bit_generator(gen.span, *gen.pattern, *gen.expr, guards)
             }
+            ast::GeneratorType::Map => {
+                self.map_generator(gen.span, *gen.pattern, *gen.expr, guards)
+            }
         }
     }
 
@@ -2219,11 +2359,105 @@ impl TranslateAst {
             skip_pattern,
             tail: Some(tail),
             tail_pattern: Box::new(IExpr::Literal(Literal::nil(span))),
+            refill: None,
             pre,
             arg: Box::new(arg),
         })
     }
 
+    /// Map generators iterate over either a map, in which case the result of `maps:iterator/1`
+    /// is used, or an iterator already obtained from it. Each step of the iterator is obtained by
+    /// calling `maps:next/1`, yielding either `{K, V, NextIter}` or `none`.
+    ///
+    /// An iterator matches none of the accumulator, skip or tail patterns, so the first iteration
+    /// (and every one following an element) goes through the refill clause, which steps the
+    /// iterator and loops. Anything which is neither a map nor an iterator matches no clause at
+    /// all, raising `{bad_generator, Term}`.
+    fn map_generator(
+        &mut self,
+        span: SourceSpan,
+        pattern: ast::Expr,
+        expr: ast::Expr,
+        guards: Vec<ast::Expr>,
+    ) -> anyhow::Result<IGen> {
+        let ast::Expr::Tuple(ast::Tuple { mut elements, .. }) = pattern else { panic!("invalid map generator pattern, expected {{key, value}} tuple"); };
+        let value = elements.pop().unwrap();
+        let key = elements.pop().unwrap();
+        let iter = self.context_mut().next_var(Some(span));
+        let outer_iter = self.context_mut().next_var(Some(span));
+        let skip_key = IExpr::Var(self.context_mut().next_var(Some(span)));
+        let skip_value = IExpr::Var(self.context_mut().next_var(Some(span)));
+        let acc_guards = self.lc_guard_tests(span, guards);
+        let acc_pattern = match (self.pattern(key), self.pattern(value)) {
+            (Ok(key), Ok(value)) => Some(Box::new(ituple!(
+                span,
+                key,
+                value,
+                IExpr::Var(iter.clone())
+            ))),
+            // If it never matches, there is no need for an accumulator clause.
+            _ => None,
+        };
+        let skip_pattern = Box::new(ituple!(
+            span,
+            skip_key,
+            skip_value,
+            IExpr::Var(iter.clone())
+        ));
+        let next = IExpr::Call(ICall::new(
+            span,
+            symbols::Maps,
+            symbols::Next,
+            vec![IExpr::Var(outer_iter.clone())],
+        ));
+        // Iterators are represented as lists of the remaining entries
+        let is_iterator = IExpr::Call(ICall::new(
+            span,
+            symbols::Erlang,
+            symbols::IsList,
+            vec![IExpr::Var(outer_iter.clone())],
+        ));
+        let refill = (
+            outer_iter,
+            vec![is_iterator],
+            Box::new(IExpr::Set(ISet::new(span, iter.clone(), next))),
+        );
+        let (map, mut pre) = self.safe(expr)?;
+        let init = self.context_mut().next_var(Some(span));
+        let iterator = IExpr::Call(ICall::new(
+            span,
+            symbols::Maps,
+            symbols::Iterator,
+            vec![map.clone()],
+        ));
+        let is_map = IExpr::Call(ICall::new(
+            span,
+            symbols::Erlang,
+            symbols::IsMap,
+            vec![map.clone()],
+        ));
+        let arg = IExpr::If(IIf {
+            span,
+            annotations: Annotations::default_compiler_generated(),
+            guards: vec![is_map],
+            then_body: vec![iterator],
+            else_body: vec![map],
+        });
+        pre.push(IExpr::Set(ISet::new(span, init.clone(), arg)));
+        Ok(IGen {
+            span,
+            annotations: Annotations::default(),
+            acc_pattern,
+            acc_guards,
+            skip_pattern: Some(skip_pattern),
+            tail: Some(iter),
+            tail_pattern: Box::new(IExpr::Literal(lit_atom!(span, symbols::None))),
+            refill: Some(refill),
+            pre,
+            arg: Box::new(IExpr::Var(init)),
+        })
+    }
+
     fn bit_generator(
         &mut self,
         span: SourceSpan,
@@ -2265,6 +2499,7 @@ impl TranslateAst {
                         annotations: Annotations::default(),
                         segments: vec![tail_segment],
                     })),
+                    refill: None,
                     pre,
                     arg: Box::new(arg),
                 })
@@ -2283,6 +2518,7 @@ impl TranslateAst {
                         symbols::Underscore,
                         span,
                     )))),
+                    refill: None,
                     pre,
                     arg: Box::new(arg),
                 })
@@ -2915,6 +3151,86 @@ impl TranslateAst {
         }
     }
 
+    /// Translates the body of a `maybe` expression
+    ///
+    /// Each conditional match `P ?= E` becomes a case on `E`, with the remainder of the body
+    /// in the matching clause, and a failure clause which jumps to the `else` label with the
+    /// unmatched value.
+    fn maybe_match_exprs(
+        &mut self,
+        mut exprs: Vec<ast::Expr>,
+        label: &Var,
+    ) -> anyhow::Result<Vec<IExpr>> {
+        if exprs.is_empty() {
+            return Ok(vec![]);
+        }
+        match exprs.remove(0) {
+            ast::Expr::MaybeMatch(ast::MaybeMatch {
+                span,
+                pattern,
+                expr,
+            }) => {
+                let clause = if exprs.is_empty() {
+                    // The value of the maybe expression is the value of the last match
+                    let all =
+                        ast::Expr::Var(ast::Var(self.context_mut().next_var_name(Some(span))));
+                    let pattern = ast::Expr::Match(ast::Match {
+                        span,
+                        pattern,
+                        expr: Box::new(all.clone()),
+                    });
+                    self.clause(ast::Clause::new(
+                        span,
+                        vec![pattern],
+                        vec![],
+                        vec![all],
+                        false,
+                    ))?
+                } else {
+                    let body = self.maybe_match_exprs(exprs, label)?;
+                    let mut clause = self.clause(ast::Clause::new(
+                        span,
+                        vec![*pattern],
+                        vec![],
+                        vec![],
+                        false,
+                    ))?;
+                    clause.body = body;
+                    clause
+                };
+                let (expr, mut pre) = self.novars(*expr)?;
+                let fpat = self.context_mut().next_var(Some(span));
+                let fail = Box::new(IClause {
+                    span,
+                    annotations: Annotations::default_compiler_generated(),
+                    patterns: vec![IExpr::Var(fpat.clone())],
+                    guards: vec![],
+                    body: vec![IExpr::Apply(IApply {
+                        span,
+                        annotations: Annotations::default_compiler_generated(),
+                        callee: vec![IExpr::Var(label.clone())],
+                        args: vec![IExpr::Var(fpat)],
+                    })],
+                });
+                pre.push(IExpr::Case(ICase {
+                    span,
+                    annotations: Annotations::default(),
+                    args: vec![expr],
+                    clauses: vec![clause],
+                    fail,
+                }));
+                Ok(pre)
+            }
+            expr => {
+                let (expr, mut pre) = self.expr(expr)?;
+                let mut rest = self.maybe_match_exprs(exprs, label)?;
+                pre.push(expr);
+                pre.append(&mut rest);
+                Ok(pre)
+            }
+        }
+    }
+
     fn try_after_large(
         &mut self,
         span: SourceSpan,
@@ -242,6 +242,26 @@ impl ReadFrom for NoArgsMacroCall {
             name: reader.read()?,
         })
     }
+
+    fn try_read_from<R, S>(reader: &mut R) -> Result<Option<Self>>
+    where
+        R: TokenReader<Source = S>,
+    {
+        let _question: SymbolToken =
+            if let Some(_question) = reader.try_read_expected(&Token::Question)? {
+                _question
+            } else {
+                return Ok(None);
+            };
+
+        // A `?` which isn't followed by a macro name, e.g. the `?` of `?=`, must be handed back
+        if let Some(name) = reader.try_read()? {
+            Ok(Some(NoArgsMacroCall { _question, name }))
+        } else {
+            reader.unread_token(_question.into());
+            Ok(None)
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 use std::convert::TryFrom;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -30,6 +30,7 @@ pub struct Preprocessor<Reader: TokenReader> {
     macros: MacroContainer,
     macro_calls: BTreeMap<SourceIndex, MacroCall>,
     expanded_tokens: VecDeque<LexicalToken>,
+    features: BTreeSet<Symbol>,
     warnings_as_errors: bool,
     no_warn: bool,
 }
@@ -63,6 +64,11 @@ where
             MacroDef::Dynamic(vec![]),
         );
 
+        let features = crate::features::iter()
+            .filter(|feat| feat.enabled)
+            .map(|feat| feat.name)
+            .collect();
+
         Preprocessor {
             reporter,
             codemap: parser.codemap.clone(),
@@ -75,6 +81,7 @@ where
             macros,
             macro_calls: BTreeMap::new(),
             expanded_tokens: VecDeque::new(),
+            features,
             warnings_as_errors: parser.config.warnings_as_errors,
             no_warn: parser.config.no_warn,
         }
@@ -99,6 +106,7 @@ where
             macros: self.macros.clone(),
             macro_calls: BTreeMap::new(),
             expanded_tokens: VecDeque::new(),
+            features: self.features.clone(),
             warnings_as_errors: self.warnings_as_errors,
             no_warn: self.no_warn,
         }
@@ -111,7 +119,18 @@ where
     fn next_token(&mut self) -> Result<Option<LexicalToken>, ParserError> {
         loop {
             if let Some(token) = self.expanded_tokens.pop_front() {
-                return Ok(Some(token));
+                if self.starts_question_equals(&token) {
+                    if let Some(next) = self.expanded_tokens.pop_front() {
+                        match Self::join_question_equals(token, next) {
+                            Ok(token) => return Ok(Some(token)),
+                            Err((token, next)) => {
+                                self.expanded_tokens.push_front(next);
+                                return Ok(Some(token));
+                            }
+                        }
+                    }
+                }
+                return Ok(Some(self.feature_keyword(token)));
             }
             if self.can_directive_start {
                 match self.try_read_directive().map_err(ParserError::from)? {
@@ -150,7 +169,18 @@ where
                 } else {
                     self.can_directive_start = false;
                 }
-                return Ok(Some(token));
+                if self.starts_question_equals(&token) {
+                    if let Some(next) = self.reader.try_read_token().map_err(ParserError::from)? {
+                        match Self::join_question_equals(token, next) {
+                            Ok(token) => return Ok(Some(token)),
+                            Err((token, next)) => {
+                                self.reader.unread_token(next);
+                                return Ok(Some(token));
+                            }
+                        }
+                    }
+                }
+                return Ok(Some(self.feature_keyword(token)));
             } else {
                 break;
             }
@@ -158,6 +188,51 @@ where
         Ok(None)
     }
 
+    /// Converts atoms which are reserved words under an enabled feature into their keyword tokens
+    ///
+    /// The lexer has no knowledge of which features are enabled, so `maybe` and `else` are
+    /// always lexed as atoms. Quoted atoms are left alone, so `'maybe'` remains usable as an atom.
+    fn feature_keyword(&self, token: LexicalToken) -> LexicalToken {
+        let LexicalToken(start, tok, end) = token;
+        let keyword = match tok {
+            Token::Atom(symbols::Maybe) => Token::Maybe,
+            Token::Atom(symbols::Else) => Token::Else,
+            tok => return LexicalToken(start, tok, end),
+        };
+        if !self.features.contains(&symbols::MaybeExpr) {
+            return LexicalToken(start, tok, end);
+        }
+        let span = SourceSpan::new(start, end);
+        match self.codemap.source_slice(span.source_id(), span) {
+            Ok(slice) if slice.starts_with('\'') => LexicalToken(start, tok, end),
+            _ => LexicalToken(start, keyword, end),
+        }
+    }
+
+    /// Returns true if `token` is a `?` which may begin a `?=` operator
+    ///
+    /// Like `maybe` and `else`, the lexer has no knowledge of which features are enabled, so it
+    /// always produces `?=` as two tokens, which are only joined when `maybe_expr` is enabled.
+    fn starts_question_equals(&self, token: &LexicalToken) -> bool {
+        token.1 == Token::Question && self.features.contains(&symbols::MaybeExpr)
+    }
+
+    /// Joins a `?` immediately followed by `=` into the `?=` operator
+    ///
+    /// If the tokens are not adjacent, both are handed back unchanged.
+    fn join_question_equals(
+        token: LexicalToken,
+        next: LexicalToken,
+    ) -> Result<LexicalToken, (LexicalToken, LexicalToken)> {
+        match (token, next) {
+            (
+                LexicalToken(start, Token::Question, end),
+                LexicalToken(next_start, Token::Equals, next_end),
+            ) if end == next_start => Ok(LexicalToken(start, Token::QuestionEquals, next_end)),
+            (token, next) => Err((token, next)),
+        }
+    }
+
     fn expand_macro(&mut self, call: MacroCall) -> PResult<VecDeque<LexicalToken>> {
         if let Some(expanded) = self.try_expand_predefined_macro(&call)? {
             Ok(vec![expanded].into())
@@ -244,11 +319,13 @@ where
                         match arg.tokens.as_slice() {
                             [LexicalToken(_, Token::Atom(feature), _)] => {
                                 match crate::features::get(feature) {
-                                    Some(feat) if feat.enabled => LexicalToken(
-                                        span.start(),
-                                        Token::Atom(symbols::True),
-                                        span.end(),
-                                    ),
+                                    Some(feat) if self.features.contains(&feat.name) => {
+                                        LexicalToken(
+                                            span.start(),
+                                            Token::Atom(symbols::True),
+                                            span.end(),
+                                        )
+                                    }
                                     Some(_) => LexicalToken(
                                         span.start(),
                                         Token::Atom(symbols::False),
@@ -508,6 +585,33 @@ where
                     });
                 }
             }
+            Directive::Feature(ref d) if !ignore => {
+                let name = d.name.symbol();
+                if crate::features::get(&name).is_none() {
+                    let span = d.name.span();
+                    return Err(Diagnostic::error()
+                        .with_message("unknown feature")
+                        .with_labels(vec![Label::primary(span.source_id(), span)
+                            .with_message(format!("'{}' is not a recognized feature", name))])
+                        .into());
+                }
+                match d.action.symbol() {
+                    symbols::Enable => {
+                        self.features.insert(name);
+                    }
+                    symbols::Disable => {
+                        self.features.remove(&name);
+                    }
+                    _ => {
+                        let span = d.action.span();
+                        return Err(Diagnostic::error()
+                            .with_message("invalid -feature directive")
+                            .with_labels(vec![Label::primary(span.source_id(), span)
+                                .with_message("expected either 'enable' or 'disable'")])
+                            .into());
+                    }
+                }
+            }
             Directive::File(ref f) if !ignore => {
                 // TODO
                 let span = f.span();
@@ -32,6 +32,10 @@ impl Pass for KernelToSsa {
 
     fn run<'a>(&mut self, mut module: Self::Input<'a>) -> anyhow::Result<Self::Output<'a>> {
         let mut ir_module = Module::new(module.name);
+        ir_module.on_load = module
+            .on_load
+            .as_ref()
+            .map(|name| Span::new(name.span(), name.resolve(module.name.name)));
 
         // Declare all functions in the module, and store their refs so we can access them later
         let mut functions = Vec::with_capacity(module.functions.len());
@@ -42,11 +46,15 @@ impl Pass for KernelToSsa {
             } else {
                 Visibility::DEFAULT
             };
-            let visibility = if kfunction.has_annotation(symbols::Nif) {
+            let mut visibility = if kfunction.has_annotation(symbols::Nif) {
                 base_visibility | Visibility::NIF
             } else {
                 base_visibility
             };
+            let is_closure = kfunction.has_annotation(symbols::Closure);
+            if is_closure {
+                visibility |= Visibility::CLOSURE;
+            }
             let mut params = vec![];
             params.resize(name.arity as usize, Type::Term(TermType::Any));
             let signature = Signature {
@@ -62,7 +70,7 @@ impl Pass for KernelToSsa {
                     ],
                 ),
             };
-            let id = if kfunction.has_annotation(symbols::Closure) {
+            let id = if is_closure {
                 ir_module.declare_closure(signature.clone())
             } else {
                 ir_module.declare_function(signature.clone())
@@ -1,138 +1,120 @@
-use firefly_diagnostics::{Spanned, SourceSpan};
-use firefly_intern::Ident;
-use firefly_number::{Integer, Float};
+use std::borrow::Cow;
 
-#[derive(Debug)]
+use firefly_diagnostics::{SourceSpan, Spanned};
+use firefly_intern::{Ident, Symbol};
+use firefly_number::{Float, Integer};
+
+/// The root of a parsed abstract format file, i.e. a sequence of terms, each terminated by `.`
+#[derive(Debug, Clone)]
 pub struct Root {
+    pub span: SourceSpan,
     pub items: Vec<Item>,
 }
-impl Root {
-    pub fn span(&self) -> SourceSpan {
-        self.items
-            .first()
-            .map(|i| i.span())
-            .unwrap_or(SourceSpan::UNKNOWN)
-    }
-}
 
-#[derive(Debug)]
+/// An Erlang term, with source span information attached to each node
+#[derive(Debug, Clone, Spanned)]
 pub enum Item {
-    Lit(Literal),
-    Attribute(Spanned<Attr>),
-    Function(Spanned<Func>),
-    Tuple(Spanned<Tuple>),
-    List(Spanned<List>),
-    Eof(SourceSpan),
+    Atom(Ident),
+    /// A string literal, e.g. `"foo"`
+    ///
+    /// NOTE: `~p` prints lists of printable integers as strings, so consumers which expect
+    /// a list should use `Item::elements`, which handles both representations.
+    String(Ident),
+    Char(#[span] SourceSpan, char),
+    Int(#[span] SourceSpan, Integer),
+    Float(#[span] SourceSpan, Float),
+    Tuple(#[span] SourceSpan, Vec<Item>),
+    List {
+        #[span]
+        span: SourceSpan,
+        head: Vec<Item>,
+        tail: Option<Box<Item>>,
+    },
+    Map(#[span] SourceSpan, Vec<(Item, Item)>),
+    Binary(#[span] SourceSpan, Vec<u8>),
 }
-
 impl Item {
-    pub fn tuple(&self) -> Option<&Tuple> {
+    pub fn atom(&self) -> Option<Ident> {
         match self {
-            Item::Tuple(inner) => Some(inner),
+            Self::Atom(a) => Some(*a),
             _ => None,
         }
     }
 
-    pub fn atom(&self) -> Option<Ident> {
+    pub fn tuple(&self) -> Option<&[Item]> {
         match self {
-            Item::Atom(inner) => Some(*inner),
+            Self::Tuple(_, elements) => Some(elements.as_slice()),
             _ => None,
         }
     }
 
-    pub fn string(&self) -> Option<Ident> {
+    /// If this item is a tuple whose first element is an atom, returns the atom and the remaining elements.
+    ///
+    /// Nearly all nodes in the abstract format are of this shape, e.g. `{var, Anno, 'X'}`.
+    pub fn tagged(&self) -> Option<(Symbol, &[Item])> {
         match self {
-            Item::String(inner) => Some(*inner),
+            Self::Tuple(_, elements) => match elements.split_first() {
+                Some((Self::Atom(tag), rest)) => Some((tag.name, rest)),
+                _ => None,
+            },
             _ => None,
         }
     }
 
-    pub fn integer(&self) -> Option<&Int> {
+    pub fn integer(&self) -> Option<&Integer> {
         match self {
-            Item::Int(inner) => Some(inner),
+            Self::Int(_, i) => Some(i),
             _ => None,
         }
     }
 
-    pub fn float(&self) -> Option<&Float> {
+    pub fn is_nil(&self) -> bool {
         match self {
-            Item::Float(inner) => Some(inner),
-            _ => None,
+            Self::List { head, tail, .. } => head.is_empty() && tail.is_none(),
+            Self::String(s) => s.as_str().get().is_empty(),
+            _ => false,
         }
     }
 
-    pub fn list(&self) -> Option<&List> {
+    /// Returns the elements of this item if it is a proper list.
+    ///
+    /// Strings are treated as lists of characters, and improper lists return `None`.
+    pub fn elements(&self) -> Option<Vec<Cow<'_, Item>>> {
         match self {
-            Item::List(inner) => Some(inner),
+            Self::String(s) => Some(
+                s.as_str()
+                    .get()
+                    .chars()
+                    .map(|c| Cow::Owned(Self::Int(s.span, Integer::from(c))))
+                    .collect(),
+            ),
+            Self::List { head, tail, .. } => {
+                let mut elements = head.iter().map(Cow::Borrowed).collect::<Vec<_>>();
+                match tail.as_deref() {
+                    None => Some(elements),
+                    Some(tail) => {
+                        elements.extend(tail.elements()?);
+                        Some(elements)
+                    }
+                }
+            }
             _ => None,
         }
     }
 
-    pub fn list_iter(&self) -> Option<impl Iterator<Item = &Item>> {
-        self.list().map(|v| ListIterator {
-            curr: Some(v),
-            idx: 0,
-        })
-    }
-
-    pub fn span(&self) -> SourceSpan {
+    /// Returns the characters of this item if it is a string, or a proper list of character codes
+    pub fn chars(&self) -> Option<String> {
         match self {
-            Item::Atom(ident) => ident.span,
-            Item::String(ident) => ident.span,
-            Item::Int(int) => int.span,
-            Item::Tuple(tup) => tup.span,
-            Item::List(list) => list.span,
-            Item::Float(float) => float.span,
-        }
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct Literal {
-    Atom(Ident),
-    Bool(Ident),
-    Int(SourceSpan, Integer),
-    Float(SourceSpan, Float),
-    String(Ident),
-}
-
-#[derive(Debug)]
-pub struct Tuple(Vec<Item>);
-
-#[derive(Debug)]
-pub struct List {
-    pub span: SourceSpan,
-    pub head: Vec<Item>,
-    pub tail: Option<Box<Item>>,
-}
-
-pub struct ListIterator<'a> {
-    curr: Option<&'a List>,
-    idx: usize,
-}
-impl<'a> Iterator for ListIterator<'a> {
-    type Item = &'a Item;
-    fn next(&mut self) -> Option<Self::Item> {
-        if let Some(list) = self.curr {
-            if let Some(result) = list.heads.get(self.idx) {
-                self.idx += 1;
-                return Some(result);
-            } else {
-                self.idx = 0;
-                if let Some(tail) = &list.tail {
-                    if let Some(tail_list) = tail.list() {
-                        self.curr = Some(tail_list);
-                        return self.next();
-                    } else {
-                        unimplemented!()
-                    }
-                } else {
-                    self.curr = None;
-                    return None;
-                }
-            }
-        } else {
-            None
+            Self::String(s) => Some(s.as_str().get().to_string()),
+            _ => self
+                .elements()?
+                .iter()
+                .map(|e| match &**e {
+                    Self::Int(_, i) => i.to_char(),
+                    Self::Char(_, c) => Some(*c),
+                    _ => None,
+                })
+                .collect(),
         }
     }
 }
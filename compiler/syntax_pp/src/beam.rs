@@ -0,0 +1,246 @@
+//! Recovery of the abstract format of a module from the debug info of a compiled BEAM file
+//!
+//! Modules compiled with `+debug_info` carry their abstract code in either the `Dbgi` chunk
+//! (OTP 20+), or the legacy `Abst` chunk. The abstract code is decoded from the external term
+//! format, and rendered in the same textual form accepted for `.abstr` inputs. The rendered
+//! text is registered with the codemap, so that diagnostics raised while translating the forms
+//! have a source to refer to.
+use std::fmt::Write;
+use std::path::Path;
+
+use firefly_beam::beam::reader::RawBeamFile;
+use firefly_beam::serialization::etf::Term;
+use firefly_diagnostics::*;
+use firefly_parser::Parser;
+
+use crate::ast::Root;
+use crate::parser::ParseError;
+
+/// Reads the abstract code contained in the BEAM file at `path`, and parses it into a [`Root`].
+///
+/// Returns `ParseError::NoDebugInfo` if the module was not compiled with `+debug_info`.
+pub fn parse_beam(
+    parser: &Parser<()>,
+    reporter: Reporter,
+    path: &Path,
+) -> Result<Root, ParseError> {
+    let source = abstract_code(path)?;
+    let name = FileName::Virtual(format!("{}.abstr", path.display()).into());
+    let id = parser.codemap.add(name, source);
+    let file = parser.codemap.get(id).unwrap();
+    parser.parse(reporter, file)
+}
+
+/// Reads the abstract code contained in the BEAM file at `path`, rendered as a sequence of
+/// `.`-terminated forms.
+pub fn abstract_code(path: &Path) -> Result<String, ParseError> {
+    let invalid = |reason: String| ParseError::InvalidBeam {
+        path: path.to_path_buf(),
+        reason,
+    };
+    let no_debug_info = || ParseError::NoDebugInfo {
+        path: path.to_path_buf(),
+    };
+
+    let beam = RawBeamFile::from_file(path).map_err(|err| invalid(err.to_string()))?;
+    let decode = |data: &[u8]| {
+        Term::decode(std::io::Cursor::new(data)).map_err(|err| invalid(err.to_string()))
+    };
+
+    let forms = if let Some(chunk) = beam.get_chunk(b"Dbgi") {
+        // {debug_info_v1, Backend, Data}
+        let term = decode(&chunk.data)?;
+        match tuple(&term) {
+            Some([tag, backend, data]) if atom(tag) == Some("debug_info_v1") => {
+                match atom(backend) {
+                    Some("erl_abstract_code") => match tuple(data) {
+                        Some([forms, _opts]) if atom(forms) == Some("none") => {
+                            return Err(no_debug_info())
+                        }
+                        Some([Term::List(forms), _opts]) => forms.elements.clone(),
+                        _ => return Err(invalid("malformed Dbgi chunk".to_string())),
+                    },
+                    Some(backend) => {
+                        return Err(invalid(format!(
+                            "unsupported debug info backend '{}'",
+                            backend
+                        )))
+                    }
+                    None => return Err(invalid("malformed Dbgi chunk".to_string())),
+                }
+            }
+            _ => return Err(invalid("unsupported Dbgi chunk version".to_string())),
+        }
+    } else {
+        match beam.get_chunk(b"Abst") {
+            // Modules compiled without debug info have an empty `Abst` chunk
+            Some(chunk) if !chunk.data.is_empty() => {
+                // {raw_abstract_v1, Forms}
+                let term = decode(&chunk.data)?;
+                match tuple(&term) {
+                    Some([tag, Term::List(forms)]) if atom(tag) == Some("raw_abstract_v1") => {
+                        forms.elements.clone()
+                    }
+                    Some([tag, _]) if atom(tag) == Some("raw_abstract_v1") => {
+                        return Err(invalid("malformed Abst chunk".to_string()))
+                    }
+                    _ => return Err(invalid("unsupported Abst chunk version".to_string())),
+                }
+            }
+            _ => return Err(no_debug_info()),
+        }
+    };
+
+    // The forms themselves are validated when they are translated, which handles the
+    // `{Line, Column}` annotations of OTP 24+ as well as plain line numbers
+    let mut source = String::new();
+    for form in forms.iter() {
+        render(form, &mut source).map_err(invalid)?;
+        source.push_str(".\n");
+    }
+    Ok(source)
+}
+
+fn atom(term: &Term) -> Option<&str> {
+    match term {
+        Term::Atom(a) => Some(a.name.as_str()),
+        _ => None,
+    }
+}
+
+fn tuple(term: &Term) -> Option<&[Term]> {
+    match term {
+        Term::Tuple(t) => Some(t.elements.as_slice()),
+        _ => None,
+    }
+}
+
+/// Renders `term` in a form that can be read back by the abstract format parser
+fn render(term: &Term, out: &mut String) -> Result<(), String> {
+    match term {
+        Term::Atom(a) => {
+            out.push('\'');
+            for c in a.name.chars() {
+                match c {
+                    '\\' => out.push_str("\\\\"),
+                    '\'' => out.push_str("\\'"),
+                    '\n' => out.push_str("\\n"),
+                    '\t' => out.push_str("\\t"),
+                    c => out.push(c),
+                }
+            }
+            out.push('\'');
+        }
+        Term::FixInteger(i) => write!(out, "{}", i.value).unwrap(),
+        Term::BigInteger(i) => write!(out, "{}", i.value).unwrap(),
+        Term::Float(f) => {
+            // Erlang requires a fractional part, e.g. `1.0e10` rather than `1e10`
+            let mut s = format!("{:?}", f.value);
+            if !s.contains('.') {
+                match s.find('e') {
+                    Some(index) => s.insert_str(index, ".0"),
+                    None => s.push_str(".0"),
+                }
+            }
+            out.push_str(&s);
+        }
+        Term::Binary(b) => {
+            out.push_str("<<");
+            for (i, byte) in b.bytes.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write!(out, "{}", byte).unwrap();
+            }
+            out.push_str(">>");
+        }
+        Term::List(list) => {
+            out.push('[');
+            render_elements(&list.elements, out)?;
+            out.push(']');
+        }
+        Term::ImproperList(list) => {
+            out.push('[');
+            render_elements(&list.elements, out)?;
+            out.push('|');
+            render(&list.last, out)?;
+            out.push(']');
+        }
+        Term::Tuple(tuple) => {
+            out.push('{');
+            render_elements(&tuple.elements, out)?;
+            out.push('}');
+        }
+        Term::Map(map) => {
+            out.push_str("#{");
+            for (i, (k, v)) in map.entries.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                render(k, out)?;
+                out.push_str(" => ");
+                render(v, out)?;
+            }
+            out.push('}');
+        }
+        other => return Err(format!("unexpected term in abstract code: {}", other)),
+    }
+    Ok(())
+}
+
+fn render_elements(elements: &[Term], out: &mut String) -> Result<(), String> {
+    for (i, element) in elements.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        render(element, out)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use firefly_diagnostics::*;
+    use firefly_parser::Parser;
+
+    #[test]
+    fn beam_with_abst_chunk() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../../library/beam/tests/testdata/ast/test.beam");
+        let codemap = Arc::new(CodeMap::new());
+        let parser = Parser::new((), codemap.clone());
+        let reporter = Reporter::new();
+        let module = super::parse_beam(&parser, reporter.clone(), &path)
+            .and_then(|root| crate::translate(&reporter, codemap.clone(), &root));
+        match module {
+            Ok(module) => assert_eq!(module.name().as_str().get(), "test"),
+            Err(err) => {
+                reporter.diagnostic(err.to_diagnostic());
+                reporter.print(&codemap);
+                panic!("failed to load abstract code from beam");
+            }
+        }
+    }
+
+    #[test]
+    fn beam_with_line_column_annotations() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../../library/beam/tests/testdata/ast/line_column.beam");
+        let codemap = Arc::new(CodeMap::new());
+        let parser = Parser::new((), codemap.clone());
+        let reporter = Reporter::new();
+        let module = super::parse_beam(&parser, reporter.clone(), &path)
+            .and_then(|root| crate::translate(&reporter, codemap.clone(), &root));
+        match module {
+            Ok(module) => assert_eq!(module.name().as_str().get(), "line_column"),
+            Err(err) => {
+                reporter.diagnostic(err.to_diagnostic());
+                reporter.print(&codemap);
+                panic!("failed to load abstract code from beam");
+            }
+        }
+    }
+}
@@ -1,5 +1,15 @@
+//! This crate provides a parser for Erlang Abstract Format files (e.g. as produced by `erlc -P`,
+//! or by writing out the forms of a module with `io:format("~p.~n", [Form])`), as well as the
+//! translation of those forms into the Erlang AST provided by `firefly_syntax_erl`.
+//!
+//! Abstract code can also be recovered from BEAM files compiled with `+debug_info`, see
+//! [`parse_beam`].
 mod ast;
+mod beam;
 mod parser;
+mod translate;
 
 pub use self::ast::*;
+pub use self::beam::{abstract_code, parse_beam};
 pub use self::parser::*;
+pub use self::translate::translate;
@@ -1,57 +1,142 @@
 use firefly_diagnostics::*;
+use firefly_syntax_erl::{LexicalError, ParserError, Token};
 
-#[derive(thiserror::Error, Debug)]
+pub type LalrPopError = lalrpop_util::ParseError<SourceIndex, Token, ParseError>;
+
+#[derive(Debug, thiserror::Error)]
 pub enum ParseError {
-    #[error("error occurred when reading {path:?}: {source}")]
-    RootFileError {
-        #[source]
+    #[error("error reading {path:?}: {source}")]
+    RootFile {
         source: std::io::Error,
         path: std::path::PathBuf,
     },
-    #[error("parsing failed: {0}")]
-    LalrPop(#[from] lalrpop_util::ParseError<SourceIndex, Token, ()>),
+
+    #[error("{path:?} contains no debug info, recompile it with +debug_info")]
+    NoDebugInfo { path: std::path::PathBuf },
+
+    #[error("unable to read abstract code from {path:?}: {reason}")]
+    InvalidBeam {
+        path: std::path::PathBuf,
+        reason: String,
+    },
+
+    #[error(transparent)]
+    Lexical {
+        #[from]
+        source: LexicalError,
+    },
+
+    #[error("{}", .diagnostic.message)]
+    ShowDiagnostic { diagnostic: Diagnostic },
+
+    #[error("invalid token")]
+    InvalidToken { location: SourceIndex },
+
+    #[error("unrecognized token")]
+    UnrecognizedToken {
+        span: SourceSpan,
+        expected: Vec<String>,
+    },
+
+    #[error("extra token")]
+    ExtraToken { span: SourceSpan },
+
+    #[error("unexpected eof")]
+    UnexpectedEOF {
+        location: SourceIndex,
+        expected: Vec<String>,
+    },
+}
+impl ParseError {
+    /// Constructs an error for a term which is syntactically valid, but is not valid abstract format
+    pub fn invalid_form(span: SourceSpan, message: impl Into<String>) -> Self {
+        Self::ShowDiagnostic {
+            diagnostic: Diagnostic::error()
+                .with_message("invalid abstract format")
+                .with_labels(vec![
+                    Label::primary(span.source_id(), span).with_message(message.into())
+                ]),
+        }
+    }
 }
+
+impl From<Diagnostic> for ParseError {
+    fn from(err: Diagnostic) -> Self {
+        ParseError::ShowDiagnostic { diagnostic: err }
+    }
+}
+
+impl From<ParserError> for ParseError {
+    fn from(err: ParserError) -> Self {
+        ParseError::ShowDiagnostic {
+            diagnostic: err.to_diagnostic(),
+        }
+    }
+}
+
+impl From<LalrPopError> for ParseError {
+    fn from(err: LalrPopError) -> Self {
+        use lalrpop_util::ParseError::*;
+        match err {
+            InvalidToken { location } => Self::InvalidToken { location },
+            UnrecognizedEOF { location, expected } => Self::UnexpectedEOF { location, expected },
+            UnrecognizedToken {
+                token: (l, _, r),
+                expected,
+            } => Self::UnrecognizedToken {
+                span: SourceSpan::new(l, r),
+                expected,
+            },
+            ExtraToken { token: (l, _, r) } => Self::ExtraToken {
+                span: SourceSpan::new(l, r),
+            },
+            User { error } => error,
+        }
+    }
+}
+
 impl ToDiagnostic for ParseError {
     fn to_diagnostic(&self) -> Diagnostic {
-        use lalrpop_util::ParseError::*;
         match self {
-            Self::RootFileError { source, path } => Diagnostic::error().with_message(format!(
-                "error occurred while reading {:?}: {}",
-                path, source
-            )),
-            Self::LalrPop(InvalidToken { location }) => {
-                let source_id = location.source_id();
+            Self::RootFile { .. } | Self::NoDebugInfo { .. } | Self::InvalidBeam { .. } => {
+                Diagnostic::error().with_message(self.to_string())
+            }
+            Self::Lexical { source } => source.to_diagnostic(),
+            Self::ShowDiagnostic { diagnostic } => diagnostic.clone(),
+            Self::UnrecognizedToken {
+                ref span,
+                ref expected,
+            } => Diagnostic::error()
+                .with_message("unrecognized token")
+                .with_labels(vec![Label::primary(span.source_id(), *span)
+                    .with_message(format!("expected: {}", expected.join(", ")))]),
+            Self::InvalidToken { location } => {
                 let index = *location;
                 Diagnostic::error()
-                    .with_message("invalid token")
+                    .with_message("unexpected token")
                     .with_labels(vec![Label::primary(
-                        source_id,
+                        index.source_id(),
                         SourceSpan::new(index, index),
                     )
-                    .with_message("invalid token encountered here")])
+                    .with_message("did not expect this token")])
             }
-            Self::LalrPop(UnrecognizedEOF { location, expected }) => {
-                let source_id = location.source_id();
+            Self::UnexpectedEOF {
+                location,
+                ref expected,
+            } => {
                 let index = *location;
                 Diagnostic::error()
                     .with_message("unexpected end of file")
                     .with_labels(vec![Label::primary(
-                        source_id,
+                        index.source_id(),
                         SourceSpan::new(index, index),
                     )
-                    .with_message(&format!("expected one of: {}", expected.join(", ")))])
+                    .with_message(format!("expected: {}", expected.join(", ")))])
             }
-            Self::LalrPop(ExtraToken { token: (l, _, r) }) => Diagnostic::error()
-                .with_message("extra token")
-                .with_labels(vec![Label::primary(l.source_id(), SourceSpan::new(*l, *r))
-                    .with_message("did not expect this token")]),
-            Self::LalrPop(UnrecognizedToken {
-                token: (l, _, r), ..
-            }) => Diagnostic::error()
+            Self::ExtraToken { span } => Diagnostic::error()
                 .with_message("unexpected token")
-                .with_labels(vec![Label::primary(l.source_id(), SourceSpan::new(*l, *r))
+                .with_labels(vec![Label::primary(span.source_id(), *span)
                     .with_message("did not expect this token")]),
-            Self::LalrPop(User { .. }) => Diagnostic::error().with_message("parsing failed"),
         }
     }
 }
@@ -1,12 +1,6 @@
 mod error;
 pub use self::error::ParseError;
 
-mod lexer;
-pub use self::lexer::Lexer;
-
-mod token;
-pub use self::token::Token;
-
 /// Used in the grammar for easy span creation
 macro_rules! span {
     ($l:expr, $r:expr) => {
@@ -31,60 +25,50 @@ pub(crate) mod grammar {
     // in that cached source directory because of https://github.com/lalrpop/lalrpop/issues/280.
     // Later runs of `cargo vendor` then copy the source from that directory, including the
     // generated file.
-    include!(concat!(env!("OUT_DIR"), "/grammar.rs"));
+    include!(concat!(env!("OUT_DIR"), "/parser/grammar.rs"));
 }
 
-use firefly_beam::beam::AbstractCode;
-use firefly_diagnostics::{ToDiagnostic, Diagnostic, Label, Reporter, SourceSpan};
-use firefly_parser::{Parse, Parser, Scanner, Source};
-
-use crate::ast::{self, Form};
+use std::path::PathBuf;
+use std::sync::Arc;
 
-/// Parses forms from the given AbstractCode object
-pub from_abstract_code(code: &AbstractCode) -> anyhow::Result<Form> {
-    use firefly_beam::serialization::etf::pattern::VarList;
+use firefly_diagnostics::{CodeMap, Reporter, SourceIndex};
+use firefly_parser::{Parse, Parser, Scanner, Source};
+use firefly_syntax_erl::{Lexer, LexicalToken, Token};
 
-    let (_, form) = code
-        .code()
-        .as_match(("raw_abstract_v1", VarList(FormPattern)))?;
-
-    Ok(form)
-}
+use crate::ast;
 
 impl Parse for ast::Root {
     type Parser = grammar::RootParser;
     type Error = ParseError;
     type Config = ();
-    type Token = Result<(SourceIndex, Token, SourceIndex), ()>;
+    type Token = Result<(SourceIndex, Token, SourceIndex), ParseError>;
 
-    fn root_file_error(source: std::io::Error, path: std::path::PathBuf) -> Self::Error {
-        ParseError::RootFileError { source, path }
+    fn root_file_error(source: std::io::Error, path: PathBuf) -> Self::Error {
+        ParseError::RootFile { source, path }
     }
 
-    fn parse<S>(
-        _parser: &Parser<Self::Config>,
-        reporter: Reporter,
-        source: S,
-    ) -> Result<Self, ()>
+    fn parse<S>(parser: &Parser<()>, reporter: Reporter, source: S) -> Result<Self, ParseError>
     where
         S: Source,
     {
         let scanner = Scanner::new(source);
-        let lexer = Lexer::new(scanner);
-        Self::parse_tokens(errors, lexer)
+        let lexer = Lexer::new(scanner).map(|lexed| {
+            lexed
+                .map(|LexicalToken(l, tok, r)| (l, tok, r))
+                .map_err(ParseError::from)
+        });
+        Self::parse_tokens(reporter, parser.codemap.clone(), lexer)
     }
 
-    fn parse_tokens<S>(reporter: Reporter, tokens: S) -> Result<Self, ()>
+    fn parse_tokens<S>(
+        _reporter: Reporter,
+        _codemap: Arc<CodeMap>,
+        tokens: S,
+    ) -> Result<Self, ParseError>
     where
         S: IntoIterator<Item = Self::Token>,
     {
-        match Self::Parser::new().parse(tokens) {
-            Ok(inner) => Ok(inner),
-            Err(err) => {
-                errors.error(err.into());
-                Err(())
-            }
-        }
+        Self::Parser::new().parse(tokens).map_err(ParseError::from)
     }
 }
 
@@ -93,46 +77,41 @@ mod test {
     use std::sync::Arc;
 
     use firefly_diagnostics::*;
-    use firefly_parser::{Parse, Parser};
-
-    use super::*;
+    use firefly_parser::Parser;
 
-    fn fail_with(reporter: &Reporter, codemap: &CodeMap) -> ! {
-        use term::termcolor::{ColorChoice, StandardStream};
+    use crate::ast::*;
 
-        let config = term::Config::default();
-        let mut out = StandardStream::stderr(ColorChoice::Always);
-        for diagnostic in reporter.diagnostics() {
-            term::emit(&mut out, &config, codemap, &diagnostic).unwrap();
+    fn parse(input: &str) -> Root {
+        let codemap = Arc::new(CodeMap::new());
+        let parser = Parser::new((), codemap.clone());
+        let reporter = Reporter::new();
+        match parser.parse_string::<Root, _, _>(reporter.clone(), input) {
+            Ok(root) => root,
+            Err(err) => {
+                reporter.diagnostic(err.to_diagnostic());
+                reporter.print(&codemap);
+                panic!("parsing failed");
+            }
         }
-        panic!();
-    }
-
-    fn parse<T, S>(input: S) -> T
-    where
-        T: Parse<T, Config = (), Error = ParseError>,
-        S: AsRef<str>,
-    {
-        let parser = Parser::new((), Arc::new(CodeMap::new()));
-        let mut errors = Errors::new();
-        match parser.parse_string::<T, S>(&mut errors, input) {
-            Ok(ast) => return ast,
-            Err(()) => fail_with(&errors, &parser.codemap),
-        };
     }
 
     #[test]
     fn simple() {
-        let _: Root = parse(
-            "
-{woo, '123fwoo', {}}.
-",
-        );
+        let root = parse("{woo, '123fwoo', {}, [1, 2 | 3], #{a => -1.5}, <<\"ab\", 99>>}.\n");
+        assert_eq!(root.items.len(), 1);
+        let (tag, rest) = root.items[0].tagged().unwrap();
+        assert_eq!(tag.as_str().get(), "woo");
+        assert_eq!(rest.len(), 5);
+        assert!(rest[2].elements().is_none());
+        match &rest[4] {
+            Item::Binary(_, bytes) => assert_eq!(bytes.as_slice(), b"abc"),
+            other => panic!("expected binary, got {:?}", other),
+        }
     }
 
     #[test]
     fn basic_ast() {
-        let _: Root = parse(
+        let root = parse(
             "
 {attribute,1,file,{\"woo.erl\",1}}.
 {attribute,1,module,woo}.
@@ -152,9 +131,10 @@ mod test {
 {function,14,binary,0,
     [{clause,14,[],[],
     [{bin,14,[{bin_element,14,{string,14,\"woo\"},default,default}]}]}]}.
-{function,16,string,0,[{clause,16,[],[],[{string,16,\"woo\"}]}]}.
+{function,16,string,0,[{clause,{16,1},[],[],[{string,{16,7},\"woo\"}]}]}.
 {eof,17}.
 ",
         );
+        assert_eq!(root.items.len(), 9);
     }
 }
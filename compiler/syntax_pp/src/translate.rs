@@ -0,0 +1,1207 @@
+//! Translation of Erlang Abstract Format terms into the Erlang AST
+//!
+//! Annotations in the abstract format (line numbers, `{Line, Column}` pairs, or annotation lists)
+//! are ignored; instead, each node is given the span of the term it was translated from, so that
+//! diagnostics point at the relevant part of the input file.
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use firefly_binary::{BinaryEntrySpecifier, BitVec};
+use firefly_diagnostics::*;
+use firefly_intern::{symbols, Ident, Symbol};
+use firefly_syntax_base::{BinaryOp, DeprecatedFlag, Deprecation, FunctionName, UnaryOp};
+use firefly_syntax_erl::*;
+use firefly_syntax_erl::binary::specifier_from_parsed;
+
+use crate::ast::{Item, Root};
+use crate::parser::ParseError;
+
+type Result<T> = std::result::Result<T, ParseError>;
+
+/// Translates the forms contained in `root` into an Erlang module.
+///
+/// The forms are expected to be in the order produced by `epp`, i.e. the module attribute
+/// must be present, and all forms must be terminated by `.`.
+pub fn translate(reporter: &Reporter, codemap: Arc<CodeMap>, root: &Root) -> Result<Module> {
+    let translator = Translator { reporter };
+    let mut forms = Vec::with_capacity(root.items.len());
+    for item in root.items.iter() {
+        if let Some(form) = translator.form(item)? {
+            forms.push(form);
+        }
+    }
+
+    let module = Module::new_from_pp(reporter, codemap, root.span, forms)
+        .map_err(|err| ParseError::invalid_form(root.span, err.to_string()))?;
+    if reporter.is_failed() {
+        return Err(ParseError::ShowDiagnostic {
+            diagnostic: Diagnostic::error()
+                .with_message("parsing failed, see diagnostics for details"),
+        });
+    }
+    Ok(module)
+}
+
+struct Translator<'a> {
+    reporter: &'a Reporter,
+}
+impl<'a> Translator<'a> {
+    fn invalid(&self, item: &Item, expected: &str) -> ParseError {
+        ParseError::invalid_form(item.span(), format!("expected {}", expected))
+    }
+
+    /// Destructures a node of the form `{Tag, ...}`, returning the tag and remaining elements
+    fn node<'i>(&self, item: &'i Item, expected: &str) -> Result<(&'static str, &'i [Item])> {
+        item.tagged()
+            .map(|(tag, rest)| (tag.as_str().get(), rest))
+            .ok_or_else(|| self.invalid(item, expected))
+    }
+
+    fn atom(&self, item: &Item) -> Result<Ident> {
+        item.atom().ok_or_else(|| self.invalid(item, "an atom"))
+    }
+
+    fn list<'i>(&self, item: &'i Item) -> Result<Vec<Cow<'i, Item>>> {
+        item.elements()
+            .ok_or_else(|| self.invalid(item, "a proper list"))
+    }
+
+    fn arity(&self, item: &Item) -> Result<u8> {
+        item.integer()
+            .and_then(|i| i.to_usize())
+            .and_then(|i| u8::try_from(i).ok())
+            .ok_or_else(|| self.invalid(item, "a valid arity"))
+    }
+
+    /// Translates a `{Name, Arity}` pair, as found in attributes such as `export`
+    fn function_name(&self, item: &Item) -> Result<Span<FunctionName>> {
+        match item.tuple() {
+            Some([name, arity]) => {
+                let name = self.atom(name)?;
+                let arity = self.arity(arity)?;
+                Ok(Span::new(
+                    item.span(),
+                    FunctionName::new_local(name.name, arity),
+                ))
+            }
+            _ => Err(self.invalid(item, "a tuple of {Name, Arity}")),
+        }
+    }
+
+    fn function_names(&self, item: &Item) -> Result<Vec<Span<FunctionName>>> {
+        self.list(item)?
+            .iter()
+            .map(|name| self.function_name(name))
+            .collect()
+    }
+
+    /// Unwraps an `{atom, Anno, Name}` node
+    fn atom_node(&self, item: &Item) -> Result<Ident> {
+        match self.node(item, "an atom")? {
+            ("atom", [_, name]) => self.atom(name).map(|a| Ident::new(a.name, item.span())),
+            _ => Err(self.invalid(item, "an atom")),
+        }
+    }
+
+    /// Unwraps an `{atom, Anno, Name}` or `{var, Anno, Name}` node
+    fn name_node(&self, item: &Item) -> Result<Name> {
+        match self.node(item, "an atom or variable")? {
+            ("atom", [_, name]) => Ok(Name::Atom(Ident::new(self.atom(name)?.name, item.span()))),
+            ("var", [_, name]) => Ok(Name::Var(Ident::new(self.atom(name)?.name, item.span()))),
+            _ => Err(self.invalid(item, "an atom or variable")),
+        }
+    }
+
+    fn form(&self, item: &Item) -> Result<Option<TopLevel>> {
+        let span = item.span();
+        match self.node(item, "a form")? {
+            ("attribute", [_, name, value]) => self.attribute(span, self.atom(name)?, value),
+            ("function", [_, name, arity, clauses]) => {
+                let name = Name::Atom(self.atom(name)?);
+                let arity = self.arity(arity)?;
+                let clauses = self
+                    .list(clauses)?
+                    .iter()
+                    .map(|clause| self.clause(clause).map(|c| (Some(name), c)))
+                    .collect::<Result<Vec<_>>>()?;
+                if clauses.is_empty() {
+                    return Err(self.invalid(item, "at least one function clause"));
+                }
+                if clauses.iter().any(|(_, c)| c.patterns.len() != arity as usize) {
+                    return Err(ParseError::invalid_form(
+                        span,
+                        format!("expected all clauses to have arity {}", arity),
+                    ));
+                }
+                let function = Function::new(self.reporter, span, clauses)?;
+                Ok(Some(TopLevel::Function(function)))
+            }
+            // Errors and warnings produced by the tool which generated the abstract code are
+            // re-raised here, as the forms they refer to were dropped
+            ("error", [_info]) => {
+                self.reporter.show_error(
+                    "abstract code contains an error",
+                    &[(span, "the form which produced this error was dropped")],
+                );
+                Ok(None)
+            }
+            ("warning", [_info]) => {
+                self.reporter.show_warning(
+                    "abstract code contains a warning",
+                    &[(span, "this warning was raised when the abstract code was generated")],
+                );
+                Ok(None)
+            }
+            ("eof", [_]) => Ok(None),
+            _ => Err(self.invalid(item, "a form")),
+        }
+    }
+
+    fn attribute(&self, span: SourceSpan, name: Ident, value: &Item) -> Result<Option<TopLevel>> {
+        let attr = match name.as_str().get() {
+            "module" => return Ok(Some(TopLevel::Module(self.atom(value)?))),
+            "file" => return Ok(None),
+            "record" => return self.record(span, value).map(|r| Some(TopLevel::Record(r))),
+            "export" => Attribute::Export(span, self.function_names(value)?),
+            "export_type" => Attribute::ExportType(span, self.function_names(value)?),
+            "nifs" => Attribute::Nifs(span, self.function_names(value)?),
+            "import" => match value.tuple() {
+                Some([module, imports]) => {
+                    Attribute::Import(span, self.atom(module)?, self.function_names(imports)?)
+                }
+                _ => return Err(self.invalid(value, "a tuple of {Module, Imports}")),
+            },
+            "behaviour" | "behavior" => Attribute::Behaviour(span, self.atom(value)?),
+            "on_load" => Attribute::OnLoad(span, self.function_name(value)?),
+            "compile" => Attribute::Compile(span, self.constant(value)?),
+            "vsn" => Attribute::Vsn(span, self.constant(value)?),
+            "author" => Attribute::Author(span, self.constant(value)?),
+            "type" => Attribute::Type(self.type_def(span, value, false)?),
+            "opaque" => Attribute::Type(self.type_def(span, value, true)?),
+            "spec" => Attribute::Spec(self.type_spec(span, value)?),
+            "callback" => {
+                let spec = self.type_spec(span, value)?;
+                Attribute::Callback(Callback {
+                    span,
+                    optional: false,
+                    module: spec.module,
+                    function: spec.function,
+                    sigs: spec.sigs,
+                })
+            }
+            "deprecated" => Attribute::Deprecation(self.deprecations(value)?),
+            "removed" => {
+                let removed = self
+                    .list(value)?
+                    .iter()
+                    .map(|r| match r.tuple() {
+                        Some([function, arity, description]) => {
+                            let function = self.atom(function)?;
+                            let arity = self.arity(arity)?;
+                            let description = description
+                                .chars()
+                                .ok_or_else(|| self.invalid(description, "a string"))?;
+                            Ok((
+                                Span::new(r.span(), FunctionName::new_local(function.name, arity)),
+                                Ident::new(Symbol::intern(&description), r.span()),
+                            ))
+                        }
+                        _ => Err(self.invalid(r, "a tuple of {Function, Arity, Description}")),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Attribute::Removed(span, removed)
+            }
+            _ => Attribute::Custom(UserAttribute {
+                span,
+                name,
+                value: self.constant(value)?,
+            }),
+        };
+
+        Ok(Some(TopLevel::Attribute(attr)))
+    }
+
+    fn deprecations(&self, value: &Item) -> Result<Vec<Deprecation>> {
+        match value {
+            Item::List { .. } => self
+                .list(value)?
+                .iter()
+                .map(|d| self.deprecation(d))
+                .collect(),
+            _ => Ok(vec![self.deprecation(value)?]),
+        }
+    }
+
+    fn deprecation(&self, item: &Item) -> Result<Deprecation> {
+        let span = item.span();
+        if let Some(a) = item.atom() {
+            if a.name == symbols::Module {
+                return Ok(Deprecation::Module {
+                    span,
+                    flag: DeprecatedFlag::Eventually,
+                });
+            }
+        }
+        let (function, arity, flag) = match item.tuple() {
+            Some([module, flag]) if module.atom().map(|a| a.name) == Some(symbols::Module) => {
+                return Ok(Deprecation::Module {
+                    span,
+                    flag: self.deprecated_flag(flag)?,
+                });
+            }
+            Some([function, arity]) => (function, arity, DeprecatedFlag::Eventually),
+            Some([function, arity, flag]) => (function, arity, self.deprecated_flag(flag)?),
+            _ => return Err(self.invalid(item, "a valid deprecation")),
+        };
+        let function = self.atom(function)?;
+        let arity = self.arity(arity)?;
+        Ok(Deprecation::Function {
+            span,
+            function: Span::new(span, FunctionName::new_local(function.name, arity)),
+            flag,
+        })
+    }
+
+    fn deprecated_flag(&self, item: &Item) -> Result<DeprecatedFlag> {
+        if let Some(flag) = item.atom() {
+            return match flag.as_str().get() {
+                "eventually" => Ok(DeprecatedFlag::Eventually),
+                "next_version" => Ok(DeprecatedFlag::NextVersion),
+                "next_major_release" => Ok(DeprecatedFlag::NextMajorRelease),
+                _ => Err(self.invalid(
+                    item,
+                    "one of 'eventually', 'next_version', or 'next_major_release'",
+                )),
+            };
+        }
+        item.chars()
+            .map(|s| DeprecatedFlag::Description(Ident::new(Symbol::intern(&s), item.span())))
+            .ok_or_else(|| self.invalid(item, "a deprecation flag or description"))
+    }
+
+    fn record(&self, span: SourceSpan, value: &Item) -> Result<Record> {
+        match value.tuple() {
+            Some([name, fields]) => {
+                let name = self.atom(name)?;
+                let fields = self
+                    .list(fields)?
+                    .iter()
+                    .map(|f| self.record_field_decl(f))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(Record {
+                    span,
+                    name,
+                    fields,
+                    default: None,
+                })
+            }
+            _ => Err(self.invalid(value, "a tuple of {Name, Fields}")),
+        }
+    }
+
+    fn record_field_decl(&self, item: &Item) -> Result<RecordField> {
+        let span = item.span();
+        match self.node(item, "a record field declaration")? {
+            ("record_field", [_, name]) => Ok(RecordField {
+                span,
+                name: self.atom_node(name)?,
+                value: None,
+                ty: None,
+                is_default: false,
+            }),
+            ("record_field", [_, name, value]) => Ok(RecordField {
+                span,
+                name: self.atom_node(name)?,
+                value: Some(self.expr(value)?),
+                ty: None,
+                is_default: false,
+            }),
+            ("typed_record_field", [field, ty]) => {
+                let mut field = self.record_field_decl(field)?;
+                field.span = span;
+                field.ty = Some(self.ty(ty)?);
+                Ok(field)
+            }
+            _ => Err(self.invalid(item, "a record field declaration")),
+        }
+    }
+
+    fn type_def(&self, span: SourceSpan, value: &Item, opaque: bool) -> Result<TypeDef> {
+        match value.tuple() {
+            Some([name, ty, params]) => {
+                let name = self.atom(name)?;
+                let ty = self.ty(ty)?;
+                let params = self
+                    .list(params)?
+                    .iter()
+                    .map(|p| self.name_node(p))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(TypeDef {
+                    span,
+                    opaque,
+                    name,
+                    params,
+                    ty,
+                })
+            }
+            _ => Err(self.invalid(value, "a tuple of {Name, Type, Params}")),
+        }
+    }
+
+    fn type_spec(&self, span: SourceSpan, value: &Item) -> Result<TypeSpec> {
+        let (name, sigs) = match value.tuple() {
+            Some([name, sigs]) => (name, sigs),
+            _ => return Err(self.invalid(value, "a tuple of {{Function, Arity}, Types}")),
+        };
+        let (module, function) = match name.tuple() {
+            Some([function, _arity]) => (None, self.atom(function)?),
+            Some([module, function, _arity]) => (Some(self.atom(module)?), self.atom(function)?),
+            _ => return Err(self.invalid(name, "{Function, Arity} or {Module, Function, Arity}")),
+        };
+        let sigs = self
+            .list(sigs)?
+            .iter()
+            .map(|sig| self.type_sig(sig))
+            .collect::<Result<Vec<_>>>()?;
+        if sigs.is_empty() {
+            return Err(self.invalid(value, "at least one type signature"));
+        }
+        Ok(TypeSpec {
+            span,
+            module,
+            function,
+            sigs,
+        })
+    }
+
+    fn type_sig(&self, item: &Item) -> Result<TypeSig> {
+        let span = item.span();
+        match self.node(item, "a function type")? {
+            ("type", [_, kind, args]) if kind.atom().map(|a| a.name) == Some(symbols::Fun) => {
+                match self.list(args)?.as_slice() {
+                    [params, ret] => {
+                        let params = match self.node(params, "a product type")? {
+                            ("type", [_, product, params])
+                                if product.atom().map(|a| a.as_str().get() == "product")
+                                    == Some(true) =>
+                            {
+                                self.list(params)?
+                                    .iter()
+                                    .map(|p| self.ty(p))
+                                    .collect::<Result<Vec<_>>>()?
+                            }
+                            _ => return Err(self.invalid(params, "a product type")),
+                        };
+                        Ok(TypeSig {
+                            span,
+                            params,
+                            ret: Box::new(self.ty(ret)?),
+                            guards: None,
+                        })
+                    }
+                    _ => Err(self.invalid(args, "a list of [Params, Return]")),
+                }
+            }
+            ("type", [_, kind, args])
+                if kind.atom().map(|a| a.as_str().get() == "bounded_fun") == Some(true) =>
+            {
+                match self.list(args)?.as_slice() {
+                    [fun, constraints] => {
+                        let mut sig = self.type_sig(fun)?;
+                        let guards = self
+                            .list(constraints)?
+                            .iter()
+                            .map(|c| self.type_guard(c))
+                            .collect::<Result<Vec<_>>>()?;
+                        sig.span = span;
+                        sig.guards = Some(guards);
+                        Ok(sig)
+                    }
+                    _ => Err(self.invalid(args, "a list of [Fun, Constraints]")),
+                }
+            }
+            _ => Err(self.invalid(item, "a function type")),
+        }
+    }
+
+    fn type_guard(&self, item: &Item) -> Result<TypeGuard> {
+        let span = item.span();
+        match self.node(item, "a type constraint")? {
+            ("type", [_, kind, args])
+                if kind.atom().map(|a| a.as_str().get() == "constraint") == Some(true) =>
+            {
+                match self.list(args)?.as_slice() {
+                    [_is_subtype, constraint] => match self.list(constraint)?.as_slice() {
+                        [var, ty] => Ok(TypeGuard {
+                            span,
+                            var: self.name_node(var)?,
+                            ty: self.ty(ty)?,
+                        }),
+                        _ => Err(self.invalid(constraint, "a list of [Var, Type]")),
+                    },
+                    _ => Err(self.invalid(args, "a list of [is_subtype, [Var, Type]]")),
+                }
+            }
+            _ => Err(self.invalid(item, "a type constraint")),
+        }
+    }
+
+    fn types(&self, item: &Item) -> Result<Vec<Type>> {
+        self.list(item)?.iter().map(|t| self.ty(t)).collect()
+    }
+
+    fn ty(&self, item: &Item) -> Result<Type> {
+        let span = item.span();
+        match self.node(item, "a type")? {
+            ("ann_type", [_, args]) => match self.list(args)?.as_slice() {
+                [name, ty] => Ok(Type::Annotated {
+                    span,
+                    name: self.name_node(name)?,
+                    ty: Box::new(self.ty(ty)?),
+                }),
+                _ => Err(self.invalid(args, "a list of [Var, Type]")),
+            },
+            ("paren_type", [_, args]) => match self.list(args)?.as_slice() {
+                [ty] => self.ty(ty),
+                _ => Err(self.invalid(args, "a list of [Type]")),
+            },
+            ("atom", [_, _]) | ("var", [_, _]) => Ok(Type::Name(self.name_node(item)?)),
+            ("integer", [_, i]) => match i {
+                Item::Int(_, i) => Ok(Type::Integer(span, i.clone())),
+                _ => Err(self.invalid(i, "an integer")),
+            },
+            ("char", [_, c]) => Ok(Type::Char(span, self.char(c)?)),
+            ("op", [_, op, rhs]) => {
+                let op = self.atom(op)?;
+                let op = UnaryOp::from_symbol(op.name)
+                    .map_err(|_| ParseError::invalid_form(op.span, "invalid unary operator"))?;
+                Ok(Type::UnaryOp {
+                    span,
+                    op,
+                    rhs: Box::new(self.ty(rhs)?),
+                })
+            }
+            ("op", [_, op, lhs, rhs]) => {
+                let op = self.atom(op)?;
+                let op = BinaryOp::from_symbol(op.name)
+                    .map_err(|_| ParseError::invalid_form(op.span, "invalid binary operator"))?;
+                Ok(Type::BinaryOp {
+                    span,
+                    lhs: Box::new(self.ty(lhs)?),
+                    op,
+                    rhs: Box::new(self.ty(rhs)?),
+                })
+            }
+            ("remote_type", [_, args]) => match self.list(args)?.as_slice() {
+                [module, fun, args] => Ok(Type::Remote {
+                    span,
+                    module: self.atom_node(module)?,
+                    fun: self.atom_node(fun)?,
+                    args: self.types(args)?,
+                }),
+                _ => Err(self.invalid(args, "a list of [Module, Name, Args]")),
+            },
+            ("user_type", [_, name, args]) => Ok(Type::Generic {
+                span,
+                fun: self.atom(name)?,
+                params: self.types(args)?,
+            }),
+            ("type", [_, name, args]) => self.builtin_type(span, self.atom(name)?, args),
+            _ => Err(self.invalid(item, "a type")),
+        }
+    }
+
+    fn builtin_type(&self, span: SourceSpan, name: Ident, args: &Item) -> Result<Type> {
+        let is_any = args.atom().map(|a| a.as_str().get() == "any") == Some(true);
+        match name.as_str().get() {
+            "nil" => Ok(Type::Nil(span)),
+            "tuple" if is_any => Ok(Type::Generic {
+                span,
+                fun: name,
+                params: vec![],
+            }),
+            "tuple" => Ok(Type::Tuple(span, self.types(args)?)),
+            "map" if is_any => Ok(Type::Generic {
+                span,
+                fun: name,
+                params: vec![],
+            }),
+            "map" => {
+                let fields = self
+                    .list(args)?
+                    .iter()
+                    .map(|field| match self.node(field, "a map field type")? {
+                        ("type", [_, kind, kv])
+                            if matches!(
+                                kind.atom().map(|a| a.as_str().get()),
+                                Some("map_field_assoc" | "map_field_exact")
+                            ) =>
+                        {
+                            match self.list(kv)?.as_slice() {
+                                [k, v] => Ok(Type::KeyValuePair(
+                                    field.span(),
+                                    Box::new(self.ty(k)?),
+                                    Box::new(self.ty(v)?),
+                                )),
+                                _ => Err(self.invalid(kv, "a list of [Key, Value]")),
+                            }
+                        }
+                        _ => Err(self.invalid(field, "a map field type")),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(Type::Map(span, fields))
+            }
+            "record" => {
+                let args = self.list(args)?;
+                let (record, fields) = args
+                    .split_first()
+                    .ok_or_else(|| ParseError::invalid_form(span, "expected a record name"))?;
+                let record = self.atom_node(record)?;
+                let fields = fields
+                    .iter()
+                    .map(|field| match self.node(field, "a record field type")? {
+                        ("type", [_, kind, args])
+                            if kind.atom().map(|a| a.as_str().get() == "field_type")
+                                == Some(true) =>
+                        {
+                            match self.list(args)?.as_slice() {
+                                [name, ty] => Ok(Type::Field(
+                                    field.span(),
+                                    self.atom_node(name)?,
+                                    Box::new(self.ty(ty)?),
+                                )),
+                                _ => Err(self.invalid(args, "a list of [Name, Type]")),
+                            }
+                        }
+                        _ => Err(self.invalid(field, "a record field type")),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(Type::Record(span, record, fields))
+            }
+            "binary" => match self.list(args)?.as_slice() {
+                [m, n] => Ok(Type::Binary(
+                    span,
+                    Box::new(self.ty(m)?),
+                    Box::new(self.ty(n)?),
+                )),
+                _ => Err(self.invalid(args, "a list of [M, N]")),
+            },
+            "range" => match self.list(args)?.as_slice() {
+                [start, end] => Ok(Type::Range {
+                    span,
+                    start: Box::new(self.ty(start)?),
+                    end: Box::new(self.ty(end)?),
+                }),
+                _ => Err(self.invalid(args, "a list of [Low, High]")),
+            },
+            "union" => {
+                let mut types = self.types(args)?.into_iter();
+                let first = types
+                    .next()
+                    .ok_or_else(|| ParseError::invalid_form(span, "expected a non-empty union"))?;
+                Ok(types.fold(first, |lhs, rhs| Type::union(span, lhs, rhs)))
+            }
+            "fun" => {
+                if args.is_nil() {
+                    return Ok(Type::AnyFun { span, ret: None });
+                }
+                match self.list(args)?.as_slice() {
+                    [params, ret] => {
+                        let ret = Box::new(self.ty(ret)?);
+                        match self.node(params, "a product type")? {
+                            ("type", [_, kind, _])
+                                if kind.atom().map(|a| a.as_str().get() == "any") == Some(true) =>
+                            {
+                                Ok(Type::AnyFun {
+                                    span,
+                                    ret: Some(ret),
+                                })
+                            }
+                            ("type", [_, kind, params])
+                                if kind.atom().map(|a| a.as_str().get() == "product")
+                                    == Some(true) =>
+                            {
+                                Ok(Type::Fun {
+                                    span,
+                                    params: self.types(params)?,
+                                    ret,
+                                })
+                            }
+                            _ => Err(self.invalid(params, "a product type")),
+                        }
+                    }
+                    _ => Err(self.invalid(args, "a list of [Params, Return]")),
+                }
+            }
+            _ => Ok(Type::Generic {
+                span,
+                fun: name,
+                params: self.types(args)?,
+            }),
+        }
+    }
+
+    fn char(&self, item: &Item) -> Result<char> {
+        match item {
+            Item::Char(_, c) => Ok(*c),
+            Item::Int(_, i) => i.to_char().ok_or_else(|| self.invalid(item, "a character")),
+            _ => Err(self.invalid(item, "a character")),
+        }
+    }
+
+    /// Translates a literal term (as found in attributes) into an expression
+    fn constant(&self, item: &Item) -> Result<Expr> {
+        let span = item.span();
+        match item {
+            Item::Atom(a) => Ok(Expr::Literal(Literal::Atom(*a))),
+            Item::String(s) => Ok(Expr::Literal(Literal::String(*s))),
+            Item::Char(_, c) => Ok(Expr::Literal(Literal::Char(span, *c))),
+            Item::Int(_, i) => Ok(Expr::Literal(Literal::Integer(span, i.clone()))),
+            Item::Float(_, f) => Ok(Expr::Literal(Literal::Float(span, *f))),
+            Item::Tuple(_, elements) => Ok(Expr::Tuple(Tuple {
+                span,
+                elements: elements
+                    .iter()
+                    .map(|e| self.constant(e))
+                    .collect::<Result<Vec<_>>>()?,
+            })),
+            Item::List { head, tail, .. } => {
+                let tail = match tail.as_deref() {
+                    None => Expr::Literal(Literal::Nil(span)),
+                    Some(tail) => self.constant(tail)?,
+                };
+                head.iter().rev().try_fold(tail, |tail, head| {
+                    Ok(Expr::Cons(Cons {
+                        span,
+                        head: Box::new(self.constant(head)?),
+                        tail: Box::new(tail),
+                    }))
+                })
+            }
+            Item::Map(_, fields) => Ok(Expr::Map(Map {
+                span,
+                fields: fields
+                    .iter()
+                    .map(|(k, v)| {
+                        Ok(MapField::Assoc {
+                            span: SourceSpan::new(k.span().start(), v.span().end()),
+                            key: self.constant(k)?,
+                            value: self.constant(v)?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+            })),
+            Item::Binary(_, bytes) => {
+                let mut bin = BitVec::new();
+                bin.push_bytes(bytes.as_slice());
+                Ok(Expr::Literal(Literal::Binary(span, bin)))
+            }
+        }
+    }
+
+    fn exprs(&self, item: &Item) -> Result<Vec<Expr>> {
+        self.list(item)?.iter().map(|e| self.expr(e)).collect()
+    }
+
+    fn clause(&self, item: &Item) -> Result<Clause> {
+        match self.node(item, "a clause")? {
+            ("clause", [_, patterns, guards, body]) => Ok(Clause::new(
+                item.span(),
+                self.exprs(patterns)?,
+                self.guards(guards)?,
+                self.exprs(body)?,
+                false,
+            )),
+            _ => Err(self.invalid(item, "a clause")),
+        }
+    }
+
+    fn clauses(&self, item: &Item) -> Result<Vec<Clause>> {
+        self.list(item)?.iter().map(|c| self.clause(c)).collect()
+    }
+
+    fn if_clause(&self, item: &Item) -> Result<Clause> {
+        match self.node(item, "a clause")? {
+            ("clause", [_, patterns, guards, body]) if patterns.is_nil() => Ok(Clause::for_if(
+                item.span(),
+                self.guards(guards)?,
+                self.exprs(body)?,
+                false,
+            )),
+            _ => Err(self.invalid(item, "an if clause")),
+        }
+    }
+
+    fn catch_clause(&self, item: &Item) -> Result<Clause> {
+        let span = item.span();
+        match self.node(item, "a clause")? {
+            ("clause", [_, patterns, guards, body]) => match self.list(patterns)?.as_slice() {
+                [pattern] => match self.node(pattern, "a tuple pattern")? {
+                    ("tuple", [_, elements]) => match self.list(elements)?.as_slice() {
+                        [kind, error, trace] => {
+                            let trace = self.expr(trace)?;
+                            let trace = match trace {
+                                Expr::Var(ref v) if v.is_wildcard() => None,
+                                trace => Some(trace),
+                            };
+                            Ok(Clause::for_catch(
+                                span,
+                                self.expr(kind)?,
+                                self.expr(error)?,
+                                trace,
+                                self.guards(guards)?,
+                                self.exprs(body)?,
+                            ))
+                        }
+                        _ => Err(self.invalid(pattern, "a pattern of {Class, Reason, Stack}")),
+                    },
+                    _ => Err(self.invalid(pattern, "a pattern of {Class, Reason, Stack}")),
+                },
+                _ => Err(self.invalid(patterns, "a single pattern")),
+            },
+            _ => Err(self.invalid(item, "a catch clause")),
+        }
+    }
+
+    fn guards(&self, item: &Item) -> Result<Vec<Guard>> {
+        self.list(item)?
+            .iter()
+            .map(|guard| {
+                Ok(Guard {
+                    span: guard.span(),
+                    conditions: self.exprs(guard)?,
+                })
+            })
+            .collect()
+    }
+
+    fn fun_clauses(&self, name: Option<Name>, item: &Item) -> Result<Vec<(Option<Name>, Clause)>> {
+        let clauses = self
+            .list(item)?
+            .iter()
+            .map(|c| self.clause(c).map(|c| (name, c)))
+            .collect::<Result<Vec<_>>>()?;
+        if clauses.is_empty() {
+            return Err(self.invalid(item, "at least one clause"));
+        }
+        Ok(clauses)
+    }
+
+    fn expr(&self, item: &Item) -> Result<Expr> {
+        let span = item.span();
+        match self.node(item, "an expression")? {
+            ("var", [_, name]) => Ok(Expr::Var(Var(Ident::new(self.atom(name)?.name, span)))),
+            ("atom", [_, name]) => Ok(Expr::Literal(Literal::Atom(Ident::new(
+                self.atom(name)?.name,
+                span,
+            )))),
+            ("char", [_, c]) => Ok(Expr::Literal(Literal::Char(span, self.char(c)?))),
+            ("integer", [_, i]) => match i {
+                Item::Int(_, i) => Ok(Expr::Literal(Literal::Integer(span, i.clone()))),
+                _ => Err(self.invalid(i, "an integer")),
+            },
+            ("float", [_, f]) => match f {
+                Item::Float(_, f) => Ok(Expr::Literal(Literal::Float(span, *f))),
+                _ => Err(self.invalid(f, "a float")),
+            },
+            ("string", [_, s]) => {
+                let s = s.chars().ok_or_else(|| self.invalid(s, "a string"))?;
+                Ok(Expr::Literal(Literal::String(Ident::new(
+                    Symbol::intern(&s),
+                    span,
+                ))))
+            }
+            ("nil", [_]) => Ok(Expr::Literal(Literal::Nil(span))),
+            ("cons", [_, head, tail]) => Ok(Expr::Cons(Cons {
+                span,
+                head: Box::new(self.expr(head)?),
+                tail: Box::new(self.expr(tail)?),
+            })),
+            ("tuple", [_, elements]) => Ok(Expr::Tuple(Tuple {
+                span,
+                elements: self.exprs(elements)?,
+            })),
+            ("map", [_, fields]) => Ok(Expr::Map(Map {
+                span,
+                fields: self.map_fields(fields)?,
+            })),
+            ("map", [_, map, updates]) => Ok(Expr::MapUpdate(MapUpdate {
+                span,
+                map: Box::new(self.expr(map)?),
+                updates: self.map_fields(updates)?,
+            })),
+            ("bin", [_, elements]) => Ok(Expr::Binary(Binary {
+                span,
+                elements: self
+                    .list(elements)?
+                    .iter()
+                    .map(|e| self.binary_element(e))
+                    .collect::<Result<Vec<_>>>()?,
+            })),
+            ("record", [_, name, fields]) => {
+                let name = self.atom(name)?;
+                let mut fields = self.record_fields(fields)?;
+                let default = match fields.iter().position(|f| f.is_default) {
+                    None => None,
+                    Some(pos) => fields.remove(pos).value.map(Box::new),
+                };
+                Ok(Expr::Record(Record {
+                    span,
+                    name,
+                    fields,
+                    default,
+                }))
+            }
+            ("record", [_, record, name, updates]) => Ok(Expr::RecordUpdate(RecordUpdate {
+                span,
+                record: Box::new(self.expr(record)?),
+                name: self.atom(name)?,
+                updates: self.record_fields(updates)?,
+            })),
+            ("record_field", [_, record, name, field]) => Ok(Expr::RecordAccess(RecordAccess {
+                span,
+                record: Box::new(self.expr(record)?),
+                name: self.atom(name)?,
+                field: self.atom_node(field)?,
+            })),
+            ("record_index", [_, name, field]) => Ok(Expr::RecordIndex(RecordIndex {
+                span,
+                name: self.atom(name)?,
+                field: self.atom_node(field)?,
+            })),
+            ("match", [_, pattern, expr]) => Ok(Expr::Match(Match {
+                span,
+                pattern: Box::new(self.expr(pattern)?),
+                expr: Box::new(self.expr(expr)?),
+            })),
+            ("op", [_, op, operand]) => {
+                let op = self.atom(op)?;
+                let op = UnaryOp::from_symbol(op.name)
+                    .map_err(|_| ParseError::invalid_form(op.span, "invalid unary operator"))?;
+                Ok(Expr::UnaryExpr(UnaryExpr {
+                    span,
+                    op,
+                    operand: Box::new(self.expr(operand)?),
+                }))
+            }
+            ("op", [_, op, lhs, rhs]) => {
+                let op = self.atom(op)?;
+                let op = BinaryOp::from_symbol(op.name)
+                    .map_err(|_| ParseError::invalid_form(op.span, "invalid binary operator"))?;
+                Ok(Expr::BinaryExpr(BinaryExpr {
+                    span,
+                    lhs: Box::new(self.expr(lhs)?),
+                    op,
+                    rhs: Box::new(self.expr(rhs)?),
+                }))
+            }
+            ("block", [_, body]) => Ok(Expr::Begin(Begin {
+                span,
+                body: self.exprs(body)?,
+            })),
+            ("if", [_, clauses]) => Ok(Expr::If(If {
+                span,
+                clauses: self
+                    .list(clauses)?
+                    .iter()
+                    .map(|c| self.if_clause(c))
+                    .collect::<Result<Vec<_>>>()?,
+            })),
+            ("case", [_, expr, clauses]) => Ok(Expr::Case(Case {
+                span,
+                expr: Box::new(self.expr(expr)?),
+                clauses: self.clauses(clauses)?,
+            })),
+            ("receive", [_, clauses]) => Ok(Expr::Receive(Receive {
+                span,
+                clauses: Some(self.clauses(clauses)?),
+                after: None,
+            })),
+            ("receive", [_, clauses, timeout, body]) => {
+                let clauses = self.clauses(clauses)?;
+                let after_span = SourceSpan::new(timeout.span().start(), body.span().end());
+                Ok(Expr::Receive(Receive {
+                    span,
+                    clauses: if clauses.is_empty() {
+                        None
+                    } else {
+                        Some(clauses)
+                    },
+                    after: Some(After {
+                        span: after_span,
+                        timeout: Box::new(self.expr(timeout)?),
+                        body: self.exprs(body)?,
+                    }),
+                }))
+            }
+            ("try", [_, exprs, clauses, catch_clauses, after]) => {
+                let clauses = self.clauses(clauses)?;
+                let catch_clauses = self
+                    .list(catch_clauses)?
+                    .iter()
+                    .map(|c| self.catch_clause(c))
+                    .collect::<Result<Vec<_>>>()?;
+                let after = self.exprs(after)?;
+                Ok(Expr::Try(Try {
+                    span,
+                    exprs: self.exprs(exprs)?,
+                    clauses: if clauses.is_empty() {
+                        None
+                    } else {
+                        Some(clauses)
+                    },
+                    catch_clauses: if catch_clauses.is_empty() {
+                        None
+                    } else {
+                        Some(catch_clauses)
+                    },
+                    after: if after.is_empty() { None } else { Some(after) },
+                }))
+            }
+            ("maybe", [_, body]) => Ok(Expr::Maybe(Maybe {
+                span,
+                body: self.exprs(body)?,
+                else_clauses: None,
+            })),
+            ("maybe", [_, body, else_clauses]) => match self.node(else_clauses, "an else block")? {
+                ("else", [_, clauses]) => Ok(Expr::Maybe(Maybe {
+                    span,
+                    body: self.exprs(body)?,
+                    else_clauses: Some(self.clauses(clauses)?),
+                })),
+                _ => Err(self.invalid(else_clauses, "an else block")),
+            },
+            ("maybe_match", [_, pattern, expr]) => Ok(Expr::MaybeMatch(MaybeMatch {
+                span,
+                pattern: Box::new(self.expr(pattern)?),
+                expr: Box::new(self.expr(expr)?),
+            })),
+            ("catch", [_, expr]) => Ok(Expr::Catch(Catch {
+                span,
+                expr: Box::new(self.expr(expr)?),
+            })),
+            ("call", [_, callee, args]) => {
+                let callee = match self.node(callee, "an expression")? {
+                    ("remote", [_, module, function]) => Expr::Remote(Remote::new(
+                        callee.span(),
+                        self.expr(module)?,
+                        self.expr(function)?,
+                    )),
+                    _ => self.expr(callee)?,
+                };
+                Ok(Expr::try_resolve_apply(span, callee, self.exprs(args)?))
+            }
+            ("remote", [_, module, function]) => Ok(Expr::Remote(Remote::new(
+                span,
+                self.expr(module)?,
+                self.expr(function)?,
+            ))),
+            ("lc", [_, body, qualifiers]) => Ok(Expr::ListComprehension(ListComprehension {
+                span,
+                body: Box::new(self.expr(body)?),
+                qualifiers: self.qualifiers(qualifiers)?,
+            })),
+            ("bc", [_, body, qualifiers]) => Ok(Expr::BinaryComprehension(BinaryComprehension {
+                span,
+                body: Box::new(self.expr(body)?),
+                qualifiers: self.qualifiers(qualifiers)?,
+            })),
+            ("mc", [_, body, qualifiers]) => match self.node(body, "a map comprehension body")? {
+                ("map_field_assoc", [_, key, value]) => {
+                    Ok(Expr::MapComprehension(MapComprehension {
+                        span,
+                        key: Box::new(self.expr(key)?),
+                        value: Box::new(self.expr(value)?),
+                        qualifiers: self.qualifiers(qualifiers)?,
+                    }))
+                }
+                _ => Err(self.invalid(body, "a map comprehension body")),
+            },
+            ("fun", [_, fun]) => match self.node(fun, "a fun definition")? {
+                ("function", [function, arity]) => {
+                    let function = self.atom(function)?;
+                    let arity = self.arity(arity)?;
+                    Ok(Expr::FunctionVar(FunctionVar::PartiallyResolved(
+                        Span::new(span, FunctionName::new_local(function.name, arity)),
+                    )))
+                }
+                ("function", [module, function, arity]) => {
+                    let module = self.name_node(module)?;
+                    let function = self.name_node(function)?;
+                    let arity = match self.node(arity, "an arity")? {
+                        ("integer", [_, i]) => Arity::Int(self.arity(i)?),
+                        ("var", [_, v]) => Arity::Var(Ident::new(self.atom(v)?.name, arity.span())),
+                        _ => return Err(self.invalid(arity, "an integer or variable")),
+                    };
+                    Ok(Expr::FunctionVar(FunctionVar::detect(
+                        span,
+                        Some(module),
+                        function,
+                        arity,
+                    )))
+                }
+                ("clauses", [clauses]) => {
+                    let clauses = self.fun_clauses(None, clauses)?;
+                    Ok(Expr::Fun(Fun::new(self.reporter, span, clauses)?))
+                }
+                _ => Err(self.invalid(fun, "a fun definition")),
+            },
+            ("named_fun", [_, name, clauses]) => {
+                let name = Name::Var(Ident::new(self.atom(name)?.name, span));
+                let clauses = self.fun_clauses(Some(name), clauses)?;
+                Ok(Expr::Fun(Fun::new(self.reporter, span, clauses)?))
+            }
+            _ => Err(self.invalid(item, "an expression")),
+        }
+    }
+
+    fn map_fields(&self, item: &Item) -> Result<Vec<MapField>> {
+        self.list(item)?
+            .iter()
+            .map(|field| {
+                let span = field.span();
+                match self.node(field, "a map field")? {
+                    ("map_field_assoc", [_, key, value]) => Ok(MapField::Assoc {
+                        span,
+                        key: self.expr(key)?,
+                        value: self.expr(value)?,
+                    }),
+                    ("map_field_exact", [_, key, value]) => Ok(MapField::Exact {
+                        span,
+                        key: self.expr(key)?,
+                        value: self.expr(value)?,
+                    }),
+                    _ => Err(self.invalid(field, "a map field")),
+                }
+            })
+            .collect()
+    }
+
+    fn record_fields(&self, item: &Item) -> Result<Vec<RecordField>> {
+        self.list(item)?
+            .iter()
+            .map(|field| {
+                let span = field.span();
+                match self.node(field, "a record field")? {
+                    ("record_field", [_, name, value]) => {
+                        let value = Some(self.expr(value)?);
+                        match self.name_node(name)? {
+                            Name::Atom(name) => Ok(RecordField {
+                                span,
+                                name,
+                                value,
+                                ty: None,
+                                is_default: false,
+                            }),
+                            Name::Var(name) if name.name == symbols::Underscore => {
+                                Ok(RecordField {
+                                    span,
+                                    name,
+                                    value,
+                                    ty: None,
+                                    is_default: true,
+                                })
+                            }
+                            Name::Var(_) => Err(self.invalid(name, "an atom, or '_'")),
+                        }
+                    }
+                    _ => Err(self.invalid(field, "a record field")),
+                }
+            })
+            .collect()
+    }
+
+    fn qualifiers(&self, item: &Item) -> Result<Vec<Expr>> {
+        self.list(item)?
+            .iter()
+            .map(|qualifier| {
+                let span = qualifier.span();
+                match qualifier.tagged().map(|(tag, rest)| (tag.as_str().get(), rest)) {
+                    Some(("generate", [_, pattern, expr])) => Ok(Expr::Generator(Generator {
+                        span,
+                        ty: GeneratorType::Default,
+                        pattern: Box::new(self.expr(pattern)?),
+                        expr: Box::new(self.expr(expr)?),
+                    })),
+                    Some(("b_generate", [_, pattern, expr])) => Ok(Expr::Generator(Generator {
+                        span,
+                        ty: GeneratorType::Bitstring,
+                        pattern: Box::new(self.expr(pattern)?),
+                        expr: Box::new(self.expr(expr)?),
+                    })),
+                    Some(("m_generate", [_, pattern, expr])) => {
+                        match self.node(pattern, "a map generator pattern")? {
+                            ("map_field_exact", [_, key, value]) => {
+                                Ok(Expr::Generator(Generator {
+                                    span,
+                                    ty: GeneratorType::Map,
+                                    pattern: Box::new(Expr::Tuple(Tuple {
+                                        span: pattern.span(),
+                                        elements: vec![self.expr(key)?, self.expr(value)?],
+                                    })),
+                                    expr: Box::new(self.expr(expr)?),
+                                }))
+                            }
+                            _ => Err(self.invalid(pattern, "a map generator pattern")),
+                        }
+                    }
+                    _ => self.expr(qualifier),
+                }
+            })
+            .collect()
+    }
+
+    fn binary_element(&self, item: &Item) -> Result<BinaryElement> {
+        let span = item.span();
+        match self.node(item, "a binary element")? {
+            ("bin_element", [_, value, size, tsl]) => {
+                let bit_expr = self.expr(value)?;
+                let bit_size = if is_default(size) {
+                    None
+                } else {
+                    Some(self.expr(size)?)
+                };
+                let specifier = if is_default(tsl) {
+                    None
+                } else {
+                    let types = self
+                        .list(tsl)?
+                        .iter()
+                        .map(|ty| match &**ty {
+                            Item::Atom(name) => Ok(BitType::Name(name.span, *name)),
+                            Item::Tuple(ty_span, elements) => match elements.as_slice() {
+                                [Item::Atom(name), Item::Int(_, i)] => Ok(BitType::Sized(
+                                    *ty_span,
+                                    *name,
+                                    i.to_usize()
+                                        .ok_or_else(|| self.invalid(ty, "a valid unit"))?,
+                                )),
+                                _ => Err(self.invalid(ty, "a type specifier")),
+                            },
+                            _ => Err(self.invalid(ty, "a type specifier")),
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    match specifier_from_parsed(&types, bit_size.is_some()) {
+                        Ok(specifier) => Some(specifier),
+                        Err(err) => {
+                            self.reporter.diagnostic(err.to_diagnostic());
+                            Some(BinaryEntrySpecifier::default())
+                        }
+                    }
+                };
+                Ok(BinaryElement {
+                    span,
+                    bit_expr,
+                    bit_size,
+                    specifier,
+                })
+            }
+            _ => Err(self.invalid(item, "a binary element")),
+        }
+    }
+}
+
+#[inline]
+fn is_default(item: &Item) -> bool {
+    item.atom().map(|a| a.as_str().get() == "default") == Some(true)
+}
@@ -126,6 +126,70 @@ impl DataFlowGraph {
         self.insts[inst].arguments_mut(&mut self.value_lists)
     }
 
+    /// Unlinks `inst` from the block containing it
+    ///
+    /// NOTE: The results of the instruction must no longer be in use
+    pub fn remove_inst(&mut self, inst: Inst) {
+        let block = self.insts[inst].block;
+        let node = &self.insts[inst] as *const InstNode;
+        let mut cursor = unsafe { self.blocks[block].insts.cursor_mut_from_ptr(node) };
+        cursor.remove();
+    }
+
+    /// Unlinks all of the instructions of `from`, and appends them, in order, to the end of `to`
+    pub fn move_insts(&mut self, from: Block, to: Block) {
+        let mut insts = self.blocks[from].insts.take();
+        while let Some(node) = insts.pop_front() {
+            self.insts[node.key].block = to;
+            self.blocks[to].insts.push_back(node);
+        }
+    }
+
+    /// Returns an iterator over all of the values used by `inst`, including the arguments it passes to its successors
+    pub fn inst_uses(&self, inst: Inst) -> impl Iterator<Item = Value> + '_ {
+        let successor_args: [&[Value]; 2] = match &*self[inst] {
+            InstData::CondBr(CondBr {
+                then_dest,
+                else_dest,
+                ..
+            }) => [
+                then_dest.1.as_slice(&self.value_lists),
+                else_dest.1.as_slice(&self.value_lists),
+            ],
+            _ => [&[], &[]],
+        };
+        self.inst_args(inst)
+            .iter()
+            .chain(successor_args.into_iter().flatten())
+            .copied()
+    }
+
+    /// Replaces all uses of `value` in this function with `replacement`
+    pub fn replace_uses(&mut self, value: Value, replacement: Value) {
+        let insts = self
+            .blocks()
+            .flat_map(|(_, data)| data.insts())
+            .collect::<Vec<_>>();
+        let replace = |args: &mut [Value]| {
+            for arg in args.iter_mut().filter(|arg| **arg == value) {
+                *arg = replacement;
+            }
+        };
+        for inst in insts {
+            let node = &mut self.insts[inst];
+            if let InstData::CondBr(CondBr {
+                then_dest,
+                else_dest,
+                ..
+            }) = &mut node.data.item
+            {
+                replace(then_dest.1.as_mut_slice(&mut self.value_lists));
+                replace(else_dest.1.as_mut_slice(&mut self.value_lists));
+            }
+            replace(node.arguments_mut(&mut self.value_lists));
+        }
+    }
+
     pub fn append_inst_args(&mut self, inst: Inst, args: &[Value]) {
         let vlist = self.insts[inst]
             .arguments_list()
@@ -435,6 +499,50 @@ impl DataFlowGraph {
         self.blocks[block].params.as_slice(&self.value_lists)
     }
 
+    /// Removes the parameter at `index` from `block`, renumbering the parameters which follow it
+    ///
+    /// NOTE: The parameter must no longer be in use, and the corresponding argument must be
+    /// removed from every branch to `block`, see `remove_branch_arg`
+    pub fn remove_block_param(&mut self, block: Block, index: usize) {
+        self.blocks[block]
+            .params
+            .remove(index, &mut self.value_lists);
+        let params = self.blocks[block].params.as_slice(&self.value_lists);
+        for (num, param) in params.iter().enumerate().skip(index) {
+            if let ValueData::Param { num: ref mut n, .. } = self.values[*param] {
+                *n = num as u16;
+            }
+        }
+    }
+
+    /// Removes the argument at `index` from every edge of the branch `inst` to `destination`
+    pub fn remove_branch_arg(&mut self, inst: Inst, destination: Block, index: usize) {
+        match &mut self.insts[inst].data.item {
+            InstData::Br(Br {
+                op,
+                destination: dest,
+                args,
+            }) if *dest == destination => {
+                // The first argument of a conditional branch is the condition
+                let offset = if *op == Opcode::Br { 0 } else { 1 };
+                args.remove(index + offset, &mut self.value_lists);
+            }
+            InstData::CondBr(CondBr {
+                then_dest,
+                else_dest,
+                ..
+            }) => {
+                if then_dest.0 == destination {
+                    then_dest.1.remove(index, &mut self.value_lists);
+                }
+                if else_dest.0 == destination {
+                    else_dest.1.remove(index, &mut self.value_lists);
+                }
+            }
+            _ => (),
+        }
+    }
+
     pub fn block_param_types(&self, block: Block) -> Vec<Type> {
         self.block_params(block)
             .iter()
@@ -4,7 +4,7 @@ use std::rc::Rc;
 
 use cranelift_entity::PrimaryMap;
 
-use firefly_diagnostics::SourceSpan;
+use firefly_diagnostics::{SourceSpan, Span};
 use firefly_intern::{symbols, Ident, Symbol};
 use firefly_syntax_base::*;
 use firefly_util::emit::Emit;
@@ -36,6 +36,8 @@ pub struct Module {
     pub constants: Rc<RefCell<ConstantPool>>,
     /// This set contains the local functions which are lifted closures (i.e. expect a closure env)
     pub closures: BTreeSet<FunctionName>,
+    /// The function to run when this module is loaded, if declared with `-on_load`
+    pub on_load: Option<Span<FunctionName>>,
 }
 // UNSAFE: These is only safe because we make sure we don't actually use
 // a module in more than one thread
@@ -53,13 +55,7 @@ impl Emit for Module {
     }
 
     fn emit(&self, f: &mut std::fs::File) -> anyhow::Result<()> {
-        use std::io::Write;
-        write!(f, "module {}\n", &self.name)?;
-        for function in self.functions.iter() {
-            writeln!(f)?;
-            crate::write::write_function(f, function)?;
-        }
-
+        crate::write::write_module(f, self)?;
         Ok(())
     }
 }
@@ -73,6 +69,7 @@ impl Module {
             natives: Rc::new(RefCell::new(BTreeMap::new())),
             constants: Rc::new(RefCell::new(ConstantPool::new())),
             closures: BTreeSet::new(),
+            on_load: None,
         }
     }
 
@@ -208,7 +205,8 @@ impl Module {
     }
 
     /// Same as `declare_function`, but marks the function as a known closure
-    pub fn declare_closure(&mut self, signature: Signature) -> FuncRef {
+    pub fn declare_closure(&mut self, mut signature: Signature) -> FuncRef {
+        signature.visibility.insert(Visibility::CLOSURE);
         let local_mfa = signature.mfa().to_local();
         self.closures.insert(local_mfa);
         self.declare_function(signature)
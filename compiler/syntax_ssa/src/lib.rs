@@ -1,5 +1,7 @@
 #![deny(warnings)]
 pub mod ir;
+pub mod parser;
+pub mod passes;
 pub mod write;
 
 pub use self::ir::*;
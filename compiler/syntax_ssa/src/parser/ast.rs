@@ -0,0 +1,176 @@
+//! The syntactic structure of the textual IR, prior to name resolution
+//!
+//! Values and blocks are referred to by name in the text, and may be referenced before
+//! they are defined, so the text is first parsed into these structures, which are then
+//! lowered into a [`crate::Module`] once all names are known.
+use firefly_binary::BinaryEntrySpecifier;
+use firefly_diagnostics::SourceSpan;
+use firefly_intern::{Ident, Symbol};
+use firefly_number::Integer;
+use firefly_syntax_base::{FunctionName, Type, Visibility};
+
+use crate::{ConstantItem, Immediate, Opcode};
+
+use super::ParseError;
+
+pub struct ModuleDecl {
+    pub name: Ident,
+    pub functions: Vec<FunctionDecl>,
+}
+
+pub struct FunctionDecl {
+    pub span: SourceSpan,
+    pub visibility: Visibility,
+    pub name: Ident,
+    pub params: Vec<Type>,
+    pub results: Vec<Type>,
+    pub blocks: Vec<BlockDecl>,
+}
+
+impl FunctionDecl {
+    /// Constructs a function from the lines of its body, grouping instructions into blocks
+    pub fn new(
+        span: SourceSpan,
+        visibility: Visibility,
+        name: Ident,
+        params: Vec<Type>,
+        results: Vec<Type>,
+        lines: Vec<Line>,
+    ) -> Result<Self, ParseError> {
+        let mut blocks: Vec<BlockDecl> = vec![];
+        for line in lines {
+            match line {
+                Line::Block(name, params) => blocks.push(BlockDecl {
+                    name,
+                    params,
+                    insts: vec![],
+                }),
+                Line::Inst(inst) => match blocks.last_mut() {
+                    Some(block) => block.insts.push(inst),
+                    None => {
+                        return Err(ParseError::invalid(
+                            inst.span,
+                            "instructions must belong to a block",
+                        ))
+                    }
+                },
+            }
+        }
+        Ok(Self {
+            span,
+            visibility,
+            name,
+            params,
+            results,
+            blocks,
+        })
+    }
+}
+
+/// A line of a function body, either the header of a block, or an instruction
+pub enum Line {
+    Block(Ident, Vec<(Ident, Type)>),
+    Inst(InstDecl),
+}
+
+pub struct BlockDecl {
+    pub name: Ident,
+    pub params: Vec<(Ident, Type)>,
+    pub insts: Vec<InstDecl>,
+}
+
+pub struct InstDecl {
+    pub span: SourceSpan,
+    pub results: Vec<Ident>,
+    pub op: Opcode,
+    pub operands: Operands,
+    pub types: Vec<Type>,
+}
+
+/// A branch destination and the arguments passed to it
+pub type Successor = (Ident, Vec<Ident>);
+
+pub enum Operands {
+    /// Operands of unary/binary operators, returns, and primops
+    Simple(Vec<Operand>),
+    Const(ConstantItem),
+    Call(FunctionName, Vec<Ident>),
+    CallIndirect(Ident, Vec<Ident>),
+    MakeFun(FunctionName, Vec<Ident>),
+    Br(Option<Ident>, Successor),
+    CondBr(Ident, Successor, Successor),
+    Switch(Ident, Vec<(u32, Ident)>, Ident),
+    IsType(Ident, Type),
+    SetElement(Ident, isize, Operand),
+    Bits(BinaryEntrySpecifier, Vec<Operand>),
+}
+impl Operands {
+    /// Returns the names of the blocks this instruction may branch to
+    pub fn successors(&self) -> Vec<Ident> {
+        match self {
+            Self::Br(_, (dest, _)) => vec![*dest],
+            Self::CondBr(_, (a, _), (b, _)) => vec![*a, *b],
+            Self::Switch(_, arms, default) => {
+                let mut dests = arms.iter().map(|(_, dest)| *dest).collect::<Vec<_>>();
+                dests.push(*default);
+                dests
+            }
+            _ => vec![],
+        }
+    }
+}
+
+/// An operand as written in the text, prior to being interpreted according to its opcode
+pub enum Arg {
+    /// A bare name, e.g. a value, block, or type, or one of `true`, `false` or `none`
+    Name(Ident),
+    /// An integer without a type, e.g. `1`
+    Integer(SourceSpan, Integer),
+    Float(SourceSpan, f64),
+    Atom(SourceSpan, Symbol),
+    /// An immediate with an explicit type, e.g. `i64 1`, or `[]`
+    Immediate(SourceSpan, Immediate),
+    /// A string, hexadecimal, or bitstring literal
+    Constant(SourceSpan, ConstantItem),
+    /// A name applied to a list of values, e.g. a branch destination `block1(v2)`, or the
+    /// callee of an indirect call
+    Apply(Ident, Vec<Ident>),
+    /// A function name applied to a list of values, e.g. `erlang:'+'/2(v0, v1)`
+    Call(SourceSpan, FunctionName, Vec<Ident>),
+    /// A value indexed by a constant, e.g. `v2[1]`
+    Index(SourceSpan, Ident, isize),
+    /// An arm of a switch, e.g. `0 => block1`
+    Arm(SourceSpan, u32, Ident),
+    /// A type which can't be written as a bare name, e.g. `list<term>`
+    Type(SourceSpan, Type),
+}
+impl Arg {
+    pub fn span(&self) -> SourceSpan {
+        match self {
+            Self::Name(id) | Self::Apply(id, _) => id.span,
+            Self::Integer(span, _)
+            | Self::Float(span, _)
+            | Self::Atom(span, _)
+            | Self::Immediate(span, _)
+            | Self::Constant(span, _)
+            | Self::Call(span, _, _)
+            | Self::Index(span, _, _)
+            | Self::Arm(span, _, _)
+            | Self::Type(span, _) => *span,
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub enum Operand {
+    Value(Ident),
+    Immediate(SourceSpan, Immediate),
+}
+impl Operand {
+    pub fn span(&self) -> SourceSpan {
+        match self {
+            Self::Value(id) => id.span,
+            Self::Immediate(span, _) => *span,
+        }
+    }
+}
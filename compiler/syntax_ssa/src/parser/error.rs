@@ -0,0 +1,132 @@
+use firefly_diagnostics::*;
+
+use super::lexer::Token;
+
+pub type LalrPopError = lalrpop_util::ParseError<SourceIndex, Token, ParseError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ParseError {
+    #[error("error reading {path:?}: {source}")]
+    RootFile {
+        source: std::io::Error,
+        path: std::path::PathBuf,
+    },
+
+    #[error("encountered unexpected character '{found}'")]
+    UnexpectedCharacter { start: SourceIndex, found: char },
+
+    #[error("unclosed {kind} literal")]
+    Unclosed { span: SourceSpan, kind: &'static str },
+
+    #[error("invalid literal: {reason}")]
+    InvalidLiteral { span: SourceSpan, reason: String },
+
+    #[error("{}", .diagnostic.message)]
+    ShowDiagnostic { diagnostic: Diagnostic },
+
+    #[error("invalid token")]
+    InvalidToken { location: SourceIndex },
+
+    #[error("unrecognized token")]
+    UnrecognizedToken {
+        span: SourceSpan,
+        expected: Vec<String>,
+    },
+
+    #[error("extra token")]
+    ExtraToken { span: SourceSpan },
+
+    #[error("unexpected eof")]
+    UnexpectedEOF {
+        location: SourceIndex,
+        expected: Vec<String>,
+    },
+}
+impl ParseError {
+    /// Constructs an error for IR which is syntactically valid, but semantically invalid
+    pub fn invalid(span: SourceSpan, message: impl Into<String>) -> Self {
+        Self::ShowDiagnostic {
+            diagnostic: Diagnostic::error()
+                .with_message("invalid ssa ir")
+                .with_labels(vec![
+                    Label::primary(span.source_id(), span).with_message(message.into())
+                ]),
+        }
+    }
+}
+
+impl From<Diagnostic> for ParseError {
+    fn from(err: Diagnostic) -> Self {
+        ParseError::ShowDiagnostic { diagnostic: err }
+    }
+}
+
+impl From<LalrPopError> for ParseError {
+    fn from(err: LalrPopError) -> Self {
+        use lalrpop_util::ParseError::*;
+        match err {
+            InvalidToken { location } => Self::InvalidToken { location },
+            UnrecognizedEOF { location, expected } => Self::UnexpectedEOF { location, expected },
+            UnrecognizedToken {
+                token: (l, _, r),
+                expected,
+            } => Self::UnrecognizedToken {
+                span: SourceSpan::new(l, r),
+                expected,
+            },
+            ExtraToken { token: (l, _, r) } => Self::ExtraToken {
+                span: SourceSpan::new(l, r),
+            },
+            User { error } => error,
+        }
+    }
+}
+
+impl ToDiagnostic for ParseError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            Self::RootFile { .. } => Diagnostic::error().with_message(self.to_string()),
+            Self::UnexpectedCharacter { start, .. } => Diagnostic::error()
+                .with_message("unexpected character")
+                .with_labels(vec![Label::primary(
+                    start.source_id(),
+                    SourceSpan::new(*start, *start),
+                )
+                .with_message(self.to_string())]),
+            Self::Unclosed { span, .. } | Self::InvalidLiteral { span, .. } => {
+                Diagnostic::error()
+                    .with_message(self.to_string())
+                    .with_labels(vec![Label::primary(span.source_id(), *span)])
+            }
+            Self::ShowDiagnostic { diagnostic } => diagnostic.clone(),
+            Self::UnrecognizedToken { span, expected } => Diagnostic::error()
+                .with_message("unrecognized token")
+                .with_labels(vec![Label::primary(span.source_id(), *span)
+                    .with_message(format!("expected: {}", expected.join(", ")))]),
+            Self::InvalidToken { location } => {
+                let index = *location;
+                Diagnostic::error()
+                    .with_message("unexpected token")
+                    .with_labels(vec![Label::primary(
+                        index.source_id(),
+                        SourceSpan::new(index, index),
+                    )
+                    .with_message("did not expect this token")])
+            }
+            Self::UnexpectedEOF { location, expected } => {
+                let index = *location;
+                Diagnostic::error()
+                    .with_message("unexpected end of file")
+                    .with_labels(vec![Label::primary(
+                        index.source_id(),
+                        SourceSpan::new(index, index),
+                    )
+                    .with_message(format!("expected: {}", expected.join(", ")))])
+            }
+            Self::ExtraToken { span } => Diagnostic::error()
+                .with_message("unexpected token")
+                .with_labels(vec![Label::primary(span.source_id(), *span)
+                    .with_message("did not expect this token")]),
+        }
+    }
+}
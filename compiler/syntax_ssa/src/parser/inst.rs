@@ -0,0 +1,348 @@
+//! Interpretation of the operands of an instruction, which depends on its opcode
+use firefly_binary::{BinaryEntrySpecifier, BitVec, Endianness};
+use firefly_diagnostics::SourceSpan;
+use firefly_intern::{Ident, Symbol};
+use firefly_number::Integer;
+use firefly_syntax_base::Type;
+
+use crate::{ConstantData, ConstantItem, Immediate, ImmediateTerm, Opcode};
+
+use super::ast::*;
+use super::{types, ParseError};
+
+impl InstDecl {
+    /// Constructs an instruction from its opcode and its operands as written
+    ///
+    /// The opcode of a bitstring instruction includes its type specifier, e.g. `bs.push.sint.big`,
+    /// which may be followed by a unit, e.g. `bs.push.sint.big(8)`.
+    pub fn new(
+        span: SourceSpan,
+        results: Vec<Ident>,
+        opcode: Ident,
+        unit: Option<(SourceSpan, Integer)>,
+        args: Vec<Arg>,
+        types: Vec<Type>,
+    ) -> Result<Self, ParseError> {
+        let name = opcode.as_str().get();
+        let bits = if let Some(spec) = name.strip_prefix("bs.match.skip.") {
+            Some((Opcode::BitsMatchSkip, spec))
+        } else if let Some(spec) = name.strip_prefix("bs.push.") {
+            Some((Opcode::BitsPush, spec))
+        } else {
+            name.strip_prefix("bs.match.")
+                .filter(|spec| *spec != "start")
+                .map(|spec| (Opcode::BitsMatch, spec))
+        };
+
+        let (op, operands) = match bits {
+            Some((op, spec)) => {
+                let unit = unit.map(|(span, unit)| small(span, unit)).transpose()?;
+                let spec = bits_spec(opcode.span, spec, unit)?;
+                let operands = args.into_iter().map(operand).collect::<Result<_, _>>()?;
+                (op, Operands::Bits(spec, operands))
+            }
+            None => {
+                if let Some((span, _)) = unit {
+                    return Err(ParseError::invalid(
+                        span,
+                        "a unit is only valid for bitstring opcodes",
+                    ));
+                }
+                let op = name.parse::<Opcode>().map_err(|_| {
+                    ParseError::invalid(opcode.span, format!("unknown opcode '{}'", name))
+                })?;
+                (op, operands_for(op, opcode.span, args)?)
+            }
+        };
+
+        Ok(Self {
+            span,
+            results,
+            op,
+            operands,
+            types,
+        })
+    }
+}
+
+fn operands_for(op: Opcode, span: SourceSpan, args: Vec<Arg>) -> Result<Operands, ParseError> {
+    let mut args = args.into_iter();
+    let mut next = || {
+        args.next()
+            .ok_or_else(|| ParseError::invalid(span, "missing operand"))
+    };
+    let operands = match op {
+        Opcode::ConstBigInt | Opcode::ConstBinary => Operands::Const(constant(next()?)?),
+        Opcode::Call | Opcode::Enter => match next()? {
+            Arg::Call(_, callee, args) => Operands::Call(callee, args),
+            arg => return Err(ParseError::invalid(arg.span(), "expected function call")),
+        },
+        Opcode::CallIndirect | Opcode::EnterIndirect => match next()? {
+            Arg::Apply(callee, args) => Operands::CallIndirect(callee, args),
+            arg => return Err(ParseError::invalid(arg.span(), "expected function call")),
+        },
+        Opcode::MakeFun => match next()? {
+            Arg::Call(_, callee, env) => Operands::MakeFun(callee, env),
+            arg => return Err(ParseError::invalid(arg.span(), "expected function name")),
+        },
+        Opcode::Br => Operands::Br(None, successor(next()?)?),
+        Opcode::BrIf | Opcode::BrUnless => {
+            let cond = value(next()?)?;
+            Operands::Br(Some(cond), successor(next()?)?)
+        }
+        Opcode::CondBr => {
+            let cond = value(next()?)?;
+            let then_dest = successor(next()?)?;
+            let else_dest = successor(next()?)?;
+            Operands::CondBr(cond, then_dest, else_dest)
+        }
+        Opcode::Switch => {
+            let scrutinee = value(next()?)?;
+            let mut arms = vec![];
+            loop {
+                match next()? {
+                    Arg::Arm(_, value, dest) => arms.push((value, dest)),
+                    arg => break Operands::Switch(scrutinee, arms, block(arg)?),
+                }
+            }
+        }
+        Opcode::IsType => {
+            let arg = value(next()?)?;
+            Operands::IsType(arg, ty(next()?)?)
+        }
+        Opcode::SetElement | Opcode::SetElementMut => match next()? {
+            Arg::Index(_, tuple, index) => Operands::SetElement(tuple, index, operand(next()?)?),
+            arg => return Err(ParseError::invalid(arg.span(), "expected tuple element")),
+        },
+        _ => {
+            let operands = args.map(operand).collect::<Result<_, _>>()?;
+            return Ok(Operands::Simple(operands));
+        }
+    };
+    match args.next() {
+        None => Ok(operands),
+        Some(arg) => Err(ParseError::invalid(arg.span(), "unexpected operand")),
+    }
+}
+
+/// Converts an argument to a value or an immediate, as used by most instructions
+fn operand(arg: Arg) -> Result<Operand, ParseError> {
+    match arg {
+        Arg::Name(id) => Ok(match id.as_str().get() {
+            "true" => Operand::Immediate(id.span, Immediate::Term(ImmediateTerm::Bool(true))),
+            "false" => Operand::Immediate(id.span, Immediate::Term(ImmediateTerm::Bool(false))),
+            "none" => Operand::Immediate(id.span, Immediate::Term(ImmediateTerm::None)),
+            _ => Operand::Value(id),
+        }),
+        Arg::Integer(span, Integer::Small(i)) => Ok(Operand::Immediate(
+            span,
+            Immediate::Term(ImmediateTerm::Integer(i)),
+        )),
+        Arg::Integer(span, Integer::Big(_)) => Err(ParseError::invalid(
+            span,
+            "big integers must be constructed with const.bigint",
+        )),
+        Arg::Float(span, n) => Ok(Operand::Immediate(
+            span,
+            Immediate::Term(ImmediateTerm::Float(n)),
+        )),
+        Arg::Atom(span, a) => Ok(Operand::Immediate(
+            span,
+            Immediate::Term(ImmediateTerm::Atom(a)),
+        )),
+        Arg::Immediate(span, imm) => Ok(Operand::Immediate(span, imm)),
+        arg => Err(ParseError::invalid(
+            arg.span(),
+            "expected value or immediate",
+        )),
+    }
+}
+
+fn value(arg: Arg) -> Result<Ident, ParseError> {
+    match operand(arg)? {
+        Operand::Value(id) => Ok(id),
+        Operand::Immediate(span, _) => Err(ParseError::invalid(span, "expected value")),
+    }
+}
+
+fn block(arg: Arg) -> Result<Ident, ParseError> {
+    match arg {
+        Arg::Name(id) => Ok(id),
+        arg => Err(ParseError::invalid(arg.span(), "expected block")),
+    }
+}
+
+/// Converts an argument to a block reference with optional arguments, e.g. `block1(v2, v3)`
+fn successor(arg: Arg) -> Result<Successor, ParseError> {
+    match arg {
+        Arg::Name(id) => Ok((id, vec![])),
+        Arg::Apply(id, args) => Ok((id, args)),
+        arg => Err(ParseError::invalid(arg.span(), "expected block")),
+    }
+}
+
+fn ty(arg: Arg) -> Result<Type, ParseError> {
+    match arg {
+        Arg::Name(id) => types::named(id),
+        Arg::Type(_, ty) => Ok(ty),
+        arg => Err(ParseError::invalid(arg.span(), "expected type")),
+    }
+}
+
+fn constant(arg: Arg) -> Result<ConstantItem, ParseError> {
+    match arg {
+        Arg::Integer(_, i) => Ok(ConstantItem::Integer(i)),
+        Arg::Float(_, n) => Ok(ConstantItem::Float(n)),
+        Arg::Atom(_, a) => Ok(ConstantItem::Atom(a)),
+        Arg::Constant(_, constant) => Ok(constant),
+        Arg::Name(id) if id.as_str().get() == "true" => Ok(ConstantItem::Bool(true)),
+        Arg::Name(id) if id.as_str().get() == "false" => Ok(ConstantItem::Bool(false)),
+        arg => Err(ParseError::invalid(arg.span(), "expected constant")),
+    }
+}
+
+/// Converts an integer which must fit in `T`
+pub fn small<T: TryFrom<i64>>(span: SourceSpan, i: Integer) -> Result<T, ParseError> {
+    match i {
+        Integer::Small(i) => i
+            .try_into()
+            .map_err(|_| ParseError::invalid(span, "integer is out of range")),
+        Integer::Big(_) => Err(ParseError::invalid(span, "integer is out of range")),
+    }
+}
+
+/// Constructs an integer immediate with an explicit type, e.g. `i64 1`
+pub fn typed_integer(ty: Ident, span: SourceSpan, i: Integer) -> Result<Immediate, ParseError> {
+    match ty.as_str().get() {
+        "i8" => Ok(Immediate::I8(small(span, i)?)),
+        "i16" => Ok(Immediate::I16(small(span, i)?)),
+        "i32" => Ok(Immediate::I32(small(span, i)?)),
+        "i64" => Ok(Immediate::I64(small(span, i)?)),
+        "isize" => Ok(Immediate::Isize(small(span, i)?)),
+        _ => Err(ParseError::invalid(ty.span, "expected integer type")),
+    }
+}
+
+/// Constructs a float immediate with an explicit type, i.e. `f64 1.0`
+pub fn typed_float(ty: Ident, n: f64) -> Result<Immediate, ParseError> {
+    match ty.as_str().get() {
+        "f64" => Ok(Immediate::F64(n)),
+        _ => Err(ParseError::invalid(ty.span, "expected float type")),
+    }
+}
+
+/// Constructs a boolean immediate with an explicit type, i.e. `i1 true`
+pub fn typed_bool(ty: Ident, b: Ident) -> Result<Immediate, ParseError> {
+    if ty.as_str().get() != "i1" {
+        return Err(ParseError::invalid(ty.span, "expected 'i1'"));
+    }
+    match b.as_str().get() {
+        "true" => Ok(Immediate::I1(true)),
+        "false" => Ok(Immediate::I1(false)),
+        _ => Err(ParseError::invalid(b.span, "expected 'true' or 'false'")),
+    }
+}
+
+/// Constructs constant data from the digits of a hexadecimal literal
+///
+/// Constant data is written in big-endian order, i.e. the last byte first
+pub fn hex(span: SourceSpan, digits: Symbol) -> Result<ConstantItem, ParseError> {
+    let digits = digits.as_str().get();
+    let mut bytes = vec![];
+    if digits.len() > 1 {
+        if digits.len() % 2 != 0 {
+            return Err(ParseError::invalid(
+                span,
+                "expected an even number of digits",
+            ));
+        }
+        for i in (0..digits.len()).step_by(2).rev() {
+            bytes.push(u8::from_str_radix(&digits[i..(i + 2)], 16).unwrap());
+        }
+    }
+    Ok(ConstantItem::Bytes(ConstantData::from(bytes)))
+}
+
+/// Constructs a bitstring from its segments, all of which are bytes, except that the last may
+/// have a size of less than 8 bits, e.g. `<<255, 5:3>>`
+pub fn bitstring(
+    segments: Vec<(SourceSpan, Integer, Option<(SourceSpan, Integer)>)>,
+) -> Result<ConstantItem, ParseError> {
+    let num_segments = segments.len();
+    let mut bytes = Vec::with_capacity(num_segments);
+    let mut size = 0;
+    for (i, (value_span, value, segment_size)) in segments.into_iter().enumerate() {
+        let byte = small::<u8>(value_span, value)?;
+        match segment_size {
+            None => {
+                bytes.push(byte);
+                size += 8;
+            }
+            Some((size_span, segment_size)) => {
+                let segment_size = small::<u8>(size_span, segment_size)?;
+                if i + 1 != num_segments || segment_size == 0 || segment_size > 7 {
+                    return Err(ParseError::invalid(
+                        size_span,
+                        "only the last segment may have a size, which must be less than 8 bits",
+                    ));
+                }
+                if byte >> segment_size != 0 {
+                    return Err(ParseError::invalid(
+                        value_span,
+                        "value does not fit in segment",
+                    ));
+                }
+                // The bits of a partial byte are stored in its most significant bits
+                bytes.push(byte << (8 - segment_size));
+                size += segment_size as usize;
+            }
+        }
+    }
+    let mut bits = BitVec::new();
+    bits.push_bits(bytes.as_slice(), size);
+    Ok(ConstantItem::Bitstring(bits))
+}
+
+/// Parses the type specifier suffix of a bitstring opcode, e.g. `sint.big` or `utf8`
+fn bits_spec(
+    span: SourceSpan,
+    spec: &str,
+    unit: Option<u8>,
+) -> Result<BinaryEntrySpecifier, ParseError> {
+    let invalid = || ParseError::invalid(span, format!("invalid bitstring type '{}'", spec));
+    let mut parts = spec.split('.');
+    let ty = parts.next().unwrap();
+    let endianness = match parts.next() {
+        None => None,
+        Some("big") => Some(Endianness::Big),
+        Some("little") => Some(Endianness::Little),
+        Some("native") => Some(Endianness::Native),
+        Some(_) => return Err(invalid()),
+    };
+    if parts.next().is_some() {
+        return Err(invalid());
+    }
+    match (ty, endianness) {
+        ("sint" | "uint", Some(endianness)) => Ok(BinaryEntrySpecifier::Integer {
+            signed: ty == "sint",
+            endianness,
+            unit: unit.unwrap_or(1),
+        }),
+        ("float", Some(endianness)) => Ok(BinaryEntrySpecifier::Float {
+            endianness,
+            unit: unit.unwrap_or(1),
+        }),
+        ("bytes", None) if unit.is_none() => Ok(BinaryEntrySpecifier::Binary { unit: 8 }),
+        ("bits", None) => Ok(BinaryEntrySpecifier::Binary {
+            unit: unit.unwrap_or(1),
+        }),
+        ("utf8", None) if unit.is_none() => Ok(BinaryEntrySpecifier::Utf8),
+        ("utf16", Some(endianness)) if unit.is_none() => {
+            Ok(BinaryEntrySpecifier::Utf16 { endianness })
+        }
+        ("utf32", Some(endianness)) if unit.is_none() => {
+            Ok(BinaryEntrySpecifier::Utf32 { endianness })
+        }
+        _ => Err(invalid()),
+    }
+}
@@ -0,0 +1,359 @@
+use std::fmt;
+
+use firefly_diagnostics::{ByteOffset, SourceIndex, SourceSpan};
+use firefly_intern::Symbol;
+use firefly_number::Integer;
+use firefly_parser::{Scanner, Source};
+
+use super::ParseError;
+
+/// The tokens of the textual SSA IR
+///
+/// Unlike Erlang, the IR is line-oriented, so newlines are significant and are
+/// produced as tokens of their own.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Newline,
+    /// A bare identifier, e.g. `v1`, `block0`, `const.int`
+    Ident(Symbol),
+    /// A quoted atom, e.g. `'ok'`
+    Atom(Symbol),
+    /// A string literal, e.g. `"hello"`
+    String(Symbol),
+    Integer(Integer),
+    Float(f64),
+    /// A hexadecimal literal, e.g. `0xff00`, holding the digits without the prefix
+    Hex(Symbol),
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    LAngle,
+    RAngle,
+    Comma,
+    Colon,
+    Semicolon,
+    Equals,
+    Slash,
+    Question,
+    Bang,
+    RightArrow,
+    RightFatArrow,
+    // Keywords, names which collide with these must be quoted
+    Module,
+    Function,
+    Pub,
+    Nif,
+    Closure,
+    Tail,
+}
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Newline => f.write_str("newline"),
+            Self::Ident(id) => write!(f, "{}", id),
+            Self::Atom(a) => write!(f, "'{}'", a),
+            Self::String(s) => write!(f, "\"{}\"", s),
+            Self::Integer(i) => write!(f, "{}", i),
+            Self::Float(n) => write!(f, "{}", n),
+            Self::Hex(digits) => write!(f, "0x{}", digits),
+            Self::LParen => f.write_str("("),
+            Self::RParen => f.write_str(")"),
+            Self::LBrace => f.write_str("{"),
+            Self::RBrace => f.write_str("}"),
+            Self::LBracket => f.write_str("["),
+            Self::RBracket => f.write_str("]"),
+            Self::LAngle => f.write_str("<"),
+            Self::RAngle => f.write_str(">"),
+            Self::Comma => f.write_str(","),
+            Self::Colon => f.write_str(":"),
+            Self::Semicolon => f.write_str(";"),
+            Self::Equals => f.write_str("="),
+            Self::Slash => f.write_str("/"),
+            Self::Question => f.write_str("?"),
+            Self::Bang => f.write_str("!"),
+            Self::RightArrow => f.write_str("->"),
+            Self::RightFatArrow => f.write_str("=>"),
+            Self::Module => f.write_str("module"),
+            Self::Function => f.write_str("function"),
+            Self::Pub => f.write_str("pub"),
+            Self::Nif => f.write_str("nif"),
+            Self::Closure => f.write_str("closure"),
+            Self::Tail => f.write_str("tail"),
+        }
+    }
+}
+
+pub type Lexed = Result<(SourceIndex, Token, SourceIndex), ParseError>;
+
+/// The lexer for the textual SSA IR, it produces tokens until the end of the input is reached
+///
+/// Comments begin with `//` and extend to the end of the line.
+pub struct Lexer<S> {
+    scanner: Scanner<S>,
+    token_start: SourceIndex,
+    token_end: SourceIndex,
+    eof: bool,
+}
+impl<S> Lexer<S>
+where
+    S: Source,
+{
+    pub fn new(scanner: Scanner<S>) -> Self {
+        let start = scanner.start();
+        Self {
+            scanner,
+            token_start: start,
+            token_end: start,
+            eof: false,
+        }
+    }
+
+    #[inline]
+    fn pop(&mut self) -> char {
+        let (pos, c) = self.scanner.pop();
+        self.token_end = pos + ByteOffset::from_char_len(c);
+        c
+    }
+
+    #[inline]
+    fn read(&self) -> char {
+        self.scanner.read().1
+    }
+
+    #[inline]
+    fn peek(&self) -> char {
+        self.scanner.peek().1
+    }
+
+    #[inline]
+    fn span(&self) -> SourceSpan {
+        SourceSpan::new(self.token_start, self.token_end)
+    }
+
+    #[inline]
+    fn slice(&self) -> &str {
+        self.scanner.slice(self.span())
+    }
+
+    fn lex(&mut self) -> Result<Option<(SourceIndex, Token, SourceIndex)>, ParseError> {
+        loop {
+            match self.read() {
+                ' ' | '\t' | '\r' => {
+                    self.pop();
+                }
+                '/' if self.peek() == '/' => {
+                    while !matches!(self.read(), '\n' | '\0') {
+                        self.pop();
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        let (start, c) = self.scanner.read();
+        self.token_start = start;
+        self.token_end = start;
+        let token = match c {
+            '\0' => return Ok(None),
+            '\n' => self.single(Token::Newline),
+            '(' => self.single(Token::LParen),
+            ')' => self.single(Token::RParen),
+            '{' => self.single(Token::LBrace),
+            '}' => self.single(Token::RBrace),
+            '[' => self.single(Token::LBracket),
+            ']' => self.single(Token::RBracket),
+            '<' => self.single(Token::LAngle),
+            '>' => self.single(Token::RAngle),
+            ',' => self.single(Token::Comma),
+            ':' => self.single(Token::Colon),
+            ';' => self.single(Token::Semicolon),
+            '/' => self.single(Token::Slash),
+            '?' => self.single(Token::Question),
+            '!' => self.single(Token::Bang),
+            '=' => {
+                self.pop();
+                if self.read() == '>' {
+                    self.single(Token::RightFatArrow)
+                } else {
+                    Token::Equals
+                }
+            }
+            '-' => match self.peek() {
+                '>' => {
+                    self.pop();
+                    self.single(Token::RightArrow)
+                }
+                c if c.is_ascii_digit() => {
+                    self.pop();
+                    self.lex_number()?
+                }
+                _ => return Err(ParseError::UnexpectedCharacter { start, found: c }),
+            },
+            '0' if self.peek() == 'x' => {
+                self.pop();
+                self.pop();
+                while self.read().is_ascii_hexdigit() {
+                    self.pop();
+                }
+                Token::Hex(Symbol::intern(&self.slice()[2..]))
+            }
+            c if c.is_ascii_digit() => self.lex_number()?,
+            '\'' => Token::Atom(self.lex_quoted('\'', "atom")?),
+            '"' => Token::String(self.lex_quoted('"', "string")?),
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                while is_ident_char(self.read()) {
+                    self.pop();
+                }
+                match self.slice() {
+                    "module" => Token::Module,
+                    "function" => Token::Function,
+                    "pub" => Token::Pub,
+                    "nif" => Token::Nif,
+                    "closure" => Token::Closure,
+                    "tail" => Token::Tail,
+                    name => Token::Ident(Symbol::intern(name)),
+                }
+            }
+            c => return Err(ParseError::UnexpectedCharacter { start, found: c }),
+        };
+
+        Ok(Some((self.token_start, token, self.token_end)))
+    }
+
+    #[inline]
+    fn single(&mut self, token: Token) -> Token {
+        self.pop();
+        token
+    }
+
+    fn lex_number(&mut self) -> Result<Token, ParseError> {
+        while self.read().is_ascii_digit() {
+            self.pop();
+        }
+        // A fractional part is required for floats, so `1.` is never a float
+        if self.read() != '.' || !self.peek().is_ascii_digit() {
+            return self
+                .slice()
+                .parse::<Integer>()
+                .map(Token::Integer)
+                .map_err(|_| ParseError::InvalidLiteral {
+                    span: self.span(),
+                    reason: "invalid integer".to_string(),
+                });
+        }
+        self.pop();
+        while self.read().is_ascii_digit() {
+            self.pop();
+        }
+        if matches!(self.read(), 'e' | 'E') {
+            self.pop();
+            if matches!(self.read(), '+' | '-') {
+                self.pop();
+            }
+            while self.read().is_ascii_digit() {
+                self.pop();
+            }
+        }
+        self.slice()
+            .parse::<f64>()
+            .map(Token::Float)
+            .map_err(|err| ParseError::InvalidLiteral {
+                span: self.span(),
+                reason: err.to_string(),
+            })
+    }
+
+    /// Lexes a quoted atom or string, handling the escapes produced by `str::escape_debug`
+    fn lex_quoted(&mut self, delimiter: char, kind: &'static str) -> Result<Symbol, ParseError> {
+        self.pop();
+        let mut buf = String::new();
+        loop {
+            match self.read() {
+                '\0' | '\n' => {
+                    return Err(ParseError::Unclosed {
+                        span: self.span(),
+                        kind,
+                    })
+                }
+                c if c == delimiter => {
+                    self.pop();
+                    break;
+                }
+                '\\' => {
+                    self.pop();
+                    let escaped = match self.pop() {
+                        'n' => '\n',
+                        't' => '\t',
+                        'r' => '\r',
+                        '0' => '\0',
+                        'u' => self.lex_unicode_escape()?,
+                        c @ ('\\' | '\'' | '"') => c,
+                        c => {
+                            return Err(ParseError::InvalidLiteral {
+                                span: self.span(),
+                                reason: format!("unknown escape sequence '\\{}'", c),
+                            })
+                        }
+                    };
+                    buf.push(escaped);
+                }
+                _ => buf.push(self.pop()),
+            }
+        }
+        Ok(Symbol::intern(&buf))
+    }
+
+    /// Lexes the remainder of an escape of the form `\u{1f}`
+    fn lex_unicode_escape(&mut self) -> Result<char, ParseError> {
+        let invalid = |span| ParseError::InvalidLiteral {
+            span,
+            reason: "invalid unicode escape".to_string(),
+        };
+        if self.pop() != '{' {
+            return Err(invalid(self.span()));
+        }
+        let mut code = 0u32;
+        loop {
+            match self.pop() {
+                '}' => break,
+                c => match c.to_digit(16) {
+                    Some(digit) if code <= 0x10ffff => code = code * 16 + digit,
+                    _ => return Err(invalid(self.span())),
+                },
+            }
+        }
+        char::from_u32(code).ok_or_else(|| invalid(self.span()))
+    }
+}
+
+#[inline]
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '@' || c == '.'
+}
+
+impl<S> Iterator for Lexer<S>
+where
+    S: Source,
+{
+    type Item = Lexed;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.eof {
+            return None;
+        }
+        match self.lex() {
+            Ok(Some(token)) => Some(Ok(token)),
+            Ok(None) => {
+                self.eof = true;
+                None
+            }
+            Err(err) => {
+                self.eof = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
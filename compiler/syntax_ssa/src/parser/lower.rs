@@ -0,0 +1,463 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use firefly_intern::{symbols, Ident, Symbol};
+use firefly_syntax_base::*;
+
+use crate::*;
+use crate::{BinaryOp, UnaryOp};
+
+use super::ast::*;
+use super::ParseError;
+
+/// Lowers the parsed module into a [`Module`], resolving value, block, and function names
+pub fn lower(decl: ModuleDecl) -> Result<Module, ParseError> {
+    let mut module = Module::new(decl.name);
+
+    // Declare all functions first, so that they can be referenced regardless of their order
+    let mut declared = BTreeMap::new();
+    let mut functions = Vec::with_capacity(decl.functions.len());
+    for function in decl.functions.iter() {
+        let signature = Signature::new(
+            function.visibility,
+            CallConv::Erlang,
+            module.name(),
+            function.name.name,
+            FunctionType::new(function.params.clone(), function.results.clone()),
+        );
+        let mfa = signature.mfa().to_local();
+        if declared.insert(mfa, function.name).is_some() {
+            return Err(ParseError::invalid(
+                function.name.span,
+                format!("{} is defined more than once", mfa),
+            ));
+        }
+        let id = if function.visibility.contains(Visibility::CLOSURE) {
+            module.declare_closure(signature.clone())
+        } else {
+            module.declare_function(signature.clone())
+        };
+        functions.push((id, signature));
+    }
+
+    for (function, (id, signature)) in decl.functions.into_iter().zip(functions) {
+        let lowering = FunctionLowering {
+            function: Function::new(
+                id,
+                function.span,
+                signature,
+                module.signatures.clone(),
+                module.callees.clone(),
+                module.constants.clone(),
+            ),
+            module: &mut module,
+            blocks: HashMap::new(),
+            values: HashMap::new(),
+        };
+        let function = lowering.lower(function)?;
+        module.define_function(function);
+    }
+
+    Ok(module)
+}
+
+struct FunctionLowering<'m> {
+    module: &'m mut Module,
+    function: Function,
+    blocks: HashMap<Symbol, Block>,
+    values: HashMap<Symbol, Value>,
+}
+impl<'m> FunctionLowering<'m> {
+    fn lower(mut self, decl: FunctionDecl) -> Result<Function, ParseError> {
+        if decl.blocks.is_empty() {
+            return Err(ParseError::invalid(
+                decl.span,
+                "function definitions must have at least one block",
+            ));
+        }
+
+        // Create all blocks up front, in the order they appear in the text
+        for block in decl.blocks.iter() {
+            let id = self.function.dfg.make_block();
+            if self.blocks.insert(block.name.name, id).is_some() {
+                return Err(ParseError::invalid(
+                    block.name.span,
+                    format!("block {} is defined more than once", block.name),
+                ));
+            }
+            for (name, ty) in block.params.iter() {
+                let value = self
+                    .function
+                    .dfg
+                    .append_block_param(id, ty.clone(), name.span);
+                self.define(*name, value)?;
+            }
+        }
+
+        let entry = &decl.blocks[0];
+        let params = entry.params.iter().map(|(_, ty)| ty.clone());
+        if !params.eq(decl.params.iter().cloned()) {
+            return Err(ParseError::invalid(
+                entry.name.span,
+                "the parameters of the entry block must match the function signature",
+            ));
+        }
+
+        // Values may be used before they appear in the text, but must be defined before they
+        // are used in the control flow graph, so instructions are built in reverse postorder,
+        // followed by any unreachable blocks, in the order they appear in the text
+        let order = self.block_order(&decl.blocks)?;
+        let mut blocks = decl.blocks.into_iter().map(Some).collect::<Vec<_>>();
+        for index in order {
+            let block = blocks[index].take().unwrap();
+            let id = self.blocks[&block.name.name];
+            for inst in block.insts.into_iter() {
+                self.lower_inst(id, inst)?;
+            }
+        }
+
+        Ok(self.function)
+    }
+
+    fn block_order(&self, blocks: &[BlockDecl]) -> Result<Vec<usize>, ParseError> {
+        let indices = blocks
+            .iter()
+            .enumerate()
+            .map(|(i, block)| (block.name.name, i))
+            .collect::<HashMap<_, _>>();
+        let mut successors = Vec::with_capacity(blocks.len());
+        for block in blocks.iter() {
+            let mut succs = vec![];
+            for inst in block.insts.iter() {
+                for dest in inst.operands.successors() {
+                    match indices.get(&dest.name) {
+                        Some(index) => succs.push(*index),
+                        None => {
+                            return Err(ParseError::invalid(
+                                dest.span,
+                                format!("undefined block {}", dest),
+                            ))
+                        }
+                    }
+                }
+            }
+            successors.push(succs);
+        }
+
+        // Depth-first search from the entry block, recording blocks in postorder
+        let mut postorder = Vec::with_capacity(blocks.len());
+        let mut visited = HashSet::new();
+        let mut stack = vec![(0, 0)];
+        visited.insert(0);
+        while let Some((block, next)) = stack.pop() {
+            if let Some(succ) = successors[block].get(next).copied() {
+                stack.push((block, next + 1));
+                if visited.insert(succ) {
+                    stack.push((succ, 0));
+                }
+            } else {
+                postorder.push(block);
+            }
+        }
+
+        let mut order = postorder;
+        order.reverse();
+        order.extend((0..blocks.len()).filter(|index| !visited.contains(index)));
+        Ok(order)
+    }
+
+    fn define(&mut self, name: Ident, value: Value) -> Result<(), ParseError> {
+        if self.values.insert(name.name, value).is_some() {
+            return Err(ParseError::invalid(
+                name.span,
+                format!("value {} is defined more than once", name),
+            ));
+        }
+        Ok(())
+    }
+
+    fn value(&self, name: Ident) -> Result<Value, ParseError> {
+        self.values.get(&name.name).copied().ok_or_else(|| {
+            ParseError::invalid(
+                name.span,
+                format!(
+                    "{} is undefined, or is used before it is defined in the control flow graph",
+                    name
+                ),
+            )
+        })
+    }
+
+    fn values(&self, names: &[Ident]) -> Result<Vec<Value>, ParseError> {
+        names.iter().map(|name| self.value(*name)).collect()
+    }
+
+    fn value_list(&mut self, names: &[Ident]) -> Result<ValueList, ParseError> {
+        let values = self.values(names)?;
+        let mut vlist = ValueList::default();
+        vlist.extend(values, &mut self.function.dfg.value_lists);
+        Ok(vlist)
+    }
+
+    fn successor(&mut self, successor: &Successor) -> Result<(Block, ValueList), ParseError> {
+        let (block, args) = successor;
+        Ok((self.blocks[&block.name], self.value_list(args)?))
+    }
+
+    /// Resolves a callee to a local function, a native function, a builtin, or an import
+    fn callee(&mut self, inst: &InstDecl, name: FunctionName) -> Result<FuncRef, ParseError> {
+        let current = self.module.name();
+        if name.module.is_none() || name.module == Some(current) {
+            if let Some(f) = self.module.get_callee(name.to_local()) {
+                if self.module.call_signature(f).visibility.is_locally_defined() {
+                    return Ok(f);
+                }
+            }
+            if name.module.is_none() {
+                return match nifs::get(&name.function) {
+                    Some(_) => Ok(self.module.get_or_register_native(name.function)),
+                    None => Err(ParseError::invalid(
+                        inst.span,
+                        format!("{} is not defined in this module", name),
+                    )),
+                };
+            }
+            return Err(ParseError::invalid(
+                inst.span,
+                format!("{} is not defined in this module", name),
+            ));
+        }
+        if name.module == Some(symbols::Erlang) && bifs::get(&name).is_some() {
+            return Ok(self.module.get_or_register_builtin(name));
+        }
+        Ok(self.module.import_function(Signature::generate(&name)))
+    }
+
+    fn lower_inst(&mut self, block: Block, inst: InstDecl) -> Result<(), ParseError> {
+        let op = inst.op;
+        let data = match &inst.operands {
+            Operands::Simple(operands) => self.lower_simple(&inst, operands)?,
+            Operands::Const(constant) => {
+                let imm = self.function.dfg.make_constant(constant.clone());
+                InstData::UnaryOpConst(UnaryOpConst { op, imm })
+            }
+            Operands::Call(callee, args) => {
+                let callee = self.callee(&inst, *callee)?;
+                let args = self.value_list(args)?;
+                InstData::Call(Call { op, callee, args })
+            }
+            Operands::CallIndirect(callee, args) => {
+                let callee = self.value(*callee)?;
+                let args = self.value_list(args)?;
+                InstData::CallIndirect(CallIndirect { op, callee, args })
+            }
+            Operands::MakeFun(callee, env) => {
+                let callee = self.callee(&inst, *callee)?;
+                let env = self.value_list(env)?;
+                InstData::MakeFun(MakeFun { callee, env })
+            }
+            Operands::Br(cond, (dest, args)) => {
+                let mut values = vec![];
+                if let Some(cond) = cond {
+                    values.push(*cond);
+                }
+                values.extend_from_slice(args.as_slice());
+                let args = self.value_list(&values)?;
+                InstData::Br(Br {
+                    op,
+                    destination: self.blocks[&dest.name],
+                    args,
+                })
+            }
+            Operands::CondBr(cond, then_dest, else_dest) => InstData::CondBr(CondBr {
+                cond: self.value(*cond)?,
+                then_dest: self.successor(then_dest)?,
+                else_dest: self.successor(else_dest)?,
+            }),
+            Operands::Switch(arg, arms, default) => InstData::Switch(Switch {
+                op,
+                arg: self.value(*arg)?,
+                arms: arms
+                    .iter()
+                    .map(|(value, dest)| (*value, self.blocks[&dest.name]))
+                    .collect(),
+                default: self.blocks[&default.name],
+            }),
+            Operands::IsType(arg, ty) => InstData::IsType(IsType {
+                arg: self.value(*arg)?,
+                ty: ty.clone(),
+            }),
+            Operands::SetElement(tuple, index, value) => {
+                let tuple = self.value(*tuple)?;
+                let index = Immediate::Isize(*index);
+                match value {
+                    Operand::Value(value) => InstData::SetElement(SetElement {
+                        op,
+                        index,
+                        args: [tuple, self.value(*value)?],
+                    }),
+                    Operand::Immediate(_, value) => InstData::SetElementImm(SetElementImm {
+                        op,
+                        arg: tuple,
+                        index,
+                        value: *value,
+                    }),
+                }
+            }
+            Operands::Bits(spec, operands) => {
+                let spec = *spec;
+                let (args, imm) =
+                    split_operands(operands).ok_or_else(|| invalid_operands(&inst))?;
+                let args = self.value_list(&args)?;
+                match (op, imm) {
+                    (Opcode::BitsMatchSkip, Some(value)) => {
+                        InstData::BitsMatchSkip(BitsMatchSkip { spec, args, value })
+                    }
+                    (Opcode::BitsMatch, None) => InstData::BitsMatch(BitsMatch { spec, args }),
+                    (Opcode::BitsPush, None) => InstData::BitsPush(BitsPush { spec, args }),
+                    _ => return Err(invalid_operands(&inst)),
+                }
+            }
+        };
+
+        if inst.results.len() != inst.types.len() {
+            return Err(ParseError::invalid(
+                inst.span,
+                format!(
+                    "expected {} result types, but got {}",
+                    inst.results.len(),
+                    inst.types.len()
+                ),
+            ));
+        }
+
+        let id = self.function.dfg.push_inst(block, data, inst.span);
+        for (name, ty) in inst.results.iter().zip(inst.types.into_iter()) {
+            let value = self.function.dfg.append_result(id, ty);
+            self.define(*name, value)?;
+        }
+
+        Ok(())
+    }
+
+    /// Lowers instructions whose operands are a list of values and immediates, the shape of which
+    /// determines the instruction format, e.g. `add v1, v2` vs `add v1, 1`
+    fn lower_simple(
+        &mut self,
+        inst: &InstDecl,
+        operands: &[Operand],
+    ) -> Result<InstData, ParseError> {
+        let op = inst.op;
+        let data = match op {
+            Opcode::ImmInt
+            | Opcode::ImmFloat
+            | Opcode::ImmBool
+            | Opcode::ImmAtom
+            | Opcode::ImmNil
+            | Opcode::ImmNone
+            | Opcode::ImmNull
+            | Opcode::Tuple
+            | Opcode::IsNull
+            | Opcode::Cast
+            | Opcode::Trunc
+            | Opcode::Zext
+            | Opcode::Neg
+            | Opcode::Not
+            | Opcode::Bnot
+            | Opcode::Head
+            | Opcode::Tail => match operands {
+                [Operand::Value(arg)] => InstData::UnaryOp(UnaryOp {
+                    op,
+                    arg: self.value(*arg)?,
+                }),
+                [Operand::Immediate(_, imm)] => InstData::UnaryOpImm(UnaryOpImm { op, imm: *imm }),
+                _ => return Err(invalid_operands(inst)),
+            },
+            Opcode::Ret => match operands {
+                [Operand::Value(a), Operand::Value(b)] => InstData::Ret(Ret {
+                    op,
+                    args: [self.value(*a)?, self.value(*b)?],
+                }),
+                [Operand::Immediate(_, imm), Operand::Value(arg)] => InstData::RetImm(RetImm {
+                    op,
+                    imm: *imm,
+                    arg: self.value(*arg)?,
+                }),
+                _ => return Err(invalid_operands(inst)),
+            },
+            Opcode::RecvStart
+            | Opcode::RecvNext
+            | Opcode::RecvPeek
+            | Opcode::RecvPop
+            | Opcode::RecvWait
+            | Opcode::RecvDone
+            | Opcode::NifStart
+            | Opcode::BitsMatchStart
+            | Opcode::Raise
+            | Opcode::ExceptionClass
+            | Opcode::ExceptionReason
+            | Opcode::ExceptionTrace => match operands.split_first() {
+                Some((Operand::Immediate(_, imm), rest)) => {
+                    let args = match split_operands(rest) {
+                        Some((args, None)) => args,
+                        _ => return Err(invalid_operands(inst)),
+                    };
+                    InstData::PrimOpImm(PrimOpImm {
+                        op,
+                        imm: *imm,
+                        args: self.value_list(&args)?,
+                    })
+                }
+                _ => {
+                    let args = match split_operands(operands) {
+                        Some((args, None)) => args,
+                        _ => return Err(invalid_operands(inst)),
+                    };
+                    InstData::PrimOp(PrimOp {
+                        op,
+                        args: self.value_list(&args)?,
+                    })
+                }
+            },
+            // All other simple instructions are binary operators
+            _ => match operands {
+                [Operand::Value(a), Operand::Value(b)] => InstData::BinaryOp(BinaryOp {
+                    op,
+                    args: [self.value(*a)?, self.value(*b)?],
+                }),
+                [Operand::Value(arg), Operand::Immediate(_, imm)] => {
+                    InstData::BinaryOpImm(BinaryOpImm {
+                        op,
+                        arg: self.value(*arg)?,
+                        imm: *imm,
+                    })
+                }
+                _ => return Err(invalid_operands(inst)),
+            },
+        };
+        Ok(data)
+    }
+}
+
+/// Splits operands into the leading values and an optional trailing immediate, returning
+/// `None` if an immediate appears anywhere but last
+fn split_operands(operands: &[Operand]) -> Option<(Vec<Ident>, Option<Immediate>)> {
+    let mut values = vec![];
+    let mut imm = None;
+    for operand in operands {
+        if imm.is_some() {
+            return None;
+        }
+        match operand {
+            Operand::Value(value) => values.push(*value),
+            Operand::Immediate(_, value) => imm = Some(*value),
+        }
+    }
+    Some((values, imm))
+}
+
+fn invalid_operands(inst: &InstDecl) -> ParseError {
+    ParseError::invalid(
+        inst.span,
+        format!("invalid operands for {} instruction", inst.op),
+    )
+}
@@ -0,0 +1,324 @@
+//! A parser for the textual form of the SSA IR, as written by [`crate::write`]
+//!
+//! This allows the IR to be handwritten, or emitted via `--emit=ssa`, edited, and fed back
+//! into the compiler as an `.ssa` input, e.g. to test later stages of the pipeline in
+//! isolation. Value names are not preserved, so round-tripping a module through the
+//! parser may renumber its values.
+mod ast;
+mod error;
+mod inst;
+mod lexer;
+mod lower;
+mod types;
+
+pub use self::error::ParseError;
+
+/// Used in the grammar for easy span creation
+macro_rules! span {
+    ($l:expr, $r:expr) => {
+        firefly_diagnostics::SourceSpan::new($l, $r)
+    };
+    ($i:expr) => {
+        firefly_diagnostics::SourceSpan::new($i, $i)
+    };
+}
+
+#[cfg_attr(rustfmt, rustfmt_skip)]
+#[allow(unknown_lints)]
+#[allow(clippy)]
+#[allow(unused)]
+pub(crate) mod grammar {
+    // During the build step, `build.rs` will output the generated parser to `OUT_DIR` to avoid
+    // adding it to the source directory, so we just directly include the generated parser here.
+    //
+    // Even with `.gitignore` and the `exclude` in the `Cargo.toml`, the generated parser can still
+    // end up in the source directory. This could happen when `cargo build` builds the file out of
+    // the Cargo cache (`$HOME/.cargo/registrysrc`), and the build script would then put its output
+    // in that cached source directory because of https://github.com/lalrpop/lalrpop/issues/280.
+    // Later runs of `cargo vendor` then copy the source from that directory, including the
+    // generated file.
+    include!(concat!(env!("OUT_DIR"), "/parser/grammar.rs"));
+}
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use firefly_diagnostics::{CodeMap, Reporter};
+use firefly_parser::{Parse, Parser, Scanner, Source};
+
+use self::lexer::{Lexed, Lexer};
+
+impl Parse for crate::Module {
+    type Parser = grammar::ModuleParser;
+    type Error = ParseError;
+    type Config = ();
+    type Token = Lexed;
+
+    fn root_file_error(source: std::io::Error, path: PathBuf) -> Self::Error {
+        ParseError::RootFile { source, path }
+    }
+
+    fn parse<S>(parser: &Parser<()>, reporter: Reporter, source: S) -> Result<Self, ParseError>
+    where
+        S: Source,
+    {
+        let scanner = Scanner::new(source);
+        let lexer = Lexer::new(scanner);
+        Self::parse_tokens(reporter, parser.codemap.clone(), lexer)
+    }
+
+    fn parse_tokens<S>(
+        _reporter: Reporter,
+        _codemap: Arc<CodeMap>,
+        tokens: S,
+    ) -> Result<Self, ParseError>
+    where
+        S: IntoIterator<Item = Self::Token>,
+    {
+        let module = Self::Parser::new().parse(tokens)?;
+        lower::lower(module)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use firefly_diagnostics::*;
+    use firefly_parser::Parser;
+
+    use crate::Module;
+
+    use super::ParseError;
+
+    fn parse(input: &str) -> Result<Module, ParseError> {
+        let codemap = Arc::new(CodeMap::new());
+        let parser = Parser::new((), codemap);
+        parser.parse_string::<Module, _, _>(Reporter::new(), input)
+    }
+
+    fn print(module: &Module) -> String {
+        let mut buf = vec![];
+        crate::write::write_module(&mut buf, module).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    /// Parses `input`, and checks that printing and re-parsing the result reaches a fixpoint
+    fn roundtrip(input: &str) -> Module {
+        let module = match parse(input) {
+            Ok(module) => module,
+            Err(err) => panic!("parsing failed: {:?}", err.to_diagnostic()),
+        };
+        let printed = print(&module);
+        let reparsed = match parse(&printed) {
+            Ok(module) => module,
+            Err(err) => panic!(
+                "parsing printed module failed: {:?}\n{}",
+                err.to_diagnostic(),
+                &printed
+            ),
+        };
+        assert_eq!(printed, print(&reparsed));
+        module
+    }
+
+    #[test]
+    fn simple() {
+        let module = roundtrip(
+            "
+module foo
+
+pub function bar(term) -> i1, term {
+block0(v0: term):
+    v1 = const.int 1  : term
+    v2, v3 = call erlang:'+'/2(v0, v1)  : i1, term
+    br.if v2, block1(v3)
+    v4 = add v3, 2  : term
+    ret i1 false, v4
+
+block1(v5: term):
+    ret i1 true, v5
+}
+",
+        );
+        assert_eq!(module.name.as_str().get(), "foo");
+        assert_eq!(module.functions.len(), 1);
+        let function = &module.functions[0];
+        assert!(function.signature.visibility.is_public());
+        assert_eq!(function.dfg.blocks().count(), 2);
+    }
+
+    #[test]
+    fn forward_references() {
+        // block2 is defined before block1 in the text, but uses a value defined in block1
+        roundtrip(
+            "
+module foo
+
+function loop(term, term) -> i1, term {
+block0(v0: term, v1: term):
+    br block1
+
+block2(v5: term):
+    v6 = cons v5, v2  : term
+    ret i1 false, v6
+
+block1:
+    v2 = tuple isize 2  : tuple
+    v3 = tuple.set v2[1], 'ok'  : tuple
+    v4 = tuple.set.mut v3[2], v1  : tuple
+    cond.br v0, block2(v4), block3
+
+block3:
+    v7 = const.atom 'not found'  : term
+    v8 = const.float 1.0  : float
+    v9 = list.concat v7, v8  : term
+    ret i1 true, v9
+}
+",
+        );
+    }
+
+    #[test]
+    fn calls_and_closures() {
+        roundtrip(
+            "
+module 'my-mod'
+
+pub function outer(term) -> i1, term {
+block0(v0: term):
+    v1 = fun.make 'inner-0'/2(v0)  : fun
+    v2, v3 = call.indirect v1(v0)  : i1, term
+    switch v2, 0 => block1, 1 => block2, block2
+
+block1:
+    v4, v5 = call other:thing/1(v3)  : i1, term
+    tail call outer/1(v5)
+
+block2:
+    v6 = bs.match.start v3  : match_context
+    ret i1 true, v3
+}
+
+closure function 'inner-0'(term, term) -> i1, term {
+block0(v0: term, v1: term):
+    v2 = fun.env.get v0, isize 0  : term
+    v3 = is_type v2, list<term>  : i1
+    v4 = const.binary <<1, 2, 255>>  : bytes
+    v5 = const.binary <<255, 3:4>>  : bits
+    v6 = const.bigint 1000000000000000000000  : term
+    ret i1 false, v4
+}
+
+nif function nif_impl(term) -> i1, term {
+block0(v0: term):
+    v1, v2 = bs.push.uint.big(8) v0, v0  : i1, term
+    v3, v4 = bs.match.skip.sint.little(1) v0, v0, i64 1  : i1, term
+    ret i1 false, v0
+}
+",
+        );
+    }
+
+    #[test]
+    fn undefined_value() {
+        let result = parse(
+            "
+module foo
+
+function bar(term) -> i1, term {
+block0(v0: term):
+    ret i1 false, v1
+}
+",
+        );
+        assert!(matches!(result, Err(ParseError::ShowDiagnostic { .. })));
+    }
+
+    #[test]
+    fn undefined_block() {
+        let result = parse(
+            "
+module foo
+
+function bar(term) -> i1, term {
+block0(v0: term):
+    br block1(v0)
+}
+",
+        );
+        assert!(matches!(result, Err(ParseError::ShowDiagnostic { .. })));
+    }
+
+    #[test]
+    fn unknown_opcode() {
+        let result = parse(
+            "
+module foo
+
+function bar(term) -> i1, term {
+block0(v0: term):
+    v1 = frobnicate v0  : term
+    ret i1 false, v1
+}
+",
+        );
+        assert!(matches!(result, Err(ParseError::ShowDiagnostic { .. })));
+    }
+
+    #[test]
+    fn partial_bitstrings() {
+        let module = roundtrip(
+            "
+module foo
+
+function bar() -> i1, term {
+block0:
+    v0 = const.binary <<255, 5:3>>  : bits
+    ret i1 false, v0
+}
+",
+        );
+        let printed = print(&module);
+        assert!(printed.contains("<<255, 5:3>>"), "{}", &printed);
+    }
+
+    #[test]
+    fn keywords_are_quoted() {
+        let module = roundtrip(
+            "
+module 'module'
+
+pub function 'function'() -> i1, term {
+block0:
+    v0 = fun.make 'tail'/0()  : fun
+    ret i1 false, v0
+}
+
+closure function 'tail'() -> i1, term {
+block0:
+    v0 = const.atom 'ok'  : atom
+    ret i1 false, v0
+}
+",
+        );
+        assert_eq!(module.name.as_str().get(), "module");
+        let printed = print(&module);
+        assert!(printed.contains("function 'function'()"), "{}", &printed);
+    }
+
+    #[test]
+    fn unrecognized_token() {
+        let result = parse(
+            "
+module foo
+
+function bar(term) -> i1, term {
+block0(v0: term):
+    ret i1 false, v0)
+}
+",
+        );
+        assert!(matches!(result, Err(ParseError::UnrecognizedToken { .. })));
+    }
+}
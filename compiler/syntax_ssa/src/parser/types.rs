@@ -0,0 +1,130 @@
+//! Resolution of the type names used in the textual IR, in the form produced by the `Display`
+//! implementation of [`Type`]
+use firefly_diagnostics::SourceSpan;
+use firefly_intern::Ident;
+use firefly_number::Integer;
+use firefly_syntax_base::*;
+
+use super::inst::small;
+use super::ParseError;
+
+/// Resolves a type written as a bare name, e.g. `term` or `i64`
+pub fn named(name: Ident) -> Result<Type, ParseError> {
+    let ty = match name.as_str().get() {
+        "invalid" => Type::Invalid,
+        "exception" => Type::Exception,
+        "trace" => Type::ExceptionTrace,
+        "recv_context" => Type::RecvContext,
+        "recv_state" => Type::RecvState,
+        "binary_builder" => Type::BinaryBuilder,
+        "match_context" => Type::MatchContext,
+        "void" => Type::Primitive(PrimitiveType::Void),
+        "i1" => Type::Primitive(PrimitiveType::I1),
+        "i8" => Type::Primitive(PrimitiveType::I8),
+        "i16" => Type::Primitive(PrimitiveType::I16),
+        "i32" => Type::Primitive(PrimitiveType::I32),
+        "i64" => Type::Primitive(PrimitiveType::I64),
+        "isize" => Type::Primitive(PrimitiveType::Isize),
+        "f64" => Type::Primitive(PrimitiveType::F64),
+        "term" => Type::Term(TermType::Any),
+        "bool" => Type::Term(TermType::Bool),
+        "int" => Type::Term(TermType::Integer),
+        "float" => Type::Term(TermType::Float),
+        "number" => Type::Term(TermType::Number),
+        "atom" => Type::Term(TermType::Atom),
+        "bits" => Type::Term(TermType::Bitstring),
+        "bytes" => Type::Term(TermType::Binary),
+        "nil" => Type::Term(TermType::Nil),
+        "cons" => Type::Term(TermType::Cons),
+        "map" => Type::Term(TermType::Map),
+        "reference" => Type::Term(TermType::Reference),
+        "port" => Type::Term(TermType::Port),
+        "pid" => Type::Term(TermType::Pid),
+        "list" => Type::Term(TermType::List(None)),
+        "tuple" => Type::Term(TermType::Tuple(None)),
+        "fun" => Type::Term(TermType::Fun(None)),
+        _ => return Err(ParseError::invalid(name.span, "unknown type")),
+    };
+    Ok(ty)
+}
+
+/// Resolves a type with parameters, e.g. `ptr<i8>`, `list<term>` or `tuple<atom, term>`
+pub fn parameterized(span: SourceSpan, name: Ident, params: Vec<Type>) -> Result<Type, ParseError> {
+    match name.as_str().get() {
+        "ptr" => match single(span, params)? {
+            Type::Primitive(pointee) => Ok(Type::Primitive(PrimitiveType::Ptr(Box::new(pointee)))),
+            _ => Err(ParseError::invalid(span, "expected primitive type")),
+        },
+        "list" => {
+            let element = term(span, single(span, params)?)?;
+            Ok(Type::Term(TermType::List(Some(Box::new(element)))))
+        }
+        "tuple" => {
+            let elements = params
+                .into_iter()
+                .map(|ty| term(span, ty))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Type::Term(TermType::Tuple(Some(elements))))
+        }
+        _ => Err(ParseError::invalid(name.span, "unknown type")),
+    }
+}
+
+/// Resolves a type name followed by `?`, of which only `list?` is valid
+pub fn maybe(name: Ident) -> Result<Type, ParseError> {
+    match name.as_str().get() {
+        "list" => Ok(Type::Term(TermType::MaybeImproperList)),
+        _ => Err(ParseError::invalid(name.span, "unknown type")),
+    }
+}
+
+/// Resolves a type name followed by a signature, of which only `fun(..)` is valid
+pub fn signature(name: Ident, ty: FunctionType) -> Result<Type, ParseError> {
+    match name.as_str().get() {
+        "fun" => Ok(Type::Term(TermType::Fun(Some(Box::new(ty))))),
+        _ => Err(ParseError::invalid(name.span, "unknown type")),
+    }
+}
+
+/// Resolves a structure type, e.g. `{i64, ptr<i8>}`
+pub fn structure(span: SourceSpan, fields: Vec<Type>) -> Result<Type, ParseError> {
+    let fields = fields
+        .into_iter()
+        .map(|ty| primitive(span, ty))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Type::Primitive(PrimitiveType::Struct(fields)))
+}
+
+/// Resolves an array type, e.g. `[i8; 4]`
+pub fn array(span: SourceSpan, element: Type, arity: Integer) -> Result<Type, ParseError> {
+    let element = primitive(span, element)?;
+    let arity = small(span, arity)?;
+    Ok(Type::Primitive(PrimitiveType::Array(
+        Box::new(element),
+        arity,
+    )))
+}
+
+fn primitive(span: SourceSpan, ty: Type) -> Result<PrimitiveType, ParseError> {
+    match ty {
+        Type::Primitive(ty) => Ok(ty),
+        _ => Err(ParseError::invalid(span, "expected primitive type")),
+    }
+}
+
+fn term(span: SourceSpan, ty: Type) -> Result<TermType, ParseError> {
+    match ty {
+        Type::Term(ty) => Ok(ty),
+        _ => Err(ParseError::invalid(span, "expected term type")),
+    }
+}
+
+fn single(span: SourceSpan, mut params: Vec<Type>) -> Result<Type, ParseError> {
+    match params.len() {
+        1 => Ok(params.pop().unwrap()),
+        _ => Err(ParseError::invalid(
+            span,
+            "expected a single type parameter",
+        )),
+    }
+}
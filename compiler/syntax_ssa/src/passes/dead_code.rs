@@ -0,0 +1,163 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::*;
+
+use super::*;
+
+/// This pass removes code which can have no effect on the result of a function
+///
+/// This includes blocks which are unreachable from the entry block, instructions without side
+/// effects whose results are never used, and block parameters which are never used, along with
+/// the corresponding arguments passed by each predecessor.
+#[derive(Default)]
+pub struct EliminateDeadCode;
+impl FunctionPass for EliminateDeadCode {
+    fn run_on_function(&mut self, function: &mut Function) -> bool {
+        let dfg = &mut function.dfg;
+        let Some(entry) = entry_block(dfg) else {
+            return false;
+        };
+        let mut changed = remove_unreachable_blocks(dfg, entry);
+        changed |= remove_unused_insts(dfg);
+        changed |= remove_unused_params(dfg, entry);
+        changed
+    }
+}
+
+fn remove_unreachable_blocks(dfg: &mut DataFlowGraph, entry: Block) -> bool {
+    let mut reachable = BTreeSet::new();
+    let mut worklist = vec![entry];
+    while let Some(block) = worklist.pop() {
+        if !reachable.insert(block) {
+            continue;
+        }
+        for inst in dfg.block_insts(block) {
+            worklist.extend(successors(dfg, inst).into_iter().map(|(succ, _)| succ));
+        }
+    }
+    let unreachable = dfg
+        .blocks()
+        .map(|(b, _)| b)
+        .filter(|b| !reachable.contains(b))
+        .collect::<Vec<_>>();
+    for block in unreachable.iter().copied() {
+        dfg.remove_block(block);
+    }
+    !unreachable.is_empty()
+}
+
+fn remove_unused_insts(dfg: &mut DataFlowGraph) -> bool {
+    let insts = dfg
+        .blocks()
+        .flat_map(|(_, data)| data.insts())
+        .collect::<Vec<_>>();
+    let mut uses = count_uses(dfg, &insts);
+    // Visiting instructions in reverse ensures that users are removed before the values they use
+    let mut worklist = insts;
+    let mut removed = BTreeSet::new();
+    while let Some(inst) = worklist.pop() {
+        if removed.contains(&inst) || !is_pure(dfg[inst].opcode()) {
+            continue;
+        }
+        if dfg.inst_results(inst).iter().any(|v| uses.contains_key(v)) {
+            continue;
+        }
+        for arg in dfg.inst_uses(inst).collect::<Vec<_>>() {
+            let count = uses.get_mut(&arg).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                uses.remove(&arg);
+                worklist.extend(definition(dfg, arg));
+            }
+        }
+        dfg.remove_inst(inst);
+        removed.insert(inst);
+    }
+    !removed.is_empty()
+}
+
+fn remove_unused_params(dfg: &mut DataFlowGraph, entry: Block) -> bool {
+    let insts = dfg
+        .blocks()
+        .flat_map(|(_, data)| data.insts())
+        .collect::<Vec<_>>();
+    let uses = count_uses(dfg, &insts);
+    let preds = predecessors(dfg);
+    let mut changed = false;
+    for (block, branches) in preds.iter() {
+        if *block == entry {
+            continue;
+        }
+        let mut branches = branches.clone();
+        branches.dedup();
+        let params = dfg.block_params(*block).to_vec();
+        for (index, param) in params.iter().enumerate().rev() {
+            if uses.contains_key(param) {
+                continue;
+            }
+            for branch in branches.iter().copied() {
+                dfg.remove_branch_arg(branch, *block, index);
+            }
+            dfg.remove_block_param(*block, index);
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Returns the number of times each value is used by `insts`, values which are unused have no entry
+fn count_uses(dfg: &DataFlowGraph, insts: &[Inst]) -> BTreeMap<Value, usize> {
+    let mut uses = BTreeMap::<Value, usize>::new();
+    for inst in insts.iter().copied() {
+        for arg in dfg.inst_uses(inst) {
+            *uses.entry(arg).or_default() += 1;
+        }
+    }
+    uses
+}
+
+/// Returns true if instructions with opcode `op` have no side effects, and so can be removed if their results are unused
+fn is_pure(op: Opcode) -> bool {
+    matches!(
+        op,
+        Opcode::ImmInt
+            | Opcode::ImmFloat
+            | Opcode::ImmBool
+            | Opcode::ImmAtom
+            | Opcode::ImmNil
+            | Opcode::ImmNone
+            | Opcode::ImmNull
+            | Opcode::ConstBigInt
+            | Opcode::ConstBinary
+            | Opcode::IsNull
+            | Opcode::Cast
+            | Opcode::Trunc
+            | Opcode::Zext
+            | Opcode::IcmpEq
+            | Opcode::IcmpNeq
+            | Opcode::IcmpGt
+            | Opcode::IcmpGte
+            | Opcode::IcmpLt
+            | Opcode::IcmpLte
+            | Opcode::Eq
+            | Opcode::EqExact
+            | Opcode::Neq
+            | Opcode::NeqExact
+            | Opcode::Gt
+            | Opcode::Gte
+            | Opcode::Lt
+            | Opcode::Lte
+            | Opcode::IsType
+            | Opcode::IsTaggedTuple
+            | Opcode::Cons
+            | Opcode::Head
+            | Opcode::Tail
+            | Opcode::Tuple
+            | Opcode::GetElement
+            | Opcode::MakeFun
+            | Opcode::UnpackEnv
+            | Opcode::ExceptionClass
+            | Opcode::ExceptionReason
+            | Opcode::ExceptionTrace
+    )
+}
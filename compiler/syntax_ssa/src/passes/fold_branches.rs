@@ -0,0 +1,151 @@
+use firefly_intern::symbols;
+use firefly_syntax_base::*;
+
+use crate::*;
+
+use super::*;
+
+/// This pass replaces conditional branches whose outcome is known at compile-time with unconditional ones
+///
+/// A condition is known when it is a constant, or a type test whose outcome is implied by the type
+/// of the value being tested, see [`InferTypes`]. Likewise, switches on a constant, and conditional
+/// branches whose destinations are all the same, are replaced with an unconditional branch to the
+/// destination which would be taken.
+///
+/// Blocks which become unreachable as a result are left for [`EliminateDeadCode`] to clean up.
+#[derive(Default)]
+pub struct FoldBranches;
+impl FunctionPass for FoldBranches {
+    fn run_on_function(&mut self, function: &mut Function) -> bool {
+        let dfg = &mut function.dfg;
+        let blocks = dfg.blocks().map(|(b, _)| b).collect::<Vec<_>>();
+        let mut changed = false;
+        for block in blocks {
+            let insts = dfg.block_insts(block).collect::<Vec<_>>();
+            for (i, inst) in insts.iter().copied().enumerate() {
+                match fold(dfg, inst) {
+                    Folded::Unchanged => continue,
+                    Folded::Removed => {
+                        changed = true;
+                    }
+                    Folded::Jump => {
+                        // Anything following an unconditional branch is dead
+                        for dead in insts[(i + 1)..].iter().copied() {
+                            dfg.remove_inst(dead);
+                        }
+                        changed = true;
+                        break;
+                    }
+                }
+            }
+        }
+        changed
+    }
+}
+
+enum Folded {
+    /// The branch could not be folded
+    Unchanged,
+    /// The branch is never taken, and was removed
+    Removed,
+    /// The branch was replaced with an unconditional branch
+    Jump,
+}
+
+fn fold(dfg: &mut DataFlowGraph, inst: Inst) -> Folded {
+    let destination = match &*dfg[inst] {
+        InstData::Br(Br {
+            op: op @ (Opcode::BrIf | Opcode::BrUnless),
+            args,
+            ..
+        }) => {
+            let cond = args.as_slice(&dfg.value_lists)[0];
+            let Some(truthy) = truthiness(dfg, cond) else {
+                return Folded::Unchanged;
+            };
+            if truthy != (*op == Opcode::BrIf) {
+                dfg.remove_inst(inst);
+                return Folded::Removed;
+            }
+            let InstData::Br(br) = &mut dfg.insts[inst].data.item else {
+                unreachable!()
+            };
+            br.op = Opcode::Br;
+            br.args.remove(0, &mut dfg.value_lists);
+            return Folded::Jump;
+        }
+        InstData::CondBr(CondBr {
+            cond,
+            then_dest,
+            else_dest,
+        }) => match truthiness(dfg, *cond) {
+            Some(true) => *then_dest,
+            Some(false) => *else_dest,
+            None if then_dest.0 == else_dest.0
+                && then_dest.1.as_slice(&dfg.value_lists)
+                    == else_dest.1.as_slice(&dfg.value_lists) =>
+            {
+                *then_dest
+            }
+            None => return Folded::Unchanged,
+        },
+        InstData::Switch(Switch {
+            arg, arms, default, ..
+        }) => {
+            let target = match integer_constant(dfg, *arg) {
+                Some(value) => arms
+                    .iter()
+                    .find(|(key, _)| *key as i64 == value)
+                    .map(|(_, block)| *block)
+                    .unwrap_or(*default),
+                None if arms.iter().all(|(_, block)| block == default) => *default,
+                None => return Folded::Unchanged,
+            };
+            (target, ValueList::new())
+        }
+        _ => return Folded::Unchanged,
+    };
+    let (destination, args) = destination;
+    dfg.insts[inst].data.item = InstData::Br(Br {
+        op: Opcode::Br,
+        destination,
+        args,
+    });
+    Folded::Jump
+}
+
+/// Returns the truthiness of the condition `cond`, if it is known at compile-time
+fn truthiness(dfg: &DataFlowGraph, cond: Value) -> Option<bool> {
+    let inst = definition(dfg, cond)?;
+    match &*dfg[inst] {
+        InstData::UnaryOpImm(UnaryOpImm { imm, .. }) => match imm {
+            Immediate::Term(ImmediateTerm::Atom(a)) if *a == symbols::True => Some(true),
+            Immediate::Term(ImmediateTerm::Atom(a)) if *a == symbols::False => Some(false),
+            imm => imm.as_bool(),
+        },
+        InstData::IsType(IsType {
+            arg,
+            ty: Type::Term(test),
+        }) => match dfg.value_type(*arg) {
+            Type::Term(ty) => ty.test_outcome(test),
+            _ => None,
+        },
+        InstData::BinaryOpImm(BinaryOpImm {
+            op: Opcode::IsTaggedTuple,
+            arg,
+            ..
+        }) => match dfg.value_type(*arg) {
+            Type::Term(ty) if ty.is_disjoint(&TermType::Tuple(None)) => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns the value of `value` as an integer, if it is an integer constant
+fn integer_constant(dfg: &DataFlowGraph, value: Value) -> Option<i64> {
+    match &*dfg[definition(dfg, value)?] {
+        InstData::UnaryOpImm(UnaryOpImm { imm, .. }) => imm.as_i64(),
+        _ => None,
+    }
+}
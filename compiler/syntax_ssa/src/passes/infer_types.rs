@@ -0,0 +1,565 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use firefly_intern::{symbols, Symbol};
+use firefly_pass::Pass;
+use firefly_syntax_base::*;
+
+use crate::*;
+use crate::{BinaryOp, UnaryOp};
+
+/// The maximum depth to which the element types of tuples and lists are tracked
+const MAX_DEPTH: usize = 3;
+
+/// The maximum number of passes over a function before we give up on reaching a fixpoint
+const MAX_ITERATIONS: usize = 32;
+
+/// This pass infers more precise term types for the values of each function in a module
+///
+/// Inference is a forward dataflow analysis over the SSA values of a function, seeded by the
+/// types of constants, the entry block parameters, and the signatures of callees, most notably
+/// the BIF signatures defined in `firefly_syntax_base::bifs`. Block parameters are given the union
+/// of the types flowing in along each edge, narrowed by any `is_type` test guarding that edge.
+///
+/// Values whose inferred type is strictly more precise than their current type are updated in place,
+/// which makes the results visible when emitting SSA IR, and lets code generation elide type tests
+/// whose outcome is already known.
+#[derive(Default)]
+pub struct InferTypes;
+impl InferTypes {
+    pub fn new() -> Self {
+        Self
+    }
+}
+impl Pass for InferTypes {
+    type Input<'a> = Module;
+    type Output<'a> = Module;
+
+    fn run<'a>(&mut self, mut module: Self::Input<'a>) -> anyhow::Result<Self::Output<'a>> {
+        for function in module.functions.iter_mut() {
+            let Some(entry) = function.dfg.blocks().next().map(|(b, _)| b) else { continue; };
+            let inference = Inference::new(&function.dfg, entry);
+            if let Some(types) = inference.solve() {
+                apply(&mut function.dfg, entry, types);
+            }
+        }
+        Ok(module)
+    }
+}
+
+struct Inference<'f> {
+    dfg: &'f DataFlowGraph,
+    entry: Block,
+    /// The inferred type of each value reached so far, values without an entry have not been reached yet
+    types: BTreeMap<Value, TermType>,
+    /// The types of the elements stored into tuples allocated in this function via `tuple.set.mut`
+    stores: BTreeMap<Value, BTreeMap<usize, TermType>>,
+    /// Tuples mutated in place which were not allocated in this function, nothing can be assumed about their elements
+    mutated: BTreeSet<Value>,
+    changed: bool,
+}
+impl<'f> Inference<'f> {
+    fn new(dfg: &'f DataFlowGraph, entry: Block) -> Self {
+        let mut types = BTreeMap::new();
+        for param in dfg.block_params(entry).iter().copied() {
+            if let Type::Term(ty) = dfg.value_type(param) {
+                types.insert(param, ty);
+            }
+        }
+
+        let mut inference = Self {
+            dfg,
+            entry,
+            types,
+            stores: BTreeMap::new(),
+            mutated: BTreeSet::new(),
+            changed: false,
+        };
+        for (_, block_data) in dfg.blocks() {
+            for inst in block_data.insts() {
+                if dfg[inst].opcode() != Opcode::SetElementMut {
+                    continue;
+                }
+                let root = inference.root(dfg.inst_args(inst)[0]);
+                if inference.opcode_of(root) != Some(Opcode::Tuple) {
+                    inference.mutated.insert(root);
+                }
+            }
+        }
+        inference
+    }
+
+    /// Runs the analysis to a fixpoint, returning the inferred types, or `None` if no fixpoint was reached
+    fn solve(mut self) -> Option<BTreeMap<Value, TermType>> {
+        for _ in 0..MAX_ITERATIONS {
+            self.changed = false;
+            for (_, block_data) in self.dfg.blocks() {
+                for inst in block_data.insts() {
+                    self.visit(inst);
+                }
+            }
+            if !self.changed {
+                return Some(self.types);
+            }
+        }
+        None
+    }
+
+    fn visit(&mut self, inst: Inst) {
+        let dfg = self.dfg;
+        let data: &InstData = &dfg[inst];
+        match dfg.analyze_branch(inst) {
+            BranchInfo::NotABranch => (),
+            BranchInfo::SingleDest(destination, args) => {
+                let guard = match data.opcode() {
+                    Opcode::BrIf => Some((dfg.inst_args(inst)[0], true)),
+                    Opcode::BrUnless => Some((dfg.inst_args(inst)[0], false)),
+                    _ => None,
+                };
+                self.visit_edge(destination, args, guard);
+            }
+            BranchInfo::MultiDest(targets) => {
+                let cond = match data {
+                    InstData::CondBr(CondBr { cond, .. }) => Some(*cond),
+                    _ => None,
+                };
+                for (i, target) in targets.iter().enumerate() {
+                    let guard = cond.map(|cond| (cond, i == 0));
+                    self.visit_edge(target.destination, target.args, guard);
+                }
+            }
+        }
+
+        let results = dfg.inst_results(inst);
+        if results.is_empty() {
+            return;
+        }
+        if let InstData::Call(Call { callee, .. }) = data {
+            let mfa = dfg.callee_signature(*callee).mfa();
+            for (i, result) in results.iter().copied().enumerate() {
+                let Type::Term(ty) = dfg.value_type(result) else { continue; };
+                // The BIF signatures for arithmetic can't express that the result type depends on the operands
+                let ty = if i == 1 && ty == TermType::Number && mfa.is_arith_op() {
+                    let args = dfg.inst_args(inst);
+                    match self.arith(mfa.function, args) {
+                        Some(ty) => ty,
+                        None => continue,
+                    }
+                } else {
+                    ty
+                };
+                self.set(result, ty);
+            }
+            return;
+        }
+
+        let result = results[0];
+        let Type::Term(ty) = dfg.value_type(result) else { return; };
+        if let Some(ty) = self.transfer(result, data, ty) {
+            self.set(result, ty);
+        }
+    }
+
+    /// Propagates the types of `args` to the parameters of `destination`
+    ///
+    /// If `guard` is provided, the edge is only taken when the given value has the given truthiness
+    fn visit_edge(&mut self, destination: Block, args: &[Value], guard: Option<(Value, bool)>) {
+        // The entry block parameters must retain the types given by the function signature
+        if destination == self.entry {
+            return;
+        }
+        let params = self.dfg.block_params(destination);
+        for (param, arg) in params.iter().copied().zip(args.iter().copied()) {
+            if !self.dfg.value_type(param).is_term() {
+                continue;
+            }
+            let Some(ty) = self.edge_type(arg, guard) else { continue; };
+            let ty = match self.types.get(&param) {
+                Some(prev) => prev.union(&ty),
+                None => ty,
+            };
+            self.set(param, ty);
+        }
+    }
+
+    /// Returns the type of `arg` when it flows along an edge taken under the given guard
+    fn edge_type(&self, arg: Value, guard: Option<(Value, bool)>) -> Option<TermType> {
+        let ty = self.types.get(&arg).cloned()?;
+        let Some((cond, true)) = guard else { return Some(ty); };
+        let Some(inst) = self.definition(cond) else { return Some(ty); };
+        match &*self.dfg[inst] {
+            InstData::IsType(IsType {
+                arg: tested,
+                ty: Type::Term(test),
+            }) if *tested == arg => Some(narrow(ty, test)),
+            InstData::BinaryOpImm(BinaryOpImm {
+                op: Opcode::IsTaggedTuple,
+                arg: tested,
+                ..
+            }) if *tested == arg => Some(narrow(ty, &TermType::Tuple(None))),
+            // When branching on the error flag of a multi-value result, the other results hold the
+            // exception rather than a value of the type they are declared with
+            _ if self.definition(arg) == Some(inst) => Some(TermType::Any),
+            _ => Some(ty),
+        }
+    }
+
+    /// Computes the type of the first result of an instruction, given its current type `ty`
+    ///
+    /// Returns `None` if the operands this depends on have not been reached yet
+    fn transfer(&mut self, result: Value, data: &InstData, ty: TermType) -> Option<TermType> {
+        match data {
+            InstData::BinaryOp(BinaryOp {
+                op: Opcode::Add | Opcode::Sub | Opcode::Mul,
+                args,
+            }) => {
+                let lhs = self.types.get(&args[0])?;
+                let rhs = self.types.get(&args[1])?;
+                Some(arith(lhs, rhs))
+            }
+            InstData::BinaryOpImm(BinaryOpImm {
+                op: Opcode::Add | Opcode::Sub | Opcode::Mul,
+                arg,
+                imm,
+            }) => {
+                let lhs = self.types.get(arg)?;
+                let rhs = imm.ty().as_term().unwrap_or(TermType::Number);
+                Some(arith(lhs, &rhs))
+            }
+            InstData::UnaryOp(UnaryOp {
+                op: Opcode::Neg,
+                arg,
+            }) => Some(numeric(self.types.get(arg)?)),
+            InstData::UnaryOp(UnaryOp {
+                op: Opcode::Head,
+                arg,
+            }) => match self.types.get(arg)? {
+                TermType::List(Some(element)) => Some(element.as_ref().clone()),
+                _ => Some(ty),
+            },
+            InstData::UnaryOp(UnaryOp {
+                op: Opcode::Tail,
+                arg,
+            }) => match self.types.get(arg)? {
+                list @ TermType::List(Some(_)) => Some(list.clone()),
+                _ => Some(ty),
+            },
+            InstData::UnaryOpImm(UnaryOpImm {
+                op: Opcode::Tuple,
+                imm,
+            }) => {
+                let Some(arity) = imm.as_i64() else { return Some(ty); };
+                let stores = self.stores.get(&result);
+                let elements = (0..(arity as usize))
+                    .map(|i| {
+                        stores
+                            .and_then(|s| s.get(&i))
+                            .cloned()
+                            .unwrap_or(TermType::Any)
+                    })
+                    .collect();
+                Some(TermType::Tuple(Some(elements)))
+            }
+            InstData::BinaryOpImm(BinaryOpImm {
+                op: Opcode::GetElement,
+                arg,
+                imm,
+            }) => match (self.types.get(arg)?, imm.as_i64()) {
+                (TermType::Tuple(Some(elements)), Some(index))
+                    if (index as usize) < elements.len() =>
+                {
+                    Some(elements[index as usize].clone())
+                }
+                _ => Some(ty),
+            },
+            InstData::SetElement(SetElement {
+                op,
+                index,
+                args: [tuple, value],
+            }) => {
+                let value = self.types.get(value)?.clone();
+                self.set_element(*op, *tuple, index, value, ty)
+            }
+            InstData::SetElementImm(SetElementImm {
+                op,
+                arg,
+                index,
+                value,
+            }) => {
+                let value = value.ty().as_term().unwrap_or(TermType::Any);
+                self.set_element(*op, *arg, index, value, ty)
+            }
+            _ => Some(ty),
+        }
+    }
+
+    fn set_element(
+        &mut self,
+        op: Opcode,
+        tuple: Value,
+        index: &Immediate,
+        value: TermType,
+        ty: TermType,
+    ) -> Option<TermType> {
+        let index = index.as_i64().map(|i| i as usize);
+        if op == Opcode::SetElementMut {
+            // The tuple is updated in place, so the stored type applies to the tuple allocation itself
+            let root = self.root(tuple);
+            if let (Some(index), false) = (index, self.mutated.contains(&root)) {
+                let stores = self.stores.entry(root).or_default();
+                let stored = match stores.get(&index) {
+                    Some(prev) => prev.union(&value),
+                    None => value,
+                };
+                if stores.get(&index) != Some(&stored) {
+                    stores.insert(index, stored);
+                    self.changed = true;
+                }
+            }
+            return self.types.get(&tuple).cloned();
+        }
+
+        match (self.types.get(&tuple)?, index) {
+            (TermType::Tuple(Some(elements)), Some(index)) if index < elements.len() => {
+                let mut elements = elements.clone();
+                elements[index] = value;
+                Some(TermType::Tuple(Some(elements)))
+            }
+            _ => Some(ty),
+        }
+    }
+
+    /// Computes the result type of an arithmetic BIF, given its arguments
+    fn arith(&self, op: Symbol, args: &[Value]) -> Option<TermType> {
+        match (op, args) {
+            (symbols::Plus | symbols::Minus | symbols::Star, [lhs, rhs]) => {
+                let lhs = self.types.get(lhs)?;
+                let rhs = self.types.get(rhs)?;
+                Some(arith(lhs, rhs))
+            }
+            (symbols::Plus | symbols::Minus, [arg]) => Some(numeric(self.types.get(arg)?)),
+            _ => Some(TermType::Number),
+        }
+    }
+
+    fn set(&mut self, value: Value, ty: TermType) {
+        let ty = if self.mutated.contains(&value) {
+            erase_elements(ty)
+        } else {
+            limit_depth(ty, MAX_DEPTH)
+        };
+        if self.types.get(&value) != Some(&ty) {
+            self.types.insert(value, ty);
+            self.changed = true;
+        }
+    }
+
+    /// Returns the tuple allocation which `value` aliases through in-place updates
+    fn root(&self, mut value: Value) -> Value {
+        while self.opcode_of(value) == Some(Opcode::SetElementMut) {
+            let inst = self.definition(value).unwrap();
+            value = self.dfg.inst_args(inst)[0];
+        }
+        value
+    }
+
+    fn definition(&self, value: Value) -> Option<Inst> {
+        match self.dfg.values[value] {
+            ValueData::Inst { inst, .. } => Some(inst),
+            ValueData::Param { .. } => None,
+        }
+    }
+
+    fn opcode_of(&self, value: Value) -> Option<Opcode> {
+        self.definition(value).map(|inst| self.dfg[inst].opcode())
+    }
+}
+
+/// Updates the types of the values in `dfg` for which a more precise type was inferred
+fn apply(dfg: &mut DataFlowGraph, entry: Block, types: BTreeMap<Value, TermType>) {
+    for (value, ty) in types.into_iter() {
+        let Type::Term(current) = dfg.value_type(value) else { continue; };
+        let ty = match dfg.values[value] {
+            ValueData::Param { block, .. } if block == entry => continue,
+            // Block parameters are materialized during code generation, so we avoid committing
+            // them to a specific tuple layout
+            ValueData::Param { .. } => erase_elements(ty),
+            ValueData::Inst { inst, .. } => match dfg[inst].opcode() {
+                Opcode::Cast | Opcode::Trunc | Opcode::Zext => continue,
+                _ => ty,
+            },
+        };
+        if ty != current && ty.is_subtype_of(&current) {
+            dfg.set_value_type(value, Type::Term(ty));
+        }
+    }
+}
+
+/// Narrows `ty` with the knowledge that a value of that type passed a type test against `test`
+fn narrow(ty: TermType, test: &TermType) -> TermType {
+    let test = test.tested_type();
+    if test.is_subtype_of(&ty) {
+        test
+    } else {
+        ty
+    }
+}
+
+fn numeric(ty: &TermType) -> TermType {
+    if ty.is_numeric() {
+        ty.clone()
+    } else {
+        TermType::Number
+    }
+}
+
+/// Computes the result type of an arithmetic operation on values of type `lhs` and `rhs`
+fn arith(lhs: &TermType, rhs: &TermType) -> TermType {
+    match (numeric(lhs), numeric(rhs)) {
+        (TermType::Integer, TermType::Integer) => TermType::Integer,
+        // Mixed integer/float ops always produce floats
+        (TermType::Float, _) | (_, TermType::Float) => TermType::Float,
+        _ => TermType::Number,
+    }
+}
+
+fn erase_elements(ty: TermType) -> TermType {
+    limit_depth(ty, 0)
+}
+
+fn limit_depth(ty: TermType, depth: usize) -> TermType {
+    match ty {
+        TermType::Tuple(Some(_)) if depth == 0 => TermType::Tuple(None),
+        TermType::Tuple(Some(elements)) => TermType::Tuple(Some(
+            elements
+                .into_iter()
+                .map(|t| limit_depth(t, depth - 1))
+                .collect(),
+        )),
+        TermType::List(Some(_)) if depth == 0 => TermType::List(None),
+        TermType::List(Some(element)) => {
+            TermType::List(Some(Box::new(limit_depth(*element, depth - 1))))
+        }
+        ty => ty,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use firefly_diagnostics::*;
+    use firefly_parser::Parser;
+    use firefly_pass::Pass;
+
+    use crate::Module;
+
+    use super::InferTypes;
+
+    fn infer(input: &str) -> String {
+        let codemap = Arc::new(CodeMap::new());
+        let parser = Parser::new((), codemap);
+        let module = parser
+            .parse_string::<Module, _, _>(Reporter::new(), input)
+            .unwrap_or_else(|err| panic!("parsing failed: {:?}", err.to_diagnostic()));
+        let module = InferTypes::new().run(module).unwrap();
+        let mut buf = vec![];
+        crate::write::write_module(&mut buf, &module).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn block_params_take_union_of_incoming_types() {
+        let output = infer(
+            "
+module foo
+
+function bar(term) -> i1, term {
+block0(v0: term):
+    v1 = const.int 1  : int
+    v2 = const.int 2  : int
+    cond.br v0, block1(v1), block1(v2)
+
+block1(v3: term):
+    v4 = add v3, v3  : number
+    ret i1 false, v4
+}
+",
+        );
+        assert!(output.contains("block1(v1: int)"), "{}", output);
+        assert!(output.contains("v4 = add v1, v1  : int"), "{}", output);
+    }
+
+    #[test]
+    fn tuple_elements() {
+        let output = infer(
+            "
+module foo
+
+function bar(term) -> i1, term {
+block0(v0: term):
+    v1 = const.int 1  : int
+    v2 = tuple isize 2  : tuple
+    v3 = tuple.set.mut v2[0], v1  : tuple
+    v4 = tuple.set.mut v3[1], v0  : tuple
+    v5 = tuple.get v2, isize 0  : term
+    ret i1 false, v5
+}
+",
+        );
+        assert!(
+            output.contains("v2 = tuple isize 2  : tuple<int, term>"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("v5 = tuple.get v2, isize 0  : int"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn type_tests_narrow_guarded_edges() {
+        let output = infer(
+            "
+module foo
+
+function bar(term) -> i1, term {
+block0(v0: term):
+    v1 = is_type v0, int  : i1
+    cond.br v1, block1(v0), block2(v0)
+
+block1(v2: term):
+    ret i1 false, v2
+
+block2(v3: term):
+    ret i1 false, v3
+}
+",
+        );
+        assert!(output.contains("block1(v1: int)"), "{}", output);
+        assert!(output.contains("block2(v2: term)"), "{}", output);
+    }
+
+    #[test]
+    fn error_results_are_not_narrowed() {
+        let output = infer(
+            "
+module foo
+
+function bar(term) -> i1, term {
+block0(v0: term):
+    v1 = const.int 1  : int
+    v2, v3 = call erlang:'+'/2(v1, v1)  : i1, number
+    br.if v2, block1(v3)
+    ret i1 false, v3
+
+block1(v4: term):
+    ret i1 true, v4
+}
+",
+        );
+        assert!(output.contains(": i1, int"), "{}", output);
+        assert!(output.contains("block1(v1: term)"), "{}", output);
+    }
+}
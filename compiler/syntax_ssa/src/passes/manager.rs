@@ -0,0 +1,170 @@
+use firefly_pass::Pass;
+
+use crate::*;
+
+use super::*;
+
+/// The maximum number of times the pipeline is run over a function before we give up on reaching a fixpoint
+const MAX_ITERATIONS: usize = 8;
+
+/// A transformation applied to each function of a module by a [`PassManager`]
+pub trait FunctionPass {
+    /// Runs this pass over `function`, returning true if the function was modified
+    fn run_on_function(&mut self, function: &mut Function) -> bool;
+}
+
+/// This pass runs a pipeline of [`FunctionPass`] over each function in a module
+///
+/// The simplifications performed by each pass tend to expose opportunities for the others, e.g.
+/// folding a branch can make a block unreachable, which leaves its sibling with a single predecessor
+/// that it can then be merged with. As a result, the pipeline is repeated over each function until
+/// none of the passes make any further changes.
+#[derive(Default)]
+pub struct PassManager {
+    passes: Vec<Box<dyn FunctionPass>>,
+}
+impl PassManager {
+    /// Creates an empty pipeline, which leaves modules untouched
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates the standard optimization pipeline, as used when optimizations are enabled
+    pub fn default_pipeline() -> Self {
+        let mut pm = Self::new();
+        pm.add(FoldBranches);
+        pm.add(EliminateDeadCode);
+        pm.add(PropagateCopies);
+        pm.add(MergeBlocks);
+        pm
+    }
+
+    /// Appends `pass` to the pipeline
+    pub fn add<P: FunctionPass + 'static>(&mut self, pass: P) {
+        self.passes.push(Box::new(pass));
+    }
+}
+impl Pass for PassManager {
+    type Input<'a> = Module;
+    type Output<'a> = Module;
+
+    fn run<'a>(&mut self, mut module: Self::Input<'a>) -> anyhow::Result<Self::Output<'a>> {
+        if self.passes.is_empty() {
+            return Ok(module);
+        }
+        for function in module.functions.iter_mut() {
+            if entry_block(&function.dfg).is_none() {
+                continue;
+            }
+            for _ in 0..MAX_ITERATIONS {
+                let mut changed = false;
+                for pass in self.passes.iter_mut() {
+                    changed |= pass.run_on_function(function);
+                }
+                if !changed {
+                    break;
+                }
+            }
+        }
+        Ok(module)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use firefly_diagnostics::*;
+    use firefly_parser::Parser;
+    use firefly_pass::Pass;
+
+    use crate::Module;
+
+    use super::*;
+
+    fn optimize(input: &str) -> String {
+        let codemap = Arc::new(CodeMap::new());
+        let parser = Parser::new((), codemap);
+        let module = parser
+            .parse_string::<Module, _, _>(Reporter::new(), input)
+            .unwrap_or_else(|err| panic!("parsing failed: {:?}", err.to_diagnostic()));
+        let module = PassManager::default_pipeline().run(module).unwrap();
+        let mut buf = vec![];
+        crate::write::write_module(&mut buf, &module).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn constant_branches_are_folded() {
+        let output = optimize(
+            "
+module foo
+
+function bar(term) -> i1, term {
+block0(v0: term):
+    v1 = const.bool true  : bool
+    cond.br v1, block1, block2
+
+block1:
+    ret i1 false, v0
+
+block2:
+    v2 = const.atom 'error'  : atom
+    ret i1 false, v2
+}
+",
+        );
+        assert!(!output.contains("cond.br"), "{}", output);
+        assert!(!output.contains("block1"), "{}", output);
+        assert!(!output.contains("'error'"), "{}", output);
+        assert!(output.contains("ret i1 false, v0"), "{}", output);
+    }
+
+    #[test]
+    fn single_incoming_values_are_propagated() {
+        let output = optimize(
+            "
+module foo
+
+function bar(term, term) -> i1, term {
+block0(v0: term, v1: term):
+    cond.br v1, block1(v0), block2
+
+block1(v2: term):
+    ret i1 false, v2
+
+block2:
+    br block1(v0)
+}
+",
+        );
+        assert!(output.contains("block1:"), "{}", output);
+        assert!(output.contains("ret i1 false, v0"), "{}", output);
+    }
+
+    #[test]
+    fn blocks_are_merged_into_their_only_predecessor() {
+        let output = optimize(
+            "
+module foo
+
+function bar(term) -> i1, term {
+block0(v0: term):
+    v1 = const.int 1  : int
+    br block1(v1)
+
+block1(v2: term):
+    v3 = add v0, v2  : number
+    br block2(v3)
+
+block2(v4: number):
+    ret i1 false, v4
+}
+",
+        );
+        assert!(!output.contains("block1"), "{}", output);
+        assert!(!output.contains("block2"), "{}", output);
+        assert!(output.contains("= cast "), "{}", output);
+        assert!(output.contains("ret i1 false, "), "{}", output);
+    }
+}
@@ -0,0 +1,83 @@
+use std::collections::BTreeMap;
+
+use firefly_diagnostics::Spanned;
+
+use crate::*;
+
+use super::*;
+
+/// This pass merges blocks into their predecessor, when it is their only predecessor and ends in an unconditional branch to them
+///
+/// The arguments of the branch are substituted for the parameters of the merged block, with a cast
+/// inserted where the type of an argument differs from that of the parameter it is passed to.
+///
+/// A block is only merged into a predecessor which precedes it in the layout, as code generation
+/// depends on definitions being visited before their uses.
+#[derive(Default)]
+pub struct MergeBlocks;
+impl FunctionPass for MergeBlocks {
+    fn run_on_function(&mut self, function: &mut Function) -> bool {
+        let dfg = &mut function.dfg;
+        let Some(entry) = entry_block(dfg) else {
+            return false;
+        };
+        let blocks = dfg.blocks().map(|(b, _)| b).collect::<Vec<_>>();
+        let positions = blocks
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (*b, i))
+            .collect::<BTreeMap<_, _>>();
+        // Predecessors are tracked by branch instruction, so this remains valid as blocks are merged
+        let preds = predecessors(dfg);
+        let mut changed = false;
+        for block in blocks {
+            if !dfg.is_block_inserted(block) {
+                continue;
+            }
+            // Merging a successor may leave this block ending in a branch to another candidate
+            while let Some(last) = dfg.last_inst(block) {
+                let InstData::Br(Br {
+                    op: Opcode::Br,
+                    destination,
+                    args,
+                }) = &*dfg[last]
+                else {
+                    break;
+                };
+                let succ = *destination;
+                if succ == entry
+                    || succ == block
+                    || preds[&succ].len() != 1
+                    || positions[&succ] < positions[&block]
+                {
+                    break;
+                }
+                let args = args.as_slice(&dfg.value_lists).to_vec();
+                let params = dfg.block_params(succ).to_vec();
+                let span = dfg[last].span();
+                dfg.remove_inst(last);
+                for (param, arg) in params.into_iter().zip(args) {
+                    let ty = dfg.value_type(param);
+                    let value = if dfg.value_type(arg) == ty {
+                        arg
+                    } else {
+                        let cast = dfg.push_inst(
+                            block,
+                            InstData::UnaryOp(UnaryOp {
+                                op: Opcode::Cast,
+                                arg,
+                            }),
+                            span,
+                        );
+                        dfg.append_result(cast, ty)
+                    };
+                    dfg.replace_uses(param, value);
+                }
+                dfg.move_insts(succ, block);
+                dfg.remove_block(succ);
+                changed = true;
+            }
+        }
+        changed
+    }
+}
@@ -0,0 +1,66 @@
+mod dead_code;
+mod fold_branches;
+mod infer_types;
+mod manager;
+mod merge_blocks;
+mod propagate_copies;
+
+pub use self::dead_code::EliminateDeadCode;
+pub use self::fold_branches::FoldBranches;
+pub use self::infer_types::InferTypes;
+pub use self::manager::{FunctionPass, PassManager};
+pub use self::merge_blocks::MergeBlocks;
+pub use self::propagate_copies::PropagateCopies;
+
+use std::collections::BTreeMap;
+
+use crate::*;
+
+/// Returns the entry block of `dfg`, if it has any blocks
+fn entry_block(dfg: &DataFlowGraph) -> Option<Block> {
+    dfg.blocks().next().map(|(b, _)| b)
+}
+
+/// Returns the instruction which defines `value`, or `None` if it is a block parameter
+fn definition(dfg: &DataFlowGraph, value: Value) -> Option<Inst> {
+    match dfg.values[value] {
+        ValueData::Inst { inst, .. } => Some(inst),
+        ValueData::Param { .. } => None,
+    }
+}
+
+/// Returns the block in which `value` is defined
+fn defining_block(dfg: &DataFlowGraph, value: Value) -> Block {
+    match dfg.values[value] {
+        ValueData::Inst { inst, .. } => dfg.insts[inst].block,
+        ValueData::Param { block, .. } => block,
+    }
+}
+
+/// Returns the destination blocks of `inst`, once per edge, and the arguments passed along each edge
+fn successors(dfg: &DataFlowGraph, inst: Inst) -> Vec<(Block, &[Value])> {
+    match dfg.analyze_branch(inst) {
+        BranchInfo::NotABranch => vec![],
+        BranchInfo::SingleDest(block, args) => vec![(block, args)],
+        BranchInfo::MultiDest(targets) => targets
+            .into_iter()
+            .map(|jt| (jt.destination, jt.args))
+            .collect(),
+    }
+}
+
+/// Returns the branch instructions transferring control to each block of `dfg`, once per edge
+///
+/// Conditional branches may appear anywhere in a block, not just at the end, so every instruction is considered.
+fn predecessors(dfg: &DataFlowGraph) -> BTreeMap<Block, Vec<Inst>> {
+    let mut preds = BTreeMap::<Block, Vec<Inst>>::new();
+    for (block, _) in dfg.blocks() {
+        preds.entry(block).or_default();
+        for inst in dfg.block_insts(block) {
+            for (succ, _) in successors(dfg, inst) {
+                preds.entry(succ).or_default().push(inst);
+            }
+        }
+    }
+    preds
+}
@@ -0,0 +1,85 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::*;
+
+use super::*;
+
+/// This pass replaces block parameters which receive the same value along every incoming edge with that value
+///
+/// Such parameters are common in the output of lowering from Kernel, where the result of each
+/// branch of a case is passed to a common join block, even if the result is the same in all branches.
+/// Parameters passed back to their own block, e.g. the state of a loop which is not modified by the
+/// loop, are ignored when determining whether this is the case.
+///
+/// A parameter is only replaced when the value has the same type, and is defined in a block which
+/// precedes the parameter's block in the layout, as code generation depends on definitions being
+/// visited before their uses.
+#[derive(Default)]
+pub struct PropagateCopies;
+impl FunctionPass for PropagateCopies {
+    fn run_on_function(&mut self, function: &mut Function) -> bool {
+        let dfg = &mut function.dfg;
+        let Some(entry) = entry_block(dfg) else {
+            return false;
+        };
+        let positions = dfg
+            .blocks()
+            .enumerate()
+            .map(|(i, (b, _))| (b, i))
+            .collect::<BTreeMap<_, _>>();
+        let preds = predecessors(dfg);
+        let mut changed = false;
+        for (block, branches) in preds.iter() {
+            if *block == entry || branches.is_empty() {
+                continue;
+            }
+            let mut branches = branches.clone();
+            branches.dedup();
+            for index in (0..dfg.num_block_params(*block)).rev() {
+                let param = dfg.block_params(*block)[index];
+                let Some(value) = incoming_value(dfg, *block, &branches, index) else {
+                    continue;
+                };
+                if dfg.value_type(value) != dfg.value_type(param) {
+                    continue;
+                }
+                if positions[&defining_block(dfg, value)] >= positions[block] {
+                    continue;
+                }
+                dfg.replace_uses(param, value);
+                for branch in branches.iter().copied() {
+                    dfg.remove_branch_arg(branch, *block, index);
+                }
+                dfg.remove_block_param(*block, index);
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+/// Returns the value passed as the parameter at `index` of `block` along every edge from `branches`, if there is only one
+fn incoming_value(
+    dfg: &DataFlowGraph,
+    block: Block,
+    branches: &[Inst],
+    index: usize,
+) -> Option<Value> {
+    let param = dfg.block_params(block)[index];
+    let mut incoming = BTreeSet::new();
+    for branch in branches.iter().copied() {
+        for (_, args) in successors(dfg, branch)
+            .into_iter()
+            .filter(|(succ, _)| *succ == block)
+        {
+            if args[index] != param {
+                incoming.insert(args[index]);
+            }
+        }
+    }
+    if incoming.len() == 1 {
+        incoming.into_iter().next()
+    } else {
+        None
+    }
+}
@@ -1,17 +1,40 @@
 use std::fmt;
 use std::io::{self, Write};
 
-use super::{Block, DataFlowGraph, Function, Immediate, Inst, Value};
+use firefly_binary::{BinaryEntrySpecifier, Bitstring};
+use firefly_intern::Symbol;
+use firefly_syntax_base::{FunctionName, Visibility};
 
+use super::{
+    Block, ConstantItem, DataFlowGraph, Function, Immediate, ImmediateTerm, Inst, Module, Value,
+};
+
+/// Writes `module` in the textual form of the IR, which can be read back by [`crate::parser`]
+pub fn write_module(w: &mut dyn Write, module: &Module) -> io::Result<()> {
+    writeln!(w, "module {}", DisplayAtom(module.name.name))?;
+    for function in module.functions.iter() {
+        writeln!(w)?;
+        write_function(w, function)?;
+    }
+    Ok(())
+}
+
+/// Writes `func` in the textual form of the IR, which can be read back by [`crate::parser`]
 pub fn write_function(w: &mut dyn Write, func: &Function) -> io::Result<()> {
-    let is_public = func.signature.visibility.is_public();
-    if is_public {
+    let visibility = func.signature.visibility;
+    if visibility.is_public() {
         write!(w, "pub ")?;
     }
+    if visibility.is_nif() {
+        write!(w, "nif ")?;
+    }
+    if visibility.contains(Visibility::CLOSURE) {
+        write!(w, "closure ")?;
+    }
     write!(w, "function ")?;
     write_spec(w, func)?;
-    if func.signature.visibility.is_externally_defined() {
-        return Ok(());
+    if visibility.is_externally_defined() {
+        return writeln!(w);
     }
     writeln!(w, " {{")?;
     let mut any = false;
@@ -31,7 +54,7 @@ pub fn write_function(w: &mut dyn Write, func: &Function) -> io::Result<()> {
 }
 
 fn write_spec(w: &mut dyn Write, func: &Function) -> io::Result<()> {
-    write!(w, "{}(", &func.signature.name)?;
+    write!(w, "{}(", DisplayAtom(func.signature.name))?;
     let args = func
         .signature
         .params()
@@ -123,33 +146,42 @@ fn write_instruction(
 
 fn write_operands(w: &mut dyn Write, dfg: &DataFlowGraph, inst: Inst) -> io::Result<()> {
     use crate::ir::*;
-    use firefly_binary::BinaryEntrySpecifier;
 
     let pool = &dfg.value_lists;
     match dfg[inst].as_ref() {
         InstData::BinaryOp(BinaryOp { args, .. }) => write!(w, " {}, {}", args[0], args[1]),
-        InstData::BinaryOpImm(BinaryOpImm { arg, imm, .. }) => write!(w, " {}, {}", arg, imm),
+        InstData::BinaryOpImm(BinaryOpImm { arg, imm, .. }) => {
+            write!(w, " {}, {}", arg, DisplayImmediate(*imm))
+        }
         InstData::UnaryOp(UnaryOp { arg, .. }) => write!(w, " {}", arg),
-        InstData::UnaryOpImm(UnaryOpImm { imm, .. }) => write!(w, " {}", imm),
-        InstData::UnaryOpConst(UnaryOpConst { imm, .. }) => write!(w, " {}", dfg.constant(*imm)),
+        InstData::UnaryOpImm(UnaryOpImm { imm, .. }) => write!(w, " {}", DisplayImmediate(*imm)),
+        InstData::UnaryOpConst(UnaryOpConst { imm, .. }) => {
+            write!(w, " {}", DisplayConstant(&dfg.constant(*imm)))
+        }
         InstData::Ret(Ret { args, .. }) => write!(w, " {}", DisplayValues(args.as_slice())),
-        InstData::RetImm(RetImm { arg, imm, .. }) => write!(w, " {}, {}", imm, arg),
+        InstData::RetImm(RetImm { arg, imm, .. }) => {
+            write!(w, " {}, {}", DisplayImmediate(*imm), arg)
+        }
         InstData::Call(Call { args, .. }) => {
             let func_data = dfg.call_signature(inst).unwrap();
             write!(
                 w,
                 " {}({})",
-                &func_data.mfa(),
+                DisplayFunctionName(func_data.mfa()),
                 DisplayValues(args.as_slice(pool))
             )
         }
         InstData::CallIndirect(CallIndirect { callee, args, .. }) => {
-            write!(w, " {:?}({})", &callee, DisplayValues(args.as_slice(pool)))
+            write!(w, " {}({})", callee, DisplayValues(args.as_slice(pool)))
         }
         InstData::MakeFun(MakeFun { callee, env, .. }) => {
-            let sig = dfg.callee_signature(*callee);
-            let mfa = sig.mfa();
-            write!(w, " {}({})", &mfa, DisplayValues(env.as_slice(pool)))
+            let mfa = dfg.callee_signature(*callee).mfa();
+            write!(
+                w,
+                " {}({})",
+                DisplayFunctionName(mfa),
+                DisplayValues(env.as_slice(pool))
+            )
         }
         InstData::CondBr(CondBr {
             cond,
@@ -192,117 +224,33 @@ fn write_operands(w: &mut dyn Write, dfg: &DataFlowGraph, inst: Inst) -> io::Res
             write!(w, " {}", DisplayValues(args.as_slice(pool)))
         }
         InstData::PrimOpImm(PrimOpImm { imm, args, .. }) => {
-            write!(w, " {}, {}", imm, DisplayValues(args.as_slice(pool)))
+            let args = args.as_slice(pool);
+            if args.is_empty() {
+                write!(w, " {}", DisplayImmediate(*imm))
+            } else {
+                write!(w, " {}, {}", DisplayImmediate(*imm), DisplayValues(args))
+            }
         }
         InstData::IsType(IsType { ty, arg, .. }) => {
             write!(w, " {}, {}", arg, ty)
         }
         InstData::BitsMatch(BitsMatch { spec, args, .. }) => {
-            let values = DisplayValues(args.as_slice(pool));
-            match spec {
-                BinaryEntrySpecifier::Integer {
-                    endianness, signed, ..
-                } => {
-                    if *signed {
-                        write!(w, ".sint.{} {}", endianness, values)
-                    } else {
-                        write!(w, ".uint.{} {}", endianness, values)
-                    }
-                }
-                BinaryEntrySpecifier::Float {
-                    endianness, unit, ..
-                } => {
-                    write!(w, ".float.{}({}) {}", endianness, unit, values)
-                }
-                BinaryEntrySpecifier::Binary { unit: 8, .. } => {
-                    write!(w, ".bytes {}", values)
-                }
-                BinaryEntrySpecifier::Binary { unit, .. } => {
-                    write!(w, ".bits({}) {}", unit, values)
-                }
-                BinaryEntrySpecifier::Utf8 => {
-                    write!(w, ".utf8 {}", values)
-                }
-                BinaryEntrySpecifier::Utf16 { endianness, .. } => {
-                    write!(w, ".utf16.{} {}", endianness, values)
-                }
-                BinaryEntrySpecifier::Utf32 { endianness, .. } => {
-                    write!(w, ".utf32.{} {}", endianness, values)
-                }
-            }
+            write_bits_spec(w, spec)?;
+            write!(w, " {}", DisplayValues(args.as_slice(pool)))
         }
         InstData::BitsMatchSkip(BitsMatchSkip {
             spec, args, value, ..
         }) => {
-            let values = DisplayValuesWithImmediate(args.as_slice(pool), *value);
-            match spec {
-                BinaryEntrySpecifier::Integer {
-                    endianness, signed, ..
-                } => {
-                    if *signed {
-                        write!(w, ".sint.{} {}", endianness, values)
-                    } else {
-                        write!(w, ".uint.{} {}", endianness, values)
-                    }
-                }
-                BinaryEntrySpecifier::Float {
-                    endianness, unit, ..
-                } => {
-                    write!(w, ".float.{}({}) {}", endianness, unit, values)
-                }
-                BinaryEntrySpecifier::Binary { unit: 8, .. } => {
-                    write!(w, ".bytes {}", values)
-                }
-                BinaryEntrySpecifier::Binary { unit, .. } => {
-                    write!(w, ".bits({}) {}", unit, values)
-                }
-                BinaryEntrySpecifier::Utf8 => {
-                    write!(w, ".utf8 {}", values)
-                }
-                BinaryEntrySpecifier::Utf16 { endianness, .. } => {
-                    write!(w, ".utf16.{} {}", endianness, values)
-                }
-                BinaryEntrySpecifier::Utf32 { endianness, .. } => {
-                    write!(w, ".utf32.{} {}", endianness, values)
-                }
-            }
+            write_bits_spec(w, spec)?;
+            write!(
+                w,
+                " {}",
+                DisplayValuesWithImmediate(args.as_slice(pool), *value)
+            )
         }
         InstData::BitsPush(BitsPush { spec, args, .. }) => {
-            let values = DisplayValues(args.as_slice(pool));
-            match spec {
-                BinaryEntrySpecifier::Integer {
-                    endianness,
-                    signed,
-                    unit,
-                    ..
-                } => {
-                    if *signed {
-                        write!(w, ".sint.{}({}) {}", endianness, unit, values)
-                    } else {
-                        write!(w, ".uint.{}({}) {}", endianness, unit, values)
-                    }
-                }
-                BinaryEntrySpecifier::Float {
-                    endianness, unit, ..
-                } => {
-                    write!(w, ".float.{}({}) {}", endianness, unit, values)
-                }
-                BinaryEntrySpecifier::Binary { unit: 8, .. } => {
-                    write!(w, ".bytes {}", values)
-                }
-                BinaryEntrySpecifier::Binary { unit, .. } => {
-                    write!(w, ".bits({}) {}", unit, values)
-                }
-                BinaryEntrySpecifier::Utf8 => {
-                    write!(w, ".utf8 {}", values)
-                }
-                BinaryEntrySpecifier::Utf16 { endianness, .. } => {
-                    write!(w, ".utf16.{} {}", endianness, values)
-                }
-                BinaryEntrySpecifier::Utf32 { endianness, .. } => {
-                    write!(w, ".utf32.{} {}", endianness, values)
-                }
-            }
+            write_bits_spec(w, spec)?;
+            write!(w, " {}", DisplayValues(args.as_slice(pool)))
         }
         InstData::SetElement(SetElement { index, args, .. }) => {
             let argv = args.as_slice();
@@ -310,7 +258,31 @@ fn write_operands(w: &mut dyn Write, dfg: &DataFlowGraph, inst: Inst) -> io::Res
         }
         InstData::SetElementImm(SetElementImm {
             arg, index, value, ..
-        }) => write!(w, " {}[{}], {}", arg, index, value),
+        }) => write!(w, " {}[{}], {}", arg, index, DisplayImmediate(*value)),
+    }
+}
+
+/// The type specifier of a bitstring operation is written as a suffix of the opcode
+fn write_bits_spec(w: &mut dyn Write, spec: &BinaryEntrySpecifier) -> io::Result<()> {
+    match spec {
+        BinaryEntrySpecifier::Integer {
+            signed: true,
+            endianness,
+            unit,
+        } => write!(w, ".sint.{}({})", endianness, unit),
+        BinaryEntrySpecifier::Integer {
+            signed: false,
+            endianness,
+            unit,
+        } => write!(w, ".uint.{}({})", endianness, unit),
+        BinaryEntrySpecifier::Float { endianness, unit } => {
+            write!(w, ".float.{}({})", endianness, unit)
+        }
+        BinaryEntrySpecifier::Binary { unit: 8 } => write!(w, ".bytes"),
+        BinaryEntrySpecifier::Binary { unit } => write!(w, ".bits({})", unit),
+        BinaryEntrySpecifier::Utf8 => write!(w, ".utf8"),
+        BinaryEntrySpecifier::Utf16 { endianness } => write!(w, ".utf16.{}", endianness),
+        BinaryEntrySpecifier::Utf32 { endianness } => write!(w, ".utf32.{}", endianness),
     }
 }
 
@@ -347,9 +319,118 @@ impl<'a> fmt::Display for DisplayValuesWithImmediate<'a> {
             }
         }
         if self.0.is_empty() {
-            write!(f, "{}", &self.1)
+            write!(f, "{}", DisplayImmediate(self.1))
+        } else {
+            write!(f, ", {}", DisplayImmediate(self.1))
+        }
+    }
+}
+
+/// Returns true if `name` can be written without quotes
+fn is_bare_name(name: &str) -> bool {
+    if matches!(
+        name,
+        "module" | "function" | "pub" | "nif" | "closure" | "tail"
+    ) {
+        return false;
+    }
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => (),
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@' || c == '.')
+}
+
+/// Writes an atom, quoting it if necessary
+struct DisplayAtom(Symbol);
+impl fmt::Display for DisplayAtom {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = self.0.as_str().get();
+        if is_bare_name(name) {
+            f.write_str(name)
+        } else {
+            write!(f, "'{}'", name.escape_debug())
+        }
+    }
+}
+
+/// Writes an atom, always quoting it, as is done for atom terms
+struct DisplayQuotedAtom(Symbol);
+impl fmt::Display for DisplayQuotedAtom {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "'{}'", self.0.as_str().get().escape_debug())
+    }
+}
+
+struct DisplayFunctionName(FunctionName);
+impl fmt::Display for DisplayFunctionName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(module) = self.0.module {
+            write!(f, "{}:", DisplayAtom(module))?;
+        }
+        write!(f, "{}/{}", DisplayAtom(self.0.function), self.0.arity)
+    }
+}
+
+/// Floats are always written with a fractional part, so they can't be confused with integers
+struct DisplayFloat(f64);
+impl fmt::Display for DisplayFloat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = self.0.to_string();
+        if s.contains('.') || !self.0.is_finite() {
+            f.write_str(&s)
         } else {
-            write!(f, ", {}", &self.1)
+            write!(f, "{}.0", s)
+        }
+    }
+}
+
+/// Immediates of primitive type are prefixed with their type, e.g. `i1 true`, while
+/// term immediates are written as they would be in Erlang, e.g. `true`
+struct DisplayImmediate(Immediate);
+impl fmt::Display for DisplayImmediate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Immediate::Term(ImmediateTerm::Atom(a)) => write!(f, "{}", DisplayQuotedAtom(a)),
+            Immediate::Term(ImmediateTerm::Float(n)) => write!(f, "{}", DisplayFloat(n)),
+            Immediate::Term(t) => write!(f, "{}", t),
+            Immediate::I1(i) => write!(f, "i1 {}", i),
+            Immediate::I8(i) => write!(f, "i8 {}", i),
+            Immediate::I16(i) => write!(f, "i16 {}", i),
+            Immediate::I32(i) => write!(f, "i32 {}", i),
+            Immediate::I64(i) => write!(f, "i64 {}", i),
+            Immediate::Isize(i) => write!(f, "isize {}", i),
+            Immediate::F64(n) => write!(f, "f64 {}", DisplayFloat(n)),
+        }
+    }
+}
+
+struct DisplayConstant<'a>(&'a ConstantItem);
+impl<'a> fmt::Display for DisplayConstant<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            ConstantItem::Atom(a) => write!(f, "{}", DisplayQuotedAtom(*a)),
+            ConstantItem::Float(n) => write!(f, "{}", DisplayFloat(*n)),
+            // Bitstrings are written as their bytes, with the size of the last segment if it is
+            // a partial byte, as in Erlang, e.g. `<<255, 5:3>>`
+            ConstantItem::Bitstring(bits) => {
+                let trailing_bits = bits.bit_size() % 8;
+                let num_bytes = bits.byte_size();
+                f.write_str("<<")?;
+                for (i, byte) in bits.bytes().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    if trailing_bits > 0 && i == num_bytes - 1 {
+                        write!(f, "{}:{}", byte >> (8 - trailing_bits), trailing_bits)?;
+                    } else {
+                        write!(f, "{}", byte)?;
+                    }
+                }
+                f.write_str(">>")
+            }
+            other => write!(f, "{}", other),
         }
     }
 }
@@ -8,6 +8,7 @@ pub mod emit;
 pub mod error;
 pub mod ffi;
 pub mod fs;
+pub mod md5;
 pub mod mem;
 pub mod seq;
 pub mod threading;
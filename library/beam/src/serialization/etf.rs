@@ -34,6 +34,7 @@ use std::convert::From;
 
 use num::bigint::BigInt;
 
+pub use self::codec::AtomCache;
 pub use self::codec::{DecodeError, DecodeResult};
 pub use self::codec::{EncodeError, EncodeResult};
 
@@ -62,11 +63,27 @@ impl Term {
         codec::Decoder::new(reader).decode()
     }
 
+    /// Decodes a message received on a distribution connection.
+    ///
+    /// Returns the control message, and the message payload, if present.
+    pub fn decode_distribution<R: std::io::Read>(
+        reader: R,
+        cache: &mut AtomCache,
+    ) -> Result<(Term, Option<Term>), DecodeError> {
+        codec::Decoder::new(reader).decode_distribution(cache)
+    }
+
     /// Encodes the term.
     pub fn encode<W: std::io::Write>(&self, writer: W) -> EncodeResult {
         codec::Encoder::new(writer).encode(self)
     }
 
+    /// Encodes the term, compressing it with zlib, as done by `term_to_binary/2`
+    /// with the `compressed` option.
+    pub fn encode_compressed<W: std::io::Write>(&self, writer: W) -> EncodeResult {
+        codec::Encoder::new(writer).compressed().encode(self)
+    }
+
     pub fn as_match<'a, P>(&'a self, pattern: P) -> pattern::Result<P::Output>
     where
         P: pattern::Pattern<'a>,
@@ -363,10 +380,10 @@ pub struct Pid {
     pub node: Atom,
     pub id: u32,
     pub serial: u32,
-    pub creation: u8,
+    pub creation: u32,
 }
 impl Pid {
-    pub fn new<T>(node: T, id: u32, serial: u32, creation: u8) -> Self
+    pub fn new<T>(node: T, id: u32, serial: u32, creation: u32) -> Self
     where
         Atom: From<T>,
     {
@@ -399,16 +416,16 @@ impl<'a> From<(&'a str, u32, u32)> for Pid {
 #[derive(Debug, PartialEq, Clone)]
 pub struct Port {
     pub node: Atom,
-    pub id: u32,
-    pub creation: u8,
+    pub id: u64,
+    pub creation: u32,
 }
 impl std::fmt::Display for Port {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "#Port<{}.{}>", self.node, self.id)
     }
 }
-impl<'a> From<(&'a str, u32)> for Port {
-    fn from((node, id): (&'a str, u32)) -> Self {
+impl<'a> From<(&'a str, u64)> for Port {
+    fn from((node, id): (&'a str, u64)) -> Self {
         Port {
             node: Atom::from(node),
             id,
@@ -422,7 +439,7 @@ impl<'a> From<(&'a str, u32)> for Port {
 pub struct Reference {
     pub node: Atom,
     pub id: Vec<u32>,
-    pub creation: u8,
+    pub creation: u32,
 }
 impl std::fmt::Display for Reference {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -36,6 +36,12 @@ pub enum DecodeError {
         value: i32,
         range: std::ops::Range<i32>,
     },
+
+    #[fail(display = "reference to unknown atom cache entry: {}", index)]
+    UnknownAtomCacheRef { index: usize },
+
+    #[fail(display = "term is encoded in a node-local format, and cannot be decoded")]
+    LocalTerm,
 }
 impl std::convert::From<std::io::Error> for DecodeError {
     fn from(err: std::io::Error) -> DecodeError {
@@ -77,6 +83,9 @@ const NEW_FLOAT_EXT: u8 = 70;
 const BIT_BINARY_EXT: u8 = 77;
 const COMPRESSED_TERM: u8 = 80;
 const ATOM_CACHE_REF: u8 = 82;
+const NEW_PID_EXT: u8 = 88;
+const NEW_PORT_EXT: u8 = 89;
+const NEWER_REFERENCE_EXT: u8 = 90;
 const SMALL_INTEGER_EXT: u8 = 97;
 const INTEGER_EXT: u8 = 98;
 const FLOAT_EXT: u8 = 99;
@@ -100,18 +109,67 @@ const MAP_EXT: u8 = 116;
 const FUN_EXT: u8 = 117;
 const ATOM_UTF8_EXT: u8 = 118;
 const SMALL_ATOM_UTF8_EXT: u8 = 119;
+const V4_PORT_EXT: u8 = 120;
+const LOCAL_EXT: u8 = 121;
+
+/// Distribution header flag indicating that an atom cache reference is a new cache entry
+const NEW_CACHE_ENTRY_FLAG: u8 = 0x08;
+/// Distribution header flag indicating that atom lengths are encoded in 2 bytes
+const LONG_ATOMS_FLAG: u8 = 0x01;
+/// The number of segments in an atom cache
+const ATOM_CACHE_SEGMENTS: usize = 8;
+/// The number of entries in each atom cache segment
+const ATOM_CACHE_SEGMENT_SIZE: usize = 256;
+
+/// The atom cache of a distribution connection
+///
+/// Atoms sent over a connection are stored in a cache shared by both ends of it, after
+/// which the sender may refer to them by their location in the cache. The cache is updated
+/// with the new entries found in each distribution header received on the connection.
+pub struct AtomCache {
+    entries: Vec<Option<Atom>>,
+}
+impl AtomCache {
+    pub fn new() -> Self {
+        AtomCache {
+            entries: vec![None; ATOM_CACHE_SEGMENTS * ATOM_CACHE_SEGMENT_SIZE],
+        }
+    }
+    pub fn get(&self, index: usize) -> Option<&Atom> {
+        self.entries.get(index).and_then(|entry| entry.as_ref())
+    }
+    fn insert(&mut self, index: usize, atom: Atom) {
+        self.entries[index] = Some(atom);
+    }
+}
+impl Default for AtomCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 pub struct Decoder<R> {
     reader: R,
     buf: Vec<u8>,
+    /// The atoms referenced by the distribution header of the message being decoded,
+    /// indexed by `ATOM_CACHE_REF`
+    atom_refs: Vec<Atom>,
 }
 impl<R: std::io::Read> Decoder<R> {
     pub fn new(reader: R) -> Self {
         Decoder {
             reader,
             buf: Vec::new(),
+            atom_refs: Vec::new(),
         }
     }
+    /// Decodes a term
+    ///
+    /// If the input is a message received on a distribution connection, the control
+    /// message is returned, or if the message has a payload, a tuple of the control
+    /// message and the payload. Since no atom cache is available, any references to
+    /// atoms cached by previous messages cannot be resolved, use `decode_distribution`
+    /// when decoding messages from a connection.
     pub fn decode(mut self) -> DecodeResult {
         let version = self.reader.read_u8()?;
         if version != VERSION {
@@ -120,10 +178,85 @@ impl<R: std::io::Read> Decoder<R> {
         let tag = self.reader.read_u8()?;
         match tag {
             COMPRESSED_TERM => self.decode_compressed_term(),
-            DISTRIBUTION_HEADER => unimplemented!(),
+            DISTRIBUTION_HEADER => {
+                let mut cache = AtomCache::new();
+                match self.decode_distribution_message(&mut cache)? {
+                    (control, None) => Ok(control),
+                    (control, Some(payload)) => Ok(Term::from(Tuple::from(vec![control, payload]))),
+                }
+            }
             _ => self.decode_term_with_tag(tag),
         }
     }
+    /// Decodes a message received on a distribution connection, i.e. a distribution header,
+    /// followed by a control message, and an optional payload
+    ///
+    /// New atom cache entries in the header are added to `cache`.
+    pub fn decode_distribution(
+        mut self,
+        cache: &mut AtomCache,
+    ) -> Result<(Term, Option<Term>), DecodeError> {
+        let version = self.reader.read_u8()?;
+        if version != VERSION {
+            return Err(DecodeError::UnsupportedVersion { version });
+        }
+        match self.reader.read_u8()? {
+            DISTRIBUTION_HEADER => self.decode_distribution_message(cache),
+            tag => Err(DecodeError::UnknownTag { tag }),
+        }
+    }
+    fn decode_distribution_message(
+        &mut self,
+        cache: &mut AtomCache,
+    ) -> Result<(Term, Option<Term>), DecodeError> {
+        self.decode_distribution_header(cache)?;
+        let control = self.decode_term()?;
+        let payload = match self.reader.read_u8() {
+            Ok(tag) => Some(self.decode_term_with_tag(tag)?),
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => None,
+            Err(err) => return Err(err.into()),
+        };
+        Ok((control, payload))
+    }
+    fn decode_distribution_header(&mut self, cache: &mut AtomCache) -> Result<(), DecodeError> {
+        self.atom_refs.clear();
+        let count = self.reader.read_u8()? as usize;
+        if count == 0 {
+            // The flags and atom cache refs are omitted when there are no refs
+            return Ok(());
+        }
+        // Each ref has a 4-bit flag field, followed by a final 4-bit field containing
+        // flags which apply to the header as a whole
+        let mut flags = vec![0; count / 2 + 1];
+        self.reader.read_exact(&mut flags)?;
+        let flag = |i: usize| (flags[i / 2] >> ((i % 2) * 4)) & 0x0F;
+        let long_atoms = flag(count) & LONG_ATOMS_FLAG != 0;
+        for i in 0..count {
+            let segment = (flag(i) & 0x07) as usize;
+            let index = segment * ATOM_CACHE_SEGMENT_SIZE + self.reader.read_u8()? as usize;
+            if flag(i) & NEW_CACHE_ENTRY_FLAG != 0 {
+                let len = if long_atoms {
+                    self.reader.read_u16::<BigEndian>()? as usize
+                } else {
+                    self.reader.read_u8()? as usize
+                };
+                self.buf.resize(len, 0);
+                self.reader.read_exact(&mut self.buf)?;
+                let name = std::str::from_utf8(&self.buf)
+                    .or_else(|e| auxiliary::invalid_data_error(e.to_string()))?;
+                let atom = Atom::from(name);
+                cache.insert(index, atom.clone());
+                self.atom_refs.push(atom);
+            } else {
+                let atom = cache
+                    .get(index)
+                    .cloned()
+                    .ok_or(DecodeError::UnknownAtomCacheRef { index })?;
+                self.atom_refs.push(atom);
+            }
+        }
+        Ok(())
+    }
     fn decode_term(&mut self) -> DecodeResult {
         let tag = self.reader.read_u8()?;
         self.decode_term_with_tag(tag)
@@ -132,7 +265,7 @@ impl<R: std::io::Read> Decoder<R> {
         match tag {
             NEW_FLOAT_EXT => self.decode_new_float_ext(),
             BIT_BINARY_EXT => self.decode_bit_binary_ext(),
-            ATOM_CACHE_REF => unimplemented!(),
+            ATOM_CACHE_REF => self.decode_atom_cache_ref(),
             SMALL_INTEGER_EXT => self.decode_small_integer_ext(),
             INTEGER_EXT => self.decode_integer_ext(),
             FLOAT_EXT => self.decode_float_ext(),
@@ -140,6 +273,11 @@ impl<R: std::io::Read> Decoder<R> {
             REFERENCE_EXT => self.decode_reference_ext(),
             PORT_EXT => self.decode_port_ext(),
             PID_EXT => self.decode_pid_ext(),
+            NEW_PID_EXT => self.decode_new_pid_ext(),
+            NEW_PORT_EXT => self.decode_new_port_ext(),
+            V4_PORT_EXT => self.decode_v4_port_ext(),
+            NEWER_REFERENCE_EXT => self.decode_newer_reference_ext(),
+            LOCAL_EXT => Err(DecodeError::LocalTerm),
             SMALL_TUPLE_EXT => self.decode_small_tuple_ext(),
             LARGE_TUPLE_EXT => self.decode_large_tuple_ext(),
             NIL_EXT => self.decode_nil_ext(),
@@ -242,7 +380,16 @@ impl<R: std::io::Read> Decoder<R> {
             node,
             id: self.reader.read_u32::<BigEndian>()?,
             serial: self.reader.read_u32::<BigEndian>()?,
-            creation: self.reader.read_u8()?,
+            creation: self.reader.read_u8()? as u32,
+        }))
+    }
+    fn decode_new_pid_ext(&mut self) -> DecodeResult {
+        let node = self.decode_term().and_then(auxiliary::term_into_atom)?;
+        Ok(Term::from(Pid {
+            node,
+            id: self.reader.read_u32::<BigEndian>()?,
+            serial: self.reader.read_u32::<BigEndian>()?,
+            creation: self.reader.read_u32::<BigEndian>()?,
         }))
     }
     fn decode_port_ext(&mut self) -> DecodeResult {
@@ -254,8 +401,24 @@ impl<R: std::io::Read> Decoder<R> {
         })?;
         Ok(Term::from(Port {
             node,
-            id: self.reader.read_u32::<BigEndian>()?,
-            creation: self.reader.read_u8()?,
+            id: self.reader.read_u32::<BigEndian>()? as u64,
+            creation: self.reader.read_u8()? as u32,
+        }))
+    }
+    fn decode_new_port_ext(&mut self) -> DecodeResult {
+        let node = self.decode_term().and_then(auxiliary::term_into_atom)?;
+        Ok(Term::from(Port {
+            node,
+            id: self.reader.read_u32::<BigEndian>()? as u64,
+            creation: self.reader.read_u32::<BigEndian>()?,
+        }))
+    }
+    fn decode_v4_port_ext(&mut self) -> DecodeResult {
+        let node = self.decode_term().and_then(auxiliary::term_into_atom)?;
+        Ok(Term::from(Port {
+            node,
+            id: self.reader.read_u64::<BigEndian>()?,
+            creation: self.reader.read_u32::<BigEndian>()?,
         }))
     }
     fn decode_reference_ext(&mut self) -> DecodeResult {
@@ -263,13 +426,23 @@ impl<R: std::io::Read> Decoder<R> {
         Ok(Term::from(Reference {
             node,
             id: vec![self.reader.read_u32::<BigEndian>()?],
-            creation: self.reader.read_u8()?,
+            creation: self.reader.read_u8()? as u32,
         }))
     }
     fn decode_new_reference_ext(&mut self) -> DecodeResult {
         let id_count = self.reader.read_u16::<BigEndian>()? as usize;
         let node = self.decode_term().and_then(auxiliary::term_into_atom)?;
-        let creation = self.reader.read_u8()?;
+        let creation = self.reader.read_u8()? as u32;
+        let mut id = Vec::with_capacity(id_count);
+        for _ in 0..id_count {
+            id.push(self.reader.read_u32::<BigEndian>()?);
+        }
+        Ok(Term::from(Reference { node, id, creation }))
+    }
+    fn decode_newer_reference_ext(&mut self) -> DecodeResult {
+        let id_count = self.reader.read_u16::<BigEndian>()? as usize;
+        let node = self.decode_term().and_then(auxiliary::term_into_atom)?;
+        let creation = self.reader.read_u32::<BigEndian>()?;
         let mut id = Vec::with_capacity(id_count);
         for _ in 0..id_count {
             id.push(self.reader.read_u32::<BigEndian>()?);
@@ -401,6 +574,14 @@ impl<R: std::io::Read> Decoder<R> {
             .or_else(|e| auxiliary::invalid_data_error(e.to_string()))?;
         Ok(Term::from(Atom::from(name)))
     }
+    fn decode_atom_cache_ref(&mut self) -> DecodeResult {
+        let index = self.reader.read_u8()? as usize;
+        self.atom_refs
+            .get(index)
+            .cloned()
+            .map(Term::from)
+            .ok_or(DecodeError::UnknownAtomCacheRef { index })
+    }
     fn decode_small_atom_utf8_ext(&mut self) -> DecodeResult {
         let len = self.reader.read_u8()?;
         self.buf.resize(len as usize, 0);
@@ -413,14 +594,42 @@ impl<R: std::io::Read> Decoder<R> {
 
 pub struct Encoder<W> {
     writer: W,
+    compressed: bool,
 }
 impl<W: std::io::Write> Encoder<W> {
     pub fn new(writer: W) -> Self {
-        Encoder { writer }
+        Encoder {
+            writer,
+            compressed: false,
+        }
+    }
+    /// Compresses the encoded term with zlib
+    ///
+    /// As with `term_to_binary/2`, the term is written uncompressed if compression
+    /// would not make it any smaller.
+    pub fn compressed(mut self) -> Self {
+        self.compressed = true;
+        self
     }
     pub fn encode(mut self, term: &Term) -> EncodeResult {
         self.writer.write_u8(VERSION)?;
-        self.encode_term(term)
+        if !self.compressed {
+            return self.encode_term(term);
+        }
+
+        let mut buf = Vec::new();
+        Encoder::new(&mut buf).encode_term(term)?;
+        let mut zlib_encoder = zlib::Encoder::new(Vec::new())?;
+        zlib_encoder.write_all(&buf)?;
+        let compressed = zlib_encoder.finish().into_result()?;
+        if compressed.len() + 5 < buf.len() {
+            self.writer.write_u8(COMPRESSED_TERM)?;
+            self.writer.write_u32::<BigEndian>(buf.len() as u32)?;
+            self.writer.write_all(&compressed)?;
+        } else {
+            self.writer.write_all(&buf)?;
+        }
+        Ok(())
     }
     fn encode_term(&mut self, term: &Term) -> EncodeResult {
         match *term {
@@ -568,29 +777,37 @@ impl<W: std::io::Write> Encoder<W> {
         self.writer.write_all(&bytes)?;
         Ok(())
     }
+    // Like OTP 23+, the encodings with 32-bit creations are always used for pids,
+    // ports and references
     fn encode_pid(&mut self, x: &Pid) -> EncodeResult {
-        self.writer.write_u8(PID_EXT)?;
+        self.writer.write_u8(NEW_PID_EXT)?;
         self.encode_atom(&x.node)?;
         self.writer.write_u32::<BigEndian>(x.id)?;
         self.writer.write_u32::<BigEndian>(x.serial)?;
-        self.writer.write_u8(x.creation)?;
+        self.writer.write_u32::<BigEndian>(x.creation)?;
         Ok(())
     }
     fn encode_port(&mut self, x: &Port) -> EncodeResult {
-        self.writer.write_u8(PORT_EXT)?;
-        self.encode_atom(&x.node)?;
-        self.writer.write_u32::<BigEndian>(x.id)?;
-        self.writer.write_u8(x.creation)?;
+        if x.id > std::u32::MAX as u64 {
+            self.writer.write_u8(V4_PORT_EXT)?;
+            self.encode_atom(&x.node)?;
+            self.writer.write_u64::<BigEndian>(x.id)?;
+        } else {
+            self.writer.write_u8(NEW_PORT_EXT)?;
+            self.encode_atom(&x.node)?;
+            self.writer.write_u32::<BigEndian>(x.id as u32)?;
+        }
+        self.writer.write_u32::<BigEndian>(x.creation)?;
         Ok(())
     }
     fn encode_reference(&mut self, x: &Reference) -> EncodeResult {
-        self.writer.write_u8(NEW_REFERENCE_EXT)?;
+        self.writer.write_u8(NEWER_REFERENCE_EXT)?;
         if x.id.len() > std::u16::MAX as usize {
             return Err(EncodeError::TooLargeReferenceId(x.clone()));
         }
         self.writer.write_u16::<BigEndian>(x.id.len() as u16)?;
         self.encode_atom(&x.node)?;
-        self.writer.write_u8(x.creation)?;
+        self.writer.write_u32::<BigEndian>(x.creation)?;
         for n in &x.id {
             self.writer.write_u32::<BigEndian>(*n)?;
         }
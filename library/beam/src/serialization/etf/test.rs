@@ -145,12 +145,20 @@ fn pid_test() {
         ])
         .try_into()
     ); // PID_EXT
+    assert_eq!(
+        Ok(Pid::new("a@localhost", 85, 0, 1679412052)),
+        decode(&[
+            131, 88, 100, 0, 11, 97, 64, 108, 111, 99, 97, 108, 104, 111, 115, 116, 0, 0, 0, 85, 0,
+            0, 0, 0, 100, 25, 203, 84
+        ])
+        .try_into()
+    ); // NEW_PID_EXT
 
     // Encode
     assert_eq!(
         vec![
-            131, 103, 100, 0, 13, 110, 111, 110, 111, 100, 101, 64, 110, 111, 104, 111, 115, 116,
-            0, 0, 0, 49, 0, 0, 0, 0, 0
+            131, 88, 100, 0, 13, 110, 111, 110, 111, 100, 101, 64, 110, 111, 104, 111, 115, 116, 0,
+            0, 0, 49, 0, 0, 0, 0, 0, 0, 0, 0
         ],
         encode(Term::from(Pid::from(("nonode@nohost", 49, 0))))
     );
@@ -173,15 +181,38 @@ fn port_test() {
         ])
         .try_into()
     ); // PORT_EXT
+    assert_eq!(
+        Ok(Port::from(("nonode@nohost", 366))),
+        decode(&[
+            131, 89, 100, 0, 13, 110, 111, 110, 111, 100, 101, 64, 110, 111, 104, 111, 115, 116, 0,
+            0, 1, 110, 0, 0, 0, 0
+        ])
+        .try_into()
+    ); // NEW_PORT_EXT
+    assert_eq!(
+        Ok(Port::from(("nonode@nohost", 1 << 40))),
+        decode(&[
+            131, 120, 100, 0, 13, 110, 111, 110, 111, 100, 101, 64, 110, 111, 104, 111, 115, 116,
+            0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0
+        ])
+        .try_into()
+    ); // V4_PORT_EXT
 
     // Encode
     assert_eq!(
         vec![
-            131, 102, 100, 0, 13, 110, 111, 110, 111, 100, 101, 64, 110, 111, 104, 111, 115, 116,
-            0, 0, 1, 110, 0
+            131, 89, 100, 0, 13, 110, 111, 110, 111, 100, 101, 64, 110, 111, 104, 111, 115, 116, 0,
+            0, 1, 110, 0, 0, 0, 0
         ],
         encode(Term::from(Port::from(("nonode@nohost", 366))))
     );
+    assert_eq!(
+        vec![
+            131, 120, 100, 0, 13, 110, 111, 110, 111, 100, 101, 64, 110, 111, 104, 111, 115, 116,
+            0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0
+        ],
+        encode(Term::from(Port::from(("nonode@nohost", 1 << 40))))
+    );
 }
 
 #[test]
@@ -206,10 +237,21 @@ fn reference_test() {
         // NEW_REFERENCE_EXT
         decode(&[131, 101, 115, 3, 102, 111, 111, 0, 0, 0, 2, 0]).try_into()
     );
+    assert_eq!(
+        Ok(Reference::from((
+            "nonode@nohost",
+            vec![157446, 2399666180, 3797393418]
+        ))),
+        decode(&[
+            131, 90, 0, 3, 100, 0, 13, 110, 111, 110, 111, 100, 101, 64, 110, 111, 104, 111, 115,
+            116, 0, 0, 0, 0, 0, 2, 103, 6, 143, 8, 0, 4, 226, 87, 160, 10
+        ])
+        .try_into()
+    ); // NEWER_REFERENCE_EXT
 
     // Encode
     assert_eq!(
-        vec![131, 114, 0, 1, 100, 0, 3, 102, 111, 111, 0, 0, 0, 0, 123],
+        vec![131, 90, 0, 1, 100, 0, 3, 102, 111, 111, 0, 0, 0, 0, 0, 0, 0, 123],
         encode(Term::from(Reference::from(("foo", 123))))
     );
 }
@@ -255,11 +297,19 @@ fn internal_fun_test() {
         110, 111, 110, 111, 100, 101, 64, 110, 111, 104, 111, 115, 116, 0, 0, 0, 36, 0, 0, 0, 0, 0,
         97, 10,
     ];
+    // The same fun, as encoded by OTP 23+, in which the pid is a NEW_PID_EXT
+    let new_bytes = [
+        131, 112, 0, 0, 0, 71, 1, 115, 60, 203, 97, 151, 228, 98, 75, 71, 169, 49, 166, 34, 126,
+        65, 11, 0, 0, 0, 0, 0, 0, 0, 1, 100, 0, 1, 97, 97, 0, 98, 3, 153, 230, 91, 88, 100, 0, 13,
+        110, 111, 110, 111, 100, 101, 64, 110, 111, 104, 111, 115, 116, 0, 0, 0, 36, 0, 0, 0, 0, 0,
+        0, 0, 0, 97, 10,
+    ];
     // Decode
     assert_eq!(Ok(term.clone()), decode(&bytes).try_into());
+    assert_eq!(Ok(term.clone()), decode(&new_bytes).try_into());
 
     // Encode
-    assert_eq!(Vec::from(&bytes[..]), encode(Term::from(term)));
+    assert_eq!(Vec::from(&new_bytes[..]), encode(Term::from(term)));
 }
 
 #[test]
@@ -471,6 +521,184 @@ fn compressed_term_test() {
     );
 }
 
+#[test]
+fn compressed_term_encode_test() {
+    let list = Term::from(List::from(
+        (1..257)
+            .map(|i| Term::from(FixInteger::from(i)))
+            .collect::<Vec<_>>(),
+    ));
+    let mut buf = Vec::new();
+    list.encode_compressed(&mut buf).unwrap();
+    assert_eq!(&[131, 80, 0, 0, 2, 9], &buf[..6]); // COMPRESSED_TERM
+    assert_eq!(list, decode(&buf));
+
+    // Terms which do not benefit from compression are written uncompressed
+    let atom = Term::from(Atom::from("foo"));
+    let mut buf = Vec::new();
+    atom.encode_compressed(&mut buf).unwrap();
+    assert_eq!(encode(atom), buf);
+}
+
+#[test]
+fn distribution_header_test() {
+    // A header with new cache entries for 'hello' and 'world', followed by
+    // the control message {'hello', 2} and the payload 'world'
+    let bytes = [
+        131, 68, 2, 0x98, 0x00, 5, 5, 104, 101, 108, 108, 111, 3, 5, 119, 111, 114, 108, 100, 104,
+        2, 82, 0, 97, 2, 82, 1,
+    ];
+    let control = Term::from(Tuple::from(vec![
+        Term::from(Atom::from("hello")),
+        Term::from(FixInteger::from(2)),
+    ]));
+    let payload = Term::from(Atom::from("world"));
+
+    let mut cache = AtomCache::new();
+    let (ctrl, msg) = Term::decode_distribution(Cursor::new(&bytes), &mut cache).unwrap();
+    assert_eq!(control, ctrl);
+    assert_eq!(Some(payload.clone()), msg);
+    assert_eq!(Some(&Atom::from("world")), cache.get(256 + 3));
+
+    // A header referencing the existing cache entry for 'world', with no payload
+    let bytes = [131, 68, 1, 0x01, 3, 82, 0];
+    let (ctrl, msg) = Term::decode_distribution(Cursor::new(&bytes), &mut cache).unwrap();
+    assert_eq!(payload, ctrl);
+    assert_eq!(None, msg);
+
+    // Without the atom cache of the connection, the reference cannot be resolved
+    assert!(matches!(
+        Term::decode(Cursor::new(&bytes)),
+        Err(DecodeError::UnknownAtomCacheRef { index: 259 })
+    ));
+}
+
+#[test]
+fn local_ext_test() {
+    assert!(matches!(
+        Term::decode(Cursor::new(&[131, 121, 0, 0, 0, 0, 0, 0, 0, 0, 97, 1])),
+        Err(DecodeError::LocalTerm)
+    ));
+}
+
+mod roundtrip {
+    use std::io::Cursor;
+
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    use crate::serialization::etf::*;
+
+    fn atom() -> impl Strategy<Value = Atom> {
+        prop_oneof!["[a-z][a-zA-Z0-9_@]{0,16}", "\\PC{1,8}"].prop_map(Atom::from)
+    }
+
+    fn pid() -> impl Strategy<Value = Pid> {
+        (atom(), any::<u32>(), any::<u32>(), any::<u32>()).prop_map(
+            |(node, id, serial, creation)| Pid {
+                node,
+                id,
+                serial,
+                creation,
+            },
+        )
+    }
+
+    fn leaf() -> impl Strategy<Value = Term> {
+        prop_oneof![
+            atom().prop_map(Term::from),
+            any::<i32>().prop_map(|i| Term::from(FixInteger::from(i))),
+            any::<i64>().prop_map(|i| Term::from(BigInteger::from(i))),
+            any::<f64>()
+                .prop_filter("NaN is not equal to itself", |f| !f.is_nan())
+                .prop_map(|f| Term::from(Float::from(f))),
+            pid().prop_map(Term::from),
+            (atom(), any::<u64>(), any::<u32>())
+                .prop_map(|(node, id, creation)| Term::from(Port { node, id, creation })),
+            (atom(), vec(any::<u32>(), 1..=5), any::<u32>())
+                .prop_map(|(node, id, creation)| Term::from(Reference { node, id, creation })),
+            (atom(), atom(), any::<u8>()).prop_map(|(module, function, arity)| {
+                Term::from(ExternalFun {
+                    module,
+                    function,
+                    arity,
+                })
+            }),
+            vec(any::<u8>(), 0..64).prop_map(|bytes| Term::from(Binary::from(bytes))),
+            (vec(any::<u8>(), 1..64), 1..=8u8).prop_map(|(mut bytes, tail_bits_size)| {
+                // Only the trailing bits of the last byte are significant
+                let last = bytes.len() - 1;
+                bytes[last] &= ((1u16 << tail_bits_size) - 1) as u8;
+                Term::from(BitBinary::from((bytes, tail_bits_size)))
+            }),
+        ]
+    }
+
+    fn term() -> impl Strategy<Value = Term> {
+        leaf().prop_recursive(4, 64, 8, |inner| {
+            prop_oneof![
+                vec(inner.clone(), 0..8).prop_map(|elements| Term::from(List::from(elements))),
+                (vec(inner.clone(), 1..8), leaf()).prop_map(|(elements, last)| {
+                    Term::from(ImproperList::from((elements, last)))
+                }),
+                vec(inner.clone(), 0..8).prop_map(|elements| Term::from(Tuple::from(elements))),
+                vec((inner.clone(), inner), 0..8)
+                    .prop_map(|entries| Term::from(Map::from(entries))),
+            ]
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn encode_decode_roundtrip(term in term()) {
+            let mut buf = Vec::new();
+            term.encode(&mut buf).unwrap();
+            prop_assert_eq!(&term, &Term::decode(Cursor::new(&buf)).unwrap());
+        }
+
+        #[test]
+        fn compressed_encode_decode_roundtrip(term in term()) {
+            let mut buf = Vec::new();
+            term.encode_compressed(&mut buf).unwrap();
+            prop_assert_eq!(&term, &Term::decode(Cursor::new(&buf)).unwrap());
+        }
+
+        #[test]
+        fn decode_encode_roundtrip(fixture in prop::sample::select(FIXTURES)) {
+            let term = Term::decode(Cursor::new(fixture)).unwrap();
+            let mut buf = Vec::new();
+            term.encode(&mut buf).unwrap();
+            prop_assert_eq!(fixture, buf.as_slice());
+        }
+    }
+
+    /// The output of `term_to_binary/1` on OTP 25, for terms containing pids, ports and references
+    const FIXTURES: &[&[u8]] = &[
+        // self() on nonode@nohost
+        &[
+            131, 88, 100, 0, 13, 110, 111, 110, 111, 100, 101, 64, 110, 111, 104, 111, 115, 116, 0,
+            0, 0, 85, 0, 0, 0, 0, 0, 0, 0, 0,
+        ],
+        // make_ref() on nonode@nohost
+        &[
+            131, 90, 0, 3, 100, 0, 13, 110, 111, 110, 111, 100, 101, 64, 110, 111, 104, 111, 115,
+            116, 0, 0, 0, 0, 0, 2, 103, 6, 143, 8, 0, 4, 226, 87, 160, 10,
+        ],
+        // A port on a@localhost
+        &[
+            131, 89, 100, 0, 11, 97, 64, 108, 111, 99, 97, 108, 104, 111, 115, 116, 0, 0, 0, 6,
+            100, 25, 203, 84,
+        ],
+        // {self(), make_ref()} on a@localhost
+        &[
+            131, 104, 2, 88, 100, 0, 11, 97, 64, 108, 111, 99, 97, 108, 104, 111, 115, 116, 0, 0,
+            0, 85, 0, 0, 0, 0, 100, 25, 203, 84, 90, 0, 3, 100, 0, 11, 97, 64, 108, 111, 99, 97,
+            108, 104, 111, 115, 116, 100, 25, 203, 84, 0, 2, 103, 6, 143, 8, 0, 4, 226, 87, 160,
+            10,
+        ],
+    ];
+}
+
 fn encode(term: Term) -> Vec<u8> {
     let mut buf = Vec::new();
     term.encode(&mut buf).unwrap();
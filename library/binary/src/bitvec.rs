@@ -135,11 +135,6 @@ impl<A: Allocator> Bitstring for BitVec<A> {
         (self.pos * 8) + self.bit_offset as usize
     }
 
-    #[inline]
-    fn bit_offset(&self) -> u8 {
-        self.bit_offset
-    }
-
     #[inline]
     unsafe fn as_bytes_unchecked(&self) -> &[u8] {
         &self.data[..self.byte_size()]
@@ -858,6 +853,31 @@ mod test {
         assert_eq!(vec.get(2), None);
     }
 
+    #[test]
+    fn bitvec_partial_byte_is_aligned() {
+        let mut vec = BitVec::new();
+
+        vec.push_byte(0b11011011);
+        vec.push_bit(true);
+        vec.push_bit(false);
+        vec.push_bit(true);
+        assert_eq!(vec.byte_size(), 2);
+        assert_eq!(vec.bit_size(), 11);
+        assert_eq!(vec.bit_offset, 3);
+
+        // The bits written to the last byte are trailing bits, the data always
+        // starts at the first bit of the buffer
+        assert_eq!(vec.bit_offset(), 0);
+        assert!(vec.is_aligned());
+        assert!(!vec.is_binary());
+        assert_eq!(vec.trailing_bits(), 3);
+
+        let selection = vec.select_bits(11).unwrap();
+        assert!(selection.is_aligned());
+        assert_eq!(selection.bit_size(), 11);
+        assert_eq!(selection.trailing_bits(), 3);
+    }
+
     #[test]
     fn bitvec_push_bit() {
         let mut vec = BitVec::new();
@@ -2,6 +2,7 @@ mod dynamic;
 
 pub use self::dynamic::DynamicCallee;
 
+use alloc::vec::Vec;
 use core::alloc::Layout;
 use core::mem;
 use core::slice;
@@ -14,7 +15,7 @@ use firefly_system::sync::RwLock;
 
 use crate::term::{Atom, OpaqueTerm};
 
-use super::{ErlangResult, FunctionSymbol, ModuleFunctionArity};
+use super::{ErlangResult, FunctionSymbol, ModuleFunctionArity, ON_LOAD_SYMBOL};
 
 lazy_static! {
     /// The symbol table used by the runtime system
@@ -59,6 +60,26 @@ pub fn module_loaded(module: Atom) -> bool {
     SYMBOLS.read().contains_module(module)
 }
 
+/// Returns the `-on_load` functions of all modules compiled into the executable, in the order they must be run
+///
+/// Modules with a lower priority are run first, modules with the same priority are ordered by name.
+pub fn on_load_functions() -> Vec<(Atom, DynamicCallee)> {
+    let mut on_load = SYMBOLS.read().on_load.clone();
+    on_load.sort_by(|a, b| {
+        a.priority
+            .cmp(&b.priority)
+            .then_with(|| a.module.as_str().cmp(b.module.as_str()))
+    });
+    on_load
+        .into_iter()
+        .map(|symbol| {
+            (symbol.module, unsafe {
+                mem::transmute::<*const (), DynamicCallee>(symbol.ptr)
+            })
+        })
+        .collect()
+}
+
 /// Performs one-time initialization of the atom table at program start, using the
 /// array of constant atom values present in the compiled program.
 ///
@@ -87,6 +108,12 @@ pub unsafe extern "C-unwind" fn init(
 
     let mut table = SYMBOLS.write();
     for symbol in data.iter().copied() {
+        // On-load functions are not callable by name, and may also be registered under their own name
+        if symbol.function.as_str() == ON_LOAD_SYMBOL {
+            table.on_load.push(symbol);
+            continue;
+        }
+
         let module = symbol.module;
         let function = symbol.function;
         let arity = symbol.arity;
@@ -114,6 +141,7 @@ struct SymbolTable {
     functions: HashMap<&'static ModuleFunctionArity, *const ()>,
     idents: HashMap<*const (), &'static ModuleFunctionArity>,
     modules: HashSet<Atom>,
+    on_load: Vec<FunctionSymbol>,
     arena: DroplessArena,
 }
 impl SymbolTable {
@@ -122,6 +150,7 @@ impl SymbolTable {
             functions: HashMap::with_capacity(size),
             idents: HashMap::with_capacity(size),
             modules: HashSet::new(),
+            on_load: Vec::new(),
             arena: DroplessArena::default(),
         }
     }
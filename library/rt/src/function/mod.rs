@@ -198,7 +198,7 @@ fn unwrap_failed(msg: &str, error: &dyn fmt::Debug) -> ! {
 /// This struct represents the serialized form of a symbol table entry
 ///
 /// This struct is intentionally laid out in memory to be identical to
-/// `ModuleFunctionArity` with an extra field (the function pointer).
+/// `ModuleFunctionArity` with extra fields (the on-load priority and function pointer).
 /// This allows the symbol table to use ModuleFunctionArity without
 /// requiring
 ///
@@ -212,6 +212,9 @@ pub struct FunctionSymbol {
     pub function: Atom,
     /// The arity of the function
     pub arity: u8,
+    /// The order in which the `-on_load` function of the module is run relative to those of other
+    /// modules, this is only meaningful for entries named `ON_LOAD_SYMBOL`, and is zero otherwise
+    pub priority: u8,
     /// An opaque pointer to the function
     ///
     /// To call the function, it is necessary to transmute this
@@ -223,6 +226,13 @@ pub struct FunctionSymbol {
     pub ptr: *const (),
 }
 
+/// The reserved function name under which the `-on_load` function of a module is recorded in the symbol table
+///
+/// The compiler emits an additional `FunctionSymbol` for each module with an `-on_load` function, using this
+/// name. The `priority` field of that symbol determines the order in which on-load functions are run, see
+/// `on_load_functions`.
+pub const ON_LOAD_SYMBOL: &str = "$on_load";
+
 /// Function symbols are read-only and pinned, and therefore Sync
 unsafe impl Sync for FunctionSymbol {}
 
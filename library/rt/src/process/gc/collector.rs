@@ -0,0 +1,239 @@
+use alloc::alloc::AllocError;
+use alloc::vec::Vec;
+use core::ops::Range;
+use core::ptr::NonNull;
+
+use firefly_alloc::gc::GcBox;
+use firefly_alloc::heap::Heap;
+use firefly_binary::Bitstring;
+use hashbrown::HashMap;
+
+use crate::term::{BitSlice, Closure, Cons, Map, OpaqueTerm, Term, Tuple};
+
+use super::super::ProcessHeap;
+
+/// A copying collector which evacuates the live data reachable from a set of roots
+/// out of the regions being collected, and into fresh heaps.
+///
+/// Process heaps cannot be traversed linearly, as they may hold arbitrary Rust types
+/// with alignment requirements we can't know a priori, so rather than a Cheney scan over
+/// the target heap, objects are traced from their roots, and moved objects are recorded
+/// in a forwarding table so that sharing is preserved.
+///
+/// In a minor collection, objects below the high-water mark of the young heap have survived
+/// a previous collection, and are promoted to the mature heap along with everything reachable
+/// from them, this preserves the invariant that the mature heap never references the young heap.
+/// Objects in the mature heap itself are left in place. In a major collection, there is no
+/// mature heap, and all live objects are copied to the new young heap.
+pub(super) struct Collector<'a> {
+    /// The heap receiving objects which have not been promoted
+    young: &'a ProcessHeap,
+    /// The heap receiving promoted objects, only used in minor collections
+    mature: Option<&'a ProcessHeap>,
+    /// The regions of memory being collected
+    regions: Vec<Range<*const u8>>,
+    /// The region of the young heap containing objects which should be promoted
+    tenured: Range<*const u8>,
+    /// Maps the address of each object which has been moved to its new location
+    forwarded: HashMap<*const (), OpaqueTerm>,
+}
+impl<'a> Collector<'a> {
+    pub fn new(
+        young: &'a ProcessHeap,
+        mature: Option<&'a ProcessHeap>,
+        regions: Vec<Range<*const u8>>,
+        tenured: Range<*const u8>,
+    ) -> Self {
+        Self {
+            young,
+            mature,
+            regions,
+            tenured,
+            forwarded: HashMap::new(),
+        }
+    }
+
+    /// Moves the object referenced by `root`, if it lives in a region being collected,
+    /// and updates `root` to point to its new location
+    pub fn collect_root(&mut self, root: &mut OpaqueTerm) -> Result<(), AllocError> {
+        *root = self.evacuate(*root, false)?;
+        Ok(())
+    }
+
+    #[inline]
+    fn is_collected(&self, ptr: *const ()) -> bool {
+        let ptr = ptr.cast::<u8>();
+        self.regions.iter().any(|region| region.contains(&ptr))
+    }
+
+    #[inline]
+    fn is_tenured(&self, ptr: *const ()) -> bool {
+        self.mature.is_some() && self.tenured.contains(&ptr.cast::<u8>())
+    }
+
+    /// Returns true if `term` has already been moved to a generation suitable for
+    /// an object which is (or is not) being promoted
+    fn forwarded(&self, ptr: *const (), promote: bool) -> Option<OpaqueTerm> {
+        let term = *self.forwarded.get(&ptr)?;
+        match self.mature {
+            Some(mature) if promote => {
+                if mature.contains(unsafe { term.as_ptr() }) {
+                    Some(term)
+                } else {
+                    // A young object which is shared with a promoted object must also be
+                    // promoted, so it is copied again; this only costs sharing, not correctness
+                    None
+                }
+            }
+            _ => Some(term),
+        }
+    }
+
+    fn evacuate(&mut self, term: OpaqueTerm, promote: bool) -> Result<OpaqueTerm, AllocError> {
+        // Only boxed terms allocated on a process heap are ever moved
+        if !term.is_box() || term.is_literal() || term.is_rc() {
+            return Ok(term);
+        }
+        let ptr = unsafe { term.as_ptr() };
+        if !self.is_collected(ptr) {
+            return Ok(term);
+        }
+        let promote = promote || self.is_tenured(ptr);
+        if let Some(forwarded) = self.forwarded(ptr, promote) {
+            return Ok(forwarded);
+        }
+
+        let heap = match self.mature {
+            Some(mature) if promote => mature,
+            _ => self.young,
+        };
+        let moved = match term.into() {
+            Term::Cons(cons) => self.evacuate_list(cons, promote)?,
+            Term::Tuple(tuple) => {
+                let elements = unsafe { tuple.as_ref() }
+                    .as_slice()
+                    .iter()
+                    .map(|element| self.evacuate(*element, promote))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Term::Tuple(Tuple::from_slice(elements.as_slice(), heap)?)
+            }
+            Term::Map(map) => {
+                let items = map
+                    .iter()
+                    .map(|(k, v)| {
+                        let k: Term = self.evacuate((*k).into(), promote)?.into();
+                        let v: Term = self.evacuate((*v).into(), promote)?.into();
+                        Ok((k, v))
+                    })
+                    .collect::<Result<Vec<_>, AllocError>>()?;
+                Term::Map(Map::new_from_iter_in(items.into_iter(), heap)?)
+            }
+            Term::Closure(fun) => {
+                let env = fun
+                    .env()
+                    .iter()
+                    .map(|element| self.evacuate(*element, promote))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Term::Closure(Closure::new_in(
+                    fun.module,
+                    fun.name,
+                    fun.arity as u8,
+                    fun.callee(),
+                    env.as_slice(),
+                    heap,
+                )?)
+            }
+            Term::RefBinary(slice) => self.evacuate_slice(slice, promote, heap)?,
+            // The remaining types do not reference other terms, so they can simply be cloned
+            other => other.clone_to_heap(heap)?,
+        };
+        let moved: OpaqueTerm = moved.into();
+        self.forwarded.insert(ptr, moved);
+        Ok(moved)
+    }
+
+    /// Moves a list, the spine of which is moved iteratively, so that long lists do not cause
+    /// unbounded recursion.
+    fn evacuate_list(
+        &mut self,
+        cons: NonNull<Cons>,
+        mut promote: bool,
+    ) -> Result<Term, AllocError> {
+        let mut old = cons;
+        let mut first = None;
+        let mut prev: Option<NonNull<Cons>> = None;
+        loop {
+            let ptr = old.as_ptr() as *const ();
+            // Once a cell is promoted, the remainder of the list must be promoted with it
+            promote = promote || self.is_tenured(ptr);
+            let heap = match self.mature {
+                Some(mature) if promote => mature,
+                _ => self.young,
+            };
+            let mut cell = Cons::new_in(heap)?;
+            let cell_term: OpaqueTerm = cell.into();
+            self.forwarded.insert(ptr, cell_term);
+            match prev {
+                None => first = Some(cell),
+                Some(mut prev) => unsafe {
+                    prev.as_mut().tail = cell_term;
+                },
+            }
+
+            let (head, tail) = unsafe { (old.as_ref().head, old.as_ref().tail) };
+            let head = self.evacuate(head, promote)?;
+            unsafe {
+                cell.as_mut().head = head;
+                cell.as_mut().tail = OpaqueTerm::NIL;
+            }
+            prev = Some(cell);
+
+            // Continue down the spine only while the next cell still needs to be moved
+            match tail.into() {
+                Term::Cons(next)
+                    if self.is_collected(next.as_ptr() as *const ())
+                        && self
+                            .forwarded(next.as_ptr() as *const (), promote)
+                            .is_none() =>
+                {
+                    old = next;
+                }
+                _ => {
+                    let tail = self.evacuate(tail, promote)?;
+                    unsafe {
+                        cell.as_mut().tail = tail;
+                    }
+                    break;
+                }
+            }
+        }
+        Ok(Term::Cons(first.unwrap()))
+    }
+
+    /// Moves a slice of another binary
+    ///
+    /// The slice borrows the bytes of its owner, so if the owner is moved, the selection
+    /// must be rebased onto the bytes at the new location of the owner.
+    fn evacuate_slice(
+        &mut self,
+        slice: GcBox<BitSlice>,
+        promote: bool,
+        heap: &ProcessHeap,
+    ) -> Result<Term, AllocError> {
+        let owner = slice.owner();
+        let moved_owner = self.evacuate(owner, promote)?;
+        if moved_owner == owner {
+            return Ok(Term::RefBinary(GcBox::new_in((&*slice).clone(), heap)?));
+        }
+        let (old, new) = match (owner.into(), moved_owner.into()) {
+            (Term::HeapBinary(old), Term::HeapBinary(new)) => (old, new),
+            _ => unreachable!("only heap binaries can be moved by the garbage collector"),
+        };
+        let bytes = unsafe { slice.as_bytes_unchecked() };
+        let offset = unsafe { bytes.as_ptr().offset_from(old.as_bytes().as_ptr()) as usize };
+        let data = &new.as_bytes()[offset..(offset + bytes.len())];
+        let rebased =
+            unsafe { BitSlice::new(moved_owner, data, slice.bit_offset(), slice.bit_size()) };
+        Ok(Term::RefBinary(GcBox::new_in(rebased, heap)?))
+    }
+}
@@ -0,0 +1,378 @@
+//! This module implements garbage collection of process heaps.
+//!
+//! Process heaps are generational, consisting of a young heap, in which all new allocations
+//! are made, and a mature heap, which holds data that has survived at least one collection.
+//! When the young heap is exhausted, allocations overflow into heap fragments attached to the
+//! process, and a collection is requested.
+//!
+//! NOTE: Collections are not yet performed automatically. Doing so requires the complete root set
+//! of the process, which can only be found once codegen emits stack maps for the terms live at each
+//! safepoint. Until then, the heap of a process grows via heap fragments for as long as it lives.
+//!
+//! Like the BEAM, we distinguish between two kinds of collection:
+//!
+//! * A minor collection copies the live data of the young heap and any heap fragments into a
+//! new young heap, except for data which was already live at the end of the previous collection
+//! (i.e. below the high-water mark), which is promoted to the mature heap.
+//! * A major collection (or full sweep) copies all live data, including the mature heap, into
+//! a new young heap, leaving the mature heap empty. A major collection is performed when the
+//! mature heap has no room for the data being promoted, or after `fullsweep_after` minor
+//! collections have occurred.
+//!
+//! Heaps are sized according to the amount of live data they hold, growing along a Fibonacci-like
+//! sequence, so that a process which retains more data collects less frequently, and shrinking
+//! after a major collection when most of the heap turned out to be garbage.
+mod collector;
+mod sizes;
+
+pub use self::sizes::{
+    heap_size_in_bytes, heap_size_in_words, next_heap_size, FULLSWEEP_AFTER, MIN_HEAP_SIZE,
+};
+
+use alloc::vec::Vec;
+use core::cmp;
+use core::ops::Range;
+
+use firefly_alloc::gc::GcError;
+use firefly_alloc::heap::{GenerationalHeap, Heap};
+use intrusive_collections::UnsafeRef;
+
+use crate::term::OpaqueTerm;
+
+use self::collector::Collector;
+use super::{Process, ProcessHeap};
+
+/// The garbage collection settings of a process, equivalent to the `min_heap_size` and
+/// `fullsweep_after` options of `erlang:spawn_opt/4`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GcSettings {
+    /// The minimum size of the young heap, in words
+    pub min_heap_size: usize,
+    /// The number of minor collections which may occur before a major collection is forced
+    pub fullsweep_after: usize,
+}
+impl Default for GcSettings {
+    fn default() -> Self {
+        Self {
+            min_heap_size: MIN_HEAP_SIZE,
+            fullsweep_after: FULLSWEEP_AFTER,
+        }
+    }
+}
+
+/// The garbage collector state of a process
+#[derive(Debug, Default)]
+pub(super) struct GcState {
+    pub settings: GcSettings,
+    /// The number of minor collections since the last major collection
+    pub minor_collections: usize,
+    /// Set when the young heap was exhausted and allocations overflowed into heap fragments
+    pub requested: bool,
+}
+
+/// The set of roots from which live data is traced during a collection
+///
+/// Each root is a pointer to a location holding a term, e.g. a stack slot, which will be
+/// updated in place if the term it references is moved.
+#[derive(Default)]
+pub struct RootSet {
+    roots: Vec<*mut OpaqueTerm>,
+}
+impl RootSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `root` to this root set
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that `root` points to a valid term, and remains valid until the
+    /// collection which uses this root set has completed.
+    #[inline]
+    pub unsafe fn push(&mut self, root: *mut OpaqueTerm) {
+        self.roots.push(root);
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.roots.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+}
+
+impl Process {
+    /// Returns the garbage collection settings of this process
+    pub fn gc_settings(&self) -> GcSettings {
+        unsafe { (*self.gc.get()).settings }
+    }
+
+    /// Sets the garbage collection settings of this process, which take effect at the next
+    /// collection
+    ///
+    /// # Safety
+    ///
+    /// Like all garbage collector state, this must only be called by the process itself, or
+    /// by the owning scheduler while the process is suspended.
+    pub unsafe fn set_gc_settings(&self, settings: GcSettings) {
+        (*self.gc.get()).settings = settings;
+    }
+
+    /// Returns true if a garbage collection has been requested for this process, because its
+    /// young heap was exhausted
+    #[inline]
+    pub fn needs_gc(&self) -> bool {
+        unsafe { (*self.gc.get()).requested }
+    }
+
+    /// Performs a garbage collection of the heap of this process, ensuring that at least `need`
+    /// bytes are available on the young heap once it completes.
+    ///
+    /// Returns the number of bytes of live data on the process heap after the collection.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that `roots` is complete, i.e. that every live reference to a term
+    /// on the process heap, or in one of its heap fragments, is included in the root set, as all
+    /// other references are invalidated by this function. This also means that it must only be
+    /// called by the process itself at a safepoint, or by the owning scheduler while the process
+    /// is suspended.
+    pub unsafe fn garbage_collect(&self, need: usize, roots: RootSet) -> Result<usize, GcError> {
+        let gc = &mut *self.gc.get();
+
+        let live = if gc.minor_collections >= gc.settings.fullsweep_after {
+            self.major_collection(need, &roots)?
+        } else {
+            match self.minor_collection(need, &roots) {
+                Err(GcError::FullsweepRequired) => self.major_collection(need, &roots)?,
+                result => result?,
+            }
+        };
+
+        gc.requested = false;
+        Ok(live)
+    }
+
+    /// Returns the address ranges of all heap fragments owned by this process
+    fn fragment_regions(&self) -> Vec<Range<*const u8>> {
+        let fragments = unsafe { &*self.fragments.get() };
+        fragments
+            .iter()
+            .map(|fragment| fragment.as_ptr_range())
+            .collect()
+    }
+
+    /// Returns the number of bytes allocated in heap fragments owned by this process
+    fn fragment_used(&self) -> usize {
+        let fragments = unsafe { &*self.fragments.get() };
+        fragments.iter().map(|fragment| fragment.heap_used()).sum()
+    }
+
+    /// Frees all heap fragments owned by this process, which must no longer be referenced
+    unsafe fn free_fragments(&self) {
+        let fragments = &mut *self.fragments.get();
+        while let Some(fragment) = fragments.pop_front() {
+            UnsafeRef::into_raw(fragment).drop_in_place();
+        }
+    }
+
+    unsafe fn minor_collection(&self, need: usize, roots: &RootSet) -> Result<usize, GcError> {
+        let gc = &mut *self.gc.get();
+        let heap = &mut *self.heap.get();
+
+        // Everything below the high-water mark will be promoted, so ensure the mature heap
+        // has room for it, if the mature heap would need to grow, we must do a full sweep
+        let young_range = heap.immature().as_ptr_range();
+        let tenured_end = heap
+            .immature()
+            .high_water_mark()
+            .map(|hwm| hwm.as_ptr() as *const u8)
+            .unwrap_or(young_range.start);
+        let tenured = young_range.start..tenured_end;
+        let tenured_size = tenured_end.offset_from(young_range.start) as usize;
+        if tenured_size > heap.mature().heap_available() {
+            if heap.mature().heap_used() > 0 {
+                return Err(GcError::FullsweepRequired);
+            }
+            let size = heap_size_in_bytes(next_heap_size(heap_size_in_words(tenured_size)));
+            drop(heap.swap_mature(ProcessHeap::with_size(size)?));
+        }
+
+        let survivors = heap.immature().heap_used() - tenured_size + self.fragment_used();
+        let size = cmp::max(
+            heap.immature().heap_size(),
+            self.heap_size_for(survivors + need),
+        );
+        let new_young = ProcessHeap::with_size(size)?;
+
+        let mut regions = self.fragment_regions();
+        regions.push(young_range);
+        {
+            let mut collector = Collector::new(&new_young, Some(heap.mature()), regions, tenured);
+            for root in roots.roots.iter().copied() {
+                collector.collect_root(&mut *root)?;
+            }
+        }
+
+        // Everything which survived this collection will be promoted by the next one
+        new_young.set_high_water_mark();
+        drop(heap.swap_immature(new_young));
+        self.free_fragments();
+        gc.minor_collections += 1;
+
+        Ok(heap.mature().heap_used() + heap.immature().heap_used())
+    }
+
+    unsafe fn major_collection(&self, need: usize, roots: &RootSet) -> Result<usize, GcError> {
+        let gc = &mut *self.gc.get();
+
+        // The amount of live data isn't known until the collection is done, so the new heap
+        // is sized to hold everything that is currently allocated
+        let used = {
+            let heap = &*self.heap.get();
+            heap.immature().heap_used() + heap.mature().heap_used() + self.fragment_used()
+        };
+        let live = self.copy_all(self.heap_size_for(used + need), roots)?;
+        gc.minor_collections = 0;
+
+        // If most of the heap turned out to be garbage, shrink it, keeping room for the
+        // live data to double before the next collection
+        let wanted = self.heap_size_for((live + need) * 2);
+        if self.heap().heap_size() > wanted * 2 {
+            return self.copy_all(wanted, roots);
+        }
+
+        Ok(live)
+    }
+
+    /// Returns the size in bytes of a young heap which can hold `bytes` of data
+    fn heap_size_for(&self, bytes: usize) -> usize {
+        let min_heap_size = self.gc_settings().min_heap_size;
+        heap_size_in_bytes(next_heap_size(cmp::max(
+            min_heap_size,
+            heap_size_in_words(bytes),
+        )))
+    }
+
+    /// Copies all live data reachable from `roots` into a new young heap of `size` bytes,
+    /// freeing the mature heap and all heap fragments
+    unsafe fn copy_all(&self, size: usize, roots: &RootSet) -> Result<usize, GcError> {
+        let heap = &mut *self.heap.get();
+        let new_young = ProcessHeap::with_size(size)?;
+
+        let mut regions = self.fragment_regions();
+        regions.push(heap.immature().as_ptr_range());
+        regions.push(heap.mature().as_ptr_range());
+        {
+            let mut collector = Collector::new(&new_young, None, regions, tenured_none());
+            for root in roots.roots.iter().copied() {
+                collector.collect_root(&mut *root)?;
+            }
+        }
+
+        // Everything which survived a full sweep is considered mature
+        new_young.set_high_water_mark();
+        drop(heap.swap_immature(new_young));
+        drop(heap.swap_mature(ProcessHeap::empty()));
+        self.free_fragments();
+
+        Ok(heap.immature().heap_used())
+    }
+}
+
+#[inline]
+fn tenured_none() -> Range<*const u8> {
+    core::ptr::null()..core::ptr::null()
+}
+
+#[cfg(test)]
+mod test {
+    use firefly_alloc::heap::{GenerationalHeap, Heap};
+
+    use crate::process::Process;
+    use crate::term::{ListBuilder, OpaqueTerm, ProcessId, Term, Tuple};
+
+    use super::*;
+
+    fn build_list(process: &Process, len: usize) -> OpaqueTerm {
+        let mut builder = ListBuilder::new(process);
+        for i in (0..len).rev() {
+            builder.push(Term::Int(i as i64)).unwrap();
+        }
+        Term::Cons(builder.finish().unwrap()).into()
+    }
+
+    fn list_len(term: OpaqueTerm) -> usize {
+        let term: Term = term.into();
+        let Term::Cons(ptr) = term else { panic!("expected list") };
+        let list = unsafe { ptr.as_ref() };
+        list.iter()
+            .enumerate()
+            .map(|(i, item)| assert_eq!(item, Ok(Term::Int(i as i64))))
+            .count()
+    }
+
+    #[test]
+    fn overflowing_allocations_are_consolidated_by_minor_collection() {
+        let process = Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap());
+        let mut list = build_list(&process, 1000);
+        assert!(process.needs_gc());
+
+        // Garbage that should not survive
+        build_list(&process, 1000);
+
+        let mut roots = RootSet::new();
+        unsafe {
+            roots.push(&mut list);
+        }
+        let live = unsafe { process.garbage_collect(0, roots).unwrap() };
+
+        assert!(!process.needs_gc());
+        assert!(process.contains(unsafe { list.as_ptr() }));
+        assert_eq!(live, process.heap().immature().heap_used());
+        assert_eq!(list_len(list), 1000);
+    }
+
+    #[test]
+    fn survivors_are_promoted_and_swept_by_major_collection() {
+        let process = Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap());
+        let list = build_list(&process, 10);
+        let mut tuple: OpaqueTerm = Tuple::from_slice(&[list, list], &process).unwrap().into();
+
+        // The first collection marks the survivors, the second promotes them
+        for _ in 0..2 {
+            let mut roots = RootSet::new();
+            unsafe {
+                roots.push(&mut tuple);
+                process.garbage_collect(0, roots).unwrap();
+            }
+        }
+        assert!(process.heap().is_mature(unsafe { tuple.as_ptr() }.cast()));
+        assert_eq!(process.heap().immature().heap_used(), 0);
+
+        unsafe {
+            process.set_gc_settings(GcSettings {
+                fullsweep_after: 0,
+                ..Default::default()
+            });
+        }
+        let mut roots = RootSet::new();
+        unsafe {
+            roots.push(&mut tuple);
+            process.garbage_collect(0, roots).unwrap();
+        }
+        assert_eq!(process.heap().mature().heap_size(), 0);
+        assert!(process.heap().is_immature(unsafe { tuple.as_ptr() }.cast()));
+
+        let tuple: Term = tuple.into();
+        let Term::Tuple(ptr) = tuple else { panic!("expected tuple") };
+        let elements = unsafe { ptr.as_ref() }.as_slice();
+        // Sharing is preserved across collections
+        assert_eq!(elements[0], elements[1]);
+        assert_eq!(list_len(elements[0]), 10);
+    }
+}
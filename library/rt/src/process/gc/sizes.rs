@@ -0,0 +1,43 @@
+use core::mem;
+
+use crate::term::OpaqueTerm;
+
+/// The default minimum size of a process heap, in words
+pub const MIN_HEAP_SIZE: usize = 233;
+
+/// The default number of minor collections which may occur before a full sweep is forced
+pub const FULLSWEEP_AFTER: usize = 65535;
+
+/// Heap sizes follow a Fibonacci-like sequence up to this size (in words), after which
+/// they grow by 20% at a time, this mirrors the growth strategy of the BEAM
+const FIBONACCI_LIMIT: usize = 1_300_000;
+
+/// Returns the size (in words) of the smallest heap in the size sequence which can hold `words`
+pub fn next_heap_size(words: usize) -> usize {
+    let (mut prev, mut size) = (12, 38);
+    while size < FIBONACCI_LIMIT {
+        if size >= words {
+            return size;
+        }
+        let next = prev + size + 1;
+        prev = size;
+        size = next;
+    }
+    while size < words {
+        size += size / 5;
+    }
+    size
+}
+
+/// Converts a heap size in words to a size in bytes
+#[inline]
+pub const fn heap_size_in_bytes(words: usize) -> usize {
+    words * mem::size_of::<OpaqueTerm>()
+}
+
+/// Converts a size in bytes to the number of words needed to hold it
+#[inline]
+pub const fn heap_size_in_words(bytes: usize) -> usize {
+    let word_size = mem::size_of::<OpaqueTerm>();
+    (bytes + word_size - 1) / word_size
+}
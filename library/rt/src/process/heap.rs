@@ -7,26 +7,61 @@ use firefly_alloc::heap::Heap;
 
 use crate::term::Term;
 
+use super::gc;
+
+/// A contiguous bump-allocated region of memory used for one generation of a process heap
+///
+/// Individual allocations are never freed, instead, live data is copied out of the heap by
+/// the garbage collector, and the heap is freed as a whole.
 pub struct ProcessHeap {
     range: *mut [u8],
     top: UnsafeCell<*mut u8>,
+    high_water_mark: UnsafeCell<Option<NonNull<u8>>>,
 }
 impl ProcessHeap {
-    const DEFAULT_HEAP_SIZE: usize = 4 * 1024;
-
+    /// Allocates a new heap using the default minimum heap size
     pub fn new() -> Self {
-        let layout =
-            Layout::from_size_align(Self::DEFAULT_HEAP_SIZE, mem::align_of::<Term>()).unwrap();
-        let nonnull = Global.allocate(layout).unwrap();
-        Self {
+        Self::with_size(gc::heap_size_in_bytes(gc::MIN_HEAP_SIZE)).unwrap()
+    }
+
+    /// Allocates a new heap of `size` bytes
+    pub fn with_size(size: usize) -> Result<Self, AllocError> {
+        let layout = Layout::from_size_align(size, mem::align_of::<Term>()).unwrap();
+        let nonnull = Global.allocate(layout)?;
+        Ok(Self {
             range: nonnull.as_ptr(),
             top: UnsafeCell::new(nonnull.as_non_null_ptr().as_ptr()),
+            high_water_mark: UnsafeCell::new(None),
+        })
+    }
+
+    /// Creates a heap with no capacity, used as a placeholder for a generation which has
+    /// not been allocated yet
+    pub fn empty() -> Self {
+        let base = NonNull::<Term>::dangling().as_ptr().cast::<u8>();
+        Self {
+            range: ptr::slice_from_raw_parts_mut(base, 0),
+            top: UnsafeCell::new(base),
+            high_water_mark: UnsafeCell::new(None),
+        }
+    }
+
+    /// Marks the current top of the heap as the high-water mark, so that everything allocated
+    /// so far is considered to have survived a collection
+    pub fn set_high_water_mark(&self) {
+        unsafe {
+            self.high_water_mark
+                .get()
+                .write(NonNull::new(self.heap_top()));
         }
     }
 }
 impl Drop for ProcessHeap {
     fn drop(&mut self) {
         let size = ptr::metadata(self.range) as usize;
+        if size == 0 {
+            return;
+        }
         let layout = Layout::from_size_align(size, mem::align_of::<Term>()).unwrap();
         unsafe { Global.deallocate(NonNull::new_unchecked(self.range.cast()), layout) }
     }
@@ -46,8 +81,8 @@ unsafe impl Allocator for ProcessHeap {
         // Make sure the requested allocation fits within the fragment
         let start = self.range.as_mut_ptr() as *const u8;
         let heap_size = self.range.len();
-        let range = start..(unsafe { start.add(heap_size) });
-        if range.contains(&new_top) {
+        let end = unsafe { start.add(heap_size) };
+        if new_top <= end {
             unsafe {
                 self.top.get().write(new_top as *mut u8);
             }
@@ -98,4 +133,9 @@ impl Heap for ProcessHeap {
     fn heap_end(&self) -> *mut u8 {
         unsafe { self.heap_start().add(self.range.len()) }
     }
+
+    #[inline]
+    fn high_water_mark(&self) -> Option<NonNull<u8>> {
+        unsafe { *self.high_water_mark.get() }
+    }
 }
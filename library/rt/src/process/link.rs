@@ -0,0 +1,38 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+
+use crate::term::{ProcessId, ReferenceId};
+
+/// The links and monitors associated with a process
+///
+/// Links are bidirectional, so each side of a link records the other. Monitors are
+/// unidirectional, but both the monitoring process and the monitored process record the
+/// monitor, the former so that it can be removed by `demonitor`, the latter so that a
+/// `'DOWN'` message can be delivered when it exits.
+#[derive(Default)]
+pub struct Links {
+    /// The processes this process is linked to
+    pub links: BTreeSet<ProcessId>,
+    /// The monitors created by this process, mapped to the process being monitored
+    pub monitoring: BTreeMap<ReferenceId, ProcessId>,
+    /// The monitors which target this process, mapped to the process which created them
+    pub monitored_by: BTreeMap<ReferenceId, ProcessId>,
+}
+impl Links {
+    /// Returns true if this process is linked to `pid`
+    #[inline]
+    pub fn is_linked(&self, pid: ProcessId) -> bool {
+        self.links.contains(&pid)
+    }
+
+    /// Links this process to `pid`, returning false if the link already existed
+    #[inline]
+    pub fn link(&mut self, pid: ProcessId) -> bool {
+        self.links.insert(pid)
+    }
+
+    /// Removes the link to `pid`, returning false if there was no such link
+    #[inline]
+    pub fn unlink(&mut self, pid: ProcessId) -> bool {
+        self.links.remove(&pid)
+    }
+}
@@ -0,0 +1,252 @@
+use alloc::alloc::AllocError;
+use alloc::boxed::Box;
+use core::ptr::{self, NonNull};
+
+use firefly_alloc::fragment::HeapFragment;
+
+use crate::term::{OpaqueTerm, Term};
+
+/// The kind of a message in a process mailbox
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u32)]
+pub enum MessageKind {
+    /// A normal message, i.e. one sent with `erlang:send/2`
+    Message = 0,
+}
+
+/// The message data inspected by a receive
+///
+/// NOTE: The layout of this struct must match that of the `erlang::Message` type in the
+/// CIR to LLVM lowering, as generated code reads the message term out of it directly.
+#[repr(C)]
+pub struct MessageData {
+    pub kind: MessageKind,
+    pub term: OpaqueTerm,
+}
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+struct MessageLink {
+    prev: *mut Message,
+    next: *mut Message,
+}
+
+/// An entry in a process mailbox
+///
+/// Messages are copied into a heap fragment when sent, which remains owned by the message
+/// until it is removed from the mailbox, at which point ownership is transferred to the
+/// receiving process.
+#[repr(C)]
+pub struct Message {
+    link: MessageLink,
+    pub data: MessageData,
+    fragment: Option<NonNull<HeapFragment>>,
+}
+impl Message {
+    /// Creates a new message by copying `term` into a new heap fragment
+    ///
+    /// Immediates require no copy, so no fragment is allocated for them.
+    pub fn new(term: Term) -> Result<Box<Self>, AllocError> {
+        let opaque: OpaqueTerm = term.into();
+        let (term, fragment) = if opaque.is_immediate() {
+            (opaque, None)
+        } else {
+            let (term, fragment) = term.clone_to_fragment()?;
+            (term.into(), Some(fragment))
+        };
+        Ok(Box::new(Self {
+            link: MessageLink {
+                prev: ptr::null_mut(),
+                next: ptr::null_mut(),
+            },
+            data: MessageData {
+                kind: MessageKind::Message,
+                term,
+            },
+            fragment,
+        }))
+    }
+
+    /// Returns the message term
+    #[inline]
+    pub fn term(&self) -> OpaqueTerm {
+        self.data.term
+    }
+
+    /// Takes ownership of the heap fragment containing the message term, if there is one
+    #[inline]
+    pub fn take_fragment(&mut self) -> Option<NonNull<HeapFragment>> {
+        self.fragment.take()
+    }
+}
+impl Drop for Message {
+    fn drop(&mut self) {
+        if let Some(fragment) = self.fragment.take() {
+            unsafe {
+                fragment.as_ptr().drop_in_place();
+            }
+        }
+    }
+}
+// A message, and the fragment containing its term, has exactly one owner at a time
+unsafe impl Send for Message {}
+
+/// The message queue of a process
+///
+/// Messages are delivered in the order in which they are sent, but may be received out of order
+/// by a selective receive. To avoid rescanning messages which a receive has already rejected,
+/// the mailbox maintains a save pointer to the next message to be inspected, which only moves
+/// forward until a message is removed, or the receive completes.
+pub struct Mailbox {
+    head: *mut Message,
+    tail: *mut Message,
+    /// The next message to be inspected by the current receive, or null if all messages
+    /// in the queue have been inspected
+    save: *mut Message,
+    len: usize,
+}
+// Mailboxes are always accessed under a lock
+unsafe impl Send for Mailbox {}
+unsafe impl Sync for Mailbox {}
+impl Default for Mailbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Mailbox {
+    pub const fn new() -> Self {
+        Self {
+            head: ptr::null_mut(),
+            tail: ptr::null_mut(),
+            save: ptr::null_mut(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of messages in the queue
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Appends `message` to the end of the queue
+    pub fn push(&mut self, message: Box<Message>) {
+        let message = Box::into_raw(message);
+        unsafe {
+            (*message).link.prev = self.tail;
+            (*message).link.next = ptr::null_mut();
+            if self.tail.is_null() {
+                self.head = message;
+            } else {
+                (*self.tail).link.next = message;
+            }
+        }
+        self.tail = message;
+        // If every message has already been inspected, the new message is the next to inspect
+        if self.save.is_null() {
+            self.save = message;
+        }
+        self.len += 1;
+    }
+
+    /// Returns the message at the save pointer, if there is one
+    #[inline]
+    pub fn peek(&self) -> Option<&Message> {
+        unsafe { self.save.as_ref() }
+    }
+
+    /// Advances the save pointer past the current message, which did not match
+    pub fn next(&mut self) {
+        if let Some(message) = unsafe { self.save.as_ref() } {
+            self.save = message.link.next;
+        }
+    }
+
+    /// Removes the message at the save pointer from the queue, and resets the save pointer
+    /// to the start of the queue
+    pub fn remove(&mut self) -> Option<Box<Message>> {
+        let message = self.save;
+        if message.is_null() {
+            return None;
+        }
+        unsafe {
+            let MessageLink { prev, next } = (*message).link;
+            if prev.is_null() {
+                self.head = next;
+            } else {
+                (*prev).link.next = next;
+            }
+            if next.is_null() {
+                self.tail = prev;
+            } else {
+                (*next).link.prev = prev;
+            }
+            self.len -= 1;
+            self.reset();
+
+            let mut message = Box::from_raw(message);
+            message.link.prev = ptr::null_mut();
+            message.link.next = ptr::null_mut();
+            Some(message)
+        }
+    }
+
+    /// Resets the save pointer to the start of the queue, as is done when a receive completes
+    #[inline]
+    pub fn reset(&mut self) {
+        self.save = self.head;
+    }
+
+    /// Removes all messages for which `predicate` returns false, returning true if any
+    /// messages were removed
+    ///
+    /// The relative order of the remaining messages is preserved, as is the save pointer,
+    /// unless the message it points to was removed, in which case it is moved to the next
+    /// remaining message.
+    pub fn retain<F>(&mut self, mut predicate: F) -> bool
+    where
+        F: FnMut(&Message) -> bool,
+    {
+        let mut removed = false;
+        let mut current = self.head;
+        while !current.is_null() {
+            let MessageLink { prev, next } = unsafe { (*current).link };
+            if !predicate(unsafe { &*current }) {
+                unsafe {
+                    if prev.is_null() {
+                        self.head = next;
+                    } else {
+                        (*prev).link.next = next;
+                    }
+                    if next.is_null() {
+                        self.tail = prev;
+                    } else {
+                        (*next).link.prev = prev;
+                    }
+                }
+                if self.save == current {
+                    self.save = next;
+                }
+                self.len -= 1;
+                removed = true;
+                drop(unsafe { Box::from_raw(current) });
+            }
+            current = next;
+        }
+        removed
+    }
+}
+impl Drop for Mailbox {
+    fn drop(&mut self) {
+        let mut current = self.head;
+        while !current.is_null() {
+            let message = unsafe { Box::from_raw(current) };
+            current = message.link.next;
+        }
+    }
+}
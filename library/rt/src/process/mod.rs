@@ -1,17 +1,28 @@
+mod gc;
 mod heap;
+mod link;
+mod mailbox;
 mod stack;
 
 use alloc::alloc::{AllocError, Allocator, Layout};
 use core::cell::UnsafeCell;
 use core::ptr::NonNull;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU64, AtomicU8, Ordering};
 
-use firefly_alloc::heap::Heap;
+use firefly_alloc::fragment::{HeapFragment, HeapFragmentAdapter};
+use firefly_alloc::heap::{Heap, SemispaceHeap};
+use firefly_system::sync::{Mutex, MutexGuard};
+use intrusive_collections::{LinkedList, UnsafeRef};
 
 use crate::error::ErlangException;
 use crate::function::ModuleFunctionArity;
-use crate::term::ProcessId;
+use crate::term::{ProcessId, Term};
 
+pub use self::gc::{GcSettings, RootSet};
 pub use self::heap::ProcessHeap;
+pub use self::link::Links;
+pub use self::mailbox::{Mailbox, Message, MessageData, MessageKind};
 pub use self::stack::ProcessStack;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -23,13 +34,25 @@ pub enum ProcessStatus {
     Errored(NonNull<ErlangException>),
 }
 
+// The encoding of `ProcessStatus` in `Process::status`
+const STATUS_RUNNING: u8 = 0;
+const STATUS_RUNNABLE: u8 = 1;
+const STATUS_WAITING: u8 = 2;
+const STATUS_EXITING: u8 = 3;
+const STATUS_ERRORED: u8 = 4;
+
 pub struct Process {
     parent: Option<ProcessId>,
     pid: ProcessId,
     #[allow(dead_code)]
     mfa: ModuleFunctionArity,
-    /// The process status is only ever manipulated/accessed by the owning scheduler
-    status: UnsafeCell<ProcessStatus>,
+    /// The process status may be read from any thread, e.g. by a process sending a message
+    /// to this one, but is only changed by the process itself while it is running, or by the
+    /// scheduler which holds it while it is suspended
+    status: AtomicU8,
+    /// The exception with which the process is exiting, this is set before the status is
+    /// set to `Errored`, so it is always visible to those who observe that status
+    exception: AtomicPtr<ErlangException>,
     /// The process heap can be safely accessed directly via UnsafeCell because it
     /// is always the case that either:
     ///
@@ -39,18 +62,45 @@ pub struct Process {
     /// In both cases access is exclusive, and special care is taken to guarantee
     /// that when a GC takes place, that live references held by the suspended process
     /// are properly updated so that the aliasing in that case is safe.
-    heap: UnsafeCell<ProcessHeap>,
+    heap: UnsafeCell<SemispaceHeap<ProcessHeap, ProcessHeap>>,
+    /// Heap fragments which hold terms referenced by the process, e.g. received messages,
+    /// or allocations which overflowed the heap. Like the heap, these are only ever accessed
+    /// by the process itself, or the scheduler.
+    fragments: UnsafeCell<LinkedList<HeapFragmentAdapter>>,
+    /// The garbage collector state is accessed under the same conditions as the heap
+    gc: UnsafeCell<gc::GcState>,
     stack: UnsafeCell<ProcessStack>,
+    /// The mailbox may be written to by any process, so access to it is synchronized
+    mailbox: Mutex<Mailbox>,
+    /// Links and monitors are modified by other processes, so access to them is synchronized
+    links: Mutex<Links>,
+    /// When set, exit signals received from linked processes are converted to messages
+    trap_exit: AtomicBool,
+    /// The total number of reductions executed by this process, updated by the scheduler
+    /// each time the process yields
+    reductions: AtomicU64,
 }
+// The unsynchronized state of a process is only ever accessed by the process itself, or the
+// scheduler which owns it while it is suspended, so processes may be shared by schedulers
+// running on different threads
+unsafe impl Send for Process {}
+unsafe impl Sync for Process {}
 impl Process {
     pub fn new(parent: Option<ProcessId>, pid: ProcessId, mfa: ModuleFunctionArity) -> Self {
         Self {
             parent,
             pid,
             mfa,
-            status: UnsafeCell::new(ProcessStatus::Waiting),
-            heap: UnsafeCell::new(ProcessHeap::new()),
+            status: AtomicU8::new(STATUS_WAITING),
+            exception: AtomicPtr::new(ptr::null_mut()),
+            heap: UnsafeCell::new(SemispaceHeap::new(ProcessHeap::new(), ProcessHeap::empty())),
+            fragments: UnsafeCell::new(LinkedList::new(HeapFragmentAdapter::new())),
+            gc: UnsafeCell::new(gc::GcState::default()),
             stack: UnsafeCell::new(ProcessStack::new(32).unwrap()),
+            mailbox: Mutex::new(Mailbox::new()),
+            links: Mutex::new(Links::default()),
+            trap_exit: AtomicBool::new(false),
+            reductions: AtomicU64::new(0),
         }
     }
 
@@ -63,47 +113,157 @@ impl Process {
     }
 
     pub fn status(&self) -> ProcessStatus {
-        unsafe { self.status.get().read() }
+        match self.status.load(Ordering::Acquire) {
+            STATUS_RUNNING => ProcessStatus::Running,
+            STATUS_RUNNABLE => ProcessStatus::Runnable,
+            STATUS_WAITING => ProcessStatus::Waiting,
+            STATUS_EXITING => ProcessStatus::Exiting,
+            STATUS_ERRORED => {
+                let exception = self.exception.load(Ordering::Acquire);
+                ProcessStatus::Errored(unsafe { NonNull::new_unchecked(exception) })
+            }
+            status => unreachable!("invalid process status {}", status),
+        }
     }
 
     pub fn stack(&self) -> &ProcessStack {
         unsafe { &*self.stack.get() }
     }
 
+    /// Acquires the lock on this process' mailbox
+    #[inline]
+    pub fn mailbox(&self) -> MutexGuard<'_, Mailbox> {
+        self.mailbox.lock()
+    }
+
+    /// Sends `message` to this process, copying it into a heap fragment owned by the
+    /// message until it is received
+    ///
+    /// Returns true if the process was waiting on a message, in which case the caller is
+    /// responsible for rescheduling it.
+    pub fn send(&self, message: Term) -> Result<bool, AllocError> {
+        let message = Message::new(message)?;
+        self.mailbox().push(message);
+        Ok(self.status() == ProcessStatus::Waiting)
+    }
+
+    /// Acquires the lock on the links and monitors of this process
+    #[inline]
+    pub fn links(&self) -> MutexGuard<'_, Links> {
+        self.links.lock()
+    }
+
+    /// Returns true if this process is trapping exits
+    #[inline]
+    pub fn trap_exit(&self) -> bool {
+        self.trap_exit.load(Ordering::Acquire)
+    }
+
+    /// Sets the `trap_exit` flag of this process, returning the previous value
+    #[inline]
+    pub fn set_trap_exit(&self, trap_exit: bool) -> bool {
+        self.trap_exit.swap(trap_exit, Ordering::AcqRel)
+    }
+
+    /// Returns the total number of reductions executed by this process
+    #[inline]
+    pub fn reductions(&self) -> u64 {
+        self.reductions.load(Ordering::Relaxed)
+    }
+
+    /// Adds `reductions` to the total number of reductions executed by this process
+    #[inline]
+    pub fn add_reductions(&self, reductions: u64) {
+        self.reductions.fetch_add(reductions, Ordering::Relaxed);
+    }
+
+    /// Takes ownership of the given heap fragment, keeping it alive as long as the process
+    ///
+    /// # Safety
+    ///
+    /// This function must only be called by the process itself, or the owning scheduler
+    /// while the process is suspended, as the fragment list is not synchronized.
+    pub unsafe fn attach_fragment(&self, fragment: NonNull<HeapFragment>) {
+        let fragments = &mut *self.fragments.get();
+        fragments.push_back(UnsafeRef::from_raw(fragment.as_ptr()));
+    }
+
     pub fn exit_normal(&self) {
-        unsafe {
-            self.set_status(ProcessStatus::Exiting);
-        }
+        self.set_status(ProcessStatus::Exiting);
     }
 
     pub fn exit_error(&self, exception: NonNull<ErlangException>) {
-        unsafe {
-            self.set_status(ProcessStatus::Errored(exception));
-        }
+        self.set_status(ProcessStatus::Errored(exception));
     }
 
     /// Sets the process status
     ///
-    /// # Safety
-    ///
-    /// This function must be called with exclusive access to the process status, i.e. by
-    /// the owning scheduler. It is not safe to set this status from anywhere else,
-    /// except from within the process itself, as while the process is executing it
-    /// has exclusive access to its own status.
-    pub unsafe fn set_status(&self, status: ProcessStatus) {
-        self.status.get().write(status);
+    /// The status may be observed by other threads at any time, but it must only be set by
+    /// the process itself while it is running, or by the scheduler which holds it while it
+    /// is suspended, as transitions between states are not otherwise coordinated.
+    pub fn set_status(&self, status: ProcessStatus) {
+        let status = match status {
+            ProcessStatus::Running => STATUS_RUNNING,
+            ProcessStatus::Runnable => STATUS_RUNNABLE,
+            ProcessStatus::Waiting => STATUS_WAITING,
+            ProcessStatus::Exiting => STATUS_EXITING,
+            ProcessStatus::Errored(exception) => {
+                self.exception.store(exception.as_ptr(), Ordering::Release);
+                STATUS_ERRORED
+            }
+        };
+        self.status.store(status, Ordering::Release);
     }
 
     #[inline(always)]
-    fn heap(&self) -> &ProcessHeap {
+    fn heap(&self) -> &SemispaceHeap<ProcessHeap, ProcessHeap> {
         unsafe { &*self.heap.get() }
     }
+
+    /// Allocates `layout` in a heap fragment when the young heap is exhausted, and requests
+    /// a garbage collection, which would consolidate the fragments into the heap.
+    ///
+    /// Allocations are made from the most recently attached fragment while it has room, and
+    /// new fragments are sized like heaps, so that they amortize the cost of many allocations.
+    fn allocate_overflow(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        unsafe {
+            (*self.gc.get()).requested = true;
+        }
+        let fragments = unsafe { &*self.fragments.get() };
+        if let Some(fragment) = fragments.back().get() {
+            if let Ok(ptr) = fragment.allocate(layout) {
+                return Ok(ptr);
+            }
+        }
+        let size = gc::heap_size_in_bytes(gc::next_heap_size(gc::heap_size_in_words(
+            layout.pad_to_align().size(),
+        )));
+        let fragment_layout = Layout::from_size_align(size, layout.align()).unwrap();
+        let fragment = HeapFragment::new(fragment_layout, None)?;
+        unsafe {
+            self.attach_fragment(fragment);
+            fragment.as_ref().allocate(layout)
+        }
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        let fragments = self.fragments.get_mut();
+        while let Some(fragment) = fragments.pop_front() {
+            unsafe {
+                UnsafeRef::into_raw(fragment).drop_in_place();
+            }
+        }
+    }
 }
 
 unsafe impl Allocator for Process {
     #[inline]
     fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
-        self.heap().allocate(layout)
+        self.heap()
+            .allocate(layout)
+            .or_else(|_| self.allocate_overflow(layout))
     }
 
     #[inline]
@@ -165,6 +325,10 @@ impl Heap for Process {
 
     #[inline]
     fn contains<T: ?Sized>(&self, ptr: *const T) -> bool {
-        self.heap().contains(ptr)
+        if self.heap().contains(ptr) {
+            return true;
+        }
+        let fragments = unsafe { &*self.fragments.get() };
+        fragments.iter().any(|fragment| fragment.contains(ptr))
     }
 }
@@ -50,6 +50,12 @@ impl BitSlice {
         Self { owner, selection }
     }
 
+    /// Returns the term which owns the data referenced by this slice
+    #[inline]
+    pub fn owner(&self) -> OpaqueTerm {
+        self.owner
+    }
+
     /// Returns the selection represented by this slice
     #[inline]
     pub fn as_selection(&self) -> Selection<'static> {
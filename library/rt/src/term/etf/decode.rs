@@ -0,0 +1,569 @@
+use alloc::alloc::AllocError;
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem;
+use core::ptr::{self, NonNull};
+use core::str;
+
+use firefly_alloc::gc::GcBox;
+use firefly_alloc::heap::Heap;
+use firefly_alloc::rc::Rc;
+use firefly_binary::Aligned;
+use firefly_number::{BigInt, Float, Sign, ToPrimitive};
+use miniz_oxide::inflate::core::{decompress, inflate_flags, DecompressorOxide};
+use miniz_oxide::inflate::TINFLStatus;
+
+use crate::function::{self, ModuleFunctionArity};
+use crate::term::{
+    Atom, BinaryData, BitSlice, Closure, Cons, Map, Node, OpaqueTerm, Pid, Port, PortId, ProcessId,
+    Reference, ReferenceId, Term, Tuple,
+};
+
+use super::*;
+
+/// Options which control how a term is decoded, see `erlang:binary_to_term/2`
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct DecodeOptions {
+    /// When true, decoding fails rather than creating atoms which do not already exist
+    pub safe: bool,
+}
+
+/// Produced when the input is not a valid encoding of a term
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input ended before a complete term was decoded
+    UnexpectedEof,
+    /// The input does not begin with the external term format version
+    InvalidVersion(u8),
+    /// The input contains an unknown tag, or one which cannot occur outside of distribution
+    InvalidTag(u8),
+    /// The input contains a malformed value, e.g. an atom which is not valid UTF-8
+    Invalid,
+    /// The input contains an atom which does not exist, and `safe` was requested
+    UnsafeAtom,
+    /// The input contains a fun which cannot be resolved on this node
+    UnsupportedFun,
+    /// The compressed term could not be decompressed
+    Compression,
+    /// There was not enough memory to allocate the decoded term
+    Alloc,
+}
+impl From<AllocError> for DecodeError {
+    fn from(_: AllocError) -> Self {
+        Self::Alloc
+    }
+}
+
+/// Decodes a term in the external term format from `bytes`, allocating it on `heap`
+///
+/// On success, returns the decoded term, and the number of bytes of the input which
+/// were consumed by it.
+pub fn decode<H: Heap>(
+    bytes: &[u8],
+    options: DecodeOptions,
+    heap: H,
+) -> Result<(Term, usize), DecodeError> {
+    let mut reader = Reader::new(bytes);
+    match reader.read_u8()? {
+        VERSION => (),
+        version => return Err(DecodeError::InvalidVersion(version)),
+    }
+
+    if bytes.get(1) == Some(&COMPRESSED_TERM) {
+        reader.read_u8()?;
+        let size = reader.read_u32()? as usize;
+        let (uncompressed, consumed) = inflate(&bytes[reader.position..], size)?;
+        let mut decoder = Decoder {
+            reader: Reader::new(uncompressed.as_slice()),
+            options,
+            heap,
+        };
+        let term = decoder.decode_term()?;
+        if decoder.reader.position != size {
+            return Err(DecodeError::Invalid);
+        }
+        return Ok((term, reader.position + consumed));
+    }
+
+    let mut decoder = Decoder {
+        reader,
+        options,
+        heap,
+    };
+    let term = decoder.decode_term()?;
+    Ok((term, decoder.reader.position))
+}
+
+/// Decompresses the zlib stream at the start of `input`, which must produce exactly `size` bytes
+///
+/// Returns the decompressed bytes, and the number of bytes of `input` which were consumed.
+fn inflate(input: &[u8], size: usize) -> Result<(Vec<u8>, usize), DecodeError> {
+    let mut output = vec![0; size];
+    // The decompressor state is fairly large, so we avoid placing it on the process stack
+    let mut decompressor = Box::<DecompressorOxide>::default();
+    let flags = inflate_flags::TINFL_FLAG_PARSE_ZLIB_HEADER
+        | inflate_flags::TINFL_FLAG_USING_NON_WRAPPING_OUTPUT_BUF;
+    let (status, consumed, written) = decompress(&mut decompressor, input, &mut output, 0, flags);
+    match status {
+        TINFLStatus::Done if written == size => Ok((output, consumed)),
+        _ => Err(DecodeError::Compression),
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        let end = self
+            .position
+            .checked_add(len)
+            .ok_or(DecodeError::UnexpectedEof)?;
+        let bytes = self
+            .bytes
+            .get(self.position..end)
+            .ok_or(DecodeError::UnexpectedEof)?;
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        Ok(self.read_bytes(N)?.try_into().unwrap())
+    }
+
+    fn read_u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, DecodeError> {
+        self.read_array().map(u16::from_be_bytes)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, DecodeError> {
+        self.read_array().map(u32::from_be_bytes)
+    }
+
+    fn read_i32(&mut self) -> Result<i32, DecodeError> {
+        self.read_array().map(i32::from_be_bytes)
+    }
+
+    fn read_u64(&mut self) -> Result<u64, DecodeError> {
+        self.read_array().map(u64::from_be_bytes)
+    }
+}
+
+struct Decoder<'a, H> {
+    reader: Reader<'a>,
+    options: DecodeOptions,
+    heap: H,
+}
+impl<'a, H: Heap> Decoder<'a, H> {
+    /// Lists, tuples and maps are decoded using an explicit stack of those which are not yet
+    /// complete, rather than recursively, so that deeply nested terms cannot overflow the stack
+    fn decode_term(&mut self) -> Result<Term, DecodeError> {
+        let mut stack: Vec<Container> = Vec::new();
+        loop {
+            let mut term = match self.decode_next()? {
+                Next::Term(term) => term,
+                Next::Container(container) => {
+                    stack.push(container);
+                    continue;
+                }
+            };
+            // The decoded term belongs to the innermost incomplete container, which it may
+            // complete, in which case that container belongs to the one enclosing it, and so on
+            loop {
+                let Some(container) = stack.last_mut() else { return Ok(term); };
+                match container.push(term, &self.heap)? {
+                    Some(complete) => {
+                        stack.pop();
+                        term = complete;
+                    }
+                    None => break,
+                }
+            }
+        }
+    }
+
+    /// Decodes the next term, unless it is a non-empty container, in which case its elements
+    /// are the terms which follow it
+    fn decode_next(&mut self) -> Result<Next, DecodeError> {
+        let tag = self.reader.read_u8()?;
+        match tag {
+            LIST_EXT => Ok(Next::Container(Container::List {
+                remaining: self.reader.read_u32()?,
+                first: None,
+                last: None,
+            })),
+            SMALL_TUPLE_EXT => {
+                let arity = self.reader.read_u8()? as usize;
+                self.start_tuple(arity)
+            }
+            LARGE_TUPLE_EXT => {
+                let arity = self.reader.read_u32()? as usize;
+                self.start_tuple(arity)
+            }
+            MAP_EXT => {
+                let size = self.reader.read_u32()? as usize;
+                if size == 0 {
+                    let map = Map::new_from_iter_in(core::iter::empty(), &self.heap)?;
+                    return Ok(Next::Term(map.into()));
+                }
+                Ok(Next::Container(Container::Map {
+                    size,
+                    key: None,
+                    items: Vec::with_capacity(size.min(self.reader.bytes.len())),
+                }))
+            }
+            tag => self.decode_value(tag).map(Next::Term),
+        }
+    }
+
+    fn start_tuple(&mut self, arity: usize) -> Result<Next, DecodeError> {
+        if arity == 0 {
+            return Ok(Next::Term(Tuple::from_slice(&[], &self.heap)?.into()));
+        }
+        Ok(Next::Container(Container::Tuple {
+            arity,
+            elements: Vec::with_capacity(arity.min(self.reader.bytes.len())),
+        }))
+    }
+
+    /// Decodes a term which is not a list, tuple or map
+    fn decode_value(&mut self, tag: u8) -> Result<Term, DecodeError> {
+        match tag {
+            SMALL_INTEGER_EXT => Ok(Term::Int(self.reader.read_u8()? as i64)),
+            INTEGER_EXT => Ok(Term::Int(self.reader.read_i32()? as i64)),
+            SMALL_BIG_EXT => {
+                let len = self.reader.read_u8()? as usize;
+                self.decode_big_integer(len)
+            }
+            LARGE_BIG_EXT => {
+                let len = self.reader.read_u32()? as usize;
+                self.decode_big_integer(len)
+            }
+            NEW_FLOAT_EXT => {
+                let f = f64::from_bits(self.reader.read_u64()?);
+                Ok(Term::Float(
+                    Float::new(f).map_err(|_| DecodeError::Invalid)?,
+                ))
+            }
+            FLOAT_EXT => self.decode_float_ext(),
+            ATOM_EXT | SMALL_ATOM_EXT | ATOM_UTF8_EXT | SMALL_ATOM_UTF8_EXT => {
+                self.decode_atom_with_tag(tag).map(Term::from)
+            }
+            NIL_EXT => Ok(Term::Nil),
+            STRING_EXT => {
+                let len = self.reader.read_u16()? as usize;
+                let bytes = self.reader.read_bytes(len)?;
+                match Cons::from_bytes(bytes, &self.heap)? {
+                    None => Ok(Term::Nil),
+                    Some(cons) => Ok(Term::Cons(cons)),
+                }
+            }
+            BINARY_EXT => {
+                let len = self.reader.read_u32()? as usize;
+                self.decode_bitstring(len, len * 8)
+            }
+            BIT_BINARY_EXT => {
+                let len = self.reader.read_u32()? as usize;
+                let bits = self.reader.read_u8()? as usize;
+                match (len, bits) {
+                    (0, 0) => self.decode_bitstring(0, 0),
+                    (len, bits) if len > 0 && bits > 0 && bits <= 8 => {
+                        self.decode_bitstring(len, (len - 1) * 8 + bits)
+                    }
+                    _ => Err(DecodeError::Invalid),
+                }
+            }
+            PID_EXT | NEW_PID_EXT => {
+                let name = self.decode_atom()?;
+                let number = self.reader.read_u32()?;
+                let serial = self.reader.read_u32()?;
+                let creation = self.decode_creation(tag == PID_EXT)?;
+                let id = ProcessId::new(number as usize, serial as usize)
+                    .map_err(|_| DecodeError::Invalid)?;
+                let pid = match resolve_node(name, creation) {
+                    None => Pid::Local { id },
+                    Some(node) => Pid::External { id, node },
+                };
+                Ok(GcBox::new_in(pid, &self.heap)?.into())
+            }
+            PORT_EXT | NEW_PORT_EXT | V4_PORT_EXT => {
+                let name = self.decode_atom()?;
+                let id = match tag {
+                    V4_PORT_EXT => self.reader.read_u64()?,
+                    _ => self.reader.read_u32()? as u64,
+                };
+                let creation = self.decode_creation(tag == PORT_EXT)?;
+                let id = unsafe { PortId::from_raw(id) };
+                let port = match resolve_node(name, creation) {
+                    None => Port::Local { id },
+                    Some(node) => Port::External {
+                        id,
+                        node,
+                        next: ptr::null_mut(),
+                    },
+                };
+                Ok(GcBox::new_in(port, &self.heap)?.into())
+            }
+            REFERENCE_EXT => {
+                let name = self.decode_atom()?;
+                let id = self.reader.read_u32()?;
+                let creation = self.decode_creation(true)?;
+                self.make_reference(name, creation, [id, 0, 0])
+            }
+            NEW_REFERENCE_EXT | NEWER_REFERENCE_EXT => {
+                // Reference ids hold at most three words, so longer references, such as those
+                // newer nodes create for processes, cannot be represented without losing their
+                // identity
+                let len = self.reader.read_u16()? as usize;
+                if len == 0 || len > 3 {
+                    return Err(DecodeError::Invalid);
+                }
+                let name = self.decode_atom()?;
+                let creation = self.decode_creation(tag == NEW_REFERENCE_EXT)?;
+                let mut words = [0; 3];
+                for word in words.iter_mut().take(len) {
+                    *word = self.reader.read_u32()?;
+                }
+                self.make_reference(name, creation, words)
+            }
+            EXPORT_EXT => {
+                let module = self.decode_atom()?;
+                let function = self.decode_atom()?;
+                let arity = match self.reader.read_u8()? {
+                    SMALL_INTEGER_EXT => self.reader.read_u8()?,
+                    _ => return Err(DecodeError::Invalid),
+                };
+                let mfa = ModuleFunctionArity::new(module, function, arity as usize);
+                let callee = function::find_symbol(&mfa).ok_or(DecodeError::UnsupportedFun)?;
+                let fun = Closure::new_in(
+                    module,
+                    function,
+                    arity,
+                    callee as *const (),
+                    &[],
+                    &self.heap,
+                )?;
+                Ok(fun.into())
+            }
+            // Funs with free variables refer to code by the unique identifiers assigned by the
+            // BEAM compiler, which we have no means of resolving
+            FUN_EXT | NEW_FUN_EXT => Err(DecodeError::UnsupportedFun),
+            tag => Err(DecodeError::InvalidTag(tag)),
+        }
+    }
+
+    fn decode_big_integer(&mut self, len: usize) -> Result<Term, DecodeError> {
+        let sign = match self.reader.read_u8()? {
+            0 => Sign::Plus,
+            1 => Sign::Minus,
+            _ => return Err(DecodeError::Invalid),
+        };
+        let digits = self.reader.read_bytes(len)?;
+        let i = BigInt::from_bytes_le(sign, digits);
+        // Integers are always normalized to the smallest representation which can hold them
+        if let Some(small) = i.to_i64().and_then(|i| Term::try_from(i).ok()) {
+            return Ok(small);
+        }
+        Ok(GcBox::new_in(i, &self.heap)?.into())
+    }
+
+    /// Decodes the textual float representation used by minor version 0
+    fn decode_float_ext(&mut self) -> Result<Term, DecodeError> {
+        let bytes = self.reader.read_bytes(31)?;
+        let len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        let f = str::from_utf8(&bytes[..len])
+            .ok()
+            .and_then(|s| s.trim().parse::<f64>().ok())
+            .ok_or(DecodeError::Invalid)?;
+        Ok(Term::Float(
+            Float::new(f).map_err(|_| DecodeError::Invalid)?,
+        ))
+    }
+
+    fn decode_bitstring(&mut self, byte_size: usize, bit_size: usize) -> Result<Term, DecodeError> {
+        let bytes = self.reader.read_bytes(byte_size)?;
+        let (bin, data): (OpaqueTerm, *const [u8]) = if byte_size <= BinaryData::MAX_HEAP_BYTES {
+            let mut bin = BinaryData::with_capacity_small(byte_size, &self.heap)?;
+            bin.copy_from_slice(bytes);
+            let data = bin.as_bytes() as *const [u8];
+            (bin.into(), data)
+        } else {
+            let mut bin = BinaryData::with_capacity_large(byte_size, &self.heap)?;
+            {
+                // SAFETY: There can be no other references to this Rc yet
+                let b = unsafe { Rc::get_mut(&mut bin).unwrap_unchecked() };
+                b.copy_from_slice(bytes);
+            }
+            let data = bin.as_bytes() as *const [u8];
+            (bin.into(), data)
+        };
+        if bit_size == byte_size * 8 {
+            return Ok(bin.into());
+        }
+        // Bitstrings are represented as a slice of the binary which holds their bytes
+        let slice = unsafe { BitSlice::new(bin, &*data, 0, bit_size) };
+        Ok(GcBox::new_in(slice, &self.heap)?.into())
+    }
+
+    fn decode_atom(&mut self) -> Result<Atom, DecodeError> {
+        let tag = self.reader.read_u8()?;
+        self.decode_atom_with_tag(tag)
+    }
+
+    fn decode_atom_with_tag(&mut self, tag: u8) -> Result<Atom, DecodeError> {
+        let len = match tag {
+            ATOM_EXT | ATOM_UTF8_EXT => self.reader.read_u16()? as usize,
+            SMALL_ATOM_EXT | SMALL_ATOM_UTF8_EXT => self.reader.read_u8()? as usize,
+            tag => return Err(DecodeError::InvalidTag(tag)),
+        };
+        let bytes = self.reader.read_bytes(len)?;
+        let name = match tag {
+            ATOM_EXT | SMALL_ATOM_EXT => {
+                Cow::Owned(bytes.iter().map(|b| *b as char).collect::<String>())
+            }
+            _ => Cow::Borrowed(str::from_utf8(bytes).map_err(|_| DecodeError::Invalid)?),
+        };
+        if self.options.safe {
+            Atom::try_from_str_existing(name.as_ref()).map_err(|_| DecodeError::UnsafeAtom)
+        } else {
+            Atom::try_from(name.as_ref()).map_err(|_| DecodeError::Invalid)
+        }
+    }
+
+    fn make_reference(
+        &self,
+        name: Atom,
+        creation: u32,
+        words: [u32; 3],
+    ) -> Result<Term, DecodeError> {
+        let id = ReferenceId::from_words(words);
+        let reference = match resolve_node(name, creation) {
+            None => Reference::Local { id },
+            Some(node) => Reference::External { id, node },
+        };
+        Ok(GcBox::new_in(reference, &self.heap)?.into())
+    }
+
+    /// Older pid, port and reference encodings only carry the low byte of the node creation
+    fn decode_creation(&mut self, short: bool) -> Result<u32, DecodeError> {
+        if short {
+            self.reader.read_u8().map(|creation| creation as u32)
+        } else {
+            self.reader.read_u32()
+        }
+    }
+}
+
+/// The result of decoding the next term of the input
+enum Next {
+    Term(Term),
+    /// A non-empty container, whose elements follow
+    Container(Container),
+}
+
+/// A list, tuple or map whose elements have not all been decoded yet
+enum Container {
+    /// The cells of a list are allocated as each element is decoded, once there are no
+    /// `remaining` elements, the next term is the tail of the list
+    List {
+        remaining: u32,
+        first: Option<NonNull<Cons>>,
+        last: Option<NonNull<Cons>>,
+    },
+    Tuple {
+        arity: usize,
+        elements: Vec<OpaqueTerm>,
+    },
+    /// The key of the current entry is held until its value has been decoded
+    Map {
+        size: usize,
+        key: Option<Term>,
+        items: Vec<(Term, Term)>,
+    },
+}
+impl Container {
+    /// Adds the next decoded term to this container, returning the container as a term once
+    /// it is complete
+    fn push<H: Heap>(&mut self, term: Term, heap: &H) -> Result<Option<Term>, DecodeError> {
+        match self {
+            Self::List {
+                remaining: 0,
+                first,
+                last,
+            } => match (first, last) {
+                (Some(first), Some(last)) => {
+                    unsafe {
+                        last.as_mut().tail = term.into();
+                    }
+                    Ok(Some(Term::Cons(*first)))
+                }
+                // An empty list is just its tail
+                _ => Ok(Some(term)),
+            },
+            Self::List {
+                remaining,
+                first,
+                last,
+            } => {
+                let mut cell = Cons::new_in(heap)?;
+                unsafe {
+                    cell.as_uninit_mut().write(Cons {
+                        head: term.into(),
+                        tail: OpaqueTerm::NIL,
+                    });
+                }
+                match last {
+                    None => *first = Some(cell),
+                    Some(prev) => unsafe {
+                        prev.as_mut().tail = Term::Cons(cell).into();
+                    },
+                }
+                *last = Some(cell);
+                *remaining -= 1;
+                Ok(None)
+            }
+            Self::Tuple { arity, elements } => {
+                elements.push(term.into());
+                if elements.len() < *arity {
+                    return Ok(None);
+                }
+                Ok(Some(Tuple::from_slice(elements.as_slice(), heap)?.into()))
+            }
+            Self::Map { size, key, items } => match key.take() {
+                None => {
+                    *key = Some(term);
+                    Ok(None)
+                }
+                Some(key) => {
+                    items.push((key, term));
+                    if items.len() < *size {
+                        return Ok(None);
+                    }
+                    let items = mem::take(items);
+                    Ok(Some(Map::new_from_iter_in(items.into_iter(), heap)?.into()))
+                }
+            },
+        }
+    }
+}
+
+/// Returns the node with the given name and creation, or `None` if it is the local node
+fn resolve_node(name: Atom, creation: u32) -> Option<Arc<Node>> {
+    if name == local_node_name() && creation == LOCAL_CREATION {
+        None
+    } else {
+        Some(Node::get_or_insert(name, creation))
+    }
+}
@@ -0,0 +1,310 @@
+use alloc::format;
+use alloc::vec::Vec;
+
+use firefly_binary::Bitstring;
+use firefly_number::{BigInt, Sign, ToPrimitive};
+
+use crate::term::{Atom, Cons, Node, Pid, Port, Reference, Term};
+
+use super::*;
+
+/// Options which control how a term is encoded, see `erlang:term_to_binary/2`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct EncodeOptions {
+    /// The zlib compression level (0-9) to use, if the encoded term should be compressed
+    pub compression: Option<u32>,
+    /// The minor version of the format to produce.
+    ///
+    /// * `0` encodes floats in their textual representation
+    /// * `1` encodes floats in their binary representation, and atoms as Latin-1 when possible
+    /// * `2` encodes atoms as UTF-8, this is the default
+    pub minor_version: u8,
+}
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self {
+            compression: None,
+            minor_version: 2,
+        }
+    }
+}
+
+/// Produced when a term cannot be encoded
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EncodeError {
+    /// The term contains a closure with free variables, which we have no means of encoding
+    /// in a form that can be resolved by the receiver
+    Closure,
+    /// The term is not a valid Erlang value, e.g. `Term::None`
+    Invalid,
+}
+
+/// Encodes `term` in the external term format, returning the encoded bytes
+pub fn encode(term: Term, options: EncodeOptions) -> Result<Vec<u8>, EncodeError> {
+    let mut encoder = Encoder {
+        buffer: Vec::new(),
+        minor_version: options.minor_version,
+    };
+    encoder.buffer.push(VERSION);
+    encoder.encode_term(term)?;
+
+    match options.compression {
+        None | Some(0) => Ok(encoder.buffer),
+        Some(level) => {
+            let uncompressed = &encoder.buffer[1..];
+            let compressed =
+                miniz_oxide::deflate::compress_to_vec_zlib(uncompressed, level.min(9) as u8);
+            // Like the BEAM, we only produce a compressed term if it is actually smaller
+            if compressed.len() + 5 >= uncompressed.len() {
+                return Ok(encoder.buffer);
+            }
+            let mut buffer = Vec::with_capacity(compressed.len() + 6);
+            buffer.push(VERSION);
+            buffer.push(COMPRESSED_TERM);
+            buffer.extend_from_slice(&(uncompressed.len() as u32).to_be_bytes());
+            buffer.extend_from_slice(compressed.as_slice());
+            Ok(buffer)
+        }
+    }
+}
+
+struct Encoder {
+    buffer: Vec<u8>,
+    minor_version: u8,
+}
+impl Encoder {
+    fn encode_term(&mut self, term: Term) -> Result<(), EncodeError> {
+        match term {
+            Term::None => return Err(EncodeError::Invalid),
+            Term::Nil => self.buffer.push(NIL_EXT),
+            Term::Bool(b) => self.encode_atom(b.into()),
+            Term::Atom(a) => self.encode_atom(a),
+            Term::Int(i) => self.encode_integer(i),
+            Term::BigInt(i) => self.encode_big_integer(&i),
+            Term::Float(f) => self.encode_float(f.inner()),
+            Term::Cons(ptr) => return self.encode_list(unsafe { ptr.as_ref() }),
+            Term::Tuple(ptr) => {
+                let elements = unsafe { ptr.as_ref() }.as_slice();
+                if elements.len() < 256 {
+                    self.buffer.push(SMALL_TUPLE_EXT);
+                    self.buffer.push(elements.len() as u8);
+                } else {
+                    self.buffer.push(LARGE_TUPLE_EXT);
+                    self.push_u32(elements.len() as u32);
+                }
+                for element in elements {
+                    self.encode_term((*element).into())?;
+                }
+            }
+            Term::Map(map) => {
+                self.buffer.push(MAP_EXT);
+                self.push_u32(map.size() as u32);
+                for (k, v) in map.iter() {
+                    self.encode_term(*k)?;
+                    self.encode_term(*v)?;
+                }
+            }
+            Term::Closure(fun) => {
+                if !fun.is_thin() {
+                    return Err(EncodeError::Closure);
+                }
+                self.buffer.push(EXPORT_EXT);
+                self.encode_atom(fun.module);
+                self.encode_atom(fun.name);
+                self.encode_integer(fun.arity as i64);
+            }
+            Term::Pid(pid) => self.encode_pid(&pid),
+            Term::Port(port) => self.encode_port(&port),
+            Term::Reference(reference) => self.encode_reference(&reference),
+            Term::HeapBinary(_)
+            | Term::RcBinary(_)
+            | Term::RefBinary(_)
+            | Term::ConstantBinary(_) => self.encode_bitstring(term.as_bitstring().unwrap()),
+        }
+        Ok(())
+    }
+
+    /// Lists are encoded iteratively, so that long lists do not cause unbounded recursion
+    fn encode_list(&mut self, cons: &Cons) -> Result<(), EncodeError> {
+        if let Some(bytes) = as_string(cons) {
+            self.buffer.push(STRING_EXT);
+            self.buffer
+                .extend_from_slice(&(bytes.len() as u16).to_be_bytes());
+            self.buffer.extend_from_slice(bytes.as_slice());
+            return Ok(());
+        }
+
+        let mut len = 1;
+        let mut current = cons;
+        while let Term::Cons(next) = current.tail() {
+            len += 1;
+            current = unsafe { next.as_ref() };
+        }
+        self.buffer.push(LIST_EXT);
+        self.push_u32(len);
+
+        let mut current = cons;
+        loop {
+            self.encode_term(current.head())?;
+            match current.tail() {
+                Term::Cons(next) => current = unsafe { next.as_ref() },
+                tail => break self.encode_term(tail),
+            }
+        }
+    }
+
+    fn encode_atom(&mut self, atom: Atom) {
+        let name = atom.as_str();
+        if self.minor_version < 2 && name.chars().all(|c| (c as u32) < 256) {
+            self.buffer.push(ATOM_EXT);
+            self.buffer
+                .extend_from_slice(&(name.chars().count() as u16).to_be_bytes());
+            self.buffer.extend(name.chars().map(|c| c as u8));
+        } else if name.len() < 256 {
+            self.buffer.push(SMALL_ATOM_UTF8_EXT);
+            self.buffer.push(name.len() as u8);
+            self.buffer.extend_from_slice(name.as_bytes());
+        } else {
+            self.buffer.push(ATOM_UTF8_EXT);
+            self.buffer
+                .extend_from_slice(&(name.len() as u16).to_be_bytes());
+            self.buffer.extend_from_slice(name.as_bytes());
+        }
+    }
+
+    fn encode_integer(&mut self, i: i64) {
+        match i {
+            0..=255 => {
+                self.buffer.push(SMALL_INTEGER_EXT);
+                self.buffer.push(i as u8);
+            }
+            i if i32::try_from(i).is_ok() => {
+                self.buffer.push(INTEGER_EXT);
+                self.buffer.extend_from_slice(&(i as i32).to_be_bytes());
+            }
+            i => self.encode_big_integer(&BigInt::from(i)),
+        }
+    }
+
+    fn encode_big_integer(&mut self, i: &BigInt) {
+        if let Some(i) = i.to_i32() {
+            return self.encode_integer(i as i64);
+        }
+        let (sign, digits) = i.to_bytes_le();
+        if digits.len() < 256 {
+            self.buffer.push(SMALL_BIG_EXT);
+            self.buffer.push(digits.len() as u8);
+        } else {
+            self.buffer.push(LARGE_BIG_EXT);
+            self.push_u32(digits.len() as u32);
+        }
+        self.buffer.push((sign == Sign::Minus) as u8);
+        self.buffer.extend_from_slice(digits.as_slice());
+    }
+
+    fn encode_float(&mut self, f: f64) {
+        if self.minor_version > 0 {
+            self.buffer.push(NEW_FLOAT_EXT);
+            self.buffer.extend_from_slice(&f.to_bits().to_be_bytes());
+            return;
+        }
+        // The textual form is that produced by `printf("%.20e")`, padded with zeroes to 31 bytes
+        let formatted = format!("{:.20e}", f);
+        let (mantissa, exponent) = formatted.split_once('e').unwrap();
+        let exponent: i32 = exponent.parse().unwrap();
+        let sign = if exponent < 0 { '-' } else { '+' };
+        let formatted = format!("{}e{}{:02}", mantissa, sign, exponent.abs());
+        let mut bytes = [0; 31];
+        bytes[..formatted.len()].copy_from_slice(formatted.as_bytes());
+        self.buffer.push(FLOAT_EXT);
+        self.buffer.extend_from_slice(&bytes);
+    }
+
+    fn encode_bitstring(&mut self, bits: &dyn Bitstring) {
+        let byte_size = bits.byte_size();
+        if bits.is_binary() {
+            self.buffer.push(BINARY_EXT);
+            self.push_u32(byte_size as u32);
+        } else {
+            self.buffer.push(BIT_BINARY_EXT);
+            self.push_u32(byte_size as u32);
+            self.buffer.push((bits.bit_size() % 8) as u8);
+        }
+        self.buffer.extend(bits.bytes());
+    }
+
+    fn encode_pid(&mut self, pid: &Pid) {
+        let id = pid.id();
+        self.buffer.push(NEW_PID_EXT);
+        let creation = self.encode_node(pid.node().as_deref());
+        self.push_u32(id.number());
+        self.push_u32(id.serial());
+        self.push_u32(creation);
+    }
+
+    fn encode_port(&mut self, port: &Port) {
+        let (id, node) = match port {
+            Port::Local { id } => (id.as_u64(), None),
+            Port::External { id, node, .. } => (id.as_u64(), Some(node.as_ref())),
+        };
+        match u32::try_from(id) {
+            Ok(id) => {
+                self.buffer.push(NEW_PORT_EXT);
+                let creation = self.encode_node(node);
+                self.push_u32(id);
+                self.push_u32(creation);
+            }
+            Err(_) => {
+                self.buffer.push(V4_PORT_EXT);
+                let creation = self.encode_node(node);
+                self.buffer.extend_from_slice(&id.to_be_bytes());
+                self.push_u32(creation);
+            }
+        }
+    }
+
+    fn encode_reference(&mut self, reference: &Reference) {
+        let words = reference.id().words();
+        self.buffer.push(NEWER_REFERENCE_EXT);
+        self.buffer
+            .extend_from_slice(&(words.len() as u16).to_be_bytes());
+        let creation = self.encode_node(reference.node().as_deref());
+        self.push_u32(creation);
+        for word in words {
+            self.push_u32(word);
+        }
+    }
+
+    /// Encodes the name of `node`, or of the local node if `None`, returning its creation
+    fn encode_node(&mut self, node: Option<&Node>) -> u32 {
+        match node {
+            Some(node) => {
+                self.encode_atom(node.name().unwrap_or_else(local_node_name));
+                node.creation()
+            }
+            None => {
+                self.encode_atom(local_node_name());
+                LOCAL_CREATION
+            }
+        }
+    }
+
+    #[inline]
+    fn push_u32(&mut self, value: u32) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+/// Returns the bytes of `list`, if it is a proper list of bytes which can be encoded with `STRING_EXT`
+fn as_string(list: &Cons) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    for element in list.iter() {
+        match element {
+            Ok(Term::Int(i)) if bytes.len() < u16::MAX as usize => {
+                bytes.push(u8::try_from(i).ok()?)
+            }
+            _ => return None,
+        }
+    }
+    Some(bytes)
+}
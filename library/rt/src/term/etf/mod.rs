@@ -0,0 +1,218 @@
+//! This module implements the external term format, i.e. the serialization format used by
+//! `erlang:term_to_binary/1,2` and `erlang:binary_to_term/1,2`.
+//!
+//! Terms are encoded directly from, and decoded directly onto, a process heap, so no
+//! intermediate representation is needed to exchange terms with other nodes.
+//!
+//! See <https://www.erlang.org/doc/apps/erts/erl_ext_dist.html> for the specification.
+mod decode;
+mod encode;
+
+pub use self::decode::{decode, DecodeError, DecodeOptions};
+pub use self::encode::{encode, EncodeError, EncodeOptions};
+
+use super::Atom;
+
+const VERSION: u8 = 131;
+
+const NEW_FLOAT_EXT: u8 = 70;
+const BIT_BINARY_EXT: u8 = 77;
+const COMPRESSED_TERM: u8 = 80;
+const NEW_PID_EXT: u8 = 88;
+const NEW_PORT_EXT: u8 = 89;
+const NEWER_REFERENCE_EXT: u8 = 90;
+const SMALL_INTEGER_EXT: u8 = 97;
+const INTEGER_EXT: u8 = 98;
+const FLOAT_EXT: u8 = 99;
+const ATOM_EXT: u8 = 100;
+const REFERENCE_EXT: u8 = 101;
+const PORT_EXT: u8 = 102;
+const PID_EXT: u8 = 103;
+const SMALL_TUPLE_EXT: u8 = 104;
+const LARGE_TUPLE_EXT: u8 = 105;
+const NIL_EXT: u8 = 106;
+const STRING_EXT: u8 = 107;
+const LIST_EXT: u8 = 108;
+const BINARY_EXT: u8 = 109;
+const SMALL_BIG_EXT: u8 = 110;
+const LARGE_BIG_EXT: u8 = 111;
+const NEW_FUN_EXT: u8 = 112;
+const EXPORT_EXT: u8 = 113;
+const NEW_REFERENCE_EXT: u8 = 114;
+const SMALL_ATOM_EXT: u8 = 115;
+const MAP_EXT: u8 = 116;
+const FUN_EXT: u8 = 117;
+const ATOM_UTF8_EXT: u8 = 118;
+const SMALL_ATOM_UTF8_EXT: u8 = 119;
+const V4_PORT_EXT: u8 = 120;
+
+/// The creation of the local node, as used when encoding local pids, ports and references
+const LOCAL_CREATION: u32 = 0;
+
+/// Returns the name of the local node, as used when encoding local pids, ports and references
+///
+/// NOTE: Until distribution is supported, the local node is always `nonode@nohost`
+fn local_node_name() -> Atom {
+    Atom::try_from("nonode@nohost").unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use firefly_alloc::gc::GcBox;
+
+    use crate::process::{Process, ProcessHeap};
+    use crate::term::*;
+
+    use super::*;
+
+    fn roundtrip(process: &Process, term: Term, options: EncodeOptions) -> Term {
+        let bytes = encode(term, options).unwrap();
+        let (decoded, used) = decode(bytes.as_slice(), DecodeOptions::default(), process).unwrap();
+        assert_eq!(used, bytes.len());
+        decoded
+    }
+
+    #[test]
+    fn encodes_like_beam() {
+        let options = EncodeOptions::default();
+        assert_eq!(encode(Term::Int(1), options), Ok(vec![131, 97, 1]));
+        assert_eq!(
+            encode(Term::Int(-1), options),
+            Ok(vec![131, 98, 255, 255, 255, 255])
+        );
+        assert_eq!(
+            encode(Term::Int(1 << 40), options),
+            Ok(vec![131, 110, 6, 0, 0, 0, 0, 0, 0, 1])
+        );
+        assert_eq!(
+            encode(Term::Bool(true), options),
+            Ok(vec![131, 119, 4, 116, 114, 117, 101])
+        );
+        assert_eq!(encode(Term::Nil, options), Ok(vec![131, 106]));
+        assert_eq!(
+            encode(
+                Term::from(1.5),
+                EncodeOptions {
+                    minor_version: 0,
+                    ..options
+                }
+            ),
+            Ok({
+                let mut expected = vec![131, 99];
+                expected.extend_from_slice(b"1.50000000000000000000e+00");
+                expected.resize(2 + 31, 0);
+                expected
+            })
+        );
+        assert_eq!(
+            encode(
+                Term::Atom(atoms::Ok),
+                EncodeOptions {
+                    minor_version: 1,
+                    ..options
+                }
+            ),
+            Ok(vec![131, 100, 0, 2, 111, 107])
+        );
+    }
+
+    #[test]
+    fn roundtrips_terms() {
+        let process = Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap());
+        let options = EncodeOptions::default();
+
+        let string = Cons::charlist_from_str("hello", &process).unwrap().unwrap();
+        let elements: Vec<OpaqueTerm> = vec![
+            Term::Int(1).into(),
+            Term::Atom(atoms::Ok).into(),
+            Term::Cons(string).into(),
+            Term::from(-0.25).into(),
+        ];
+        let tuple = Term::Tuple(Tuple::from_slice(elements.as_slice(), &process).unwrap());
+        assert_eq!(roundtrip(&process, tuple, options), tuple);
+
+        let big = Term::BigInt(GcBox::new_in(BigInt::from(1i64 << 58), &process).unwrap());
+        let pid = Term::Pid(GcBox::new_in(Pid::new_local(42, 1).unwrap(), &process).unwrap());
+        let reference = Term::Reference(
+            GcBox::new_in(
+                Reference::Local {
+                    id: ReferenceId::new(1, 1 << 40),
+                },
+                &process,
+            )
+            .unwrap(),
+        );
+        let compressed = EncodeOptions {
+            compression: Some(6),
+            ..options
+        };
+        for term in [big, pid, reference] {
+            assert_eq!(roundtrip(&process, term, options), term);
+            assert_eq!(roundtrip(&process, term, compressed), term);
+        }
+    }
+
+    #[test]
+    fn safe_decoding_rejects_new_atoms() {
+        let process = Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap());
+        let bytes = [
+            131, 119, 9, b'e', b't', b'f', b'_', b'u', b'n', b's', b'e', b'e',
+        ];
+        let safe = DecodeOptions { safe: true };
+        assert_eq!(
+            decode(&bytes, safe, &process).map(|(term, _)| term),
+            Err(DecodeError::UnsafeAtom)
+        );
+        let (term, _) = decode(&bytes, DecodeOptions::default(), &process).unwrap();
+        assert_eq!(term, Term::Atom(Atom::try_from("etf_unsee").unwrap()));
+    }
+
+    #[test]
+    fn decodes_deeply_nested_terms() {
+        // Deep enough that decoding recursively would overflow the stack
+        const DEPTH: usize = 100_000;
+        let heap = ProcessHeap::with_size(64 << 20).unwrap();
+        let mut bytes = vec![131];
+        for depth in 0..DEPTH {
+            match depth % 3 {
+                // {Inner}
+                0 => bytes.extend_from_slice(&[104, 1]),
+                // #{[] => Inner}
+                1 => bytes.extend_from_slice(&[116, 0, 0, 0, 1, 106]),
+                // [Inner]
+                _ => bytes.extend_from_slice(&[108, 0, 0, 0, 1]),
+            }
+        }
+        bytes.push(106);
+        // The tails of the lists
+        bytes.extend(core::iter::repeat(106).take(DEPTH / 3));
+
+        let (term, used) = decode(bytes.as_slice(), DecodeOptions::default(), &heap).unwrap();
+        assert_eq!(used, bytes.len());
+        let Term::Tuple(tuple) = term else { panic!("expected tuple, got {:?}", term); };
+        let Term::Map(_) = unsafe { tuple.as_ref() }.as_slice()[0].into() else {
+            panic!("expected map in {:?}", term);
+        };
+    }
+
+    #[test]
+    fn rejects_references_with_more_than_three_words() {
+        let process = Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap());
+        let mut bytes = vec![131, 90, 0, 5];
+        bytes.extend_from_slice(&[119, 13]);
+        bytes.extend_from_slice(b"nonode@nohost");
+        bytes.extend(core::iter::repeat(0).take(4 + 5 * 4));
+        assert_eq!(
+            decode(bytes.as_slice(), DecodeOptions::default(), &process).map(|(term, _)| term),
+            Err(DecodeError::Invalid)
+        );
+
+        // The same reference with three words is accepted
+        bytes[3] = 3;
+        bytes.truncate(bytes.len() - 2 * 4);
+        assert!(decode(bytes.as_slice(), DecodeOptions::default(), &process).is_ok());
+    }
+}
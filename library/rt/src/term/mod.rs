@@ -1,6 +1,7 @@
 mod atom;
 mod binary;
 mod closure;
+pub mod etf;
 mod index;
 mod list;
 mod map;
@@ -28,6 +29,7 @@ pub use firefly_number::{BigInt, Float, Integer, Number};
 use firefly_number::{DivisionError, InvalidArithmeticError, Sign, ToPrimitive};
 
 use alloc::alloc::{AllocError, Layout};
+use alloc::vec::Vec;
 use core::convert::AsRef;
 use core::fmt;
 use core::ptr::NonNull;
@@ -115,12 +117,38 @@ impl Term {
                 if heap.contains(ptr.as_ptr()) {
                     Self::Cons(ptr)
                 } else {
-                    let old = unsafe { ptr.as_ref() };
-                    let cons = Cons::new_in(heap)?;
-                    unsafe {
-                        cons.as_uninit_mut().write(*old);
+                    // The spine of the list is copied iteratively, so that long lists
+                    // do not cause unbounded recursion
+                    let first = Cons::new_in(&heap)?;
+                    let mut current = first;
+                    let mut old = unsafe { ptr.as_ref() };
+                    loop {
+                        let head = old.head().clone_to_heap(&heap)?;
+                        unsafe {
+                            current.as_uninit_mut().write(Cons {
+                                head: head.into(),
+                                tail: OpaqueTerm::NIL,
+                            });
+                        }
+                        match old.tail() {
+                            Self::Cons(next) if !heap.contains(next.as_ptr()) => {
+                                let cons = Cons::new_in(&heap)?;
+                                unsafe {
+                                    current.as_mut().tail = Self::Cons(cons).into();
+                                }
+                                current = cons;
+                                old = unsafe { next.as_ref() };
+                            }
+                            tail => {
+                                let tail = tail.clone_to_heap(&heap)?;
+                                unsafe {
+                                    current.as_mut().tail = tail.into();
+                                }
+                                break;
+                            }
+                        }
                     }
-                    Self::Cons(cons)
+                    Self::Cons(first)
                 }
             }
             Self::Tuple(ptr) => {
@@ -128,23 +156,48 @@ impl Term {
                     Self::Tuple(ptr)
                 } else {
                     let tuple = unsafe { ptr.as_ref() };
-                    Self::Tuple(Tuple::from_slice(tuple.as_slice(), heap)?)
+                    let elements = tuple
+                        .as_slice()
+                        .iter()
+                        .map(|element| {
+                            let element: Term = (*element).into();
+                            element.clone_to_heap(&heap).map(OpaqueTerm::from)
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Self::Tuple(Tuple::from_slice(elements.as_slice(), heap)?)
                 }
             }
             Self::Map(boxed) => {
                 if heap.contains(GcBox::as_ptr(&boxed)) {
                     Self::Map(boxed)
                 } else {
-                    Self::Map(GcBox::new_in((&*boxed).clone(), heap)?)
+                    let items = boxed
+                        .iter()
+                        .map(|(k, v)| Ok((k.clone_to_heap(&heap)?, v.clone_to_heap(&heap)?)))
+                        .collect::<Result<Vec<_>, AllocError>>()?;
+                    Self::Map(Map::new_from_iter_in(items.into_iter(), heap)?)
                 }
             }
             Self::Closure(boxed) => {
                 if heap.contains(GcBox::as_ptr(&boxed)) {
                     Self::Closure(boxed)
                 } else {
-                    let mut cloned = GcBox::<Closure>::with_capacity_in(boxed.env_size(), heap)?;
-                    cloned.copy_from(&boxed);
-                    Self::Closure(cloned)
+                    let env = boxed
+                        .env()
+                        .iter()
+                        .map(|element| {
+                            let element: Term = (*element).into();
+                            element.clone_to_heap(&heap).map(OpaqueTerm::from)
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Self::Closure(Closure::new_in(
+                        boxed.module,
+                        boxed.name,
+                        boxed.arity as u8,
+                        boxed.callee(),
+                        env.as_slice(),
+                        heap,
+                    )?)
                 }
             }
             Self::Pid(boxed) => {
@@ -312,7 +365,25 @@ impl Term {
                     .unwrap();
                 base.pad_to_align()
             }
-            Self::Cons(_) => Layout::new::<Cons>(),
+            Self::Cons(ptr) => {
+                let mut layout = Layout::new::<Cons>();
+                let mut cons = unsafe { ptr.as_ref() };
+                loop {
+                    let (extended, _) = layout.extend(cons.head().layout()).unwrap();
+                    layout = extended.pad_to_align();
+                    match cons.tail() {
+                        Self::Cons(next) => {
+                            let (extended, _) = layout.extend(Layout::new::<Cons>()).unwrap();
+                            layout = extended.pad_to_align();
+                            cons = unsafe { next.as_ref() };
+                        }
+                        tail => {
+                            let (extended, _) = layout.extend(tail.layout()).unwrap();
+                            break extended.pad_to_align();
+                        }
+                    }
+                }
+            }
             Self::Tuple(t) => {
                 let tuple = unsafe { t.as_ref() };
                 let base = Layout::for_value(tuple);
@@ -1,9 +1,16 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::hash::{Hash, Hasher};
 use core::ptr::NonNull;
 use core::sync::atomic::AtomicPtr;
 
+use firefly_system::sync::{const_mutex, Mutex};
+
 use super::{atom::AtomData, Atom};
 
+/// The set of remote nodes known to this node, indexed by `id - 1`
+static NODES: Mutex<Vec<Arc<Node>>> = const_mutex(Vec::new());
+
 #[repr(C)]
 #[derive(Debug)]
 pub struct Node {
@@ -22,6 +29,24 @@ impl Node {
         }
     }
 
+    /// Returns the node with the given name and creation, registering it if it has not been
+    /// seen before.
+    ///
+    /// This is how nodes referenced by pids/ports/references received from other nodes are
+    /// resolved, so that terms which refer to the same node share the same `Node`.
+    pub fn get_or_insert(name: Atom, creation: u32) -> Arc<Node> {
+        let mut nodes = NODES.lock();
+        if let Some(node) = nodes
+            .iter()
+            .find(|node| node.name() == Some(name) && node.creation == creation)
+        {
+            return node.clone();
+        }
+        let node = Arc::new(Node::new(nodes.len() + 1, name, creation));
+        nodes.push(node.clone());
+        node
+    }
+
     /// Returns the numeric identifier associated with this node
     pub fn id(&self) -> usize {
         self.id
@@ -143,7 +143,7 @@ impl ProcessId {
     /// NOTE: The value returned is guaranteed to never exceed 31 significant bits, so
     /// as to remain compatible with External Term Format.
     pub fn serial(&self) -> u32 {
-        (self.0 & Self::SERIAL_MASK >> 32) as u32
+        ((self.0 & Self::SERIAL_MASK) >> 32) as u32
     }
 
     /// Creates a process identifier from the given number and serial components, manually.
@@ -162,8 +162,13 @@ impl Hash for Reference {
     }
 }
 
+/// The unique identifier of a reference.
+///
+/// This is stored in the same form as the external term format uses, i.e. as three 32-bit
+/// words, so that references received from other nodes can be represented without loss.
+/// References created locally only ever use the low 18 bits of the first word.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct ReferenceId([u16; 4]);
+pub struct ReferenceId([u32; 3]);
 impl ReferenceId {
     /// Create a new reference id from raw components
     ///
@@ -172,24 +177,34 @@ impl ReferenceId {
         const MASK: u64 = 0xFFFF << 48;
         assert_eq!(id & MASK, 0, "invalid reference id, value is too large");
         let id = id | ((scheduler_id as u64) << 48);
-        Self(unsafe { core::mem::transmute::<u64, [u16; 4]>(id) })
+        Self([(id & 0x3FFFF) as u32, (id >> 18) as u32, (id >> 50) as u32])
+    }
+
+    /// Create a reference id from the words of an external term format reference
+    pub fn from_words(words: [u32; 3]) -> Self {
+        Self(words)
+    }
+
+    /// Returns the words of this reference id, as used by the external term format
+    pub fn words(&self) -> [u32; 3] {
+        self.0
     }
 
     /// Return the scheduler id contained in this reference
     pub fn scheduler_id(&self) -> u16 {
-        self.0[0]
+        (self.as_u64() >> 48) as u16
     }
 
     /// Get this reference id as a raw 64-bit integer value
+    ///
+    /// NOTE: References from other nodes may hold more than 64 bits of data, in which case
+    /// the value returned here is truncated.
     pub fn as_u64(&self) -> u64 {
-        unsafe { core::mem::transmute::<[u16; 4], u64>(self.0) }
+        (self.0[0] as u64 & 0x3FFFF) | ((self.0[1] as u64) << 18) | ((self.0[2] as u64) << 50)
     }
 }
 impl Display for ReferenceId {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let r0 = self.0[1];
-        let r1 = self.0[2];
-        let r2 = self.0[3];
-        write!(f, "{}.{}.{}", r0, r1, r2)
+        write!(f, "{}.{}.{}", self.0[2], self.0[1], self.0[0])
     }
 }
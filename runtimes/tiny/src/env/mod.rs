@@ -2,9 +2,11 @@ use std::alloc::Layout;
 use std::borrow::Borrow;
 use std::env::ArgsOs;
 use std::mem;
+use std::num::NonZeroUsize;
 use std::path::Path;
 use std::ptr;
 use std::sync::OnceLock;
+use std::thread;
 
 use anyhow::anyhow;
 
@@ -12,13 +14,41 @@ use firefly_arena::DroplessArena;
 use firefly_binary::{BinaryFlags, Encoding};
 use firefly_rt::term::BinaryData;
 
+use crate::time::TimeWarpMode;
+
 static ARGV: OnceLock<EnvTable> = OnceLock::new();
+static SCHEDULERS: OnceLock<usize> = OnceLock::new();
+static TIME_WARP_MODE: OnceLock<TimeWarpMode> = OnceLock::new();
+
+/// The maximum number of schedulers, as in BEAM
+const MAX_SCHEDULERS: usize = 1024;
 
 /// Returns all arguments this executable was invoked with
 pub fn argv() -> &'static [&'static BinaryData] {
     ARGV.get().unwrap().argv.as_slice()
 }
 
+/// Returns the number of schedulers to run
+///
+/// This is given by the `+S Schedulers[:SchedulersOnline]` flag, and defaults to the number
+/// of cores available.
+pub fn schedulers() -> usize {
+    *SCHEDULERS.get_or_init(|| {
+        thread::available_parallelism()
+            .map(NonZeroUsize::get)
+            .unwrap_or(1)
+            .min(MAX_SCHEDULERS)
+    })
+}
+
+/// Returns the time warp mode
+///
+/// This is given by the `+C no_time_warp | single_time_warp | multi_time_warp` flag, and
+/// defaults to `no_time_warp`.
+pub fn time_warp_mode() -> TimeWarpMode {
+    *TIME_WARP_MODE.get_or_init(TimeWarpMode::default)
+}
+
 /// Performs one-time initialization of the environment for the current executable.
 /// This is used to cache the arguments vector as constant binary values.
 pub fn init(mut argv: ArgsOs) -> anyhow::Result<()> {
@@ -67,8 +97,30 @@ pub fn init(mut argv: ArgsOs) -> anyhow::Result<()> {
         }
     }
 
-    for arg in argv {
+    while let Some(arg) = argv.next() {
         let arg = arg.to_string_lossy();
+        // Emulator flags are consumed here, and are not visible to init
+        if let Some(value) = arg
+            .strip_prefix("+S")
+            .filter(|value| value.is_empty() || value.starts_with(|c: char| c.is_ascii_digit()))
+        {
+            let value = flag_value("+S", value, &mut argv)?;
+            let schedulers = parse_schedulers(&value)?;
+            SCHEDULERS
+                .set(schedulers)
+                .map_err(|_| anyhow!("schedulers were already initialized"))?;
+            continue;
+        }
+        if let Some(value) = arg.strip_prefix("+C") {
+            let value = flag_value("+C", value, &mut argv)?;
+            let mode = value
+                .parse::<TimeWarpMode>()
+                .map_err(|_| anyhow!("invalid time warp mode '{}'", value))?;
+            TIME_WARP_MODE
+                .set(mode)
+                .map_err(|_| anyhow!("time warp mode was already initialized"))?;
+            continue;
+        }
         unsafe {
             table.insert(arg.as_bytes());
         }
@@ -81,6 +133,34 @@ pub fn init(mut argv: ArgsOs) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Returns the value of an emulator flag, which is either attached to the flag, as in `+S4`,
+/// or given as the next argument, as in `+S 4`
+fn flag_value(flag: &str, value: &str, argv: &mut ArgsOs) -> anyhow::Result<String> {
+    if value.is_empty() {
+        argv.next()
+            .map(|value| value.to_string_lossy().into_owned())
+            .ok_or_else(|| anyhow!("missing value for {}", flag))
+    } else {
+        Ok(value.to_owned())
+    }
+}
+
+/// Parses the value of `+S`, returning the number of schedulers online
+///
+/// The number of schedulers and the number online are the same thing in this runtime, the
+/// latter is used if both are given.
+fn parse_schedulers(value: &str) -> anyhow::Result<usize> {
+    let online = value.rsplit(':').next().unwrap();
+    match online.parse::<usize>() {
+        Ok(n) if n > 0 && n <= MAX_SCHEDULERS => Ok(n),
+        _ => Err(anyhow!(
+            "invalid number of schedulers '{}', expected 1 to {}",
+            value,
+            MAX_SCHEDULERS
+        )),
+    }
+}
+
 #[derive(Default)]
 struct EnvTable {
     argv: Vec<&'static BinaryData>,
@@ -0,0 +1,99 @@
+use std::ops::Deref;
+use std::ptr::NonNull;
+
+use firefly_rt::backtrace::Trace;
+use firefly_rt::error::ErlangException;
+use firefly_rt::function::ErlangResult;
+use firefly_rt::term::*;
+
+use crate::scheduler;
+
+use super::badarg;
+
+/// Returns an iterator over the entries of `map`, to be consumed with `maps:next/1`
+///
+/// Iterators are represented as a list of `{Key, Value}` pairs, in key order.
+#[export_name = "maps:iterator/1"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn iterator(map: OpaqueTerm) -> ErlangResult {
+    scheduler::with_current(|scheduler| {
+        let arc_proc = scheduler.current_process();
+        let proc = arc_proc.deref();
+        match map.into() {
+            Term::Map(m) => {
+                let mut entries = m.iter().collect::<Vec<_>>();
+                entries.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+                let mut iter = OpaqueTerm::NIL;
+                for (key, value) in entries.into_iter().rev() {
+                    let pair = Tuple::from_slice(&[(*key).into(), (*value).into()], proc).unwrap();
+                    let mut ptr = Cons::new_in(proc).unwrap();
+                    let cell = unsafe { ptr.as_mut() };
+                    cell.head = pair.into();
+                    cell.tail = iter;
+                    iter = OpaqueTerm::from(ptr);
+                }
+                ErlangResult::Ok(iter)
+            }
+            _ => {
+                let reason = Tuple::from_slice(&[atoms::Badmap.into(), map], proc).unwrap();
+                let err = ErlangException::new(atoms::Error, reason.into(), Trace::capture());
+                ErlangResult::Err(unsafe { NonNull::new_unchecked(Box::into_raw(err)) })
+            }
+        }
+    })
+}
+
+/// Advances an iterator obtained from `maps:iterator/1`
+///
+/// Returns `{Key, Value, NextIterator}`, or `none` if there are no entries remaining.
+#[export_name = "maps:next/1"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn next(iter: OpaqueTerm) -> ErlangResult {
+    if iter.is_nil() {
+        return ErlangResult::Ok(atoms::None.into());
+    }
+
+    match iter.into() {
+        Term::Cons(cons) => {
+            let cons = unsafe { cons.as_ref() };
+            match cons.head() {
+                Term::Tuple(pair) => {
+                    let pair = unsafe { pair.as_ref() };
+                    match pair.as_ref() {
+                        &[key, value] => scheduler::with_current(|scheduler| {
+                            let arc_proc = scheduler.current_process();
+                            let proc = arc_proc.deref();
+                            let next = Tuple::from_slice(&[key, value, cons.tail], proc).unwrap();
+                            ErlangResult::Ok(next.into())
+                        }),
+                        _ => badarg(Trace::capture()),
+                    }
+                }
+                _ => badarg(Trace::capture()),
+            }
+        }
+        _ => badarg(Trace::capture()),
+    }
+}
+
+/// Constructs a map from a list of `{Key, Value}` pairs
+///
+/// If the same key appears more than once, the last value associated with it is used.
+#[export_name = "maps:from_list/1"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn from_list(list: OpaqueTerm) -> ErlangResult {
+    scheduler::with_current(|scheduler| {
+        let arc_proc = scheduler.current_process();
+        let proc = arc_proc.deref();
+        if list.is_nil() {
+            return ErlangResult::Ok(Map::new_in(proc).unwrap().into());
+        }
+        match list.into() {
+            Term::Cons(cons) => match Map::from_keyword_list_in(unsafe { cons.as_ref() }, proc) {
+                Ok(map) => ErlangResult::Ok(map.into()),
+                Err(_) => badarg(Trace::capture()),
+            },
+            _ => badarg(Trace::capture()),
+        }
+    })
+}
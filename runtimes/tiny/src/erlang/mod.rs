@@ -1,7 +1,9 @@
 pub mod file;
 pub mod lists;
+pub mod maps;
 pub mod unicode;
 
+use std::borrow::Cow;
 use std::io::Write;
 use std::ops::Deref;
 use std::ptr::NonNull;
@@ -10,12 +12,15 @@ use std::sync::Arc;
 use smallvec::SmallVec;
 
 use firefly_alloc::gc::GcBox;
+use firefly_alloc::rc::Rc;
 use firefly_rt::backtrace::Trace;
 use firefly_rt::error::ErlangException;
 use firefly_rt::function::{self, ErlangResult, ModuleFunctionArity};
+use firefly_rt::process::Process;
 use firefly_rt::term::*;
 
-use crate::scheduler;
+use crate::scheduler::{self, Destination};
+use crate::time::{self, TimeUnit};
 
 macro_rules! handle_arith_result {
     ($math:expr) => {
@@ -342,6 +347,109 @@ pub extern "C-unwind" fn binary_to_list(term: OpaqueTerm) -> ErlangResult {
     }
 }
 
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:term_to_binary/1"]
+pub extern "C-unwind" fn term_to_binary1(term: OpaqueTerm) -> ErlangResult {
+    term_to_binary2(term, OpaqueTerm::NIL)
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:term_to_binary/2"]
+pub extern "C-unwind" fn term_to_binary2(term: OpaqueTerm, options: OpaqueTerm) -> ErlangResult {
+    let mut opts = etf::EncodeOptions::default();
+    match options.into() {
+        Term::Nil => (),
+        Term::Cons(ptr) => {
+            for option in unsafe { ptr.as_ref().iter() } {
+                match option {
+                    Ok(Term::Atom(a)) if a == atoms::Compressed => opts.compression = Some(6),
+                    Ok(Term::Tuple(ptr)) => match unsafe { ptr.as_ref() }.as_slice() {
+                        &[key, value] if key == atoms::Compressed.into() => match value.into() {
+                            Term::Int(level @ 0..=9) => opts.compression = Some(level as u32),
+                            _ => return badarg(Trace::capture()),
+                        },
+                        &[key, value] if key == atoms::MinorVersion.into() => match value.into() {
+                            Term::Int(version @ 0..=2) => opts.minor_version = version as u8,
+                            _ => return badarg(Trace::capture()),
+                        },
+                        _ => return badarg(Trace::capture()),
+                    },
+                    _ => return badarg(Trace::capture()),
+                }
+            }
+        }
+        _ => return badarg(Trace::capture()),
+    }
+
+    let Ok(bytes) = etf::encode(term.into(), opts) else { return badarg(Trace::capture()); };
+    scheduler::with_current_process(|proc| ErlangResult::Ok(make_binary(bytes.as_slice(), proc)))
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:binary_to_term/1"]
+pub extern "C-unwind" fn binary_to_term1(binary: OpaqueTerm) -> ErlangResult {
+    binary_to_term2(binary, OpaqueTerm::NIL)
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:binary_to_term/2"]
+pub extern "C-unwind" fn binary_to_term2(binary: OpaqueTerm, options: OpaqueTerm) -> ErlangResult {
+    let mut opts = etf::DecodeOptions::default();
+    let mut used = false;
+    match options.into() {
+        Term::Nil => (),
+        Term::Cons(ptr) => {
+            for option in unsafe { ptr.as_ref().iter() } {
+                match option {
+                    Ok(Term::Atom(a)) if a == atoms::Safe => opts.safe = true,
+                    Ok(Term::Atom(a)) if a == atoms::Used => used = true,
+                    _ => return badarg(Trace::capture()),
+                }
+            }
+        }
+        _ => return badarg(Trace::capture()),
+    }
+
+    let t: Term = binary.into();
+    let Some(bits) = t.as_bitstring().filter(|bits| bits.is_binary()) else { return badarg(Trace::capture()); };
+    let bytes: Cow<'_, [u8]> = if bits.is_aligned() {
+        Cow::Borrowed(unsafe { bits.as_bytes_unchecked() })
+    } else {
+        Cow::Owned(bits.bytes().collect())
+    };
+
+    scheduler::with_current_process(|proc| {
+        match etf::decode(bytes.as_ref(), opts, proc) {
+            Ok((term, n)) if used => {
+                let n = Term::try_from(n).unwrap();
+                let result = Tuple::from_slice(&[term.into(), n.into()], proc).unwrap();
+                ErlangResult::Ok(result.into())
+            }
+            // Without `used`, the binary must contain exactly one term
+            Ok((term, n)) if n == bytes.len() => ErlangResult::Ok(term.into()),
+            _ => badarg(Trace::capture()),
+        }
+    })
+}
+
+/// Allocates a binary containing `bytes` for `proc`
+fn make_binary(bytes: &[u8], proc: &Process) -> OpaqueTerm {
+    if bytes.len() <= BinaryData::MAX_HEAP_BYTES {
+        let mut bin = BinaryData::with_capacity_small(bytes.len(), proc).unwrap();
+        bin.copy_from_slice(bytes);
+        bin.into()
+    } else {
+        let mut bin = BinaryData::with_capacity_large(bytes.len(), proc).unwrap();
+        {
+            // SAFETY: There can be no other references to this Rc yet,
+            // so we know this is safe
+            let b = unsafe { Rc::get_mut(&mut bin).unwrap_unchecked() };
+            b.copy_from_slice(bytes);
+        }
+        bin.into()
+    }
+}
+
 #[export_name = "erlang:display/1"]
 pub extern "C-unwind" fn display(term: OpaqueTerm) -> ErlangResult {
     let term: Term = term.into();
@@ -446,6 +554,837 @@ pub extern "C-unwind" fn raise2(reason: OpaqueTerm, trace: NonNull<Trace>) -> Er
     ErlangResult::Err(unsafe { NonNull::new_unchecked(Box::into_raw(err)) })
 }
 
+#[export_name = "erlang:self/0"]
+pub extern "C-unwind" fn self0() -> OpaqueTerm {
+    scheduler::with_current(|scheduler| {
+        let arc_proc = scheduler.current_process();
+        let proc = arc_proc.deref();
+        let pid = Pid::Local { id: proc.pid() };
+        GcBox::new_in(pid, proc).unwrap().into()
+    })
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:send/2"]
+pub extern "C-unwind" fn send2(dest: OpaqueTerm, message: OpaqueTerm) -> ErlangResult {
+    let id = match dest.into() {
+        Term::Pid(pid) => match pid.as_ref() {
+            Pid::Local { id } => *id,
+            _ => return badarg(Trace::capture()),
+        },
+        // Sending to a name which is not registered is an error
+        Term::Atom(name) => match scheduler::with_current(|scheduler| scheduler.whereis(name)) {
+            Some(id) => id,
+            None => return badarg(Trace::capture()),
+        },
+        _ => return badarg(Trace::capture()),
+    };
+
+    scheduler::with_current(|scheduler| {
+        // Sending to a process which no longer exists is not an error, the message is dropped
+        if let Some(process) = scheduler.get_process(id) {
+            if process.send(message.into()).unwrap() {
+                scheduler.wake(id);
+            }
+        }
+    });
+
+    ErlangResult::Ok(message)
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:!/2"]
+pub extern "C-unwind" fn bang2(dest: OpaqueTerm, message: OpaqueTerm) -> ErlangResult {
+    send2(dest, message)
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:register/2"]
+pub extern "C-unwind" fn register2(name: OpaqueTerm, pid: OpaqueTerm) -> ErlangResult {
+    let Term::Atom(name) = name.into() else { return badarg(Trace::capture()); };
+    if name == atoms::Undefined {
+        return badarg(Trace::capture());
+    }
+    let Term::Pid(pid) = pid.into() else { return badarg(Trace::capture()); };
+    let Pid::Local { id } = pid.as_ref() else { return badarg(Trace::capture()); };
+
+    if scheduler::with_current(|scheduler| scheduler.register(name, *id)) {
+        ErlangResult::Ok(true.into())
+    } else {
+        badarg(Trace::capture())
+    }
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:unregister/1"]
+pub extern "C-unwind" fn unregister1(name: OpaqueTerm) -> ErlangResult {
+    let Term::Atom(name) = name.into() else { return badarg(Trace::capture()); };
+
+    if scheduler::with_current(|scheduler| scheduler.unregister(name)) {
+        ErlangResult::Ok(true.into())
+    } else {
+        badarg(Trace::capture())
+    }
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:whereis/1"]
+pub extern "C-unwind" fn whereis1(name: OpaqueTerm) -> ErlangResult {
+    let Term::Atom(name) = name.into() else { return badarg(Trace::capture()); };
+
+    scheduler::with_current(|scheduler| match scheduler.whereis(name) {
+        Some(id) => {
+            let arc_proc = scheduler.current_process();
+            let proc = arc_proc.deref();
+            let pid = GcBox::new_in(Pid::Local { id }, proc).unwrap();
+            ErlangResult::Ok(pid.into())
+        }
+        None => ErlangResult::Ok(atoms::Undefined.into()),
+    })
+}
+
+/// This function acts as the entry point for processes spawned via `spawn/1,3` and friends.
+///
+/// The init term is either a closure of arity zero, or a `{Module, Function, Args}` tuple.
+///
+/// NOTE: When this function is invoked, it is on the stack of the new process, not the scheduler.
+#[allow(improper_ctypes_definitions)]
+pub(crate) extern "C-unwind" fn process_start(init: OpaqueTerm) -> ErlangResult {
+    match init.into() {
+        Term::Closure(fun) => fun.apply(&[]),
+        Term::Tuple(ptr) => match unsafe { ptr.as_ref() }.as_slice() {
+            &[module, function, args] => apply3(module, function, args),
+            other => panic!("invalid process init tuple: {:?}", other),
+        },
+        other => panic!("invalid process init term: {:?}", other),
+    }
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:spawn/1"]
+pub extern "C-unwind" fn spawn1(fun: OpaqueTerm) -> ErlangResult {
+    spawn_fun(fun, SpawnOpts::default())
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:spawn/3"]
+pub extern "C-unwind" fn spawn3(
+    module: OpaqueTerm,
+    function: OpaqueTerm,
+    args: OpaqueTerm,
+) -> ErlangResult {
+    spawn_mfa(module, function, args, SpawnOpts::default())
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:spawn_link/1"]
+pub extern "C-unwind" fn spawn_link1(fun: OpaqueTerm) -> ErlangResult {
+    spawn_fun(fun, SpawnOpts::LINK)
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:spawn_link/3"]
+pub extern "C-unwind" fn spawn_link3(
+    module: OpaqueTerm,
+    function: OpaqueTerm,
+    args: OpaqueTerm,
+) -> ErlangResult {
+    spawn_mfa(module, function, args, SpawnOpts::LINK)
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:spawn_monitor/1"]
+pub extern "C-unwind" fn spawn_monitor1(fun: OpaqueTerm) -> ErlangResult {
+    spawn_fun(fun, SpawnOpts::MONITOR)
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:spawn_monitor/3"]
+pub extern "C-unwind" fn spawn_monitor3(
+    module: OpaqueTerm,
+    function: OpaqueTerm,
+    args: OpaqueTerm,
+) -> ErlangResult {
+    spawn_mfa(module, function, args, SpawnOpts::MONITOR)
+}
+
+#[derive(Default, Copy, Clone)]
+struct SpawnOpts {
+    link: bool,
+    monitor: bool,
+}
+impl SpawnOpts {
+    const LINK: Self = Self {
+        link: true,
+        monitor: false,
+    };
+    const MONITOR: Self = Self {
+        link: false,
+        monitor: true,
+    };
+}
+
+fn spawn_fun(fun: OpaqueTerm, opts: SpawnOpts) -> ErlangResult {
+    let Term::Closure(closure) = fun.into() else { return badarg(Trace::capture()); };
+    if closure.arity != 0 {
+        return badarg(Trace::capture());
+    }
+    let mfa = ModuleFunctionArity::new(closure.module, closure.name, 0);
+    spawn_internal(mfa, Term::Closure(closure), opts)
+}
+
+fn spawn_mfa(
+    module: OpaqueTerm,
+    function: OpaqueTerm,
+    args: OpaqueTerm,
+    opts: SpawnOpts,
+) -> ErlangResult {
+    let arity = match args.into() {
+        Term::Nil => 0,
+        Term::Cons(ptr) => {
+            let mut arity = 0;
+            for element in unsafe { ptr.as_ref().iter() } {
+                if element.is_err() {
+                    return badarg(Trace::capture());
+                }
+                arity += 1;
+            }
+            arity
+        }
+        _ => return badarg(Trace::capture()),
+    };
+    let (Term::Atom(m), Term::Atom(f)) = (module.into(), function.into()) else { return badarg(Trace::capture()); };
+    let mfa = ModuleFunctionArity::new(m, f, arity);
+
+    scheduler::with_current(|scheduler| {
+        let arc_proc = scheduler.current_process();
+        let proc = arc_proc.deref();
+        let init = Tuple::from_slice(&[module, function, args], proc).unwrap();
+        spawn_internal(mfa, init.into(), opts)
+    })
+}
+
+fn spawn_internal(mfa: ModuleFunctionArity, init: Term, opts: SpawnOpts) -> ErlangResult {
+    scheduler::with_current(|scheduler| {
+        let arc_proc = scheduler.current_process();
+        let proc = arc_proc.deref();
+        let reference = opts.monitor.then(|| scheduler.next_reference_id());
+        // The child may be run by another scheduler as soon as it is spawned, so links and
+        // monitors must be set up before then
+        let child = scheduler
+            .spawn(proc, mfa, init, |child| {
+                if opts.link {
+                    proc.links().link(child.pid());
+                    child.links().link(proc.pid());
+                }
+                if let Some(reference) = reference {
+                    proc.links().monitoring.insert(reference, child.pid());
+                    child.links().monitored_by.insert(reference, proc.pid());
+                }
+            })
+            .unwrap();
+        let pid = GcBox::new_in(Pid::Local { id: child.pid() }, proc).unwrap();
+        if let Some(reference) = reference {
+            let reference = GcBox::new_in(Reference::Local { id: reference }, proc).unwrap();
+            let result = Tuple::from_slice(&[pid.into(), reference.into()], proc).unwrap();
+            ErlangResult::Ok(result.into())
+        } else {
+            ErlangResult::Ok(pid.into())
+        }
+    })
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:link/1"]
+pub extern "C-unwind" fn link1(pid: OpaqueTerm) -> ErlangResult {
+    let Term::Pid(pid) = pid.into() else { return badarg(Trace::capture()); };
+    let Pid::Local { id } = pid.as_ref() else { return ErlangResult::Ok(true.into()); };
+    let id = *id;
+
+    scheduler::with_current(|scheduler| {
+        let arc_proc = scheduler.current_process();
+        let proc = arc_proc.deref();
+        if id == proc.pid() {
+            return ErlangResult::Ok(true.into());
+        }
+        match scheduler.get_process(id) {
+            Some(other) => {
+                proc.links().link(id);
+                other.links().link(proc.pid());
+            }
+            // Linking to a process which does not exist behaves as if the link was
+            // established, and then immediately broken with reason `noproc`
+            None if proc.trap_exit() => {
+                let message =
+                    Tuple::from_slice(&[atoms::EXIT.into(), pid.into(), atoms::Noproc.into()], proc)
+                        .unwrap();
+                proc.send(message.into()).unwrap();
+            }
+            None => {
+                let err = ErlangException::new(atoms::Error, atoms::Noproc.into(), Trace::capture());
+                return ErlangResult::Err(unsafe { NonNull::new_unchecked(Box::into_raw(err)) });
+            }
+        }
+        ErlangResult::Ok(true.into())
+    })
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:unlink/1"]
+pub extern "C-unwind" fn unlink1(pid: OpaqueTerm) -> ErlangResult {
+    let Term::Pid(pid) = pid.into() else { return badarg(Trace::capture()); };
+    let Pid::Local { id } = pid.as_ref() else { return ErlangResult::Ok(true.into()); };
+
+    scheduler::with_current(|scheduler| {
+        let arc_proc = scheduler.current_process();
+        let proc = arc_proc.deref();
+        proc.links().unlink(*id);
+        if let Some(other) = scheduler.get_process(*id) {
+            other.links().unlink(proc.pid());
+        }
+        ErlangResult::Ok(true.into())
+    })
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:monitor/2"]
+pub extern "C-unwind" fn monitor2(kind: OpaqueTerm, item: OpaqueTerm) -> ErlangResult {
+    let (Term::Atom(kind), Term::Pid(pid)) = (kind.into(), item.into()) else { return badarg(Trace::capture()); };
+    if kind != atoms::Process {
+        return badarg(Trace::capture());
+    }
+
+    scheduler::with_current(|scheduler| {
+        let arc_proc = scheduler.current_process();
+        let proc = arc_proc.deref();
+        let id = scheduler.next_reference_id();
+        let reference = GcBox::new_in(Reference::Local { id }, proc).unwrap();
+
+        let target = match pid.as_ref() {
+            Pid::Local { id } => scheduler.get_process(*id),
+            Pid::External { .. } => None,
+        };
+        match target {
+            Some(target) => {
+                proc.links().monitoring.insert(id, target.pid());
+                target.links().monitored_by.insert(id, proc.pid());
+            }
+            // Monitoring a process which does not exist immediately produces a 'DOWN' message
+            None => {
+                let message = Tuple::from_slice(
+                    &[
+                        atoms::DOWN.into(),
+                        reference.into(),
+                        atoms::Process.into(),
+                        pid.into(),
+                        atoms::Noproc.into(),
+                    ],
+                    proc,
+                )
+                .unwrap();
+                proc.send(message.into()).unwrap();
+            }
+        }
+
+        ErlangResult::Ok(reference.into())
+    })
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:demonitor/1"]
+pub extern "C-unwind" fn demonitor1(reference: OpaqueTerm) -> ErlangResult {
+    demonitor2(reference, OpaqueTerm::NIL)
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:demonitor/2"]
+pub extern "C-unwind" fn demonitor2(reference: OpaqueTerm, options: OpaqueTerm) -> ErlangResult {
+    let Term::Reference(reference) = reference.into() else { return badarg(Trace::capture()); };
+    let id = reference.id();
+
+    let mut flush = false;
+    let mut info = false;
+    match options.into() {
+        Term::Nil => (),
+        Term::Cons(ptr) => {
+            for option in unsafe { ptr.as_ref().iter() } {
+                match option {
+                    Ok(Term::Atom(a)) if a == atoms::Flush => flush = true,
+                    Ok(Term::Atom(a)) if a == atoms::Info => info = true,
+                    _ => return badarg(Trace::capture()),
+                }
+            }
+        }
+        _ => return badarg(Trace::capture()),
+    }
+
+    scheduler::with_current(|scheduler| {
+        let arc_proc = scheduler.current_process();
+        let proc = arc_proc.deref();
+        let target = proc.links().monitoring.remove(&id);
+        let found = match target {
+            None => false,
+            Some(target) => {
+                if let Some(target) = scheduler.get_process(target) {
+                    target.links().monitored_by.remove(&id);
+                }
+                true
+            }
+        };
+
+        if flush {
+            proc.mailbox()
+                .retain(|message| !is_down_message(message.term(), id));
+        }
+
+        // With `info`, the result indicates whether the monitor was found and removed,
+        // if not, it has already been triggered
+        ErlangResult::Ok((found || !info).into())
+    })
+}
+
+fn is_down_message(message: OpaqueTerm, id: ReferenceId) -> bool {
+    let Term::Tuple(ptr) = message.into() else { return false; };
+    match unsafe { ptr.as_ref() }.as_slice() {
+        &[tag, reference, _, _, _] if tag == atoms::DOWN.into() => match reference.into() {
+            Term::Reference(reference) => reference.id() == id,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:process_flag/2"]
+pub extern "C-unwind" fn process_flag2(flag: OpaqueTerm, value: OpaqueTerm) -> ErlangResult {
+    match (flag.into(), value.into()) {
+        (Term::Atom(flag), Term::Bool(value)) if flag == atoms::TrapExit => {
+            scheduler::with_current_process(|proc| {
+                ErlangResult::Ok(proc.set_trap_exit(value).into())
+            })
+        }
+        _ => badarg(Trace::capture()),
+    }
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:process_info/2"]
+pub extern "C-unwind" fn process_info2(pid: OpaqueTerm, item: OpaqueTerm) -> ErlangResult {
+    let Term::Pid(pid) = pid.into() else { return badarg(Trace::capture()); };
+    let Pid::Local { id } = pid.as_ref() else { return badarg(Trace::capture()); };
+    // Only the reductions of a process are currently available
+    let Term::Atom(item) = item.into() else { return badarg(Trace::capture()); };
+    if item != atoms::Reductions {
+        return badarg(Trace::capture());
+    }
+
+    scheduler::with_current(|scheduler| {
+        // Information about processes which are no longer alive is undefined
+        let Some(target) = scheduler.get_process(*id) else {
+            return ErlangResult::Ok(atoms::Undefined.into());
+        };
+        let reductions = scheduler.process_reductions(&target);
+        let arc_proc = scheduler.current_process();
+        let proc = arc_proc.deref();
+        let reductions = make_unsigned_in(reductions, proc);
+        let info = Tuple::from_slice(&[item.into(), reductions], proc).unwrap();
+        ErlangResult::Ok(info.into())
+    })
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:statistics/1"]
+pub extern "C-unwind" fn statistics1(item: OpaqueTerm) -> ErlangResult {
+    // Only reduction statistics are currently available
+    let Term::Atom(item) = item.into() else { return badarg(Trace::capture()); };
+    if item != atoms::Reductions {
+        return badarg(Trace::capture());
+    }
+
+    scheduler::with_current(|scheduler| {
+        let (total, since_last_call) = scheduler.reductions();
+        let arc_proc = scheduler.current_process();
+        let proc = arc_proc.deref();
+        let total = make_unsigned_in(total, proc);
+        let since_last_call = make_unsigned_in(since_last_call, proc);
+        let stats = Tuple::from_slice(&[total, since_last_call], proc).unwrap();
+        ErlangResult::Ok(stats.into())
+    })
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:exit/2"]
+pub extern "C-unwind" fn exit2(pid: OpaqueTerm, reason: OpaqueTerm) -> ErlangResult {
+    let Term::Pid(pid) = pid.into() else { return badarg(Trace::capture()); };
+    let Pid::Local { id } = pid.as_ref() else { return ErlangResult::Ok(true.into()); };
+    let id = *id;
+
+    let exiting = scheduler::with_current(|scheduler| {
+        let arc_proc = scheduler.current_process();
+        let proc = arc_proc.deref();
+        if id == proc.pid() {
+            let untrappable = reason == atoms::Kill.into();
+            if proc.trap_exit() && !untrappable {
+                let message =
+                    Tuple::from_slice(&[atoms::EXIT.into(), pid.into(), reason], proc).unwrap();
+                proc.send(message.into()).unwrap();
+                return false;
+            }
+            let reason = if untrappable { atoms::Killed.into() } else { reason };
+            let err = ErlangException::new(atoms::Exit, reason.into(), Trace::capture());
+            proc.exit_error(unsafe { NonNull::new_unchecked(Box::into_raw(err)) });
+            return true;
+        }
+        if let Some(target) = scheduler.get_process(id) {
+            scheduler.exit_signal(proc, &target, reason.into());
+        }
+        false
+    });
+    if exiting {
+        // The signal terminates the calling process, which never resumes
+        scheduler::process_yield();
+        unreachable!()
+    }
+    ErlangResult::Ok(true.into())
+}
+
+/// The largest relative time accepted by the timer BIFs, in milliseconds
+const MAX_TIMER_TIME: i64 = u32::MAX as i64;
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:monotonic_time/0"]
+pub extern "C-unwind" fn monotonic_time0() -> ErlangResult {
+    make_integer(time::get().monotonic_time().into())
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:monotonic_time/1"]
+pub extern "C-unwind" fn monotonic_time1(unit: OpaqueTerm) -> ErlangResult {
+    let Ok(unit) = TimeUnit::try_from(unit) else { return badarg(Trace::capture()); };
+    make_integer(TimeUnit::NATIVE.convert(time::get().monotonic_time(), unit))
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:system_time/0"]
+pub extern "C-unwind" fn system_time0() -> ErlangResult {
+    make_integer(time::get().system_time().into())
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:system_time/1"]
+pub extern "C-unwind" fn system_time1(unit: OpaqueTerm) -> ErlangResult {
+    let Ok(unit) = TimeUnit::try_from(unit) else { return badarg(Trace::capture()); };
+    make_integer(TimeUnit::NATIVE.convert(time::get().system_time(), unit))
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:time_offset/0"]
+pub extern "C-unwind" fn time_offset0() -> ErlangResult {
+    make_integer(time::get().time_offset().into())
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:time_offset/1"]
+pub extern "C-unwind" fn time_offset1(unit: OpaqueTerm) -> ErlangResult {
+    let Ok(unit) = TimeUnit::try_from(unit) else { return badarg(Trace::capture()); };
+    make_integer(TimeUnit::NATIVE.convert(time::get().time_offset(), unit))
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:convert_time_unit/3"]
+pub extern "C-unwind" fn convert_time_unit3(
+    time: OpaqueTerm,
+    from: OpaqueTerm,
+    to: OpaqueTerm,
+) -> ErlangResult {
+    let Some(time) = to_integer(time) else { return badarg(Trace::capture()); };
+    let Ok(from) = TimeUnit::try_from(from) else { return badarg(Trace::capture()); };
+    let Ok(to) = TimeUnit::try_from(to) else { return badarg(Trace::capture()); };
+    match time {
+        Integer::Small(time) => make_integer(from.convert(time, to)),
+        Integer::Big(time) => handle_safe_integer_arith_result!(Integer::from(
+            from.convert_big(&time, to)
+        )),
+    }
+}
+
+/// Only `erlang:system_flag(time_offset, finalize)` is supported, which returns the state of
+/// the time offset prior to the call
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:system_flag/2"]
+pub extern "C-unwind" fn system_flag2(flag: OpaqueTerm, value: OpaqueTerm) -> ErlangResult {
+    match (flag.into(), value.into()) {
+        (Term::Atom(flag), Term::Atom(value))
+            if flag == atoms::TimeOffset && value == atoms::Finalize =>
+        {
+            let state: Atom = time::get().finalize_time_offset().into();
+            ErlangResult::Ok(state.into())
+        }
+        _ => badarg(Trace::capture()),
+    }
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:send_after/3"]
+pub extern "C-unwind" fn send_after3(
+    time: OpaqueTerm,
+    dest: OpaqueTerm,
+    message: OpaqueTerm,
+) -> ErlangResult {
+    start_timer_internal(time, dest, message, OpaqueTerm::NIL, false)
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:send_after/4"]
+pub extern "C-unwind" fn send_after4(
+    time: OpaqueTerm,
+    dest: OpaqueTerm,
+    message: OpaqueTerm,
+    options: OpaqueTerm,
+) -> ErlangResult {
+    start_timer_internal(time, dest, message, options, false)
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:start_timer/3"]
+pub extern "C-unwind" fn start_timer3(
+    time: OpaqueTerm,
+    dest: OpaqueTerm,
+    message: OpaqueTerm,
+) -> ErlangResult {
+    start_timer_internal(time, dest, message, OpaqueTerm::NIL, true)
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:start_timer/4"]
+pub extern "C-unwind" fn start_timer4(
+    time: OpaqueTerm,
+    dest: OpaqueTerm,
+    message: OpaqueTerm,
+    options: OpaqueTerm,
+) -> ErlangResult {
+    start_timer_internal(time, dest, message, options, true)
+}
+
+/// Starts a timer which sends `message` to `dest` once `time` has elapsed, returning its
+/// reference
+///
+/// When `timeout` is set, the message sent is `{timeout, Reference, Message}`, as done by
+/// `erlang:start_timer/4`. The only option is `{abs, Bool}`, which when true, makes `time`
+/// an absolute time in milliseconds of Erlang monotonic time.
+fn start_timer_internal(
+    time: OpaqueTerm,
+    dest: OpaqueTerm,
+    message: OpaqueTerm,
+    options: OpaqueTerm,
+    timeout: bool,
+) -> ErlangResult {
+    let mut abs = false;
+    match options.into() {
+        Term::Nil => (),
+        Term::Cons(ptr) => {
+            for option in unsafe { ptr.as_ref().iter() } {
+                match option {
+                    Ok(Term::Tuple(ptr)) => match unsafe { ptr.as_ref() }.as_slice() {
+                        &[key, value] if key == atoms::Abs.into() => match value.into() {
+                            Term::Bool(value) => abs = value,
+                            _ => return badarg(Trace::capture()),
+                        },
+                        _ => return badarg(Trace::capture()),
+                    },
+                    _ => return badarg(Trace::capture()),
+                }
+            }
+        }
+        _ => return badarg(Trace::capture()),
+    }
+
+    // Timers can only be started for local processes, registered names are resolved when the
+    // timer expires
+    let to = match dest.into() {
+        Term::Pid(pid) => match pid.as_ref() {
+            Pid::Local { id } => Destination::Process(*id),
+            _ => return badarg(Trace::capture()),
+        },
+        Term::Atom(name) => Destination::Name(name),
+        _ => return badarg(Trace::capture()),
+    };
+    let Some(time) = to_i64(time) else { return badarg(Trace::capture()); };
+    let deadline = if abs {
+        time
+    } else if (0..=MAX_TIMER_TIME).contains(&time) {
+        time::monotonic_millis() + time
+    } else {
+        return badarg(Trace::capture());
+    };
+
+    scheduler::with_current(|scheduler| {
+        let arc_proc = scheduler.current_process();
+        let proc = arc_proc.deref();
+        let id = scheduler.next_reference_id();
+        let reference = GcBox::new_in(Reference::Local { id }, proc).unwrap();
+        let message = if timeout {
+            Tuple::from_slice(&[atoms::Timeout.into(), reference.into(), message], proc)
+                .unwrap()
+                .into()
+        } else {
+            message
+        };
+        // A deadline in the past expires immediately
+        scheduler
+            .start_timer(id, deadline.max(0) as u64, to, message.into())
+            .unwrap();
+        ErlangResult::Ok(reference.into())
+    })
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:cancel_timer/1"]
+pub extern "C-unwind" fn cancel_timer1(reference: OpaqueTerm) -> ErlangResult {
+    cancel_timer2(reference, OpaqueTerm::NIL)
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:cancel_timer/2"]
+pub extern "C-unwind" fn cancel_timer2(reference: OpaqueTerm, options: OpaqueTerm) -> ErlangResult {
+    let Term::Reference(timer) = reference.into() else { return badarg(Trace::capture()); };
+    let id = timer.id();
+
+    let mut is_async = false;
+    let mut info = true;
+    match options.into() {
+        Term::Nil => (),
+        Term::Cons(ptr) => {
+            for option in unsafe { ptr.as_ref().iter() } {
+                match option {
+                    Ok(Term::Tuple(ptr)) => match unsafe { ptr.as_ref() }.as_slice() {
+                        &[key, value] if key == atoms::Async.into() => match value.into() {
+                            Term::Bool(value) => is_async = value,
+                            _ => return badarg(Trace::capture()),
+                        },
+                        &[key, value] if key == atoms::Info.into() => match value.into() {
+                            Term::Bool(value) => info = value,
+                            _ => return badarg(Trace::capture()),
+                        },
+                        _ => return badarg(Trace::capture()),
+                    },
+                    _ => return badarg(Trace::capture()),
+                }
+            }
+        }
+        _ => return badarg(Trace::capture()),
+    }
+
+    scheduler::with_current(|scheduler| {
+        let result = timer_result(scheduler.cancel_timer(id));
+        match (is_async, info) {
+            (false, true) => ErlangResult::Ok(result),
+            (false, false) => ErlangResult::Ok(atoms::Ok.into()),
+            (true, info) => {
+                // The result is sent to the caller as `{cancel_timer, Reference, Result}`
+                if info {
+                    let arc_proc = scheduler.current_process();
+                    let proc = arc_proc.deref();
+                    let message =
+                        Tuple::from_slice(&[atoms::CancelTimer.into(), reference, result], proc)
+                            .unwrap();
+                    proc.send(message.into()).unwrap();
+                }
+                ErlangResult::Ok(atoms::Ok.into())
+            }
+        }
+    })
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:read_timer/1"]
+pub extern "C-unwind" fn read_timer1(reference: OpaqueTerm) -> ErlangResult {
+    read_timer2(reference, OpaqueTerm::NIL)
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:read_timer/2"]
+pub extern "C-unwind" fn read_timer2(reference: OpaqueTerm, options: OpaqueTerm) -> ErlangResult {
+    let Term::Reference(timer) = reference.into() else { return badarg(Trace::capture()); };
+    let id = timer.id();
+
+    let mut is_async = false;
+    match options.into() {
+        Term::Nil => (),
+        Term::Cons(ptr) => {
+            for option in unsafe { ptr.as_ref().iter() } {
+                match option {
+                    Ok(Term::Tuple(ptr)) => match unsafe { ptr.as_ref() }.as_slice() {
+                        &[key, value] if key == atoms::Async.into() => match value.into() {
+                            Term::Bool(value) => is_async = value,
+                            _ => return badarg(Trace::capture()),
+                        },
+                        _ => return badarg(Trace::capture()),
+                    },
+                    _ => return badarg(Trace::capture()),
+                }
+            }
+        }
+        _ => return badarg(Trace::capture()),
+    }
+
+    scheduler::with_current(|scheduler| {
+        let result = timer_result(scheduler.read_timer(id));
+        if !is_async {
+            return ErlangResult::Ok(result);
+        }
+        // The result is sent to the caller as `{read_timer, Reference, Result}`
+        let arc_proc = scheduler.current_process();
+        let proc = arc_proc.deref();
+        let message =
+            Tuple::from_slice(&[atoms::ReadTimer.into(), reference, result], proc).unwrap();
+        proc.send(message.into()).unwrap();
+        ErlangResult::Ok(atoms::Ok.into())
+    })
+}
+
+/// Returns the result of reading or cancelling a timer, which is the time remaining in
+/// milliseconds, or `false` if there is no such timer
+fn timer_result(remaining: Option<u64>) -> OpaqueTerm {
+    match remaining {
+        Some(ms) => (ms as i64).try_into().unwrap(),
+        None => false.into(),
+    }
+}
+
+/// Returns the value of `term` if it is an integer which fits in 64 bits
+fn to_i64(term: OpaqueTerm) -> Option<i64> {
+    to_integer(term)?.try_into().ok()
+}
+
+fn to_integer(term: OpaqueTerm) -> Option<Integer> {
+    let term: Term = term.into();
+    term.try_into().ok()
+}
+
+/// Returns `value` as an integer term, which is allocated on the heap if it is too large
+/// to be represented as an immediate
+fn make_integer(value: i128) -> ErlangResult {
+    match i64::try_from(value) {
+        Ok(value) => handle_safe_integer_arith_result!(Integer::new(value)),
+        Err(_) => handle_safe_integer_arith_result!(Integer::Big(BigInt::from(value))),
+    }
+}
+
+/// Returns `value` as an integer term, which is allocated on the heap of `proc` if it is too
+/// large to be represented as an immediate
+fn make_unsigned_in(value: u64, proc: &Process) -> OpaqueTerm {
+    if let Some(term) = i64::try_from(value)
+        .ok()
+        .and_then(|value| OpaqueTerm::try_from(value).ok())
+    {
+        return term;
+    }
+    let mut empty = GcBox::new_uninit_in(proc).unwrap();
+    empty.write(BigInt::from(value));
+    unsafe { empty.assume_init() }.into()
+}
+
 fn make_reason<R: Into<OpaqueTerm>>(tag: Atom, reason: R) -> OpaqueTerm {
     scheduler::with_current(|scheduler| {
         let arc_proc = scheduler.current_process();
@@ -1,5 +1,10 @@
-use firefly_rt::function::ErlangResult;
-use firefly_rt::term::{ListBuilder, OpaqueTerm};
+use std::ptr::NonNull;
+
+use firefly_rt::backtrace::Trace;
+use firefly_rt::error::ErlangException;
+use firefly_rt::function::{self, ErlangResult};
+use firefly_rt::process::Process;
+use firefly_rt::term::{atoms, ListBuilder, OpaqueTerm, Term, Tuple};
 
 use crate::env;
 use crate::scheduler;
@@ -17,10 +22,16 @@ extern "C-unwind" {
 /// The actual boot process is handled in `init:boot/1`, or if substituted with
 /// a different module, `Module:boot/1`.
 ///
+/// Before booting, the `-on_load` functions of all modules are run, see `run_on_load`.
+///
 /// NOTE: When this function is invoked, it is on the stack of the new process, not the scheduler.
 #[allow(improper_ctypes_definitions)]
 pub(crate) extern "C-unwind" fn start() -> ErlangResult {
     scheduler::with_current_process(|process| {
+        if let ErlangResult::Err(err) = run_on_load(process) {
+            return ErlangResult::Err(err);
+        }
+
         let argv = env::argv();
         let args = {
             let mut builder = ListBuilder::new(process);
@@ -35,3 +46,29 @@ pub(crate) extern "C-unwind" fn start() -> ErlangResult {
         unsafe { boot(args) }
     })
 }
+
+/// Runs the `-on_load` function of each module, such that modules are run after those they depend on
+///
+/// Startup is aborted if any of them raises, or returns anything other than `ok`. In the latter case,
+/// the `init` process exits with `{on_load_function_failed, Module, Result}` as the error reason.
+fn run_on_load(process: &Process) -> ErlangResult {
+    for (module, callee) in function::on_load_functions() {
+        let result = match unsafe { function::apply_callee(callee, &[]) } {
+            ErlangResult::Ok(result) => result,
+            err => return err,
+        };
+        match result.into() {
+            Term::Atom(a) if a == atoms::Ok => continue,
+            _ => {
+                let reason = Tuple::from_slice(
+                    &[atoms::OnLoadFunctionFailed.into(), module.into(), result],
+                    process,
+                )
+                .unwrap();
+                let err = ErlangException::new(atoms::Error, reason.into(), Trace::capture());
+                return ErlangResult::Err(unsafe { NonNull::new_unchecked(Box::into_raw(err)) });
+            }
+        }
+    }
+    ErlangResult::Ok(atoms::Ok.into())
+}
@@ -14,6 +14,8 @@ use firefly_rt::term::{OpaqueTerm, Term, TermType};
 
 use crate::scheduler;
 
+mod receive;
+
 macro_rules! ok {
     ($value:expr) => {
         firefly_rt::function::ErlangResult::Ok($value)
@@ -255,24 +257,18 @@ pub extern "C-unwind" fn cleanup(ptr: *mut ErlangException) {
 
 #[export_name = "__firefly_builtin_yield"]
 pub unsafe extern "C-unwind" fn process_yield() -> bool {
-    scheduler::with_current(|scheduler| scheduler.process_yield())
+    scheduler::process_yield()
 }
 
 #[allow(improper_ctypes_definitions)]
 #[export_name = "__firefly_builtin_exit"]
 pub unsafe extern "C-unwind" fn process_exit(result: ErlangResult) {
-    scheduler::with_current(|scheduler| {
-        match result {
-            ErlangResult::Ok(_) => {
-                scheduler.current_process().exit_normal();
-            }
-            ErlangResult::Err(err) => {
-                scheduler.current_process().exit_error(err);
-            }
-        }
-
-        scheduler.process_yield()
+    scheduler::with_current_process(|process| match result {
+        ErlangResult::Ok(_) => process.exit_normal(),
+        ErlangResult::Err(err) => process.exit_error(err),
     });
+
+    scheduler::process_yield();
 }
 
 #[allow(improper_ctypes_definitions)]
@@ -0,0 +1,249 @@
+//! This module implements the runtime support for `receive`.
+//!
+//! There are two sets of entry points here: the `erlang:recv_*` primops which are
+//! called by code lowered from Kernel Erlang, and the `__firefly_builtin_receive_*`
+//! intrinsics targeted by the CIR receive operations. Both are built on the save
+//! pointer maintained by the process mailbox, which ensures that a selective receive
+//! never inspects the same message twice.
+use std::ptr::{self, NonNull};
+
+use firefly_rt::backtrace::Trace;
+use firefly_rt::error::ErlangException;
+use firefly_rt::function::ErlangResult;
+use firefly_rt::process::{Message, Process, ProcessStatus};
+use firefly_rt::term::{atoms, OpaqueTerm, Term};
+
+use crate::scheduler;
+use crate::time::monotonic_millis;
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReceiveState {
+    // Indicates to the caller that a message is available to peek
+    Peek = 0,
+    // Indicates to the caller that no message was available and the process
+    // was transitioned to the waiting state.
+    Wait = 1,
+    // Indicates to the caller that the receive timed out
+    Timeout = 2,
+}
+
+/// This structure manages the context for a receive state machine.
+///
+/// It is critical that the layout for this structure be carefully
+/// maintained, as the compiler generates code which accesses it. Any
+/// changes that modify the layout or semantics of this structure must
+/// be synchronized with codegen.
+#[repr(C)]
+pub struct ReceiveContext {
+    /// The absolute deadline of this receive in milliseconds of monotonic time,
+    /// or -1 if the receive never times out
+    timeout: i64,
+    /// Reserved for the timer used to wake the process when the receive times out, this
+    /// is always `NONE`, as the scheduler keeps track of the timer of the current receive
+    timer_reference: OpaqueTerm,
+    /// The message currently being inspected, this is always the message at the
+    /// save pointer of the mailbox, or null if there is no such message.
+    ///
+    /// NOTE: Garbage collection must not occur while a receive context is live, as
+    /// the message term is read directly from this pointer by generated code.
+    message: *const Message,
+}
+impl ReceiveContext {
+    fn is_timed_out(&self) -> bool {
+        self.timeout >= 0 && monotonic_millis() >= self.timeout
+    }
+}
+
+/// The result of `erlang:recv_peek_message/0`
+#[repr(C)]
+pub struct PeekResult {
+    /// A boolean term indicating whether a message was available
+    found: OpaqueTerm,
+    /// The message term, if one was available, otherwise `NONE`
+    message: OpaqueTerm,
+}
+
+/// The receive timeout requested by the program
+enum Timeout {
+    Immediate,
+    Infinity,
+    After(i64),
+}
+impl TryFrom<OpaqueTerm> for Timeout {
+    type Error = ();
+
+    fn try_from(term: OpaqueTerm) -> Result<Self, Self::Error> {
+        match term.into() {
+            Term::Atom(a) if a == atoms::Infinity => Ok(Self::Infinity),
+            Term::Int(0) => Ok(Self::Immediate),
+            Term::Int(ms) if ms > 0 => Ok(Self::After(ms)),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Removes the message at the save pointer from the mailbox of `process`, transferring
+/// ownership of its heap fragment, if it has one, to the process itself.
+fn remove(process: &Process) {
+    let message = process.mailbox().remove();
+    if let Some(mut message) = message {
+        if let Some(fragment) = message.take_fragment() {
+            unsafe {
+                process.attach_fragment(fragment);
+            }
+        }
+    }
+}
+
+/// Sets up the context for the receive state machine, no messages are inspected yet.
+///
+/// The state transitions are `start`, `next`, `peek`, `pop` and `done`. When `next` returns
+/// `Peek`, the message can be read from the context, and if it matches, `pop` removes it
+/// from the mailbox; otherwise `next` is called again to move on to the following message.
+/// When `next` returns `Wait`, the caller must yield before calling `next` again, and when it
+/// returns `Timeout`, the caller proceeds to the `after` clause. In all cases, `done` must be
+/// called when the state machine exits.
+///
+/// An invalid timeout, e.g. a negative integer, raises `error:timeout_value`. There is no way
+/// to return an exception from this intrinsic, so the error terminates the current process.
+#[export_name = "__firefly_builtin_receive_start"]
+pub extern "C-unwind" fn receive_start(timeout: OpaqueTerm) -> ReceiveContext {
+    let timeout = match Timeout::try_from(timeout) {
+        Ok(Timeout::Infinity) => -1,
+        Ok(Timeout::Immediate) => monotonic_millis(),
+        Ok(Timeout::After(ms)) => monotonic_millis() + ms,
+        Err(_) => {
+            let err =
+                ErlangException::new(atoms::Error, atoms::TimeoutValue.into(), Trace::capture());
+            unsafe {
+                super::process_exit(ErlangResult::Err(NonNull::new_unchecked(Box::into_raw(err))));
+            }
+            unreachable!()
+        }
+    };
+    ReceiveContext {
+        timeout,
+        timer_reference: OpaqueTerm::NONE,
+        message: ptr::null(),
+    }
+}
+
+/// Moves the context to the next message to inspect, determining which state to transition to.
+///
+/// This is called upon entering the state machine, when a peeked message does not match, and
+/// after the process resumes from waiting.
+#[export_name = "__firefly_builtin_receive_next"]
+pub extern "C-unwind" fn receive_next(context: &mut ReceiveContext) -> ReceiveState {
+    scheduler::with_current(|scheduler| {
+        let process = scheduler.current_process();
+        let mut mailbox = process.mailbox();
+        if !context.message.is_null() {
+            mailbox.next();
+        }
+        if let Some(message) = mailbox.peek() {
+            context.message = message as *const Message;
+            ReceiveState::Peek
+        } else if context.is_timed_out() {
+            context.message = ptr::null();
+            ReceiveState::Timeout
+        } else {
+            context.message = ptr::null();
+            // The process is woken by its receive timer if no message arrives in time
+            if context.timeout >= 0 {
+                scheduler.start_receive_timer(context.timeout as u64);
+            }
+            process.set_status(ProcessStatus::Waiting);
+            ReceiveState::Wait
+        }
+    })
+}
+
+/// Removes the message which was peeked from the mailbox, as it matched
+#[export_name = "__firefly_builtin_receive_pop"]
+pub extern "C-unwind" fn receive_pop(context: &mut ReceiveContext) {
+    scheduler::with_current_process(remove);
+    context.message = ptr::null();
+}
+
+/// Cleans up the context when the receive state machine exits
+#[export_name = "__firefly_builtin_receive_done"]
+pub extern "C-unwind" fn receive_done(context: &mut ReceiveContext) {
+    scheduler::with_current(|scheduler| {
+        scheduler.cancel_receive_timer();
+        scheduler.current_process().mailbox().reset();
+    });
+    context.message = ptr::null();
+    context.timer_reference = OpaqueTerm::NONE;
+}
+
+/// Returns the message at the save pointer of the current process mailbox, if there is one
+#[export_name = "erlang:recv_peek_message/0"]
+pub extern "C-unwind" fn recv_peek_message() -> PeekResult {
+    scheduler::with_current_process(|process| match process.mailbox().peek() {
+        Some(message) => PeekResult {
+            found: true.into(),
+            message: message.term(),
+        },
+        None => PeekResult {
+            found: false.into(),
+            message: OpaqueTerm::NONE,
+        },
+    })
+}
+
+/// Advances the save pointer past the message which was peeked, as it did not match
+#[export_name = "erlang:recv_next/0"]
+pub extern "C-unwind" fn recv_next() {
+    scheduler::with_current_process(|process| process.mailbox().next())
+}
+
+/// Removes the message which was peeked from the mailbox, completing the receive
+#[export_name = "erlang:remove_message/0"]
+pub extern "C-unwind" fn remove_message() {
+    scheduler::with_current(|scheduler| {
+        scheduler.cancel_receive_timer();
+        remove(&scheduler.current_process());
+    })
+}
+
+/// Called when all messages in the mailbox have been inspected without a match.
+///
+/// Returns `true` if the receive timed out, in which case the `after` clause is executed,
+/// otherwise the process is suspended until there are new messages to inspect, and `false`
+/// is returned when it resumes.
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:recv_wait_timeout/1"]
+pub extern "C-unwind" fn recv_wait_timeout(timeout: OpaqueTerm) -> ErlangResult {
+    let Ok(timeout) = Timeout::try_from(timeout) else {
+        let err = ErlangException::new(atoms::Error, atoms::TimeoutValue.into(), Trace::capture());
+        return ErlangResult::Err(unsafe { NonNull::new_unchecked(Box::into_raw(err)) });
+    };
+
+    let timed_out = scheduler::with_current(|scheduler| {
+        let process = scheduler.current_process();
+        match timeout {
+            Timeout::Immediate => {
+                process.mailbox().reset();
+                return true;
+            }
+            Timeout::Infinity => (),
+            Timeout::After(ms) => match scheduler.receive_deadline() {
+                None => scheduler.start_receive_timer((monotonic_millis() + ms) as u64),
+                Some(deadline) if monotonic_millis() as u64 >= deadline => {
+                    scheduler.cancel_receive_timer();
+                    process.mailbox().reset();
+                    return true;
+                }
+                Some(_) => (),
+            },
+        }
+        // The process is woken when a message arrives, or by its receive timer
+        process.set_status(ProcessStatus::Waiting);
+        false
+    });
+    if !timed_out {
+        scheduler::process_yield();
+    }
+    ErlangResult::Ok(timed_out.into())
+}
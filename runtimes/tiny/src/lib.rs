@@ -14,6 +14,7 @@ mod init;
 mod intrinsic;
 mod scheduler;
 mod sys;
+mod time;
 
 use bus::Bus;
 use std::process::ExitCode;
@@ -39,17 +40,24 @@ fn main_internal(_name: &str, _version: &str, _argv: Vec<String>) -> ExitCode {
     // Initialize the break handler with the bus, which will broadcast on it
     break_handler::init(bus);
 
-    scheduler::init();
+    // Erlang monotonic time starts here, before any timers can be started
+    time::init(self::env::time_warp_mode());
+
+    // The main thread runs the first scheduler, the others get a thread of their own
+    scheduler::init(self::env::schedulers());
     scheduler::with_current(|scheduler| scheduler.spawn_init()).unwrap();
+    let threads = scheduler::start();
+    let mut exit_code = None;
     loop {
         // Run the scheduler for a cycle
-        let scheduled = scheduler::with_current(|scheduler| scheduler.run_once());
+        let running = scheduler::with_current(|scheduler| scheduler.run_once());
         // Check for system signals, and terminate if needed
         if let Ok(sig) = rx1.try_recv() {
             match sig {
                 // For now, SIGINT initiates a controlled shutdown
                 Signal::INT => {
                     // If an error occurs, report it before shutdown
+                    scheduler::halt();
                     break;
                 }
                 // Technically, we may never see these signals directly,
@@ -57,21 +65,28 @@ fn main_internal(_name: &str, _version: &str, _argv: Vec<String>) -> ExitCode {
                 // we handle them explicitly by immediately terminating, so
                 // that we are good citizens of the operating system
                 sig if sig.should_terminate() => {
-                    return ExitCode::FAILURE;
+                    scheduler::halt();
+                    exit_code = Some(ExitCode::FAILURE);
+                    break;
                 }
                 // All other signals can be surfaced to other parts of the
                 // system for custom use, e.g. SIGCHLD, SIGALRM, SIGUSR1/2
                 _ => (),
             }
         }
-        // If the scheduler scheduled a process this cycle, then we're busy
-        // and should keep working until we have an idle period
-        if scheduled {
+        // Keep working until the schedulers have halted, either because there
+        // are no more processes to run on any of them, or due to a signal
+        if running {
             continue;
         }
 
         break;
     }
 
-    scheduler::with_current(|s| s.shutdown())
+    // Each scheduler stops once the process it is running yields
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    exit_code.unwrap_or_else(|| scheduler::with_current(|s| s.shutdown()))
 }
@@ -1,8 +1,12 @@
+use std::mem;
 use std::ptr::NonNull;
 
+use firefly_alloc::gc::GcBox;
 use firefly_rt::error::{self, ErlangException};
-use firefly_rt::process::Process;
-use firefly_rt::term::{atoms, Term};
+use firefly_rt::process::{Process, ProcessStatus};
+use firefly_rt::term::{atoms, Pid, Reference, Term, Tuple};
+
+use super::Scheduler;
 
 pub fn log_exit(process: &Process, ptr: NonNull<ErlangException>) -> bool {
     let exception = unsafe { ptr.as_ref() };
@@ -22,3 +26,102 @@ fn is_expected_exit_reason(reason: Term) -> bool {
         _ => false,
     }
 }
+
+/// Returns the exit reason of a process which terminated due to `exception`
+///
+/// As in the BEAM, exits preserve their reason as-is, while errors and uncaught throws
+/// are paired with the stacktrace of the exception. The reason is allocated on the heap
+/// of the exiting process.
+pub fn exit_reason(process: &Process, exception: &ErlangException) -> Term {
+    let kind = exception.kind();
+    let reason = exception.reason();
+    if kind == atoms::Exit {
+        return reason;
+    }
+
+    let reason = if kind == atoms::Throw {
+        Tuple::from_slice(&[atoms::Nocatch.into(), reason.into()], process)
+            .unwrap()
+            .into()
+    } else {
+        reason
+    };
+    let trace = exception.trace().as_term().unwrap_or(Term::Nil);
+    Tuple::from_slice(&[reason.into(), trace.into()], process)
+        .unwrap()
+        .into()
+}
+
+/// Propagates the exit of `process` with `reason` to the processes it is linked to, and
+/// to the processes monitoring it.
+pub fn propagate(scheduler: &Scheduler, process: &Process, reason: Term) {
+    let id = process.pid();
+    let links = mem::take(&mut *process.links());
+
+    for linked in links.links.iter().copied() {
+        let Some(linked) = scheduler.get_process(linked) else { continue; };
+        linked.links().unlink(id);
+        deliver(scheduler, process, &linked, reason, false);
+    }
+
+    for (reference, origin) in links.monitored_by.iter() {
+        let Some(origin) = scheduler.get_process(*origin) else { continue; };
+        if origin.links().monitoring.remove(reference).is_none() {
+            continue;
+        }
+        let reference = GcBox::new_in(Reference::Local { id: *reference }, process).unwrap();
+        let pid = GcBox::new_in(Pid::Local { id }, process).unwrap();
+        let message = Tuple::from_slice(
+            &[
+                atoms::DOWN.into(),
+                reference.into(),
+                atoms::Process.into(),
+                pid.into(),
+                reason.into(),
+            ],
+            process,
+        )
+        .unwrap();
+        send(scheduler, &origin, message.into());
+    }
+
+    for (reference, target) in links.monitoring.iter() {
+        if let Some(target) = scheduler.get_process(*target) {
+            target.links().monitored_by.remove(reference);
+        }
+    }
+}
+
+/// Delivers an exit signal with `reason` from `from` to `process`
+///
+/// When `untrappable` is set, as is the case for `exit(Pid, kill)`, the process is killed
+/// regardless of whether or not it is trapping exits.
+pub fn deliver(
+    scheduler: &Scheduler,
+    from: &Process,
+    process: &Process,
+    reason: Term,
+    untrappable: bool,
+) {
+    // A process which is already exiting ignores any further exit signals
+    if let ProcessStatus::Exiting | ProcessStatus::Errored(_) = process.status() {
+        return;
+    }
+
+    if untrappable {
+        scheduler.kill(process, atoms::Killed.into());
+    } else if process.trap_exit() {
+        let pid = GcBox::new_in(Pid::Local { id: from.pid() }, from).unwrap();
+        let message =
+            Tuple::from_slice(&[atoms::EXIT.into(), pid.into(), reason.into()], from).unwrap();
+        send(scheduler, process, message.into());
+    } else if !is_expected_exit_reason(reason) {
+        scheduler.kill(process, reason);
+    }
+}
+
+fn send(scheduler: &Scheduler, process: &Process, message: Term) {
+    if process.send(message).unwrap() {
+        scheduler.wake(process.pid());
+    }
+}
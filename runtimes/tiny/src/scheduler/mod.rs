@@ -1,21 +1,31 @@
 mod exit;
 mod queue;
+mod timer;
 
 use std::arch::global_asm;
-use std::cell::{OnceCell, UnsafeCell};
+use std::cell::{Cell, OnceCell, UnsafeCell};
+use std::collections::HashMap;
 use std::mem;
 use std::ptr;
 use std::sync::{
-    atomic::{AtomicI32, AtomicU64, Ordering},
-    Arc,
+    atomic::{AtomicBool, AtomicI32, AtomicU64, AtomicUsize, Ordering},
+    Arc, Condvar, Mutex, OnceLock, RwLock, Weak,
 };
-use std::thread::{self, ThreadId};
+use std::thread::{self, JoinHandle, ThreadId};
+use std::time::Duration;
 
-use firefly_rt::function::{DynamicCallee, ModuleFunctionArity};
-use firefly_rt::process::{Process, ProcessStatus};
-use firefly_rt::term::{OpaqueTerm, Pid, ProcessId};
+use firefly_rt::backtrace::Trace;
+use firefly_rt::error::ErlangException;
+use firefly_rt::function::{DynamicCallee, ErlangResult, ModuleFunctionArity};
+use firefly_rt::process::{Message, Process, ProcessStatus};
+use firefly_rt::term::{atoms, Atom, OpaqueTerm, Pid, ProcessId, ReferenceId, Term};
+
+use crate::time::{self, TimerWheel};
 
 use self::queue::RunQueue;
+use self::timer::TimerWheels;
+
+pub use self::timer::Destination;
 
 #[thread_local]
 pub static CURRENT_PROCESS: UnsafeCell<Option<Arc<Process>>> = UnsafeCell::new(None);
@@ -23,7 +33,26 @@ pub static CURRENT_PROCESS: UnsafeCell<Option<Arc<Process>>> = UnsafeCell::new(N
 #[thread_local]
 pub static CURRENT_SCHEDULER: OnceCell<Scheduler> = OnceCell::new();
 
+/// The state shared by the schedulers of all threads
+static SCHEDULERS: OnceLock<Schedulers> = OnceLock::new();
+
+/// The maximum amount of time an idle scheduler sleeps before looking for work again
+const PARK_TIMEOUT: Duration = Duration::from_millis(1);
+
+/// The number of reductions consumed by the current process since it was last scheduled
+///
+/// Generated code increments this at function entries and loop back-edges, and yields to the
+/// scheduler once it reaches the reduction budget the code was compiled with. The scheduler
+/// resets it each time a process is swapped in, and adds it to the reductions of the process
+/// each time it yields.
+#[thread_local]
+#[export_name = "__firefly_process_reductions"]
+pub static PROCESS_REDUCTIONS: Cell<i32> = Cell::new(0);
+
 /// Returns a reference to the scheduler for the current thread
+///
+/// NOTE: Processes may be resumed by a different scheduler than the one they yielded on,
+/// so the reference must not be held across a call to `process_yield`.
 pub fn with_current<F, R>(fun: F) -> R
 where
     F: FnOnce(&Scheduler) -> R,
@@ -31,13 +60,40 @@ where
     fun(CURRENT_SCHEDULER.get().unwrap())
 }
 
-/// Initializes the scheduler for the current thread, if not already initialized,
-/// returning a reference to it
-pub fn init<'a>() -> bool {
-    CURRENT_SCHEDULER.get_or_init(|| Scheduler::new().unwrap());
+/// Initializes the runtime with `count` schedulers, and the scheduler for the current
+/// thread, which is the first of them
+///
+/// The remaining schedulers are run on their own threads, see `start`.
+pub fn init(count: usize) -> bool {
+    SCHEDULERS.get_or_init(|| Schedulers::new(count));
+    CURRENT_SCHEDULER.get_or_init(|| Scheduler::new(0).unwrap());
     true
 }
 
+/// Spawns a thread for each scheduler other than the one for the current thread
+///
+/// Each thread runs its scheduler until the runtime halts, and must be joined before exiting.
+pub fn start() -> Vec<JoinHandle<()>> {
+    let count = SCHEDULERS.get().unwrap().run_queues.len();
+    (1..count)
+        .map(|index| {
+            thread::Builder::new()
+                .name(format!("scheduler-{}", index))
+                .spawn(move || {
+                    let scheduler =
+                        CURRENT_SCHEDULER.get_or_init(|| Scheduler::new(index).unwrap());
+                    while scheduler.run_once() {}
+                })
+                .expect("could not spawn scheduler thread")
+        })
+        .collect()
+}
+
+/// Requests that all schedulers stop, which they do once their current process yields
+pub fn halt() {
+    SCHEDULERS.get().unwrap().halt();
+}
+
 /// Applies the currently executing process to the given function
 pub fn with_current_process<F, R>(fun: F) -> R
 where
@@ -47,15 +103,149 @@ where
     fun(p)
 }
 
+/// Yields the current process to the scheduler of the current thread
+///
+/// This function will appear to return normally to the caller when the process is
+/// rescheduled, which may be by the scheduler of another thread, so any scheduler state
+/// needed after yielding must be fetched again via `with_current`.
+pub fn process_yield() -> bool {
+    // Swap back to the scheduler, which is currently "suspended" in `prev`.
+    // When `swap_stack` is called it will look like a return from the last call
+    // to `swap_stack` from the scheduler loop.
+    let (current, prev) = with_current(|scheduler| {
+        let prev = scheduler.prev().registers() as *const CalleeSavedRegisters;
+        let current = scheduler.current().registers_mut() as *mut CalleeSavedRegisters;
+        (current, prev)
+    });
+
+    unsafe {
+        swap_stack(current, prev, FIRST_SWAP);
+    }
+    true
+}
+
+/// The state shared by all schedulers
+///
+/// Each scheduler has its own run queue, but an idle scheduler will steal processes from the
+/// run queues of the others, so processes are not bound to the scheduler which spawned them.
+/// Likewise, each scheduler has its own timer wheel, but its timers may be cancelled by any
+/// scheduler.
+struct Schedulers {
+    run_queues: Box<[Mutex<RunQueue>]>,
+    timers: TimerWheels,
+    // All live processes, used to resolve pids
+    registry: RwLock<HashMap<ProcessId, Weak<Process>>>,
+    // Registered process names, see `erlang:register/2`
+    names: RwLock<HashMap<Atom, ProcessId>>,
+    // The total number of reductions executed by all processes, as of the last time each yielded
+    reductions: AtomicU64,
+    // The total number of reductions as of the last call to `erlang:statistics(reductions)`
+    reductions_last_call: AtomicU64,
+    suspended: Mutex<Suspended>,
+    // The number of schedulers which are currently running a process, or looking for one
+    active: AtomicUsize,
+    // Idle schedulers sleep on this until work is scheduled, or the runtime halts
+    idle: Mutex<()>,
+    wakeup: Condvar,
+    sleeping: AtomicUsize,
+    halted: AtomicBool,
+    // The init process, whose exit determines the halt code of the runtime
+    init: OnceLock<ProcessId>,
+    halt_code: AtomicI32,
+}
+impl Schedulers {
+    fn new(count: usize) -> Self {
+        assert!(count > 0, "the runtime requires at least one scheduler");
+        Self {
+            run_queues: (0..count)
+                .map(|_| Mutex::new(RunQueue::default()))
+                .collect(),
+            timers: (0..count)
+                .map(|_| Mutex::new(TimerWheel::new(time::monotonic_millis() as u64)))
+                .collect(),
+            registry: RwLock::new(HashMap::new()),
+            names: RwLock::new(HashMap::new()),
+            reductions: AtomicU64::new(0),
+            reductions_last_call: AtomicU64::new(0),
+            suspended: Mutex::new(Suspended::default()),
+            active: AtomicUsize::new(0),
+            idle: Mutex::new(()),
+            wakeup: Condvar::new(),
+            sleeping: AtomicUsize::new(0),
+            halted: AtomicBool::new(false),
+            init: OnceLock::new(),
+            halt_code: AtomicI32::new(0),
+        }
+    }
+
+    /// Returns true if no process is runnable on any scheduler, and no timer is pending
+    ///
+    /// This is only meaningful when no scheduler is active, as only running processes can
+    /// make other processes runnable, or start timers.
+    fn is_idle(&self) -> bool {
+        self.run_queues
+            .iter()
+            .all(|rq| rq.lock().unwrap().is_empty())
+            && self.timers.iter().all(|tw| tw.lock().unwrap().is_empty())
+            && self.active.load(Ordering::SeqCst) == 0
+    }
+
+    fn is_halted(&self) -> bool {
+        self.halted.load(Ordering::Acquire)
+    }
+
+    fn halt(&self) {
+        self.halted.store(true, Ordering::Release);
+        let _guard = self.idle.lock().unwrap();
+        self.wakeup.notify_all();
+    }
+
+    /// Puts the calling scheduler to sleep until there may be work for it to do
+    fn park(&self) {
+        let guard = self.idle.lock().unwrap();
+        if self.is_halted() {
+            return;
+        }
+        self.sleeping.fetch_add(1, Ordering::SeqCst);
+        let _ = self.wakeup.wait_timeout(guard, PARK_TIMEOUT).unwrap();
+        self.sleeping.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    /// Wakes a sleeping scheduler, if there is one, so that it can steal newly scheduled work
+    fn unpark(&self) {
+        if self.sleeping.load(Ordering::SeqCst) > 0 {
+            self.wakeup.notify_one();
+        }
+    }
+}
+
+/// Processes which are not in any run queue, as they are waiting on a message
+///
+/// These are shared by all schedulers, so that a process can be woken by any of them. The
+/// exit signals which kill a process are held here until the scheduler which owns the process
+/// is able to apply them, i.e. once the process is suspended.
+#[derive(Default)]
+struct Suspended {
+    // Processes which are suspended waiting on a message, these are placed
+    // back in a run queue when a message is sent to them
+    waiting: HashMap<ProcessId, Arc<SchedulerData>>,
+    // The exit reasons of processes which were killed by an exit signal
+    exits: HashMap<ProcessId, Box<Message>>,
+}
+
 struct SchedulerData {
     process: Arc<Process>,
     registers: UnsafeCell<CalleeSavedRegisters>,
+    /// The timer and deadline of the receive the process is currently blocked in, if it has
+    /// a timeout
+    receive_timer: Cell<Option<(ReferenceId, u64)>>,
 }
 impl SchedulerData {
     fn new(process: Arc<Process>) -> Self {
         Self {
             process,
             registers: UnsafeCell::new(Default::default()),
+            receive_timer: Cell::new(None),
         }
     }
 
@@ -79,22 +269,20 @@ unsafe impl Sync for SchedulerData {}
 
 pub struct Scheduler {
     pub id: ThreadId,
+    // The index of this scheduler, which identifies its run queue
+    index: usize,
     // References are always 64-bits even on 32-bit platforms
-    #[allow(dead_code)]
     next_reference_id: AtomicU64,
-    // In this runtime, we aren't doing work-stealing, so the run queue
-    // is never accessed by any other thread
-    run_queue: UnsafeCell<RunQueue>,
+    schedulers: &'static Schedulers,
     prev: UnsafeCell<Option<Arc<SchedulerData>>>,
     current: UnsafeCell<Arc<SchedulerData>>,
-    halt_code: AtomicI32,
 }
-// This guarantee holds as long as `init` and `current` are only
+// This guarantee holds as long as `prev` and `current` are only
 // ever accessed by the scheduler when scheduling
 unsafe impl Sync for Scheduler {}
 impl Scheduler {
-    /// Creates a new scheduler with the default configuration
-    fn new() -> anyhow::Result<Self> {
+    /// Creates the scheduler with the given index, with the default configuration
+    fn new(index: usize) -> anyhow::Result<Self> {
         let id = thread::current().id();
 
         // The root process is how the scheduler gets time for itself,
@@ -106,9 +294,7 @@ impl Scheduler {
                 ProcessId::next(),
                 "root:init/0".parse().unwrap(),
             ));
-            unsafe {
-                process.set_status(ProcessStatus::Running);
-            }
+            process.set_status(ProcessStatus::Running);
             let mut registers = CalleeSavedRegisters::default();
             unsafe {
                 registers.set(1, 0x0u64);
@@ -116,20 +302,25 @@ impl Scheduler {
             Arc::new(SchedulerData {
                 process,
                 registers: UnsafeCell::new(registers),
+                receive_timer: Cell::new(None),
             })
         };
 
         // The scheduler starts with the root process running
         Ok(Self {
             id,
+            index,
             next_reference_id: AtomicU64::new(0),
-            run_queue: UnsafeCell::new(RunQueue::default()),
+            schedulers: SCHEDULERS.get().unwrap(),
             prev: UnsafeCell::new(None),
             current: UnsafeCell::new(root),
-            halt_code: AtomicI32::new(0),
         })
     }
 
+    fn run_queue(&self) -> &Mutex<RunQueue> {
+        &self.schedulers.run_queues[self.index]
+    }
+
     fn parent(&self) -> ProcessId {
         self.current().process.pid()
     }
@@ -163,6 +354,141 @@ impl Scheduler {
         self.current().process.clone()
     }
 
+    /// Returns the live process with the given identifier, if there is one
+    pub fn get_process(&self, id: ProcessId) -> Option<Arc<Process>> {
+        let registry = self.schedulers.registry.read().unwrap();
+        registry.get(&id).and_then(Weak::upgrade)
+    }
+
+    /// Registers `name` for the live process with the given identifier
+    ///
+    /// Returns false if the name is already in use, or the process is not alive, or already
+    /// has a registered name.
+    pub fn register(&self, name: Atom, id: ProcessId) -> bool {
+        let mut names = self.schedulers.names.write().unwrap();
+        if names.contains_key(&name) || names.values().any(|pid| *pid == id) {
+            return false;
+        }
+        if self.get_process(id).is_none() {
+            return false;
+        }
+        names.insert(name, id);
+        true
+    }
+
+    /// Removes the registered name `name`, returning false if it was not registered
+    pub fn unregister(&self, name: Atom) -> bool {
+        self.schedulers
+            .names
+            .write()
+            .unwrap()
+            .remove(&name)
+            .is_some()
+    }
+
+    /// Returns the identifier of the process registered as `name`, if there is one
+    pub fn whereis(&self, name: Atom) -> Option<ProcessId> {
+        self.schedulers.names.read().unwrap().get(&name).copied()
+    }
+
+    /// Returns the total number of reductions executed by `process`
+    ///
+    /// If `process` is the current process, this includes the reductions it has executed since
+    /// it was last scheduled.
+    pub fn process_reductions(&self, process: &Process) -> u64 {
+        let reductions = process.reductions();
+        if process.pid() == self.current().process.pid() {
+            reductions + PROCESS_REDUCTIONS.get().max(0) as u64
+        } else {
+            reductions
+        }
+    }
+
+    /// Returns the total number of reductions executed by all processes, and the number executed
+    /// since the last call to this function, as returned by `erlang:statistics(reductions)`
+    pub fn reductions(&self) -> (u64, u64) {
+        let total = self.schedulers.reductions.load(Ordering::Relaxed)
+            + PROCESS_REDUCTIONS.get().max(0) as u64;
+        let last = self
+            .schedulers
+            .reductions_last_call
+            .swap(total, Ordering::Relaxed);
+        (total, total.saturating_sub(last))
+    }
+
+    /// Places the given process in the run queue of this scheduler if it is waiting on a message
+    pub fn wake(&self, id: ProcessId) {
+        let data = self
+            .schedulers
+            .suspended
+            .lock()
+            .unwrap()
+            .waiting
+            .remove(&id);
+        if let Some(data) = data {
+            data.process.set_status(ProcessStatus::Runnable);
+            self.schedule(data);
+        }
+    }
+
+    /// Generates a new reference identifier which is unique to this scheduler
+    pub fn next_reference_id(&self) -> ReferenceId {
+        let id = self.next_reference_id.fetch_add(1, Ordering::Relaxed);
+        ReferenceId::new(self.index as u16, id)
+    }
+
+    /// Sends an exit signal with `reason` from `from` to `process`, as done by `exit/2`
+    ///
+    /// The process is terminated unless it is trapping exits, in which case the signal is
+    /// converted to an `{'EXIT', From, Reason}` message. Signals with a reason of `normal` are
+    /// ignored unless trapped, and `kill` cannot be trapped.
+    pub fn exit_signal(&self, from: &Process, process: &Process, reason: Term) {
+        let untrappable = matches!(reason, Term::Atom(a) if a == atoms::Kill);
+        exit::deliver(self, from, process, reason, untrappable)
+    }
+
+    /// Terminates `process`, which is not the current process, with an exit exception whose
+    /// reason is `reason`
+    ///
+    /// The process may be running on another scheduler, so the exit is recorded, and applied by
+    /// the scheduler which next suspends or selects the process to run. The process will not
+    /// resume once that happens. Only the first exit signal to kill a process has any effect.
+    fn kill(&self, process: &Process, reason: Term) {
+        let reason = Message::new(reason).unwrap();
+        let data = {
+            let mut suspended = self.schedulers.suspended.lock().unwrap();
+            suspended.exits.entry(process.pid()).or_insert(reason);
+            suspended.waiting.remove(&process.pid())
+        };
+        if let Some(data) = data {
+            self.schedule(data);
+        }
+    }
+
+    /// Applies the exit signal which killed `process`, if there is one, returning true if so
+    ///
+    /// This must only be called by the scheduler which owns the process, while it is suspended.
+    fn take_exit(&self, process: &Process) -> bool {
+        let exit = self
+            .schedulers
+            .suspended
+            .lock()
+            .unwrap()
+            .exits
+            .remove(&process.pid());
+        let Some(mut reason) = exit else { return false; };
+        // The reason is kept alive by the process from here on
+        if let Some(fragment) = reason.take_fragment() {
+            unsafe {
+                process.attach_fragment(fragment);
+            }
+        }
+        let exception =
+            ErlangException::new(atoms::Exit, reason.term().into(), Trace::new(Vec::new()));
+        process.exit_error(unsafe { ptr::NonNull::new_unchecked(Box::into_raw(exception)) });
+        true
+    }
+
     /// Swaps the prev and current scheduler data in-place and updates CURRENT_PROCESS
     ///
     /// This is intended for use when yielding to the scheduler
@@ -173,11 +499,8 @@ impl Scheduler {
         let prev = self.prev_mut();
         let proc = prev.process.clone();
         let current = self.current_mut();
-        unsafe {
-            let prev_status = current.process.status();
-            if prev_status == ProcessStatus::Running {
-                current.process.set_status(ProcessStatus::Runnable);
-            }
+        if current.process.status() == ProcessStatus::Running {
+            current.process.set_status(ProcessStatus::Runnable);
         }
         mem::swap(prev, current);
         let _ = unsafe { (&mut *CURRENT_PROCESS.get()).replace(proc) };
@@ -208,28 +531,145 @@ impl Scheduler {
         //let init_fn = function::find_symbol(&mfa).expect("unable to locate init:start/0 function!");
         let init_fn = crate::init::start as DynamicCallee;
         let process = Arc::new(Process::new(Some(self.parent()), ProcessId::next(), mfa));
+        self.schedulers.init.set(process.pid()).unwrap();
 
         let data = Arc::new(SchedulerData::new(process));
 
-        Self::runnable(&data, init_fn);
+        Self::runnable(&data, init_fn, OpaqueTerm::NONE);
+
+        Ok(self.schedule(data))
+    }
+
+    /// Spawns a new process as a child of `parent`, which begins by applying `init`
+    ///
+    /// The `init` term must be either a closure of arity zero, or a `{Module, Function, Args}`
+    /// tuple, and is copied to the heap of the new process. Since the new process may be stolen
+    /// by another scheduler as soon as it is scheduled, `setup` is applied to it beforehand, so
+    /// that links and monitors are in place before it can run.
+    pub fn spawn<F>(
+        &self,
+        parent: &Process,
+        mfa: ModuleFunctionArity,
+        init: Term,
+        setup: F,
+    ) -> anyhow::Result<Arc<Process>>
+    where
+        F: FnOnce(&Process),
+    {
+        type SpawnEntry = extern "C-unwind" fn(OpaqueTerm) -> ErlangResult;
+
+        let process = Arc::new(Process::new(Some(parent.pid()), ProcessId::next(), mfa));
+        let init = init.clone_to_heap(process.as_ref())?;
+        setup(process.as_ref());
+
+        // The entry point receives the init term as its sole argument
+        let init_fn =
+            unsafe { mem::transmute::<SpawnEntry, DynamicCallee>(crate::erlang::process_start) };
+        let data = Arc::new(SchedulerData::new(process));
+
+        Self::runnable(&data, init_fn, init.into());
 
         Ok(self.schedule(data))
     }
 
     fn schedule(&self, data: Arc<SchedulerData>) -> Arc<Process> {
         let handle = data.process.clone();
-        let rq = unsafe { &mut *self.run_queue.get() };
-        rq.schedule(data);
+        self.schedulers
+            .registry
+            .write()
+            .unwrap()
+            .insert(handle.pid(), Arc::downgrade(&handle));
+        self.run_queue().lock().unwrap().schedule(data);
+        self.schedulers.unpark();
         handle
     }
 
-    #[inline]
+    /// Returns the next process to run, taking one from the run queue of another scheduler
+    /// if there are none in our own
+    fn next(&self) -> Option<Arc<SchedulerData>> {
+        if let Some(next) = self.run_queue().lock().unwrap().next() {
+            return Some(next);
+        }
+        let run_queues = &self.schedulers.run_queues;
+        (1..run_queues.len())
+            .map(|offset| &run_queues[(self.index + offset) % run_queues.len()])
+            .find_map(|rq| rq.lock().unwrap().steal())
+    }
+
+    /// Handles the exit of `process`, notifying linked and monitoring processes
+    ///
+    /// If `log` is set, abnormal exits are reported, this is not done for processes
+    /// which were terminated by an exit signal, as the originator of the signal is
+    /// responsible for that.
+    fn terminate(&self, process: &Process, log: bool) {
+        let schedulers = self.schedulers;
+        schedulers.registry.write().unwrap().remove(&process.pid());
+        schedulers
+            .names
+            .write()
+            .unwrap()
+            .retain(|_, id| *id != process.pid());
+        // Exit signals received while the process was already exiting are ignored
+        schedulers
+            .suspended
+            .lock()
+            .unwrap()
+            .exits
+            .remove(&process.pid());
+
+        let is_init = schedulers.init.get() == Some(&process.pid());
+        let reason = match process.status() {
+            ProcessStatus::Exiting => {
+                if is_init {
+                    schedulers.halt_code.store(0, Ordering::Relaxed);
+                }
+                atoms::Normal.into()
+            }
+            ProcessStatus::Errored(exception) => {
+                let abnormal = if log {
+                    exit::log_exit(process, exception)
+                } else {
+                    true
+                };
+                if is_init && abnormal {
+                    schedulers.halt_code.store(1, Ordering::Relaxed);
+                }
+                exit::exit_reason(process, unsafe { exception.as_ref() })
+            }
+            other => panic!("expected process to be exiting, got {:?}", other),
+        };
+
+        exit::propagate(self, process, reason);
+    }
+
+    /// Runs a single process until it yields, returning false once the runtime has halted
+    ///
+    /// The runtime halts when requested to, or when no process is runnable on any scheduler.
+    /// If this scheduler has no work, but others do, it sleeps until there may be some.
     pub(super) fn run_once(&self) -> bool {
+        let schedulers = self.schedulers;
+        if schedulers.is_halted() {
+            return false;
+        }
+
+        schedulers.active.fetch_add(1, Ordering::SeqCst);
+        // Expired timers may make processes runnable
+        self.expire_timers();
         // The scheduler will yield to a process to execute
-        self.scheduler_yield()
+        let scheduled = self.scheduler_yield();
+        let last_active = schedulers.active.fetch_sub(1, Ordering::SeqCst) == 1;
+
+        if !scheduled {
+            if last_active && schedulers.is_idle() {
+                schedulers.halt();
+                return false;
+            }
+            schedulers.park();
+        }
+        true
     }
 
-    fn runnable(scheduler: &SchedulerData, init_fn: DynamicCallee) {
+    fn runnable(scheduler: &SchedulerData, init_fn: DynamicCallee, init_arg: OpaqueTerm) {
         #[derive(Copy, Clone)]
         struct StackPointer(*mut u64);
         impl StackPointer {
@@ -266,12 +706,11 @@ impl Scheduler {
             registers.set_stack_pointer(sp.0 as u64);
             registers.set_frame_pointer(sp.0 as u64);
 
-            // TODO: Set up for closures
-            // If the init function is a closure, place it in
+            // The argument to the init function is placed in
             // the first callee-save register, which will be moved to
-            // the first argument register (e.g. %rsi) by swap_stack for
+            // the first argument register (e.g. %rdi) by swap_stack for
             // the call to the entry point
-            registers.set(0, OpaqueTerm::NONE);
+            registers.set(0, init_arg);
 
             // This is used to indicate to swap_stack that this process
             // is being swapped to for the first time, which allows the
@@ -293,29 +732,13 @@ impl Scheduler {
     pub(super) fn shutdown(&self) -> std::process::ExitCode {
         use std::process::ExitCode;
 
-        if self.halt_code.load(Ordering::Relaxed) == 0 {
+        if self.schedulers.halt_code.load(Ordering::Relaxed) == 0 {
             ExitCode::SUCCESS
         } else {
             ExitCode::FAILURE
         }
     }
 
-    pub(super) fn process_yield(&self) -> bool {
-        // Swap back to the scheduler, which is currently "suspended" in `prev`.
-        // When `swap_stack` is called it will look like a return from the last call
-        // to `swap_stack` from the scheduler loop.
-        //
-        // This function will appear to return normally to the caller if the process
-        // that yielded is rescheduled
-        let prev = unsafe { (&*(self.prev.get())).as_deref().unwrap().registers() };
-        let current = unsafe { (&*(self.current.get())).registers_mut() };
-
-        unsafe {
-            swap_stack(current, prev, FIRST_SWAP);
-        }
-        true
-    }
-
     /// This function performs two roles, albeit virtually identical:
     ///
     /// First, this function is called by the scheduler to resume execution
@@ -329,12 +752,18 @@ impl Scheduler {
     /// swap in a new process.
     fn scheduler_yield(&self) -> bool {
         loop {
-            let next = {
-                let rq = unsafe { &mut *self.run_queue.get() };
-                rq.next()
-            };
-
-            match next {
+            match self.next() {
+                // The process was terminated by an exit signal while it was suspended
+                Some(scheduler_data)
+                    if self.take_exit(&scheduler_data.process)
+                        || matches!(
+                            scheduler_data.process.status(),
+                            ProcessStatus::Exiting | ProcessStatus::Errored(_)
+                        ) =>
+                {
+                    self.terminate(&scheduler_data.process, false);
+                    break true;
+                }
                 Some(scheduler_data) => {
                     // Found a process to schedule
                     unsafe {
@@ -354,17 +783,34 @@ impl Scheduler {
                     // At this point, `prev` is the process which just yielded
                     let prev = self.take_prev();
                     match prev.process.status() {
-                        ProcessStatus::Running => {
-                            let rq = unsafe { &mut *self.run_queue.get() };
-                            rq.reschedule(prev);
+                        ProcessStatus::Running | ProcessStatus::Runnable => {
+                            self.run_queue().lock().unwrap().reschedule(prev);
                         }
-                        ProcessStatus::Exiting => {
-                            self.halt_code.store(0, Ordering::Relaxed);
-                            // Process has exited normally, we're done with it
+                        ProcessStatus::Waiting => {
+                            // The process is suspended until a message arrives, but a message
+                            // or exit signal may have been sent to it, or its receive timer may
+                            // have expired, before it yielded. This is checked with the lock
+                            // held, so that senders on other threads either see the process as
+                            // waiting, or it sees what they sent.
+                            let mut suspended = self.schedulers.suspended.lock().unwrap();
+                            let pid = prev.process.pid();
+                            let timed_out =
+                                prev.receive_timer.get().map_or(false, |(_, deadline)| {
+                                    deadline <= time::monotonic_millis() as u64
+                                });
+                            if prev.process.mailbox().peek().is_some()
+                                || suspended.exits.contains_key(&pid)
+                                || timed_out
+                            {
+                                drop(suspended);
+                                prev.process.set_status(ProcessStatus::Runnable);
+                                self.run_queue().lock().unwrap().reschedule(prev);
+                            } else {
+                                suspended.waiting.insert(pid, prev);
+                            }
                         }
-                        ProcessStatus::Errored(exception) => {
-                            exit::log_exit(&prev.process, exception);
-                            self.halt_code.store(1, Ordering::Relaxed);
+                        ProcessStatus::Exiting | ProcessStatus::Errored(_) => {
+                            self.terminate(&prev.process, true);
                         }
                         other => assert_eq!(other, ProcessStatus::Running),
                     }
@@ -396,6 +842,8 @@ impl Scheduler {
     unsafe fn swap_process(&self, new: Arc<SchedulerData>) {
         // Mark the new process as Running
         new.process.set_status(ProcessStatus::Running);
+        // Give the new process a full reduction budget
+        PROCESS_REDUCTIONS.set(0);
 
         self.swap_with(new);
         let prev = self.prev();
@@ -418,6 +866,14 @@ impl Scheduler {
         // of `process_yield`, which is what the process last called before the
         // scheduler was swapped in.
         swap_stack(prev.registers_mut(), new.registers(), FIRST_SWAP);
+
+        // The process has yielded, either voluntarily or because it exhausted its
+        // reduction budget, so account for the reductions it consumed while running
+        let reductions = PROCESS_REDUCTIONS.replace(0).max(0) as u64;
+        self.current().process.add_reductions(reductions);
+        self.schedulers
+            .reductions
+            .fetch_add(reductions, Ordering::Relaxed);
     }
 }
 
@@ -30,6 +30,22 @@ impl RunQueue {
         self.scheduled.pop_front()
     }
 
+    /// Returns true if there are no processes in the queue
+    pub fn is_empty(&self) -> bool {
+        self.scheduled.is_empty() && self.visited.is_empty()
+    }
+
+    /// Removes a process from the queue so that another scheduler can execute it, if any
+    /// are available
+    ///
+    /// Processes are taken from the end of the queue, as those are the ones which would
+    /// otherwise wait the longest before being executed.
+    pub fn steal(&mut self) -> Option<Arc<SchedulerData>> {
+        self.visited
+            .pop_back()
+            .or_else(|| self.scheduled.pop_back())
+    }
+
     /// Schedules the given process immediately
     #[allow(dead_code)]
     pub fn schedule_now(&mut self, process: Arc<SchedulerData>) {
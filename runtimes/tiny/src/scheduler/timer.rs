@@ -0,0 +1,147 @@
+use std::sync::Mutex;
+
+use firefly_rt::process::Message;
+use firefly_rt::term::{Atom, ProcessId, ReferenceId, Term};
+
+use crate::time::{self, TimerWheel};
+
+use super::Scheduler;
+
+pub(super) type TimerWheels = Box<[Mutex<TimerWheel<ReferenceId, Timer>>]>;
+
+/// The action taken when a timer expires
+pub(super) enum Timer {
+    /// Wakes a process blocked in a receive with a timeout
+    Receive(ProcessId),
+    /// Sends a message to a process, as done by `erlang:send_after/3` and `erlang:start_timer/3`
+    Send {
+        to: Destination,
+        message: Box<Message>,
+    },
+}
+
+/// The recipient of a message sent when a timer expires
+#[derive(Debug, Copy, Clone)]
+pub enum Destination {
+    Process(ProcessId),
+    /// A registered name, which is resolved when the timer expires
+    Name(Atom),
+}
+
+/// Returns the current time of the timer wheels, which is monotonic time in milliseconds
+fn now() -> u64 {
+    time::monotonic_millis() as u64
+}
+
+impl Scheduler {
+    /// Returns the timer wheel of the scheduler which created `reference`
+    ///
+    /// Each scheduler has its own timer wheel, in which it starts timers using references it
+    /// generated, so the timer for a reference is found without searching every wheel.
+    fn timer_wheel(
+        &self,
+        reference: ReferenceId,
+    ) -> Option<&Mutex<TimerWheel<ReferenceId, Timer>>> {
+        self.schedulers
+            .timers
+            .get(reference.scheduler_id() as usize)
+    }
+
+    /// Starts a timer identified by `reference`, which sends `message` to `to` once monotonic
+    /// time reaches `deadline`, in milliseconds
+    pub fn start_timer(
+        &self,
+        reference: ReferenceId,
+        deadline: u64,
+        to: Destination,
+        message: Term,
+    ) -> anyhow::Result<()> {
+        let message = Message::new(message)?;
+        self.schedulers.timers[self.index].lock().unwrap().insert(
+            reference,
+            deadline,
+            Timer::Send { to, message },
+        );
+        Ok(())
+    }
+
+    /// Cancels the timer identified by `reference`, returning the milliseconds which remained
+    /// until it would have expired, or `None` if there is no such timer
+    pub fn cancel_timer(&self, reference: ReferenceId) -> Option<u64> {
+        let (deadline, _) = self
+            .timer_wheel(reference)?
+            .lock()
+            .unwrap()
+            .cancel(&reference)?;
+        Some(deadline.saturating_sub(now()))
+    }
+
+    /// Returns the milliseconds remaining until the timer identified by `reference` expires,
+    /// or `None` if there is no such timer
+    pub fn read_timer(&self, reference: ReferenceId) -> Option<u64> {
+        let deadline = self
+            .timer_wheel(reference)?
+            .lock()
+            .unwrap()
+            .deadline(&reference)?;
+        Some(deadline.saturating_sub(now()))
+    }
+
+    /// Returns the deadline of the receive the current process is blocked in, if it has one
+    pub fn receive_deadline(&self) -> Option<u64> {
+        self.current()
+            .receive_timer
+            .get()
+            .map(|(_, deadline)| deadline)
+    }
+
+    /// Starts a timer which wakes the current process when `deadline` is reached, unless
+    /// one has already been started for the receive it is blocked in
+    pub fn start_receive_timer(&self, deadline: u64) {
+        let current = self.current();
+        if current.receive_timer.get().is_some() {
+            return;
+        }
+        let reference = self.next_reference_id();
+        let timer = Timer::Receive(current.process.pid());
+        self.schedulers.timers[self.index]
+            .lock()
+            .unwrap()
+            .insert(reference, deadline, timer);
+        current.receive_timer.set(Some((reference, deadline)));
+    }
+
+    /// Cancels the timer of the receive the current process was blocked in, if it had one
+    pub fn cancel_receive_timer(&self) {
+        if let Some((reference, _)) = self.current().receive_timer.take() {
+            if let Some(wheel) = self.timer_wheel(reference) {
+                wheel.lock().unwrap().cancel(&reference);
+            }
+        }
+    }
+
+    /// Expires the timers of this scheduler whose deadline has been reached
+    pub(super) fn expire_timers(&self) {
+        let expired = self.schedulers.timers[self.index]
+            .lock()
+            .unwrap()
+            .advance(now());
+        for (_, timer) in expired {
+            match timer {
+                Timer::Receive(id) => self.wake(id),
+                Timer::Send { to, message } => {
+                    // The message is dropped if the process no longer exists, or the name is
+                    // not registered
+                    let to = match to {
+                        Destination::Process(id) => Some(id),
+                        Destination::Name(name) => self.whereis(name),
+                    };
+                    if let Some(process) = to.and_then(|id| self.get_process(id)) {
+                        process.mailbox().push(message);
+                        self.wake(process.pid());
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,380 @@
+//! This module implements Erlang time, as described in the "Time and Time Correction in Erlang"
+//! section of the ERTS user's guide.
+//!
+//! Erlang monotonic time is derived from the OS monotonic clock, and so never leaps backwards.
+//! Erlang system time is Erlang monotonic time plus the time offset, and how the time offset
+//! is maintained depends on the time warp mode, see `TimeWarpMode`. Times are kept in native
+//! time units, which are nanoseconds in this runtime.
+//!
+//! The clock that time is read from is abstracted by `Clock`, so that the behavior of each
+//! time warp mode can be tested against a clock which is under our control.
+mod wheel;
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use firefly_number::{BigInt, Sign};
+use firefly_rt::term::{atoms, Atom, OpaqueTerm, Term};
+
+pub use self::wheel::TimerWheel;
+
+static TIME: OnceLock<Time<OsClock>> = OnceLock::new();
+
+/// Initializes Erlang time for this runtime, using the given time warp mode
+///
+/// Erlang monotonic time starts at zero when this is called.
+pub fn init(mode: TimeWarpMode) {
+    TIME.get_or_init(|| Time::new(OsClock::new(), mode));
+}
+
+/// Returns the Erlang time of this runtime
+pub fn get() -> &'static Time<OsClock> {
+    TIME.get().unwrap()
+}
+
+/// Returns the current Erlang monotonic time in milliseconds, which is the time base of
+/// receive timeouts and timers
+pub fn monotonic_millis() -> i64 {
+    get().monotonic_millis()
+}
+
+/// A source of time
+pub trait Clock: Send + Sync {
+    /// Returns the nanoseconds elapsed since an arbitrary point in time, this must never
+    /// go backwards
+    fn monotonic(&self) -> i64;
+
+    /// Returns the nanoseconds elapsed since the Unix epoch, according to the OS system
+    /// time, which may change arbitrarily
+    fn system(&self) -> i64;
+}
+
+/// The clocks provided by the operating system
+pub struct OsClock {
+    epoch: Instant,
+}
+impl OsClock {
+    fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+        }
+    }
+}
+impl Clock for OsClock {
+    fn monotonic(&self) -> i64 {
+        self.epoch.elapsed().as_nanos() as i64
+    }
+
+    fn system(&self) -> i64 {
+        match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(elapsed) => elapsed.as_nanos() as i64,
+            Err(err) => -(err.duration().as_nanos() as i64),
+        }
+    }
+}
+
+/// Determines how the time offset, and in turn Erlang system time, follows OS system time
+///
+/// This is selected with the `+C` emulator flag, as in BEAM.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum TimeWarpMode {
+    /// The time offset is determined at startup and never changes, so Erlang system time
+    /// does not follow changes to OS system time.
+    #[default]
+    NoTimeWarp,
+    /// The time offset is preliminary until it is finalized with
+    /// `erlang:system_flag(time_offset, finalize)`, at which point Erlang system time warps
+    /// to OS system time once, and the time offset never changes again.
+    SingleTimeWarp,
+    /// The time offset changes whenever OS system time does, so Erlang system time always
+    /// corresponds to OS system time.
+    MultiTimeWarp,
+}
+impl FromStr for TimeWarpMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "no_time_warp" => Ok(Self::NoTimeWarp),
+            "single_time_warp" => Ok(Self::SingleTimeWarp),
+            "multi_time_warp" => Ok(Self::MultiTimeWarp),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The state of the time offset, as returned by `erlang:system_info(time_offset)`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TimeOffsetState {
+    Preliminary,
+    Final,
+    Volatile,
+}
+impl From<TimeOffsetState> for Atom {
+    fn from(state: TimeOffsetState) -> Self {
+        match state {
+            TimeOffsetState::Preliminary => atoms::Preliminary,
+            TimeOffsetState::Final => atoms::Final,
+            TimeOffsetState::Volatile => atoms::Volatile,
+        }
+    }
+}
+
+/// A unit of time, represented as the number of parts per second
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TimeUnit(u64);
+impl TimeUnit {
+    pub const SECOND: Self = Self(1);
+    pub const MILLISECOND: Self = Self(1_000);
+    pub const MICROSECOND: Self = Self(1_000_000);
+    pub const NANOSECOND: Self = Self(1_000_000_000);
+    pub const NATIVE: Self = Self::NANOSECOND;
+    pub const PERF_COUNTER: Self = Self::NANOSECOND;
+
+    /// Converts `value` from this unit to `to`, rounding towards negative infinity, as is
+    /// done by `erlang:convert_time_unit/3`
+    pub fn convert(self, value: i64, to: TimeUnit) -> i128 {
+        (value as i128 * to.0 as i128).div_euclid(self.0 as i128)
+    }
+
+    /// As `convert`, but for values which do not fit in an `i64`
+    pub fn convert_big(self, value: &BigInt, to: TimeUnit) -> BigInt {
+        let scaled = value * to.0;
+        let quotient = &scaled / self.0;
+        // Division truncates towards zero, so negative results which were inexact are rounded up
+        if scaled.sign() == Sign::Minus && &quotient * self.0 != scaled {
+            quotient - 1
+        } else {
+            quotient
+        }
+    }
+}
+impl TryFrom<OpaqueTerm> for TimeUnit {
+    type Error = ();
+
+    fn try_from(term: OpaqueTerm) -> Result<Self, Self::Error> {
+        match term.into() {
+            Term::Int(parts) if parts > 0 => Ok(Self(parts as u64)),
+            Term::Atom(a) if a == atoms::Second || a == atoms::Seconds => Ok(Self::SECOND),
+            Term::Atom(a) if a == atoms::Millisecond || a == atoms::MilliSeconds => {
+                Ok(Self::MILLISECOND)
+            }
+            Term::Atom(a) if a == atoms::Microsecond || a == atoms::MicroSeconds => {
+                Ok(Self::MICROSECOND)
+            }
+            Term::Atom(a) if a == atoms::Nanosecond || a == atoms::NanoSeconds => {
+                Ok(Self::NANOSECOND)
+            }
+            Term::Atom(a) if a == atoms::Native => Ok(Self::NATIVE),
+            Term::Atom(a) if a == atoms::PerfCounter => Ok(Self::PERF_COUNTER),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Erlang monotonic and system time, as read from the clock `C`
+pub struct Time<C> {
+    clock: C,
+    mode: TimeWarpMode,
+    // The time offset in native time units, this is only modified in single or multi
+    // time warp mode
+    offset: AtomicI64,
+    // Set once the time offset is finalized in single time warp mode
+    finalized: AtomicBool,
+}
+impl<C: Clock> Time<C> {
+    pub fn new(clock: C, mode: TimeWarpMode) -> Self {
+        let offset = clock.system() - clock.monotonic();
+        Self {
+            clock,
+            mode,
+            offset: AtomicI64::new(offset),
+            finalized: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the current Erlang monotonic time, in native time units
+    pub fn monotonic_time(&self) -> i64 {
+        self.clock.monotonic()
+    }
+
+    /// Returns the current Erlang monotonic time, in milliseconds
+    pub fn monotonic_millis(&self) -> i64 {
+        TimeUnit::NATIVE.convert(self.monotonic_time(), TimeUnit::MILLISECOND) as i64
+    }
+
+    /// Returns the current Erlang system time, in native time units
+    pub fn system_time(&self) -> i64 {
+        let monotonic = self.monotonic_time();
+        monotonic + self.offset_at(monotonic)
+    }
+
+    /// Returns the current time offset, in native time units
+    pub fn time_offset(&self) -> i64 {
+        self.offset_at(self.monotonic_time())
+    }
+
+    fn offset_at(&self, monotonic: i64) -> i64 {
+        match self.mode {
+            TimeWarpMode::MultiTimeWarp => {
+                let offset = self.clock.system() - monotonic;
+                self.offset.store(offset, Ordering::Relaxed);
+                offset
+            }
+            TimeWarpMode::NoTimeWarp | TimeWarpMode::SingleTimeWarp => {
+                self.offset.load(Ordering::Relaxed)
+            }
+        }
+    }
+
+    /// Returns the state of the time offset
+    pub fn time_offset_state(&self) -> TimeOffsetState {
+        match self.mode {
+            TimeWarpMode::NoTimeWarp => TimeOffsetState::Final,
+            TimeWarpMode::SingleTimeWarp if self.finalized.load(Ordering::Acquire) => {
+                TimeOffsetState::Final
+            }
+            TimeWarpMode::SingleTimeWarp => TimeOffsetState::Preliminary,
+            TimeWarpMode::MultiTimeWarp => TimeOffsetState::Volatile,
+        }
+    }
+
+    /// Finalizes the time offset, returning its state prior to this call
+    ///
+    /// This only has an effect in single time warp mode, the first time it is called, in
+    /// which case Erlang system time is warped to match OS system time.
+    pub fn finalize_time_offset(&self) -> TimeOffsetState {
+        let state = self.time_offset_state();
+        if state == TimeOffsetState::Preliminary
+            && self
+                .finalized
+                .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+        {
+            let offset = self.clock.system() - self.clock.monotonic();
+            self.offset.store(offset, Ordering::Relaxed);
+            return TimeOffsetState::Preliminary;
+        }
+        match state {
+            TimeOffsetState::Preliminary => TimeOffsetState::Final,
+            state => state,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    use super::*;
+
+    const SECOND: i64 = 1_000_000_000;
+
+    /// A clock which only moves when told to
+    #[derive(Default)]
+    struct ManualClock {
+        monotonic: AtomicI64,
+        system: AtomicI64,
+    }
+    impl ManualClock {
+        fn advance(&self, nanos: i64) {
+            self.monotonic.fetch_add(nanos, Ordering::Relaxed);
+            self.system.fetch_add(nanos, Ordering::Relaxed);
+        }
+
+        fn set_system(&self, nanos: i64) {
+            self.system.store(nanos, Ordering::Relaxed);
+        }
+    }
+    impl Clock for &ManualClock {
+        fn monotonic(&self) -> i64 {
+            self.monotonic.load(Ordering::Relaxed)
+        }
+
+        fn system(&self) -> i64 {
+            self.system.load(Ordering::Relaxed)
+        }
+    }
+
+    #[test]
+    fn no_time_warp_ignores_system_time_changes() {
+        let clock = ManualClock::default();
+        clock.set_system(100 * SECOND);
+        let time = Time::new(&clock, TimeWarpMode::NoTimeWarp);
+
+        clock.advance(SECOND);
+        assert_eq!(time.system_time(), 101 * SECOND);
+
+        clock.set_system(50 * SECOND);
+        assert_eq!(time.monotonic_time(), SECOND);
+        assert_eq!(time.system_time(), 101 * SECOND);
+        assert_eq!(time.finalize_time_offset(), TimeOffsetState::Final);
+        assert_eq!(time.system_time(), 101 * SECOND);
+    }
+
+    #[test]
+    fn single_time_warp_warps_once_when_finalized() {
+        let clock = ManualClock::default();
+        clock.set_system(100 * SECOND);
+        let time = Time::new(&clock, TimeWarpMode::SingleTimeWarp);
+        assert_eq!(time.time_offset_state(), TimeOffsetState::Preliminary);
+
+        clock.set_system(200 * SECOND);
+        assert_eq!(time.system_time(), 100 * SECOND);
+
+        assert_eq!(time.finalize_time_offset(), TimeOffsetState::Preliminary);
+        assert_eq!(time.time_offset_state(), TimeOffsetState::Final);
+        assert_eq!(time.system_time(), 200 * SECOND);
+
+        clock.set_system(300 * SECOND);
+        assert_eq!(time.finalize_time_offset(), TimeOffsetState::Final);
+        assert_eq!(time.system_time(), 200 * SECOND);
+    }
+
+    #[test]
+    fn multi_time_warp_follows_system_time() {
+        let clock = ManualClock::default();
+        clock.set_system(100 * SECOND);
+        let time = Time::new(&clock, TimeWarpMode::MultiTimeWarp);
+
+        clock.advance(SECOND);
+        clock.set_system(50 * SECOND);
+        assert_eq!(time.monotonic_time(), SECOND);
+        assert_eq!(time.system_time(), 50 * SECOND);
+        assert_eq!(time.time_offset(), 49 * SECOND);
+        assert_eq!(time.finalize_time_offset(), TimeOffsetState::Volatile);
+    }
+
+    #[test]
+    fn convert_time_unit_rounds_towards_negative_infinity() {
+        let native = TimeUnit::NATIVE;
+        assert_eq!(native.convert(1_999_999, TimeUnit::MILLISECOND), 1);
+        assert_eq!(native.convert(-1, TimeUnit::MILLISECOND), -1);
+        assert_eq!(TimeUnit::SECOND.convert(3, TimeUnit::MILLISECOND), 3_000);
+        assert_eq!(TimeUnit(3).convert(1, TimeUnit(2)), 0);
+        assert_eq!(
+            TimeUnit::SECOND.convert(i64::MAX, TimeUnit::NANOSECOND),
+            i64::MAX as i128 * 1_000_000_000
+        );
+    }
+
+    #[test]
+    fn convert_time_unit_accepts_big_integers() {
+        let big = BigInt::from(i64::MAX) * 1_000u32;
+        assert_eq!(
+            TimeUnit::SECOND.convert_big(&big, TimeUnit::MILLISECOND),
+            &big * 1_000u32
+        );
+        let inexact = &big + 1u32;
+        assert_eq!(
+            TimeUnit::MILLISECOND.convert_big(&inexact, TimeUnit::SECOND),
+            BigInt::from(i64::MAX)
+        );
+        assert_eq!(
+            TimeUnit::MILLISECOND.convert_big(&-inexact, TimeUnit::SECOND),
+            BigInt::from(i64::MIN)
+        );
+    }
+}
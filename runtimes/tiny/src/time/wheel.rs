@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem;
+
+/// The number of levels in the wheel
+const LEVELS: usize = 6;
+/// The number of bits of a deadline which select the slot within a level
+const SLOT_BITS: u32 = 6;
+/// The number of slots in each level
+const SLOTS: usize = 1 << SLOT_BITS;
+/// The number of ticks covered by all levels of the wheel, deadlines further in the
+/// future than this are placed in the top level, and re-placed each time it wraps around
+const MAX_TICKS: u64 = 1 << (SLOT_BITS as usize * LEVELS);
+
+/// A hierarchical timing wheel, in which each timer is identified by a key of type `K`,
+/// and carries a value of type `T` which is returned when the timer expires.
+///
+/// Time is measured in ticks, which are milliseconds for the timers of the runtime. The
+/// first level of the wheel has a slot for each of the next 64 ticks, and each level above
+/// it has slots 64 times the size of those in the level below. A timer is placed in the
+/// lowest level whose range covers its deadline, and is moved down a level each time the
+/// slot it is in is reached, until it lands in the first level and expires. This makes
+/// starting, cancelling and expiring a timer O(1), regardless of how many timers there are.
+pub struct TimerWheel<K, T> {
+    // The time up to which the wheel has been advanced
+    now: u64,
+    levels: Vec<Level<K>>,
+    timers: HashMap<K, Timer<T>>,
+    // Timers whose deadline had already passed when they were started
+    expired: Vec<K>,
+}
+
+struct Timer<T> {
+    deadline: u64,
+    value: T,
+    location: Location,
+}
+
+#[derive(Copy, Clone)]
+enum Location {
+    Expired,
+    Slot { level: usize, slot: usize },
+}
+
+struct Level<K> {
+    level: usize,
+    slots: Vec<Vec<K>>,
+    // A bit is set for each slot which contains at least one timer
+    occupied: u64,
+}
+impl<K: Copy + Eq> Level<K> {
+    fn new(level: usize) -> Self {
+        Self {
+            level,
+            slots: (0..SLOTS).map(|_| Vec::new()).collect(),
+            occupied: 0,
+        }
+    }
+
+    fn push(&mut self, slot: usize, key: K) {
+        self.slots[slot].push(key);
+        self.occupied |= 1 << slot;
+    }
+
+    fn remove(&mut self, slot: usize, key: &K) {
+        let keys = &mut self.slots[slot];
+        let index = keys.iter().position(|k| k == key).unwrap();
+        keys.swap_remove(index);
+        if keys.is_empty() {
+            self.occupied &= !(1 << slot);
+        }
+    }
+
+    fn take(&mut self, slot: usize) -> Vec<K> {
+        self.occupied &= !(1 << slot);
+        mem::take(&mut self.slots[slot])
+    }
+
+    /// Returns the next occupied slot of this level, and the time at which it is reached
+    fn next_expiration(&self, now: u64) -> Option<(usize, u64)> {
+        if self.occupied == 0 {
+            return None;
+        }
+        let slot_range = slot_range(self.level);
+        let level_range = slot_range * SLOTS as u64;
+        // Find the first occupied slot at or after the one covering `now`
+        let now_slot = (now / slot_range) as u32 % SLOTS as u32;
+        let slot =
+            (self.occupied.rotate_right(now_slot).trailing_zeros() + now_slot) as usize % SLOTS;
+        let level_start = now & !(level_range - 1);
+        let mut deadline = level_start + slot as u64 * slot_range;
+        // The slot precedes `now` within the level, which can only happen in the top
+        // level, for timers beyond the range of the wheel, as it wraps around
+        if deadline <= now {
+            deadline += level_range;
+        }
+        Some((slot, deadline))
+    }
+}
+
+impl<K: Copy + Eq + Hash, T> TimerWheel<K, T> {
+    /// Creates an empty wheel whose current time is `now`
+    pub fn new(now: u64) -> Self {
+        Self {
+            now,
+            levels: (0..LEVELS).map(Level::new).collect(),
+            timers: HashMap::new(),
+            expired: Vec::new(),
+        }
+    }
+
+    /// Returns true if there are no timers in the wheel
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+
+    /// Starts a timer which expires at `deadline`, replacing the timer for `key`, if any
+    ///
+    /// A timer whose deadline has already passed expires on the next call to `advance`.
+    pub fn insert(&mut self, key: K, deadline: u64, value: T) {
+        self.cancel(&key);
+        let location = self.place(key, deadline);
+        self.timers.insert(
+            key,
+            Timer {
+                deadline,
+                value,
+                location,
+            },
+        );
+    }
+
+    /// Cancels the timer for `key`, returning its deadline and value, if it had not expired
+    pub fn cancel(&mut self, key: &K) -> Option<(u64, T)> {
+        let timer = self.timers.remove(key)?;
+        match timer.location {
+            Location::Expired => self.expired.retain(|k| k != key),
+            Location::Slot { level, slot } => self.levels[level].remove(slot, key),
+        }
+        Some((timer.deadline, timer.value))
+    }
+
+    /// Returns the deadline of the timer for `key`, if it has not expired
+    pub fn deadline(&self, key: &K) -> Option<u64> {
+        self.timers.get(key).map(|timer| timer.deadline)
+    }
+
+    /// Advances the wheel to `now`, returning the timers which expired in order of expiry
+    pub fn advance(&mut self, now: u64) -> Vec<(K, T)> {
+        let mut fired = Vec::new();
+        for key in mem::take(&mut self.expired) {
+            let timer = self.timers.remove(&key).unwrap();
+            fired.push((key, timer.value));
+        }
+
+        while let Some((level, slot, deadline)) = self.next_expiration() {
+            if deadline > now {
+                break;
+            }
+            self.now = deadline;
+            // Timers in the slot either expire now, or move down to a lower level
+            for key in self.levels[level].take(slot) {
+                let timer = self.timers.get(&key).unwrap();
+                if timer.deadline <= self.now {
+                    let timer = self.timers.remove(&key).unwrap();
+                    fired.push((key, timer.value));
+                } else {
+                    let location = self.place(key, timer.deadline);
+                    self.timers.get_mut(&key).unwrap().location = location;
+                }
+            }
+        }
+
+        self.now = self.now.max(now);
+        fired
+    }
+
+    /// Returns the level and slot containing the next timers to process, and the time at
+    /// which they are reached
+    ///
+    /// The timers in a level always expire before those in the levels above it, so this is
+    /// the first occupied slot of the lowest occupied level.
+    fn next_expiration(&self) -> Option<(usize, usize, u64)> {
+        self.levels.iter().find_map(|level| {
+            level
+                .next_expiration(self.now)
+                .map(|(slot, deadline)| (level.level, slot, deadline))
+        })
+    }
+
+    fn place(&mut self, key: K, deadline: u64) -> Location {
+        if deadline <= self.now {
+            self.expired.push(key);
+            return Location::Expired;
+        }
+        let level = level_for(self.now, deadline);
+        let slot = ((deadline >> (SLOT_BITS as usize * level)) as usize) % SLOTS;
+        self.levels[level].push(slot, key);
+        Location::Slot { level, slot }
+    }
+}
+
+/// Returns the number of ticks covered by each slot of `level`
+fn slot_range(level: usize) -> u64 {
+    1 << (SLOT_BITS as usize * level)
+}
+
+/// Returns the lowest level whose current range covers `deadline`, i.e. the level of the
+/// most significant slot bits in which `now` and `deadline` differ
+fn level_for(now: u64, deadline: u64) -> usize {
+    let masked = ((now ^ deadline) | (SLOTS as u64 - 1)).min(MAX_TICKS - 1);
+    let significant = 63 - masked.leading_zeros() as usize;
+    significant / SLOT_BITS as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn advance(wheel: &mut TimerWheel<u32, &'static str>, now: u64) -> Vec<&'static str> {
+        wheel.advance(now).into_iter().map(|(_, v)| v).collect()
+    }
+
+    #[test]
+    fn timers_expire_in_order() {
+        let mut wheel = TimerWheel::new(0);
+        wheel.insert(1, 10, "b");
+        wheel.insert(2, 5, "a");
+        wheel.insert(3, 70, "c");
+        assert_eq!(advance(&mut wheel, 4), Vec::<&str>::new());
+        assert_eq!(advance(&mut wheel, 10), vec!["a", "b"]);
+        assert_eq!(advance(&mut wheel, 69), Vec::<&str>::new());
+        assert_eq!(advance(&mut wheel, 70), vec!["c"]);
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn distant_timers_cascade_to_exact_deadlines() {
+        let mut wheel = TimerWheel::new(100);
+        let deadline = 100 + 3 * 64 * 64 * 64 + 17;
+        wheel.insert(1, deadline, "far");
+        wheel.insert(2, 100 + 4097, "near");
+        assert_eq!(advance(&mut wheel, 100 + 4096), Vec::<&str>::new());
+        assert_eq!(advance(&mut wheel, 100 + 4097), vec!["near"]);
+        assert_eq!(advance(&mut wheel, deadline - 1), Vec::<&str>::new());
+        assert_eq!(wheel.deadline(&1), Some(deadline));
+        assert_eq!(advance(&mut wheel, deadline + 1000), vec!["far"]);
+    }
+
+    #[test]
+    fn cancelled_timers_do_not_expire() {
+        let mut wheel = TimerWheel::new(0);
+        wheel.insert(1, 300, "a");
+        wheel.insert(2, 300, "b");
+        assert_eq!(wheel.cancel(&1), Some((300, "a")));
+        assert_eq!(wheel.cancel(&1), None);
+        assert_eq!(advance(&mut wheel, 1000), vec!["b"]);
+        assert_eq!(wheel.cancel(&2), None);
+    }
+
+    #[test]
+    fn timers_in_the_past_expire_on_next_advance() {
+        let mut wheel = TimerWheel::new(50);
+        wheel.insert(1, 20, "late");
+        assert!(!wheel.is_empty());
+        assert_eq!(advance(&mut wheel, 50), vec!["late"]);
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn timers_beyond_the_range_of_the_wheel_wrap_around() {
+        let mut wheel = TimerWheel::new(1);
+        wheel.insert(1, MAX_TICKS + 5, "wrapped");
+        assert_eq!(advance(&mut wheel, MAX_TICKS), Vec::<&str>::new());
+        assert_eq!(advance(&mut wheel, MAX_TICKS + 5), vec!["wrapped"]);
+    }
+}
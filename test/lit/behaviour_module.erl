@@ -0,0 +1,8 @@
+-module(behaviour_module).
+
+-callback init(term()) -> ok.
+-callback handle(term(), term()) -> ok.
+-callback terminate() -> ok.
+-callback code_change(term()) -> ok.
+
+-optional_callbacks([code_change/1]).
@@ -0,0 +1,24 @@
+%% RUN: @firefly compile -Z analyze_only @file @tests/behaviour_module.erl 2>&1
+
+%% CHECK: callback function has the wrong arity
+%% CHECK: handle/2 is required by behaviour behaviour_module
+%% CHECK: callback function is not exported
+%% CHECK: init/1 is required by behaviour behaviour_module, but is not exported
+%% CHECK: undefined callback function
+%% CHECK: terminate/0 is required by behaviour behaviour_module
+%% CHECK: undefined behaviour
+-module(init).
+
+-export([boot/1, handle/1]).
+
+-behaviour(behaviour_module).
+-behaviour(no_such_behaviour).
+
+boot(Args) ->
+    init(Args).
+
+init(_) ->
+    ok.
+
+handle(_) ->
+    ok.
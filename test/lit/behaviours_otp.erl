@@ -0,0 +1,24 @@
+%% RUN: @firefly compile -Z analyze_only @file 2>&1
+
+%% CHECK: undefined callback function
+%% CHECK: handle_cast/2 is required by behaviour gen_server
+%% CHECK: undefined callback function
+%% CHECK: terminate/2 is required by behaviour supervisor_bridge
+-module(init).
+
+-export([boot/1, init/1, handle_call/3, handle_info/2]).
+
+-behaviour(gen_server).
+-behaviour(supervisor_bridge).
+
+boot(_) ->
+    ok.
+
+init(Args) ->
+    {ok, Args}.
+
+handle_call(_Request, _From, State) ->
+    {reply, ok, State}.
+
+handle_info(_Info, State) ->
+    {noreply, State}.
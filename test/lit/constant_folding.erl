@@ -0,0 +1,19 @@
+%% RUN: @firefly compile -o @tempfile @file && @tempfile
+
+%% CHECK: {3,true,b,ok}
+-module(init).
+
+-export([boot/1]).
+
+boot(_Args) ->
+    X = 1 + 2,
+    Y = is_atom(element(2, {a, b})),
+    Z = case {a, b} of
+            {a, B} -> B;
+            _ -> c
+        end,
+    R = if
+            X > 2 -> ok;
+            true -> error
+        end,
+    erlang:display({X, Y, Z, R}).
@@ -0,0 +1,26 @@
+%% RUN: @firefly compile --emit=core,link --output-dir @tempfile.out -o @tempfile @file && cat @tempfile.out/inline.core && @tempfile
+
+%% Calls to double/1 and classify/1 are inlined and folded, but the remote call is not
+%% CHECK: call erlang:display/1({8,
+%% CHECK: call init:double/1(5)
+%% CHECK: {8,[2,4,6],ok,{error,function_clause},10}
+-module(init).
+
+-export([boot/1, double/1]).
+
+-compile(inline).
+-compile({inline, [classify/1]}).
+
+boot(_Args) ->
+    Double = fun (X) -> X * 2 end,
+    A = double(double(2)),
+    B = [Double(N) || N <- [1, 2, 3]],
+    C = classify(ok),
+    D = try classify(other) catch error:function_clause -> {error, function_clause} end,
+    E = ?MODULE:double(5),
+    erlang:display({A, B, C, D, E}).
+
+double(X) -> X * 2.
+
+classify(ok) -> ok;
+classify(error) -> error.
@@ -0,0 +1,10 @@
+%% RUN: @firefly compile -C link_self_contained=yes -o @tempfile @file && @tempfile
+
+%% CHECK: linked
+-module(init).
+
+-export([boot/1]).
+
+boot(_Args) ->
+    %% Linked in-process by the lld bundled with the compiler
+    erlang:display(linked).
@@ -0,0 +1,10 @@
+%% RUN: @firefly compile -C link_self_contained=yes -l firefly_missing -o @tempfile @file 2>&1 || true
+
+%% CHECK: linking with `ld.lld` failed
+%% CHECK: unable to find library -lfirefly_missing
+-module(init).
+
+-export([boot/1]).
+
+boot(_Args) ->
+    erlang:display(unreachable).
@@ -0,0 +1,13 @@
+-module(lto_module).
+
+-export([add/2, name/0, unused/0]).
+
+add(A, B) ->
+    A + B.
+
+name() ->
+    unused.
+
+%% Never called, so it is stripped from the executable at link time
+unused() ->
+    erlang:display(unused).
@@ -0,0 +1,12 @@
+%% RUN: @firefly compile -C lto=fat -C lto_runtime -C opt_level=2 -o @tempfile @file @tests/lto_module.erl && @tempfile
+
+%% CHECK: {7,unused}
+-module(init).
+
+-export([boot/1]).
+
+boot(_Args) ->
+    %% The runtime is optimized along with the application, which must still
+    %% be able to find the entry point and dispatch table
+    Sum = lto_module:add(3, 4),
+    erlang:display({Sum, lto_module:name()}).
@@ -0,0 +1,15 @@
+%% RUN: @firefly compile -C lto=thin -C opt_level=2 --emit=llvm-ir,link --output-dir @tempfile.out -o @tempfile @file @tests/lto_module.erl && echo modules=$(ls @tempfile.out | grep -c '\.lto\.[0-9]*\.ll$') && echo add_calls=$(cat @tempfile.out/*.lto.*.ll | grep -c 'call .*@"lto_module:add/2"(') && echo unused_defs=$(cat @tempfile.out/*.lto.*.ll | grep -c 'define .*@"lto_module:unused/0"(') && @tempfile
+
+%% CHECK: modules=2
+%% CHECK: add_calls=0
+%% CHECK: unused_defs=0
+%% CHECK: {7,unused}
+-module(init).
+
+-export([boot/1]).
+
+boot(_Args) ->
+    %% Calls to small functions in other modules may be inlined at link time,
+    %% but must behave as if they were not
+    Sum = lto_module:add(3, 4),
+    erlang:display({Sum, lto_module:name()}).
@@ -0,0 +1,30 @@
+%% RUN: @firefly compile -o @tempfile @file && @tempfile
+
+%% CHECK: [{b,2},{c,3}]
+%% CHECK: [{a,10},{b,20}]
+%% CHECK: {1,4,9}
+%% CHECK: [{x,1},{y,2}]
+%% CHECK: {bad_generator,0}
+%% CHECK: {bad_generator,0}
+-module(init).
+
+-export([boot/1]).
+
+boot(Args) ->
+    Map = #{a => 1, b => 2, c => 3},
+    erlang:display([{K, V} || K := V <- Map, V > 1]),
+    Scaled = #{K => V * 10 || K := V <- Map, K =/= c},
+    erlang:display([{K, V} || K := V <- Scaled]),
+    #{1 := One, 2 := Four, 3 := Nine} = #{X => X * X || X <- [1, 2, 3]},
+    erlang:display({One, Four, Nine}),
+    erlang:display([{K, V} || K := V <- maps:iterator(#{x => 1, y => 2})]),
+    NotAMap = length(Args),
+    erlang:display(error_reason(fun () -> [K || K := _ <- NotAMap] end)),
+    erlang:display(error_reason(fun () -> #{K => V || K := V <- NotAMap} end)).
+
+error_reason(Fun) ->
+    try
+        Fun()
+    catch
+        error:Reason -> Reason
+    end.
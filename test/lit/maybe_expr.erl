@@ -0,0 +1,36 @@
+%% RUN: @firefly compile -o @tempfile @file && @tempfile 1 two
+
+%% CHECK: {ok,2}
+%% CHECK: {error,{not_an_integer,<<"two">>}}
+%% CHECK: {error,empty}
+-module(init).
+
+-feature(maybe_expr, enable).
+
+-export([boot/1]).
+
+boot([A, B]) ->
+    erlang:display(incr(A)),
+    erlang:display(incr(B)),
+    erlang:display(incr(<<>>)).
+
+incr(Arg) ->
+    maybe
+        ok ?= not_empty(Arg),
+        {ok, N} ?= to_integer(Arg),
+        {ok, N + 1}
+    else
+        empty -> {error, empty};
+        {error, Reason} -> {error, Reason}
+    end.
+
+not_empty(<<>>) -> empty;
+not_empty(_) -> ok.
+
+to_integer(Bin) ->
+    try
+        {ok, erlang:binary_to_integer(Bin)}
+    catch
+        error:badarg ->
+            {error, {not_an_integer, Bin}}
+    end.
@@ -0,0 +1,6 @@
+%% RUN: @firefly compile -Z analyze_only @tests/maybe_match_abstract.abstr 2>&1 || true
+
+%% Abstract code may contain a conditional match outside of a maybe expression,
+%% which is rejected rather than translated
+%% CHECK: illegal conditional match
+%% CHECK: ?= is only permitted at the top level of a maybe expression
@@ -0,0 +1,35 @@
+%% RUN: @firefly compile -D answer=42 -D trace -o @tempfile @file && @tempfile $(@firefly print version)
+
+%% CHECK: init
+%% CHECK: [hello]
+%% CHECK: [1, 2]
+%% CHECK: true
+%% CHECK: true
+%% CHECK: 16
+%% CHECK: true
+-module(init).
+
+-export([boot/1]).
+
+-compile([no_native]).
+
+-my_attr(hello).
+-other_attr([1, 2]).
+
+boot([Version]) ->
+    erlang:display(module_info(module)),
+    Attributes = module_info(attributes),
+    erlang:display(find(my_attr, Attributes)),
+    erlang:display(find(other_attr, Attributes)),
+    Compile = module_info(compile),
+    erlang:display(find(version, Compile) =:= erlang:binary_to_list(Version)),
+    erlang:display(find(options, Compile) =:= [{d, answer, "42"}, {d, trace}, no_native]),
+    erlang:display(erlang:byte_size(module_info(md5))),
+    erlang:display(module_info(md5) =:= find(md5, module_info())).
+
+find(Key, [{Key, Value} | _]) ->
+    Value;
+find(Key, [_ | Rest]) ->
+    find(Key, Rest);
+find(_Key, []) ->
+    undefined.
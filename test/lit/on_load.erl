@@ -0,0 +1,24 @@
+%% RUN: @firefly compile -o @tempfile @file @tests/on_load_a.erl @tests/on_load_b.erl && @tempfile
+
+%% This module depends on on_load_a via a local function, which in turn depends on on_load_b,
+%% so their on_load functions must run in the reverse order
+%% CHECK: b_loaded
+%% CHECK: a_loaded
+%% CHECK: loaded
+%% CHECK: booted
+-module(init).
+
+-export([boot/1]).
+
+-on_load(load/0).
+
+load() ->
+    pong = setup(),
+    erlang:display(loaded),
+    ok.
+
+setup() ->
+    on_load_a:ping().
+
+boot(_Args) ->
+    erlang:display(booted).
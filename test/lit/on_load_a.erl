@@ -0,0 +1,13 @@
+-module(on_load_a).
+
+-export([ping/0]).
+
+-on_load(load/0).
+
+load() ->
+    pong = on_load_b:ping(),
+    erlang:display(a_loaded),
+    ok.
+
+ping() ->
+    pong.
@@ -0,0 +1,12 @@
+-module(on_load_b).
+
+-export([ping/0]).
+
+-on_load(load/0).
+
+load() ->
+    erlang:display(b_loaded),
+    ok.
+
+ping() ->
+    pong.
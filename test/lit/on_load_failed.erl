@@ -0,0 +1,15 @@
+%% RUN: @firefly compile -o @tempfile @file && @tempfile 2>&1 || true
+
+%% Boot is aborted if an on_load function returns anything other than ok
+%% CHECK: {on_load_function_failed,init,{error,not_loaded}}
+-module(init).
+
+-export([boot/1]).
+
+-on_load(load/0).
+
+load() ->
+    {error, not_loaded}.
+
+boot(_Args) ->
+    erlang:display(booted).
@@ -0,0 +1,26 @@
+%% RUN: @firefly compile --emit=mlir --output-dir @tempfile.out @file && grep -A1 'func.func.*@"init:spin/1"' @tempfile.out/*.mlir | grep -q 'cir.process.reduce' && echo reduce_on_entry && [ $(awk '/func.func.*@"init:boot\/1"/,/^  }$/' @tempfile.out/*.mlir | grep -c 'cir.process.reduce') -ge 2 ] && echo reduce_on_back_edge && @firefly compile -o @tempfile @file && @tempfile +S 1
+
+%% Reductions are consumed on entry to every function, and on each back-edge, such
+%% as the loop of a receive
+%% CHECK: reduce_on_entry
+%% CHECK: reduce_on_back_edge
+%% CHECK: other_ran
+%% CHECK: spinner_killed
+-module(init).
+
+-export([boot/1]).
+
+boot(_Args) ->
+    %% There is a single scheduler, and the spinner never blocks, so without
+    %% preemption it would run forever
+    Spinner = spawn(fun() -> spin(0) end),
+    Self = self(),
+    spawn(fun() -> Self ! other_ran end),
+    receive
+        other_ran -> erlang:display(other_ran)
+    end,
+    exit(Spinner, kill),
+    erlang:display(spinner_killed).
+
+spin(N) ->
+    spin(N + 1).
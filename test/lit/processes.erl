@@ -0,0 +1,60 @@
+%% RUN: @firefly compile -o @tempfile @file && @tempfile
+
+%% CHECK: {child,hello}
+%% CHECK: {down,normal}
+%% CHECK: {down,crashed}
+%% CHECK: {trapped,shutdown}
+%% CHECK: {linked,killed}
+%% CHECK: noproc
+-module(init).
+
+-export([boot/1, echo/1]).
+
+boot(_Args) ->
+    %% Plain spawn/3 and message passing
+    Echo = spawn(init, echo, [self()]),
+    Echo ! hello,
+    receive
+        {echoed, Msg} -> erlang:display({child, Msg})
+    end,
+
+    %% Monitors deliver a 'DOWN' message with the exit reason
+    {_, Ref1} = spawn_monitor(fun() -> ok end),
+    receive
+        {'DOWN', Ref1, process, _, Reason1} -> erlang:display({down, Reason1})
+    end,
+    {_, Ref2} = spawn_monitor(fun() -> exit(crashed) end),
+    receive
+        {'DOWN', Ref2, process, _, Reason2} -> erlang:display({down, Reason2})
+    end,
+
+    %% Trapping exits converts exit signals from links to messages
+    false = process_flag(trap_exit, true),
+    Linked = spawn_link(fun() -> exit(shutdown) end),
+    receive
+        {'EXIT', Linked, Reason3} -> erlang:display({trapped, Reason3})
+    end,
+
+    %% Untrapped exit signals terminate linked processes, and propagate
+    Parent = self(),
+    Middle = spawn_link(fun() ->
+                                spawn_link(fun() -> receive after infinity -> ok end end),
+                                Parent ! ready,
+                                receive after infinity -> ok end
+                        end),
+    receive ready -> ok end,
+    exit(Middle, kill),
+    receive
+        {'EXIT', Middle, Reason4} -> erlang:display({linked, Reason4})
+    end,
+
+    %% Monitoring a process which does not exist
+    Ref3 = monitor(process, Middle),
+    receive
+        {'DOWN', Ref3, process, Middle, Reason5} -> erlang:display(Reason5)
+    end.
+
+echo(Parent) ->
+    receive
+        Msg -> Parent ! {echoed, Msg}
+    end.
@@ -0,0 +1,34 @@
+%% RUN: @firefly compile -o @tempfile @file && @tempfile
+
+%% CHECK: true
+%% CHECK: second
+%% CHECK: first
+%% CHECK: {other,3}
+%% CHECK: timeout
+-module(init).
+
+-export([boot/1]).
+
+boot(_Args) ->
+    Self = self(),
+    case self() of
+        Self -> erlang:display(true)
+    end,
+    Self ! {first, 1},
+    Self ! {second, 2},
+    erlang:send(Self, {other, 3}),
+    %% Selective receive skips over messages which do not match
+    receive
+        {second, _} -> erlang:display(second)
+    end,
+    receive
+        {first, _} -> erlang:display(first)
+    end,
+    receive
+        Msg -> erlang:display(Msg)
+    end,
+    receive
+        _ -> erlang:display(unexpected)
+    after
+        0 -> erlang:display(timeout)
+    end.
@@ -0,0 +1,34 @@
+%% RUN: @firefly compile -o @tempfile @file && @tempfile
+
+%% CHECK: true
+%% CHECK: true
+%% CHECK: true
+%% CHECK: undefined
+-module(init).
+
+-export([boot/1]).
+
+boot(_Args) ->
+    %% Each call consumes a reduction
+    {reductions, Before} = process_info(self(), reductions),
+    count(1000),
+    {reductions, After} = process_info(self(), reductions),
+    erlang:display(After - Before >= 1000),
+
+    %% The total includes the reductions of every process
+    {Total, _} = statistics(reductions),
+    erlang:display(Total >= After),
+    {_, SinceLastCall} = statistics(reductions),
+    erlang:display(SinceLastCall < Total),
+
+    %% There is no information about processes which have exited
+    {Pid, Ref} = spawn_monitor(fun() -> ok end),
+    receive
+        {'DOWN', Ref, process, Pid, _} -> ok
+    end,
+    erlang:display(process_info(Pid, reductions)).
+
+count(0) ->
+    ok;
+count(N) ->
+    count(N - 1).
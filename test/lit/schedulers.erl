@@ -0,0 +1,42 @@
+%% RUN: @firefly compile -o @tempfile @file && @tempfile +S 4
+
+%% CHECK: 54120
+%% CHECK: killed
+-module(init).
+
+-export([boot/1]).
+
+boot(_Args) ->
+    %% The workers are CPU-bound, so idle schedulers steal them from the one they
+    %% were spawned on, and their results are sent back across threads
+    Self = self(),
+    spawn_workers(Self, 8),
+    erlang:display(collect(8, 0)),
+
+    %% Exit signals are delivered to processes running on another scheduler
+    Spinner = spawn(fun() -> spin(0) end),
+    Ref = monitor(process, Spinner),
+    exit(Spinner, kill),
+    receive
+        {'DOWN', Ref, process, Spinner, Reason} -> erlang:display(Reason)
+    end.
+
+spawn_workers(_Parent, 0) ->
+    ok;
+spawn_workers(Parent, N) ->
+    spawn(fun() -> Parent ! {result, fib(20)} end),
+    spawn_workers(Parent, N - 1).
+
+collect(0, Sum) ->
+    Sum;
+collect(N, Sum) ->
+    receive
+        {result, Result} -> collect(N - 1, Sum + Result)
+    end.
+
+fib(0) -> 0;
+fib(1) -> 1;
+fib(N) -> fib(N - 1) + fib(N - 2).
+
+spin(N) ->
+    spin(N + 1).
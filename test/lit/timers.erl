@@ -0,0 +1,70 @@
+%% RUN: @firefly compile -o @tempfile @file && @tempfile
+
+%% CHECK: timeout
+%% CHECK: {sent, 1}
+%% CHECK: true
+%% CHECK: true
+%% CHECK: false
+%% CHECK: false
+%% CHECK: by_name
+%% CHECK: true
+%% CHECK: late
+%% CHECK: 1500
+%% CHECK: -1
+%% CHECK: 1180591620717411303424000
+%% CHECK: -1180591620717411304
+%% CHECK: true
+-module(init).
+
+-export([boot/1]).
+
+boot(_Args) ->
+    %% The process sleeps until the receive times out
+    Start = erlang:monotonic_time(millisecond),
+    receive
+        never -> erlang:display(never)
+    after 50 ->
+        erlang:display(timeout)
+    end,
+
+    %% Timers send their message once they expire, and expired timers can no
+    %% longer be read or cancelled
+    Self = self(),
+    Sent = erlang:send_after(10, Self, {sent, 1}),
+    receive
+        {sent, N} -> erlang:display({sent, N})
+    end,
+    Ref = erlang:start_timer(10, Self, started),
+    receive
+        {timeout, TimerRef, started} -> erlang:display(TimerRef =:= Ref)
+    end,
+
+    %% Cancelling a timer returns the time remaining, and prevents it from firing
+    Cancelled = erlang:start_timer(60000, Self, cancelled),
+    erlang:display(is_integer(erlang:cancel_timer(Cancelled))),
+    erlang:display(erlang:cancel_timer(Cancelled)),
+    erlang:display(erlang:read_timer(Sent)),
+
+    %% Timers may be started for registered names, which are resolved when the timer expires
+    true = register(timers, Self),
+    erlang:send_after(10, timers, by_name),
+    receive
+        by_name -> erlang:display(by_name)
+    end,
+    Named = erlang:start_timer(10, timers, named),
+    receive
+        {timeout, NamedRef, named} -> erlang:display(NamedRef =:= Named)
+    end,
+    erlang:send_after(10, later, late),
+    true = unregister(timers),
+    true = register(later, Self),
+    receive
+        late -> erlang:display(late)
+    end,
+
+    %% Time units are converted with floor rounding
+    erlang:display(erlang:convert_time_unit(1500000, microsecond, millisecond)),
+    erlang:display(erlang:convert_time_unit(-1, nanosecond, second)),
+    erlang:display(erlang:convert_time_unit(1 bsl 70, second, millisecond)),
+    erlang:display(erlang:convert_time_unit(-(1 bsl 70), millisecond, second)),
+    erlang:display(erlang:monotonic_time(millisecond) - Start >= 50).
@@ -0,0 +1,37 @@
+%% RUN: @firefly compile -Z analyze_only @file 2>&1
+
+%% CHECK: unused record
+%% CHECK: this record is never used
+%% CHECK: unused type
+%% CHECK: type unused_type/0 is never used
+%% CHECK: unused function
+%% CHECK: unreachable/0 is never called
+%% CHECK: unused function
+%% CHECK: unused/0 is never called
+-module(init).
+
+-export([boot/1]).
+
+-compile({nowarn_unused_function, [suppressed/0]}).
+
+-record(used, {value}).
+-record(unused, {value}).
+
+-type used_type() :: #used{}.
+-type unused_type() :: integer().
+
+-spec boot(used_type()) -> ok.
+boot(Args) ->
+    erlang:display(reachable(Args)).
+
+reachable(Args) ->
+    #used{value = Args}.
+
+unreachable() ->
+    ok.
+
+unused() ->
+    unreachable().
+
+suppressed() ->
+    ok.
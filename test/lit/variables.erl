@@ -0,0 +1,21 @@
+%% RUN: @firefly compile -Z analyze_only @file 2>&1
+
+%% CHECK: shadowed variable
+%% CHECK: the variable 'Args' is shadowed in 'fun'
+%% CHECK: exported variable
+%% CHECK: the variable 'Y' is exported from 'case'
+%% CHECK: unused variable
+%% CHECK: the variable 'Unused' is bound but never used
+-module(init).
+
+-export([boot/1]).
+
+boot(Args) ->
+    Unused = length(Args),
+    F = fun (Args) -> Args end,
+    X = case Args of
+            [] -> Y = 1;
+            _ -> Y = 2
+        end,
+    erlang:display(F(Y)),
+    erlang:display(X).
@@ -0,0 +1,36 @@
+%% RUN: @firefly compile -Z analyze_only @file 2>&1
+
+%% CHECK: exported variable
+%% CHECK: the variable 'X' is exported from 'case'
+%% CHECK: exported variable
+%% CHECK: the variable 'X' is exported from 'if'
+%% CHECK: exported variable
+%% CHECK: the variable 'X' is exported from 'receive'
+-module(init).
+
+-export([boot/1, exported_case/1, exported_if/1, exported_receive/0]).
+
+boot(_) ->
+    ok.
+
+exported_case(A) ->
+    case A of
+        1 -> X = 1;
+        _ -> X = 2
+    end,
+    X.
+
+exported_if(A) ->
+    if
+        A -> X = 1;
+        true -> X = 2
+    end,
+    X.
+
+exported_receive() ->
+    receive
+        a -> X = 1
+    after
+        0 -> X = 2
+    end,
+    X.
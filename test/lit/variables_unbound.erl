@@ -0,0 +1,31 @@
+%% RUN: @firefly compile -Z analyze_only @file 2>&1 || true
+
+%% CHECK: unbound variable
+%% CHECK: the variable 'X' is unbound
+%% CHECK: unbound variable
+%% CHECK: the variable 'Y' is unbound
+%% CHECK: unbound variable
+%% CHECK: the variable 'Z' is unbound
+%% CHECK: unbound variable
+%% CHECK: the variable 'E' is unbound
+-module(init).
+
+-export([boot/1, unbound/0, unbound_in_fun/0, unbound_in_sibling/0, unbound_after_comprehension/1]).
+
+boot(_) ->
+    ok.
+
+unbound() ->
+    X.
+
+unbound_in_fun() ->
+    F = fun () -> Y end,
+    Y = 1,
+    {F, Y}.
+
+unbound_in_sibling() ->
+    {Z = 1, Z}.
+
+unbound_after_comprehension(L) ->
+    _ = [E || E <- L],
+    E.
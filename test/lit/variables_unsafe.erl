@@ -0,0 +1,96 @@
+%% RUN: @firefly compile -Z analyze_only @file 2>&1 || true
+
+%% CHECK: unsafe variable
+%% CHECK: the variable 'X' is unsafe in 'case'
+%% CHECK: unsafe variable
+%% CHECK: the variable 'X' is unsafe in 'if'
+%% CHECK: unsafe variable
+%% CHECK: the variable 'X' is unsafe in 'receive'
+%% CHECK: unsafe variable
+%% CHECK: the variable 'X' is unsafe in 'receive'
+%% CHECK: unsafe variable
+%% CHECK: the variable 'X' is unsafe in 'try'
+%% CHECK: unsafe variable
+%% CHECK: the variable 'X' is unsafe in 'try'
+%% CHECK: unsafe variable
+%% CHECK: the variable 'X' is unsafe in 'catch'
+%% CHECK: unsafe variable
+%% CHECK: the variable 'X' is unsafe in 'andalso'
+%% CHECK: unsafe variable
+%% CHECK: the variable 'X' is unsafe in 'orelse'
+%% CHECK: unsafe variable
+%% CHECK: the variable 'X' is unsafe in 'maybe'
+-module(init).
+
+-feature(maybe_expr, enable).
+
+-export([boot/1]).
+-export([unsafe_case/1, unsafe_if/1, unsafe_receive/0, unsafe_receive_after/0]).
+-export([unsafe_try/1, unsafe_try_of/1, unsafe_catch/1]).
+-export([unsafe_andalso/1, unsafe_orelse/1, unsafe_maybe/1]).
+
+boot(_) ->
+    ok.
+
+unsafe_case(A) ->
+    case A of
+        1 -> X = 1;
+        _ -> ok
+    end,
+    X.
+
+unsafe_if(A) ->
+    if
+        A -> X = 1;
+        true -> ok
+    end,
+    X.
+
+unsafe_receive() ->
+    receive
+        a -> X = 1;
+        b -> ok
+    end,
+    X.
+
+unsafe_receive_after() ->
+    receive
+        a -> X = 1
+    after
+        0 -> ok
+    end,
+    X.
+
+unsafe_try(A) ->
+    try
+        X = A
+    catch
+        _:_ -> ok
+    end,
+    X.
+
+unsafe_try_of(A) ->
+    try A of
+        _ -> X = 1
+    catch
+        _:_ -> X = 2
+    end,
+    X.
+
+unsafe_catch(A) ->
+    catch (X = A),
+    X.
+
+unsafe_andalso(A) ->
+    _ = A andalso (X = true),
+    X.
+
+unsafe_orelse(A) ->
+    _ = A orelse (X = true),
+    X.
+
+unsafe_maybe(A) ->
+    maybe
+        {ok, X} ?= A
+    end,
+    X.
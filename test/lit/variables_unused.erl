@@ -0,0 +1,27 @@
+%% RUN: @firefly compile -Z analyze_only @file 2>&1
+
+%% A binding which is unused in its own clause is reported, even if a sibling clause uses it
+%% CHECK: unused variable
+%% CHECK: a -> Y = 1;
+%% CHECK: the variable 'Y' is bound but never used
+-module(init).
+
+-export([boot/1]).
+
+boot(Args) ->
+    [X] = Args,
+    erlang:display(unused_in_clause(X)),
+    erlang:display(used_after_clauses(X)).
+
+unused_in_clause(X) ->
+    case X of
+        a -> Y = 1;
+        b -> Y = 2, Y
+    end.
+
+used_after_clauses(X) ->
+    case X of
+        a -> Y = 1;
+        b -> Y = 2, Y
+    end,
+    Y.
@@ -0,0 +1,25 @@
+%% RUN: @firefly compile -Z analyze_only @file @tests/xref_module.erl 2>&1
+
+%% CHECK: unused export
+%% CHECK: api/0 is exported, but is never called by another module in this application
+%% CHECK: call to undefined function
+%% CHECK: the function hidden/1 is not exported by module xref_module
+%% CHECK: call to undefined function
+%% CHECK: the function exported/2 is not exported by module xref_module
+%% CHECK: call to unknown module
+%% CHECK: the module no_such_module is not part of this application
+-module(init).
+
+-export([boot/1, api/0]).
+
+-compile([warn_unknown_module, warn_unused_export]).
+
+boot(Args) ->
+    xref_module:exported(Args),
+    xref_module:hidden(Args),
+    xref_module:exported(Args, Args),
+    no_such_module:call(Args),
+    erlang:display(Args).
+
+api() ->
+    ok.
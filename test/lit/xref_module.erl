@@ -0,0 +1,9 @@
+-module(xref_module).
+
+-export([exported/1]).
+
+exported(Arg) ->
+    hidden(Arg).
+
+hidden(Arg) ->
+    erlang:display(Arg).